@@ -5,47 +5,131 @@ use iced::widget::{button, column, container, row, scrollable, text};
 use iced::{Center, Element, Fill, Subscription, Task, Theme};
 use crate::ui::theme::*;
 
-use crate::input_handler::translate::iced_key_to_keycode;
+use crate::config::known_hosts::{self, HostCheck, KnownHosts};
+use crate::config::app_data_dir;
+use crate::config::secret::SecretString;
+use crate::config::settings::{AppSettings, CREDENTIAL_SECS, Resolution};
+use crate::cli::{ConnectArgs, Event, Launch, Side};
+use crate::session::{ClientSession, HostSession, Role};
+use crate::config::{ConnectionProfile, ProfileStore, SessionRecord};
+use crate::control::{ControlCommand, ControlRequest, ControlResponse, control_api_subscription};
+use crate::input_handler::combo;
+use crate::logging;
+use crate::network::bind::resolve_bind_address;
 use crate::network::client::access_client_subscription;
 use crate::network::server::host_server_subscription;
+use crate::network::clipboard::{self, ClipboardSync, NativeContent};
+use crate::network::files;
+use crate::network::guard;
+use crate::network::paste::{self, PasteProgress};
+use crate::network::typing;
 use crate::network::{ConnectionHandle, NetworkEvent};
-use crate::protocol::{DEFAULT_PORT, ProtocolMessage};
-use crate::tailscale::TailscaleStatus;
-use crate::ui::host::{HostMessage, HostState, HostStatus};
+use crate::probe::{self, LinkReport};
+use crate::recording::{self, Recorder, RecordingClock};
+use crate::storage;
+use crate::protocol::{ColorMode, DEFAULT_PORT, ProtocolMessage};
+use crate::tailscale::{PeerPath, TailscaleStatus};
+use crate::ui::code_prompt::CodePromptState;
+use crate::ui::connecting::{ConnectingMessage, ConnectingState};
+use crate::ui::crash_report::{CrashBannerState, CrashMessage};
+use crate::ui::transfers::{Direction, TransferCommand, TransfersMessage};
+use crate::ui::clipboard_history::Direction as HistoryDirection;
+use crate::ui::host::{HostAction, HostMessage, HostState, HostStatus};
+use crate::ui::lock::{LockMessage, LockOutcome, LockState};
 use crate::ui::login::{LoginMessage, LoginState};
+use crate::ui::logs::{LogsMessage, LogsState};
 use crate::ui::mode_select::{ModeSelectMessage, ModeSelectState};
-use crate::ui::tailscale_setup::{TailscaleSetupMessage, TailscaleSetupState, TailscaleSetupStatus};
-use crate::ui::update::{UpdateBannerState, UpdateMessage, update_banner_view};
-use crate::ui::viewer::{ViewerMessage, ViewerState};
+use crate::ui::paste_file::PasteFileMessage;
+use crate::ui::reboot_wait::{self, RebootOutcome, RebootWaitMessage, RebootWaitState};
+use crate::ui::settings::{SettingsMessage, SettingsState};
+use crate::ui::tray::{TrayEvent, tray_subscription};
+use crate::ui::tailscale_setup::{SetupAction, TailscaleSetupMessage, TailscaleSetupState, TailscaleSetupStatus};
+use crate::ui::update::{UpdateAction, UpdateBannerState, UpdateMessage, update_banner_view};
+use crate::ui::viewer::{RemoteCursor, ViewerAction, ViewerMessage, ViewerState};
 use crate::updater::{self, ReleaseInfo, UpdateProgress};
 
+mod host;
+mod local_clipboard;
+mod network;
+mod screens;
+mod setup;
+mod update;
+mod viewer;
+mod window;
+
 #[derive(Debug, Clone)]
 pub enum Message {
     ModeSelect(ModeSelectMessage),
+    Settings(SettingsMessage),
+    Logs(LogsMessage),
+    Connecting(ConnectingMessage),
+    RebootWait(RebootWaitMessage),
+    Tray(TrayEvent),
     Login(LoginMessage),
     Host(HostMessage),
     Viewer(ViewerMessage),
     TailscaleSetup(TailscaleSetupMessage),
-    NetworkEvent(NetworkEvent),
+    NetworkEvent(Role, NetworkEvent),
     TailscaleCheck(TailscaleStatus),
     Update(UpdateMessage),
+    Crash(CrashMessage),
     UpdateCheckResult(Option<ReleaseInfo>),
+    /// A freshly updated build has run for the whole trial.
+    UpdateTrialPassed(String),
+    Control(ControlRequest),
+    NotifySent(Result<(), String>),
+    MetricsError(String),
+    WindowResized(iced::window::Id, iced::Size),
+    ViewportChanged(iced::Size),
+    WindowClosed(iced::window::Id),
+    MainWindowFocus(bool),
+    Lock(LockMessage),
+    UserActivity,
+    LockTick,
+    PresentationDrag,
+    PresentationToggleFullscreen,
+    PresentationClose,
+    ClipboardPoll,
+    ClipboardRead(Option<String>),
+    ClipboardSecretExpired(u64),
+    ClipboardSecretRead(Option<String>),
+    ClipboardFilesRead(Result<Vec<(String, Vec<u8>)>, String>),
     CopyError,
+    PasteFileRead(Option<String>),
+    TypeClipboardRead(Option<String>),
+    RestartSent(Result<(), String>),
+    IdleDisconnect,
+    OpenLogFolder,
+    Reconnect,
     StopComplete,
     BackToModeSelect,
     InputSent(Result<(), String>),
+    FolderShared(Result<usize, String>),
+    HostKeyChecked(String, Result<String, String>),
+    PeerPathChecked(String, Result<PeerPath, String>),
+    LinkTested(Result<LinkReport, String>),
 }
 
 pub enum Screen {
     TailscaleSetup(TailscaleSetupState),
     ModeSelect(ModeSelectState),
+    Settings(SettingsState),
+    Logs(LogsState),
     Login(LoginState),
-    Connecting,
+    Connecting(ConnectingState),
+    RebootWait(RebootWaitState),
     Hosting(HostState),
-    Viewer(ViewerState),
+    Viewer(Box<ViewerState>),
     Error(String),
 }
 
+fn scan_storage() -> Task<Message> {
+    Task::perform(
+        async { tokio::task::spawn_blocking(|| storage::usage(&app_data_dir())).await.unwrap_or_default() },
+        |usage| Message::Settings(SettingsMessage::StorageScanned(usage)),
+    )
+}
+
 #[derive(Clone, Hash)]
 struct UpdateDownloadKey {
     url: String,
@@ -90,23 +174,48 @@ fn download_update_stream(
 pub struct App {
     screen: Screen,
     tailscale_status: TailscaleStatus,
-    hosting: bool,
-    connecting: bool,
-    connect_host: Option<String>,
-    connect_port: u16,
-    connection_handle: Option<ConnectionHandle>,
+    host: HostSession,
+    client: ClientSession,
+    recorder: Option<Recorder>,
+    /// Timeline of the viewer session that just ended, shown on the error screen.
+    last_timeline: Option<String>,
+    /// The last session that connected and its access code, offered as
+    /// "Reconnect" once it ends. The profile copy has no unlock password;
+    /// `last_profile` gets it back from the store.
+    last_session: Option<(ConnectionProfile, Option<SecretString>)>,
+    /// The main window is hidden and a tray icon stands in for it.
+    in_tray: bool,
+    /// From the command line; acted on once Tailscale is running.
+    launch: Option<Launch>,
+    /// `--width`/`--height` for the session started from the command line.
+    size_override: Option<ConnectArgs>,
+    /// `--json`: session events go to stdout for scripts.
+    json_events: bool,
     update_banner: UpdateBannerState,
+    crash_banner: CrashBannerState,
+    profiles: ProfileStore,
+    known_hosts: KnownHosts,
+    settings: AppSettings,
+    announced_address: Option<String>,
+    clipboard: ClipboardSync,
+    /// A credential on our clipboard, with its number so a newer one is
+    /// not cleared by an older timer.
+    clipboard_secret: Option<(u64, SecretString)>,
+    main_window: iced::window::Id,
+    presentation: Option<Presentation>,
+    lock: Option<LockState>,
+    last_activity: std::time::Instant,
+}
+
+struct Presentation {
+    window: iced::window::Id,
+    fullscreen: bool,
 }
 
 impl App {
-    pub fn new() -> (Self, Task<Message>) {
+    pub fn new(launch: Option<Launch>, json_events: bool) -> (Self, Task<Message>) {
         updater::cleanup_old_update();
-        updater::check_post_update_health();
-
-        let update_task = Task::perform(
-            async { updater::check_for_update().await.ok().flatten() },
-            Message::UpdateCheckResult,
-        );
+        let on_trial = updater::check_post_update_health();
 
         let tailscale_task = Task::perform(
             crate::tailscale::check_tailscale(),
@@ -115,18 +224,64 @@ impl App {
 
         let setup_state = TailscaleSetupState { status: TailscaleSetupStatus::Checking };
 
+        let (main_window, open_main) = iced::window::open(iced::window::Settings {
+            position: iced::window::Position::Centered,
+            ..Default::default()
+        });
+
+        let settings = AppSettings::load_or_default();
+        let update_task = if settings.general.check_updates {
+            let channel = settings.general.update_channel;
+            Task::perform(
+                async move { updater::check_for_update(channel).await.ok().flatten() },
+                Message::UpdateCheckResult,
+            )
+        } else {
+            Task::none()
+        };
+        let trial_task = match on_trial {
+            Some(version) => {
+                let trial = std::time::Duration::from_secs(settings.general.update_trial_minutes * 60);
+                Task::perform(
+                    async move {
+                        tokio::time::sleep(trial).await;
+                        version
+                    },
+                    Message::UpdateTrialPassed,
+                )
+            }
+            None => Task::none(),
+        };
+        // Lock straight away when the lock is on but no PIN has been chosen yet.
+        let lock = (settings.lock.enabled && !settings.lock.has_pin()).then(LockState::default);
+
         (
             Self {
                 screen: Screen::TailscaleSetup(setup_state),
                 tailscale_status: TailscaleStatus::default(),
-                hosting: false,
-                connecting: false,
-                connect_host: None,
-                connect_port: DEFAULT_PORT,
-                connection_handle: None,
+                host: HostSession::default(),
+                client: ClientSession::default(),
+                recorder: None,
+                last_timeline: None,
+                last_session: None,
+                in_tray: false,
+                launch,
+                size_override: None,
+                json_events,
                 update_banner: UpdateBannerState::Hidden,
+                crash_banner: CrashBannerState { reports: crate::crash::pending_reports(), ..Default::default() },
+                profiles: ProfileStore::load_or_default(),
+                known_hosts: KnownHosts::load_or_default(),
+                settings,
+                announced_address: None,
+                clipboard: ClipboardSync::default(),
+                clipboard_secret: None,
+                main_window,
+                presentation: None,
+                lock,
+                last_activity: std::time::Instant::now(),
             },
-            Task::batch([update_task, tailscale_task]),
+            Task::batch([open_main.discard(), update_task, trial_task, tailscale_task]),
         )
     }
 
@@ -134,392 +289,510 @@ impl App {
         Screen::ModeSelect(ModeSelectState::new())
     }
 
-    pub fn update(&mut self, message: Message) -> Task<Message> {
-        match message {
-            Message::TailscaleCheck(status) => {
-                if status.is_running {
-                    self.tailscale_status = status;
-                    self.screen = Screen::ModeSelect(ModeSelectState::new());
-                } else {
-                    let is_installed = status.is_installed;
-                    self.tailscale_status = status;
-                    self.screen = Screen::TailscaleSetup(TailscaleSetupState::new(is_installed));
-                }
+    fn run_launch(&mut self) {
+        match self.launch.take() {
+            Some(Launch::Host) => {
+                // A bind error is shown on the host screen.
+                let _ = self.start_hosting();
             }
-            Message::TailscaleSetup(msg) => match msg {
-                TailscaleSetupMessage::Install => {
-                    crate::tailscale::open_install_page();
+            Some(Launch::Connect(args)) => {
+                let mut login = LoginState {
+                    known_hosts: self.known_hosts.entries.clone(),
+                    ..LoginState::with_profiles(self.profiles.profiles.clone())
+                };
+                // Saved settings for the host (viewer prefs, unlock) carry over.
+                if let Some(index) = login.saved_profiles.iter().position(|p| p.host_ip.eq_ignore_ascii_case(&args.host)) {
+                    login.update(LoginMessage::ProfileSelected(index));
                 }
-                TailscaleSetupMessage::Recheck => {
-                    if let Screen::TailscaleSetup(state) = &mut self.screen {
-                        state.status = TailscaleSetupStatus::Checking;
-                    }
-                    return Task::perform(
-                        crate::tailscale::check_tailscale(),
-                        Message::TailscaleCheck,
-                    );
+                login.host_ip = args.host.clone();
+                login.port = args.port.to_string();
+                if let Some(name) = &args.name {
+                    login.display_name = name.clone();
                 }
-            },
-            Message::UpdateCheckResult(opt) => {
-                if let Some(release) = opt {
-                    self.update_banner = UpdateBannerState::Available(release);
+                if args.edit {
+                    self.screen = Screen::Login(login);
+                } else if let Some(profile) = login.update(LoginMessage::Connect) {
+                    self.start_connecting(profile, None);
+                    self.size_override = Some(args);
                 }
             }
-            Message::Update(msg) => match msg {
-                UpdateMessage::StartDownload => {
-                    if let UpdateBannerState::Available(ref release) = self.update_banner {
-                        self.update_banner = UpdateBannerState::Downloading {
-                            release: release.clone(),
-                            downloaded: 0,
-                            total: 0,
-                        };
-                    }
-                }
-                UpdateMessage::Retry => {
-                    self.update_banner = UpdateBannerState::Hidden;
-                    return Task::perform(
-                        async { updater::check_for_update().await.ok().flatten() },
-                        Message::UpdateCheckResult,
-                    );
-                }
-                UpdateMessage::DownloadProgress(progress) => {
-                    match &progress {
-                        UpdateProgress::Started { total_bytes } => {
-                            if let UpdateBannerState::Downloading { ref release, .. } =
-                                self.update_banner
-                            {
-                                self.update_banner = UpdateBannerState::Downloading {
-                                    release: release.clone(),
-                                    downloaded: 0,
-                                    total: *total_bytes,
-                                };
-                            }
-                        }
-                        UpdateProgress::Progress { downloaded, total } => {
-                            if let UpdateBannerState::Downloading { ref release, .. } =
-                                self.update_banner
-                            {
-                                self.update_banner = UpdateBannerState::Downloading {
-                                    release: release.clone(),
-                                    downloaded: *downloaded,
-                                    total: *total,
-                                };
-                            }
-                        }
-                        UpdateProgress::Verifying => {
-                            self.update_banner = UpdateBannerState::Verifying;
-                        }
-                        UpdateProgress::Finished(_) => {}
-                        UpdateProgress::Error(e) => {
-                            self.update_banner = UpdateBannerState::Error(e.clone());
-                        }
-                    }
-                }
-                UpdateMessage::DownloadComplete(path) => {
-                    let checksum_url = match &self.update_banner {
-                        UpdateBannerState::Downloading { release, .. } => {
-                            release.checksum_url.clone()
-                        }
-                        _ => None,
-                    };
+            None => {}
+        }
+    }
 
-                    self.update_banner = UpdateBannerState::Verifying;
+    fn login_screen(&self) -> Screen {
+        Screen::Login(LoginState {
+            known_hosts: self.known_hosts.entries.clone(),
+            reconnect_to: self.last_session.as_ref().map(|(p, _)| p.host_ip.clone()),
+            ..LoginState::with_profiles(self.profiles.profiles.clone())
+        })
+    }
 
-                    if let Some(url) = checksum_url {
-                        let exe_path = path.clone();
-                        return Task::perform(
-                            async move {
-                                updater::verify_checksum(&exe_path, &url).await?;
-                                Ok(exe_path)
-                            },
-                            |result| Message::Update(UpdateMessage::VerifyComplete(result)),
-                        );
-                    } else {
-                        self.update_banner = UpdateBannerState::Ready(path);
-                    }
+    fn start_hosting(&mut self) -> Result<(), String> {
+        self.announced_address = None;
+        let mut state = HostState::new();
+        state.port_input = self.settings.host.port.to_string();
+        let result = resolve_bind_address(&self.settings.host.bind_address, self.tailscale_status.ip.as_deref());
+        match &result {
+            Ok(plan) => {
+                if let Some(warning) = &plan.warning {
+                    tracing::warn!("{warning}");
                 }
-                UpdateMessage::VerifyComplete(result) => match result {
-                    Ok(path) => {
-                        self.update_banner = UpdateBannerState::Ready(path);
-                    }
-                    Err(e) => {
-                        self.update_banner = UpdateBannerState::Error(e);
-                    }
-                },
-                UpdateMessage::ApplyAndRestart => {
-                    if let UpdateBannerState::Ready(ref path) = self.update_banner {
-                        let path = path.clone();
-                        self.update_banner = UpdateBannerState::Applying;
-                        if let Err(e) = updater::apply_update(&path) {
-                            self.update_banner = UpdateBannerState::Error(e);
-                        } else {
-                            std::process::exit(0);
+                state.bind_warning = plan.warning.clone();
+                let access_code = if self.settings.host.require_code {
+                    match guard::generate_code() {
+                        Ok(code) => Some(code),
+                        Err(e) => {
+                            state.status = HostStatus::Error(e.clone());
+                            self.screen = Screen::Hosting(state);
+                            return Err(e);
                         }
                     }
+                } else {
+                    None
+                };
+                state.access_code = access_code.clone();
+                self.host.start(plan.addr, access_code);
+            }
+            Err(e) => state.status = HostStatus::Error(e.clone()),
+        }
+        self.screen = Screen::Hosting(state);
+        result.map(|_| ())
+    }
+
+    fn stop_hosting(&mut self) -> Task<Message> {
+        if let Screen::Hosting(state) = &mut self.screen {
+            state.status = HostStatus::Stopping;
+        }
+        self.host.stop();
+        let stop = Task::perform(
+            async { tokio::time::sleep(std::time::Duration::from_secs(1)).await },
+            |_| Message::StopComplete,
+        );
+        Task::batch([self.leave_tray(), stop])
+    }
+
+    /// Shows the main window again after "Hide to Tray"; the icon goes away
+    /// with its subscription.
+    fn leave_tray(&mut self) -> Task<Message> {
+        if !self.in_tray {
+            return Task::none();
+        }
+        self.in_tray = false;
+        Task::batch([
+            iced::window::set_mode(self.main_window, iced::window::Mode::Windowed),
+            iced::window::gain_focus(self.main_window),
+        ])
+    }
+
+    fn start_connecting(&mut self, profile: ConnectionProfile, access_code: Option<SecretString>) {
+        self.client.start(profile.host_ip.clone(), profile.port, access_code);
+        self.last_timeline = None;
+        self.size_override = None;
+        self.screen = Screen::Connecting(ConnectingState::new(std::time::Instant::now()));
+        self.remember_profile(profile);
+    }
+
+    fn is_idle(&self) -> bool {
+        !self.host.is_running()
+            && !self.client.is_active()
+            && matches!(self.screen, Screen::ModeSelect(_) | Screen::Login(_) | Screen::Error(_))
+    }
+
+    fn status_label(&self) -> &'static str {
+        match self.screen {
+            Screen::TailscaleSetup(_) => "tailscale-setup",
+            Screen::ModeSelect(_) | Screen::Settings(_) | Screen::Logs(_) => "idle",
+            Screen::Login(_) => "login",
+            Screen::Connecting(_) => "connecting",
+            Screen::RebootWait(_) => "waiting-for-host",
+            Screen::Hosting(_) => "hosting",
+            Screen::Viewer(_) => "connected",
+            Screen::Error(_) => "error",
+        }
+    }
+
+    fn handle_control(&mut self, command: &ControlCommand) -> (ControlResponse, Task<Message>) {
+        match command {
+            ControlCommand::Status => (ControlResponse::Ok(self.status_label().to_string()), Task::none()),
+            ControlCommand::StartHost => {
+                if !self.is_idle() {
+                    return (ControlResponse::Error(format!("cannot start hosting while {}", self.status_label())), Task::none());
                 }
-                UpdateMessage::Dismiss => {
-                    self.update_banner = UpdateBannerState::Dismissed;
-                }
-            },
-            Message::ModeSelect(msg) => match msg {
-                ModeSelectMessage::ConnectSelected => {
-                    self.screen = Screen::Login(LoginState::new());
-                }
-                ModeSelectMessage::HostSelected => {
-                    self.hosting = true;
-                    self.screen = Screen::Hosting(HostState::new());
-                }
-            },
-            Message::Login(msg) => {
-                let is_back = matches!(msg, LoginMessage::BackToModeSelect);
-                if is_back {
-                    self.screen = self.mode_select_screen();
-                    return Task::none();
-                }
-                if let Screen::Login(state) = &mut self.screen
-                    && let Some(profile) = state.update(msg)
-                {
-                    self.connect_host = Some(profile.host_ip.clone());
-                    self.connect_port = profile.port;
-                    self.connecting = true;
-                    self.screen = Screen::Connecting;
+                match self.start_hosting() {
+                    Ok(()) => (ControlResponse::Ok("hosting".to_string()), Task::none()),
+                    Err(e) => (ControlResponse::Error(e), Task::none()),
                 }
             }
-            Message::Host(msg) => match msg {
-                HostMessage::CopyUrl => {
-                    if let Screen::Hosting(state) = &mut self.screen {
-                        state.copied = true;
-                        if let Some(ref ip) = self.tailscale_status.ip {
-                            let addr = format!("{ip}:{}", DEFAULT_PORT);
-                            return iced::clipboard::write(addr);
-                        }
-                    }
+            ControlCommand::StopHost => {
+                if !self.host.is_running() {
+                    return (ControlResponse::Error("not hosting".to_string()), Task::none());
                 }
-                HostMessage::StopHosting => {
-                    if let Screen::Hosting(state) = &mut self.screen {
-                        state.status = HostStatus::Stopping;
-                    }
-                    self.hosting = false;
-                    return Task::perform(
-                        async { tokio::time::sleep(std::time::Duration::from_secs(1)).await },
-                        |_| Message::StopComplete,
-                    );
+                (ControlResponse::Ok("stopping".to_string()), self.stop_hosting())
+            }
+            ControlCommand::GetTunnelUrl => match &self.screen {
+                Screen::Hosting(HostState { tunnel_url: Some(url), .. }) => {
+                    (ControlResponse::Ok(url.clone()), Task::none())
                 }
+                Screen::Hosting(_) => (ControlResponse::Error("server is still starting".to_string()), Task::none()),
+                _ => (ControlResponse::Error("not hosting".to_string()), Task::none()),
             },
-            Message::NetworkEvent(event) => match event {
-                NetworkEvent::Listening { port } => {
-                    if let Screen::Hosting(state) = &mut self.screen {
-                        if let Some(ref ip) = self.tailscale_status.ip {
-                            state.tunnel_url = Some(format!("{ip}:{port}"));
-                        } else {
-                            state.tunnel_url = Some(format!("0.0.0.0:{port}"));
-                        }
-                        state.status = HostStatus::Active;
-                    }
+            ControlCommand::Connect(name) => {
+                if !self.is_idle() {
+                    return (ControlResponse::Error(format!("cannot connect while {}", self.status_label())), Task::none());
                 }
-                NetworkEvent::ClientConnected => {
-                    if let Screen::Hosting(state) = &mut self.screen {
-                        state.status = HostStatus::Active;
+                let profile = self
+                    .profiles
+                    .profiles
+                    .iter()
+                    .find(|p| p.display_name.eq_ignore_ascii_case(name))
+                    .or_else(|| self.profiles.find(name))
+                    .cloned();
+                match profile {
+                    Some(profile) => {
+                        let addr = profile.server_addr();
+                        self.start_connecting(ConnectionProfile { enabled: true, ..profile }, None);
+                        (ControlResponse::Ok(format!("connecting to {addr}")), Task::none())
                     }
+                    None => (ControlResponse::Error(format!("no saved profile named {name}")), Task::none()),
                 }
-                NetworkEvent::Connected(handle) => {
-                    self.connection_handle = Some(handle);
-                    self.screen = Screen::Viewer(ViewerState::new(1, 1));
-                }
-                NetworkEvent::Frame { width, height, pixels } => {
-                    if let Screen::Viewer(state) = &mut self.screen {
-                        state.update_frame(width, height, pixels);
-                    }
-                }
-                NetworkEvent::ClientDisconnected => {
-                    if let Screen::Hosting(state) = &mut self.screen {
-                        state.client_addr = None;
-                        state.connected_since = None;
-                        state.status = HostStatus::Active;
-                    }
-                }
-                NetworkEvent::LatencyUpdate { rtt_ms } => {
-                    if let Screen::Viewer(state) = &mut self.screen {
-                        state.update_latency(rtt_ms);
-                    }
-                }
-                NetworkEvent::ClientInfo { addr } => {
-                    if let Screen::Hosting(state) = &mut self.screen {
-                        state.client_addr = Some(addr);
-                        state.connected_since = Some(std::time::Instant::now());
-                    }
-                }
-                NetworkEvent::Error(e) => {
-                    self.connecting = false;
-                    self.hosting = false;
-                    self.connection_handle = None;
-                    self.connect_host = None;
-                    self.screen = Screen::Error(e);
+            }
+        }
+    }
+
+    fn unlock(&mut self) {
+        self.lock = None;
+        self.last_activity = std::time::Instant::now();
+    }
+
+    fn toggle_presentation(&mut self) -> Task<Message> {
+        if self.presentation.is_some() {
+            return self.close_presentation();
+        }
+        let (window, open) = iced::window::open(iced::window::Settings {
+            size: iced::Size::new(1280.0, 720.0),
+            decorations: false,
+            ..Default::default()
+        });
+        self.presentation = Some(Presentation { window, fullscreen: false });
+        open.discard()
+    }
+
+    fn close_presentation(&mut self) -> Task<Message> {
+        match self.presentation.take() {
+            Some(presentation) => iced::window::close(presentation.window),
+            None => Task::none(),
+        }
+    }
+
+    fn emit(&self, event: Event) {
+        if self.json_events {
+            event.print();
+        }
+    }
+
+    /// Ends the viewer session, telling the host. The caller picks the next screen.
+    fn disconnect(&mut self) -> Task<Message> {
+        if self.client.handle().is_some() {
+            self.record_session(true);
+            self.emit(Event::Disconnected { side: Side::Viewer, reason: None });
+        }
+        self.save_viewer_prefs();
+        let close_presentation = self.close_presentation();
+        if let Some(handle) = self.client.end() {
+            drop(tokio::spawn(async move {
+                let _ = handle.send_input(ProtocolMessage::Disconnect).await;
+            }));
+        }
+        self.recorder = None;
+        crate::logging::set_quiet(false);
+        let leave_fullscreen = self.leave_fullscreen();
+        Task::batch([close_presentation, leave_fullscreen])
+    }
+
+    /// Closes the viewer's timeline with `reason` and keeps it for the error screen.
+    fn end_timeline(&mut self, reason: &str) {
+        if let Screen::Viewer(state) = &mut self.screen {
+            state.timeline.record(reason);
+            self.last_timeline = Some(state.timeline.to_text());
+        }
+    }
+
+    fn leave_fullscreen(&self) -> Task<Message> {
+        match &self.screen {
+            Screen::Viewer(state) if state.fullscreen => {
+                iced::window::set_mode(self.main_window, iced::window::Mode::Windowed)
+            }
+            _ => Task::none(),
+        }
+    }
+
+    /// The other end of whichever session is live: the host we view, or the
+    /// viewer connected to us.
+    fn peer_handle(&self) -> Option<&ConnectionHandle> {
+        self.client.handle().or(self.host.viewer())
+    }
+
+    /// Clears `secret` from this machine's clipboard after `secs`, unless
+    /// something else was copied by then.
+    fn hold_secret(&mut self, secret: SecretString, secs: u16) -> Task<Message> {
+        self.clipboard.ignore(secret.expose());
+        let id = self.clipboard_secret.as_ref().map_or(1, |(id, _)| id + 1);
+        self.clipboard_secret = Some((id, secret));
+        Task::perform(tokio::time::sleep(std::time::Duration::from_secs(secs.into())), move |_| {
+            Message::ClipboardSecretExpired(id)
+        })
+    }
+
+    fn send_to_peer(&self, msg: ProtocolMessage) -> Task<Message> {
+        match self.peer_handle() {
+            Some(handle) => {
+                let handle = handle.clone();
+                Task::perform(async move { handle.send_input(msg).await }, Message::InputSent)
+            }
+            None => Task::none(),
+        }
+    }
+
+    /// Frame rate and JPEG quality the viewer asks for, from the saver and the cap.
+    fn send_delivery(&self, bandwidth_saver: bool) -> Task<Message> {
+        let (fps, quality) = self.settings.general.delivery(bandwidth_saver);
+        self.send_to_peer(ProtocolMessage::SetFrameRate(fps))
+            .chain(self.send_to_peer(ProtocolMessage::SetQuality(quality)))
+    }
+
+    fn connected_profile(&self) -> Option<&ConnectionProfile> {
+        self.client.host().and_then(|host| self.profiles.find(host))
+    }
+
+    /// The connected profile, if its graphics options can be applied: the
+    /// host must be new enough, as older ones drop unknown messages.
+    fn graphics_options(&self) -> Option<&ConnectionProfile> {
+        let Screen::Viewer(state) = &self.screen else { return None };
+        state.host_info.as_ref().filter(|info| info.graphics_options())?;
+        self.connected_profile()
+    }
+
+    /// Colour mode for when greyscale is off.
+    fn color_mode(&self) -> ColorMode {
+        self.graphics_options().map_or(ColorMode::Full, |p| p.color_depth.color_mode())
+    }
+
+    /// The last session's profile. The saved copy is preferred: it has the
+    /// unlock password and the viewer settings the session ended with.
+    fn last_profile(&self) -> Option<ConnectionProfile> {
+        let (last, _) = self.last_session.as_ref()?;
+        Some(self.profiles.find(&last.host_ip).cloned().unwrap_or_else(|| last.clone()))
+    }
+
+    /// The host's last working resolution, else the app setting.
+    fn start_resolution(&self) -> Resolution {
+        self.connected_profile()
+            .and_then(|p| p.last_good.as_ref())
+            .map_or(self.settings.general.resolution, |r| r.resolution)
+    }
+
+    /// Remembers on the host's profile whether the session's settings worked.
+    fn record_session(&mut self, worked: bool) {
+        let Some(mut profile) = self.connected_profile().cloned() else { return };
+        let record = SessionRecord {
+            resolution: match &self.screen {
+                Screen::Viewer(state) => state.resolution,
+                _ => self.start_resolution(),
+            },
+            keyboard_layout: profile.keyboard_layout.clone(),
+        };
+        let changed = if worked { profile.record_success(record) } else { profile.record_failure(record) };
+        if changed {
+            self.remember_profile(profile);
+        }
+    }
+
+    fn save_viewer_prefs(&mut self) {
+        if let Screen::Viewer(state) = &self.screen
+            && let Some(profile) = self.connected_profile()
+            && profile.viewer != state.prefs()
+        {
+            let profile = ConnectionProfile {
+                viewer: state.prefs(),
+                ..profile.clone()
+            };
+            self.remember_profile(profile);
+        }
+    }
+
+    fn remember_profile(&mut self, profile: ConnectionProfile) {
+        self.profiles.upsert(profile);
+        if let Err(e) = self.profiles.save(&ProfileStore::default_path()) {
+            tracing::warn!("Failed to save profiles: {e}");
+        }
+    }
+
+    fn save_known_hosts(&self) {
+        if let Err(e) = self.known_hosts.save(&KnownHosts::default_path()) {
+            tracing::warn!("Failed to save known hosts: {e}");
+        }
+    }
+
+    fn import_profiles(&mut self, path: &str) -> String {
+        let path = if path.trim().is_empty() {
+            match crate::config::import::default_ssh_config_path() {
+                Some(p) => p,
+                None => return "No home directory to look for ~/.ssh/config".to_string(),
+            }
+        } else {
+            std::path::PathBuf::from(path.trim())
+        };
+
+        match crate::config::import::import_file(&path) {
+            Ok(imported) => {
+                let found = imported.len();
+                let added = self.profiles.merge(imported);
+                if let Err(e) = self.profiles.save(&ProfileStore::default_path()) {
+                    return format!("Imported {added} hosts but saving failed: {e}");
                 }
-                NetworkEvent::Stopped => {
-                    if self.connecting {
-                        self.connecting = false;
-                        self.connection_handle = None;
-                        self.connect_host = None;
-                        self.screen = Screen::Error("Connection closed".to_string());
-                    }
+                format!("Imported {added} of {found} hosts from {}", path.display())
+            }
+            Err(e) => format!("Import failed: {e}"),
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::TailscaleCheck(status) => self.on_tailscale_check(status),
+            Message::TailscaleSetup(msg) => self.on_tailscale_setup(msg),
+            Message::UpdateCheckResult(opt) => {
+                if let Some(release) = opt {
+                    self.update_banner = UpdateBannerState::Available(release);
                 }
-            },
-            Message::Viewer(msg) => {
-                if let Screen::Viewer(_state) = &mut self.screen {
-                    match &msg {
-                        ViewerMessage::Disconnect => {
-                            if let Some(handle) = &self.connection_handle {
-                                let handle = handle.clone();
-                                drop(tokio::spawn(async move {
-                                    let _ = handle.send_input(ProtocolMessage::Disconnect).await;
-                                }));
-                            }
-                            self.connecting = false;
-                            self.connection_handle = None;
-                            self.connect_host = None;
-                            self.screen = Screen::Login(LoginState::new());
-                        }
-                        ViewerMessage::MouseMoved(point) => {
-                            if let Some(handle) = &self.connection_handle {
-                                let handle = handle.clone();
-                                let x = point.x as u16;
-                                let y = point.y as u16;
-                                return Task::perform(
-                                    async move {
-                                        handle.send_input(ProtocolMessage::MouseMove { x, y }).await
-                                    },
-                                    Message::InputSent,
-                                );
-                            }
-                        }
-                        ViewerMessage::MousePressed(btn) => {
-                            if let Some(protocol_btn) = crate::input_handler::translate::mouse_button_to_protocol(btn)
-                                && let Some(handle) = &self.connection_handle
-                            {
-                                let handle = handle.clone();
-                                return Task::perform(
-                                    async move {
-                                        handle.send_input(ProtocolMessage::MouseButton {
-                                            button: protocol_btn,
-                                            pressed: true,
-                                        }).await
-                                    },
-                                    Message::InputSent,
-                                );
-                            }
-                        }
-                        ViewerMessage::MouseReleased(btn) => {
-                            if let Some(protocol_btn) = crate::input_handler::translate::mouse_button_to_protocol(btn)
-                                && let Some(handle) = &self.connection_handle
-                            {
-                                let handle = handle.clone();
-                                return Task::perform(
-                                    async move {
-                                        handle.send_input(ProtocolMessage::MouseButton {
-                                            button: protocol_btn,
-                                            pressed: false,
-                                        }).await
-                                    },
-                                    Message::InputSent,
-                                );
-                            }
-                        }
-                        ViewerMessage::MouseWheel(delta) => {
-                            if let Some(handle) = &self.connection_handle {
-                                let handle = handle.clone();
-                                let d = *delta as i16;
-                                return Task::perform(
-                                    async move {
-                                        handle.send_input(ProtocolMessage::MouseScroll {
-                                            delta_x: 0,
-                                            delta_y: d,
-                                        }).await
-                                    },
-                                    Message::InputSent,
-                                );
-                            }
-                        }
-                        ViewerMessage::KeyPressed(key) => {
-                            if let Some(keycode) = iced_key_to_keycode(key)
-                                && let Some(handle) = &self.connection_handle
-                            {
-                                let handle = handle.clone();
-                                return Task::perform(
-                                    async move {
-                                        handle.send_input(ProtocolMessage::KeyEvent {
-                                            keycode,
-                                            pressed: true,
-                                        }).await
-                                    },
-                                    Message::InputSent,
-                                );
-                            }
-                        }
-                        ViewerMessage::KeyReleased(key) => {
-                            if let Some(keycode) = iced_key_to_keycode(key)
-                                && let Some(handle) = &self.connection_handle
-                            {
-                                let handle = handle.clone();
-                                return Task::perform(
-                                    async move {
-                                        handle.send_input(ProtocolMessage::KeyEvent {
-                                            keycode,
-                                            pressed: false,
-                                        }).await
-                                    },
-                                    Message::InputSent,
-                                );
-                            }
-                        }
-                    }
+                Task::none()
+            }
+            Message::UpdateTrialPassed(version) => {
+                updater::confirm_update(&version);
+                Task::none()
+            }
+            Message::Update(msg) => self.on_update_banner(msg),
+            Message::Crash(msg) => self.on_crash(msg),
+            Message::ModeSelect(msg) => self.on_mode_select(msg),
+            Message::Tray(event) => self.on_tray(event),
+            Message::Settings(msg) => self.on_settings(msg),
+            Message::Logs(msg) => self.on_logs(msg),
+            Message::Login(msg) => self.on_login(msg),
+            Message::Host(msg) => self.on_host(msg),
+            Message::NetworkEvent(role, event) => self.on_network_event(role, event),
+            Message::Viewer(msg) => self.on_viewer(msg),
+            Message::Control(request) => {
+                let (response, task) = self.handle_control(&request.command);
+                request.reply(response);
+                task
+            }
+            Message::NotifySent(result) => {
+                if let Err(e) = result {
+                    tracing::warn!("Host address notification failed: {e}");
                 }
+                Task::none()
             }
+            Message::MetricsError(e) => {
+                tracing::warn!("{e}");
+                Task::none()
+            }
+            Message::WindowResized(id, size) => self.on_window_resized(id, size),
+            Message::ViewportChanged(size) => self.on_viewport_changed(size),
+            Message::WindowClosed(id) => self.on_window_closed(id),
+            Message::MainWindowFocus(focused) => self.on_main_window_focus(focused),
+            Message::Lock(msg) => self.on_lock(msg),
+            Message::UserActivity => {
+                self.last_activity = std::time::Instant::now();
+                Task::none()
+            }
+            Message::LockTick => self.on_lock_tick(),
+            Message::PresentationDrag => self.on_presentation_drag(),
+            Message::PresentationToggleFullscreen => self.on_presentation_toggle_fullscreen(),
+            Message::PresentationClose => self.close_presentation(),
+            Message::ClipboardPoll => self.on_clipboard_poll(),
+            Message::ClipboardFilesRead(result) => self.on_clipboard_files_read(result),
+            Message::ClipboardRead(text) => self.on_clipboard_read(text),
+            Message::ClipboardSecretExpired(id) => self.on_clipboard_secret_expired(id),
+            Message::ClipboardSecretRead(text) => self.on_clipboard_secret_read(text),
             Message::StopComplete => {
                 self.screen = self.mode_select_screen();
+                Task::none()
             }
-            Message::CopyError => {
-                if let Screen::Error(ref e) = self.screen {
-                    return iced::clipboard::write(e.clone());
+            Message::CopyError => self.copy_error(),
+            Message::PasteFileRead(text) => self.on_paste_file_read(text),
+            Message::TypeClipboardRead(text) => self.on_type_clipboard_read(text),
+            Message::IdleDisconnect => self.on_idle_disconnect(),
+            Message::RestartSent(result) => self.on_restart_sent(result),
+            Message::Connecting(msg) => self.on_connecting(msg),
+            Message::RebootWait(msg) => self.on_reboot_wait(msg),
+            Message::OpenLogFolder => {
+                if let Err(e) = logging::open_folder(&logging::log_dir()) {
+                    tracing::warn!("{e}");
                 }
+                Task::none()
             }
+            Message::Reconnect => self.reconnect(),
             Message::BackToModeSelect => {
-                self.connecting = false;
-                self.hosting = false;
-                self.connection_handle = None;
-                self.connect_host = None;
+                self.client.end();
+                self.host.stop();
                 self.screen = self.mode_select_screen();
+                Task::none()
             }
-            Message::InputSent(_) => {}
+            Message::LinkTested(result) => self.on_link_tested(result),
+            Message::PeerPathChecked(host, path) => self.on_peer_path_checked(host, path),
+            Message::HostKeyChecked(host, key) => self.on_host_key_checked(host, key),
+            Message::FolderShared(result) => self.on_folder_shared(result),
+            Message::InputSent(_) => Task::none(),
+        }
+    }
+
+    pub fn title(&self, window: iced::window::Id) -> String {
+        if self.presentation.as_ref().is_some_and(|p| p.window == window) {
+            "Rust RDP - Presentation".to_string()
+        } else {
+            "Rust RDP".to_string()
+        }
+    }
+
+    fn presentation_view(&self) -> Element<'_, Message> {
+        match &self.screen {
+            Screen::Viewer(state) => state.mirror_view(
+                Message::PresentationDrag,
+                Message::PresentationToggleFullscreen,
+                Message::PresentationClose,
+            ),
+            _ => container(text("No active session").size(18).color(TEXT_SECONDARY))
+                .center_x(Fill)
+                .center_y(Fill)
+                .into(),
         }
-        Task::none()
     }
 
-    pub fn view(&self) -> Element<'_, Message> {
+    pub fn view(&self, window: iced::window::Id) -> Element<'_, Message> {
+        let is_presentation = self.presentation.as_ref().is_some_and(|p| p.window == window);
+        if let Some(lock) = &self.lock {
+            if is_presentation {
+                return container(text("Locked").size(18).color(TEXT_SECONDARY))
+                    .center_x(Fill)
+                    .center_y(Fill)
+                    .into();
+            }
+            return lock.view(&self.settings.lock).map(Message::Lock);
+        }
+        if is_presentation {
+            return self.presentation_view();
+        }
+
         let banner = update_banner_view(&self.update_banner).map(Message::Update);
 
         let screen_content: Element<'_, Message> = match &self.screen {
             Screen::TailscaleSetup(state) => state.view().map(Message::TailscaleSetup),
             Screen::ModeSelect(state) => state.view().map(Message::ModeSelect),
+            Screen::Settings(state) => state.view().map(Message::Settings),
+            Screen::Logs(state) => state.view().map(Message::Logs),
             Screen::Login(state) => state.view().map(Message::Login),
-            Screen::Connecting => {
-                let inner = column![
-                    text("Connecting...").size(24).color(TEXT_PRIMARY),
-                    text("Establishing connection via Tailscale...").size(14).color(TEXT_SECONDARY),
-                ]
-                .spacing(12)
-                .align_x(Center);
-
-                let card = container(inner)
-                    .style(card_container_style)
-                    .padding(40)
-                    .max_width(400);
-
-                container(card)
-                    .center_x(Fill)
-                    .center_y(Fill)
-                    .into()
-            }
+            Screen::Connecting(state) => state.view(self.client.attempt()).map(Message::Connecting),
+            Screen::RebootWait(state) => state.view().map(Message::RebootWait),
             Screen::Hosting(state) => state.view().map(Message::Host),
             Screen::Viewer(state) => state.view().map(Message::Viewer),
             Screen::Error(e) => {
@@ -543,6 +816,10 @@ impl App {
                         .on_press(Message::CopyError)
                         .style(secondary_button_style)
                         .padding([10, 20]),
+                    button("Open log folder")
+                        .on_press(Message::OpenLogFolder)
+                        .style(secondary_button_style)
+                        .padding([10, 20]),
                     button("Back")
                         .on_press(Message::BackToModeSelect)
                         .style(secondary_button_style)
@@ -550,13 +827,29 @@ impl App {
                 ]
                 .spacing(12)
                 .align_y(Center);
+                let buttons = if self.last_session.is_some() {
+                    buttons.push(
+                        button("Reconnect")
+                            .on_press(Message::Reconnect)
+                            .style(primary_button_style)
+                            .padding([10, 20]),
+                    )
+                } else {
+                    buttons
+                };
 
-                let inner = column![
-                    text("Error").size(28).color(DANGER),
-                    error_text,
-                    buttons,
-                ]
-                .spacing(20)
+                let mut inner = column![text("Error").size(28).color(DANGER), error_text].spacing(20);
+                if let Some(timeline) = self.last_timeline.as_deref().filter(|t| !t.is_empty()) {
+                    inner = inner.push(
+                        column![
+                            text("Session timeline").size(14).color(TEXT_PRIMARY),
+                            scrollable(text(timeline).size(12).color(TEXT_SECONDARY)).height(160),
+                        ]
+                        .spacing(8),
+                    );
+                }
+                let inner = inner
+                    .push(buttons)
                 .align_x(Center);
 
                 let card = container(inner)
@@ -571,39 +864,144 @@ impl App {
             }
         };
 
-        column![banner, screen_content].into()
+        column![banner, self.crash_banner.view().map(Message::Crash), screen_content].into()
     }
 
     pub fn subscription(&self) -> Subscription<Message> {
-        let host_sub = if self.hosting {
-            let bind_addr = self.tailscale_status.ip.clone().unwrap_or_else(|| "0.0.0.0".to_string());
-            host_server_subscription(bind_addr, DEFAULT_PORT).map(Message::NetworkEvent)
+        let host_sub = if let (Some(session), Some(bind)) = (self.host.id(), self.host.bind()) {
+            host_server_subscription(
+                session,
+                bind,
+                self.settings.host.port,
+                self.settings.general.capture_fps(),
+                self.settings.host.shared_display,
+                self.host.access_code().cloned(),
+                self.settings.host.allow_restart,
+            )
+                .map(|event| Message::NetworkEvent(Role::Host, event))
         } else {
             Subscription::none()
         };
 
-        let client_sub = if self.connecting {
-            if let Some(ref host) = self.connect_host {
-                access_client_subscription(host.clone(), self.connect_port)
-                    .map(Message::NetworkEvent)
-            } else {
-                Subscription::none()
-            }
+        let client_sub = if let Some(target) = self.client.target() {
+            let layout = match self.connected_profile().and_then(|p| p.keyboard_layout.as_deref()) {
+                Some(setting) => crate::input_handler::layout::hello_layout(setting),
+                None => self.settings.general.hello_layout(),
+            };
+            access_client_subscription(
+                target.id,
+                target.host.clone(),
+                target.port,
+                layout,
+                target.access_code.clone(),
+            )
+                .map(|event| Message::NetworkEvent(Role::Client, event))
         } else {
             Subscription::none()
         };
 
         let keyboard_sub = match &self.screen {
-            Screen::Viewer(_) => iced::keyboard::listen()
-                .map(|event| match event {
-                    iced::keyboard::Event::KeyPressed { key, .. } => {
-                        Message::Viewer(ViewerMessage::KeyPressed(key))
-                    }
-                    iced::keyboard::Event::KeyReleased { key, .. } => {
-                        Message::Viewer(ViewerMessage::KeyReleased(key))
-                    }
-                    iced::keyboard::Event::ModifiersChanged(_) => Message::InputSent(Ok(())),
-                }),
+            // Typing into the viewer's own prompts must not reach the host.
+            Screen::Viewer(state) if self.lock.is_none() && state.code_prompt.is_none()
+                && state.credential.is_none()
+                && state.redaction.is_none() => {
+                iced::keyboard::listen()
+                    .map(|event| match event {
+                        iced::keyboard::Event::KeyPressed { key, modifiers, .. }
+                            if key == iced::keyboard::Key::Named(iced::keyboard::key::Named::Enter)
+                                && modifiers.control()
+                                && modifiers.alt() =>
+                        {
+                            Message::Viewer(ViewerMessage::ToggleFullscreen)
+                        }
+                        iced::keyboard::Event::KeyPressed { key, .. } => {
+                            Message::Viewer(ViewerMessage::KeyPressed(key))
+                        }
+                        iced::keyboard::Event::KeyReleased { key, .. } => {
+                            Message::Viewer(ViewerMessage::KeyReleased(key))
+                        }
+                        iced::keyboard::Event::ModifiersChanged(_) => Message::InputSent(Ok(())),
+                    })
+            }
+            _ => Subscription::none(),
+        };
+
+        let side_buttons_sub = match &self.screen {
+            Screen::Viewer(_) if self.lock.is_none() => iced::event::listen_with(|event, _status, _window| match event {
+                iced::Event::Mouse(iced::mouse::Event::ButtonPressed(
+                    button @ (iced::mouse::Button::Back | iced::mouse::Button::Forward),
+                )) => Some(Message::Viewer(ViewerMessage::MousePressed(button))),
+                iced::Event::Mouse(iced::mouse::Event::ButtonReleased(
+                    button @ (iced::mouse::Button::Back | iced::mouse::Button::Forward),
+                )) => Some(Message::Viewer(ViewerMessage::MouseReleased(button))),
+                _ => None,
+            }),
+            _ => Subscription::none(),
+        };
+
+        let annotation_sub = match &self.screen {
+            Screen::Viewer(state) if !state.annotations.is_empty() => {
+                iced::time::every(std::time::Duration::from_millis(50))
+                    .map(|_| Message::Viewer(ViewerMessage::AnnotationTick))
+            }
+            _ => Subscription::none(),
+        };
+
+        let recording_sub = match &self.screen {
+            Screen::Viewer(state) if state.recording.is_some() => iced::time::every(
+                std::time::Duration::from_secs(1) / crate::recording::RECORDING_FPS,
+            )
+            .map(|_| Message::Viewer(ViewerMessage::RecordingTick)),
+            _ => Subscription::none(),
+        };
+
+        // Keeps the host screen's durations and activity times current.
+        let host_tick_sub = match &self.screen {
+            Screen::Hosting(state) if state.client_addr.is_some() || !state.activity.is_empty() => {
+                iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::Host(HostMessage::Tick))
+            }
+            _ => Subscription::none(),
+        };
+
+        let idle_sub = match &self.screen {
+            Screen::Viewer(state) if state.idle.enabled() => iced::time::every(std::time::Duration::from_secs(1))
+                .map(|_| Message::Viewer(ViewerMessage::IdleTick)),
+            _ => Subscription::none(),
+        };
+
+        let reboot_sub = match &self.screen {
+            Screen::Connecting(_) => iced::time::every(std::time::Duration::from_millis(200))
+                .map(|_| Message::Connecting(ConnectingMessage::Tick)),
+            Screen::RebootWait(_) => {
+                iced::time::every(reboot_wait::CHECK_INTERVAL).map(|_| Message::RebootWait(RebootWaitMessage::Tick))
+            }
+            _ => Subscription::none(),
+        };
+
+        let logs_sub = match &self.screen {
+            Screen::Logs(_) => {
+                iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::Logs(LogsMessage::Refresh))
+            }
+            _ => Subscription::none(),
+        };
+
+        let main_window = self.main_window;
+        let focus_sub = match &self.screen {
+            Screen::Viewer(_) => iced::window::events().with(main_window).filter_map(|(main_window, (id, event))| {
+                if id != main_window {
+                    return None;
+                }
+                match event {
+                    iced::window::Event::Focused => Some(Message::MainWindowFocus(true)),
+                    iced::window::Event::Unfocused => Some(Message::MainWindowFocus(false)),
+                    _ => None,
+                }
+            }),
+            _ => Subscription::none(),
+        };
+
+        let resize_sub = match &self.screen {
+            Screen::Viewer(_) => iced::window::resize_events().map(|(id, size)| Message::WindowResized(id, size)),
             _ => Subscription::none(),
         };
 
@@ -620,15 +1018,75 @@ impl App {
                 Subscription::none()
             };
 
+        let control_sub = if self.settings.control.enabled {
+            control_api_subscription(self.settings.control.port, self.settings.control.token.clone())
+                .map(Message::Control)
+        } else {
+            Subscription::none()
+        };
+
+        let metrics_sub = if self.settings.metrics.enabled {
+            crate::metrics::metrics_subscription(self.settings.metrics.port).map(Message::MetricsError)
+        } else {
+            Subscription::none()
+        };
+
+        let clipboard_sub = if self.settings.clipboard.enabled && self.peer_handle().is_some() {
+            iced::time::every(std::time::Duration::from_millis(500)).map(|_| Message::ClipboardPoll)
+        } else {
+            Subscription::none()
+        };
+
+        let lock_sub = if self.settings.lock.enabled && self.lock.is_none() {
+            Subscription::batch([
+                iced::event::listen_with(|event, _status, _window| match event {
+                    iced::Event::Mouse(_) | iced::Event::Keyboard(_) | iced::Event::Touch(_) => {
+                        Some(Message::UserActivity)
+                    }
+                    _ => None,
+                }),
+                iced::time::every(std::time::Duration::from_secs(5)).map(|_| Message::LockTick),
+            ])
+        } else {
+            Subscription::none()
+        };
+
+        let window_close_sub = iced::window::close_events().map(Message::WindowClosed);
+
+        let tray_sub = if self.in_tray {
+            let tooltip = match &self.screen {
+                Screen::Hosting(state) => state.tray_tooltip(),
+                _ => "Rust RDP".to_string(),
+            };
+            tray_subscription(tooltip).map(Message::Tray)
+        } else {
+            Subscription::none()
+        };
+
         Subscription::batch([
+            window_close_sub,
+            tray_sub,
+            lock_sub,
             host_sub,
             client_sub,
             keyboard_sub,
+            side_buttons_sub,
+            resize_sub,
+            focus_sub,
+            annotation_sub,
+            host_tick_sub,
+            logs_sub,
+            reboot_sub,
+            idle_sub,
+            recording_sub,
             update_download_sub,
+            control_sub,
+            metrics_sub,
+            clipboard_sub,
         ])
     }
 
-    pub fn theme(&self) -> Theme {
+    pub fn theme(&self, _window: iced::window::Id) -> Theme {
         crate::ui::theme::app_theme()
     }
 }
@@ -0,0 +1,41 @@
+use super::*;
+
+impl App {
+    pub(super) fn on_host(&mut self, msg: HostMessage) -> Task<Message> {
+        let Screen::Hosting(state) = &mut self.screen else { return Task::none() };
+        match state.update(msg, self.settings.host.port) {
+            Some(HostAction::Copy(url)) => return iced::clipboard::write(url),
+            Some(HostAction::StopHosting) => return self.stop_hosting(),
+            Some(HostAction::HideToTray) => {
+                self.in_tray = true;
+                return iced::window::set_mode(self.main_window, iced::window::Mode::Hidden);
+            }
+            Some(HostAction::SetPort(port)) => {
+                // The server subscription is keyed by port, so this restarts it.
+                self.settings.host.port = port;
+                if let Err(e) = self.settings.save(&AppSettings::default_path()) {
+                    tracing::warn!("Failed to save settings: {e}");
+                }
+            }
+            Some(HostAction::AllowFirewall) => {
+                return Task::perform(crate::host_setup::allow_port(self.settings.host.port), |result| {
+                    Message::Host(HostMessage::FirewallAllowed(result))
+                });
+            }
+            Some(HostAction::CheckFirewall) => return self.check_firewall(),
+            Some(HostAction::AnswerControlRequest(accept)) => {
+                if let Some(reply) = self.host.take_control_reply() {
+                    return Task::perform(async move { reply.send(accept).await }, Message::InputSent);
+                }
+            }
+            None => {}
+        }
+        Task::none()
+    }
+
+    pub(super) fn check_firewall(&self) -> Task<Message> {
+        Task::perform(crate::host_setup::check_firewall(self.settings.host.port), |check| {
+            Message::Host(HostMessage::FirewallChecked(check))
+        })
+    }
+}
@@ -0,0 +1,114 @@
+use super::*;
+
+impl App {
+    pub(super) fn on_clipboard_poll(&mut self) -> Task<Message> {
+        match self.clipboard.poll_native() {
+            Some(NativeContent::Image(dib)) => {
+                let sent = dib.len() <= self.settings.clipboard.max_image_bytes();
+                if let Screen::Viewer(state) = &mut self.screen {
+                    state.timeline.record(if sent {
+                        "Clipboard image sent to host"
+                    } else {
+                        "Copied image is over the clipboard limit; not sent"
+                    });
+                }
+                if sent {
+                    return self.send_to_peer(ProtocolMessage::ClipboardImage(dib));
+                }
+                Task::none()
+            }
+            Some(NativeContent::Files(paths)) => {
+                let limit = self.settings.clipboard.max_files_bytes();
+                Task::perform(clipboard::read_files(paths, limit), Message::ClipboardFilesRead)
+            }
+            None => iced::clipboard::read().map(Message::ClipboardRead),
+        }
+    }
+
+    pub(super) fn on_clipboard_files_read(&mut self, result: Result<Vec<(String, Vec<u8>)>, String>) -> Task<Message> {
+        match result {
+            Ok(files) if !files.is_empty() => {
+                if let Screen::Viewer(state) = &mut self.screen {
+                    state.timeline.record(format!("{} copied file(s) sent to host", files.len()));
+                }
+                return self.send_to_peer(ProtocolMessage::ClipboardFiles(files));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!("Copied files not sent: {e}");
+                if let Screen::Viewer(state) = &mut self.screen {
+                    state.timeline.record(format!("Copied files not sent: {e}"));
+                }
+            }
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_clipboard_read(&mut self, text: Option<String>) -> Task<Message> {
+        let Some(text) = self.clipboard.local_changed(text) else { return Task::none() };
+        if let Screen::Viewer(state) = &mut self.screen {
+            state.timeline.record("Clipboard sent to host");
+            if !state.password_mode {
+                state.clipboard_history.record(HistoryDirection::Sent, text.clone());
+            }
+        }
+        self.send_to_peer(ProtocolMessage::ClipboardText(text))
+    }
+
+    pub(super) fn on_clipboard_secret_expired(&self, id: u64) -> Task<Message> {
+        if self.clipboard_secret.as_ref().is_some_and(|(held, _)| *held == id) {
+            return iced::clipboard::read().map(Message::ClipboardSecretRead);
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_clipboard_secret_read(&mut self, text: Option<String>) -> Task<Message> {
+        let Some((_, secret)) = self.clipboard_secret.take() else {
+            return Task::none();
+        };
+        // Left alone when something else was copied since.
+        if text.is_some_and(|text| text == secret.expose()) {
+            return iced::clipboard::write(String::new());
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_paste_file_read(&mut self, text: Option<String>) -> Task<Message> {
+        let Screen::Viewer(state) = &mut self.screen else {
+            return Task::none();
+        };
+        let Some(text) = text.filter(|t| !t.is_empty()) else {
+            state.paste_file.note = Some("The clipboard has no text to send".to_string());
+            return Task::none();
+        };
+        let Some(handle) = self.client.handle().cloned() else {
+            return Task::none();
+        };
+        let name = state.paste_file.file_name();
+        state.paste_file.progress = Some((0, text.len() as u64));
+        state.timeline.record(format!("Sending {name} to the host ({} KB)", text.len().div_ceil(1024)));
+        let folder = state.paste_file.folder.trim().to_string();
+        Task::run(paste::send(handle, folder, name, text), |progress| {
+            Message::Viewer(ViewerMessage::PasteFile(PasteFileMessage::Progress(progress)))
+        })
+    }
+
+    pub(super) fn on_type_clipboard_read(&mut self, text: Option<String>) -> Task<Message> {
+        let Screen::Viewer(state) = &mut self.screen else {
+            return Task::none();
+        };
+        let text = text.unwrap_or_default();
+        let count = typing::keystrokes(&text).len();
+        let Some(handle) = self.client.handle().cloned().filter(|_| count > 0) else {
+            state.timeline.record("Type Clipboard: the clipboard has no text");
+            return Task::none();
+        };
+        // Often a password, so only its length goes in the timeline.
+        state.timeline.record(format!("Typing clipboard text ({count} characters)"));
+        state.typing = Some((0, count as u64));
+        let delay = std::time::Duration::from_millis(self.settings.general.type_delay_ms);
+        Task::run(typing::type_text(handle, text, delay), |progress| {
+            Message::Viewer(ViewerMessage::TypingProgress(progress))
+        })
+    }
+}
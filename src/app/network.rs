@@ -0,0 +1,398 @@
+use super::*;
+
+impl App {
+    pub(super) fn on_network_event(&mut self, role: Role, event: NetworkEvent) -> Task<Message> {
+        match event {
+            NetworkEvent::Listening { port } => {
+                if let Screen::Hosting(state) = &mut self.screen {
+                    let address = match (self.host.bind(), &self.tailscale_status.ip) {
+                        (Some(bind), Some(ip)) if bind.is_unspecified() => format!("{ip}:{port}"),
+                        (Some(bind), _) => std::net::SocketAddr::new(bind, port).to_string(),
+                        (None, _) => format!("{}:{port}", self.tailscale_status.ip.as_deref().unwrap_or("?")),
+                    };
+                    state.tunnel_url = Some(address.clone());
+                    state.status = HostStatus::Active;
+                    self.emit(Event::Listening { address: address.clone() });
+
+                    let check_firewall = self.check_firewall();
+                    if self.settings.notify.is_configured()
+                        && self.announced_address.as_deref() != Some(address.as_str())
+                    {
+                        self.announced_address = Some(address.clone());
+                        return Task::batch([
+                            check_firewall,
+                            Task::perform(
+                                crate::notify::announce_host_address(
+                                    self.settings.notify.clone(),
+                                    address,
+                                    self.tailscale_status.hostname.clone(),
+                                ),
+                                Message::NotifySent,
+                            ),
+                        ]);
+                    }
+                    return check_firewall;
+                }
+            }
+            NetworkEvent::ClientConnected => {
+                if let Screen::Hosting(state) = &mut self.screen {
+                    state.status = HostStatus::Active;
+                }
+            }
+            NetworkEvent::Connected(handle) => {
+                self.client.connected(handle);
+                let peer = self.client.host().unwrap_or_default().to_string();
+                self.emit(Event::Connected { side: Side::Viewer, peer });
+                if let Some(profile) = self.connected_profile() {
+                    let code = self.client.target().and_then(|t| t.access_code.clone());
+                    let profile = ConnectionProfile { unlock_password: SecretString::default(), ..profile.clone() };
+                    self.last_session = Some((profile, code));
+                }
+                self.clipboard.reset();
+                let (greyscale, text_boost, saver, fullscreen) = match &mut self.screen {
+                    Screen::Viewer(state) => {
+                        // Reconnected: keep the viewer and replay its settings to the host.
+                        state.reconnecting = None;
+                        state.suspended = false;
+                        state.host_info = None;
+                        state.remote_cursor = RemoteCursor::Local;
+                        state.timeline.record("Reconnected");
+                        state.reset_viewport();
+                        (state.greyscale, state.text_boost, state.bandwidth_saver, false)
+                    }
+                    _ => {
+                        let prefs = self.connected_profile().map(|p| p.viewer.clone()).unwrap_or_default();
+                        let mut state = ViewerState::new(1, 1).with_prefs(&prefs);
+                        state.can_unlock = self.connected_profile().is_some_and(|p| p.can_unlock());
+                        state.privacy = self.settings.privacy.clone();
+                        state.idle = self.settings.idle;
+                        state.resolution = self.start_resolution();
+                        if let Some(target) = self.client.target() {
+                            state.timeline.record(format!("Connected to {}:{}", target.host, target.port));
+                        }
+                        self.screen = Screen::Viewer(Box::new(state));
+                        (prefs.greyscale, 0, prefs.bandwidth_saver, prefs.fullscreen)
+                    }
+                };
+                self.record_session(true);
+                let main_window = self.main_window;
+                let mut tasks =
+                    vec![iced::window::size(main_window).map(move |size| Message::WindowResized(main_window, size))];
+                if fullscreen {
+                    tasks.push(iced::window::set_mode(main_window, iced::window::Mode::Fullscreen));
+                }
+                if let Screen::Viewer(state) = &mut self.screen {
+                    let commands = state.transfers.reconnected();
+                    tasks.push(self.transfer_commands(commands));
+                }
+                if greyscale {
+                    tasks.push(self.send_to_peer(ProtocolMessage::SetColorMode(ColorMode::Greyscale)));
+                }
+                if text_boost > 0 {
+                    tasks.push(self.send_to_peer(ProtocolMessage::SetTextBoost(text_boost)));
+                }
+                if saver || self.settings.general.viewer_max_fps > 0 {
+                    tasks.push(self.send_delivery(saver));
+                }
+                if let Some(host) = self.client.host().map(str::to_string) {
+                    let path_host = host.clone();
+                    tasks.push(Task::perform(
+                        async move {
+                            tokio::time::sleep(crate::tailscale::PATH_CHECK_DELAY).await;
+                            crate::tailscale::peer_path(path_host).await
+                        },
+                        {
+                            let host = host.clone();
+                            move |path| Message::PeerPathChecked(host, path)
+                        },
+                    ));
+                    tasks.push(Task::perform(crate::tailscale::peer_node_key(host.clone()), move |key| {
+                        Message::HostKeyChecked(host, key)
+                    }));
+                }
+                if let Some(profile) = self.connected_profile().filter(|p| p.share_folder)
+                    && let Some(handle) = self.client.handle()
+                {
+                    let root = std::path::PathBuf::from(&profile.shared_folder);
+                    tasks.push(Task::perform(
+                        crate::network::share::send_folder(handle.clone(), root),
+                        Message::FolderShared,
+                    ));
+                }
+                return Task::batch(tasks);
+            }
+            NetworkEvent::Reconnecting { attempt, max } => {
+                self.client.reconnecting(attempt, max);
+                if let Screen::Viewer(state) = &mut self.screen {
+                    state.reconnecting = Some((attempt, max));
+                    state.suspended = false;
+                    state.timeline.record(format!("Connection lost; reconnecting (attempt {attempt}/{max})"));
+                }
+            }
+            NetworkEvent::Frame { width, height, region, pixels } => {
+                if let Screen::Viewer(state) = &mut self.screen {
+                    state.waiting_for_host = false;
+                    match region {
+                        Some(rect) => state.update_region(width, height, rect, pixels),
+                        None => state.update_frame(width, height, pixels),
+                    }
+                }
+            }
+            NetworkEvent::ClientDisconnected => {
+                self.host.viewer_disconnected();
+                self.emit(Event::Disconnected { side: Side::Host, reason: None });
+                if let Screen::Hosting(state) = &mut self.screen {
+                    state.client_disconnected();
+                    state.status = HostStatus::Active;
+                }
+            }
+            NetworkEvent::Stats(stats) => {
+                if let Screen::Viewer(state) = &mut self.screen {
+                    state.update_stats(stats);
+                }
+            }
+            NetworkEvent::LatencyUpdate { rtt_ms } => {
+                if let Screen::Viewer(state) = &mut self.screen {
+                    state.update_latency(rtt_ms);
+                }
+            }
+            NetworkEvent::ClientInfo { addr, handle } => {
+                self.host.viewer_connected(handle);
+                self.emit(Event::Connected { side: Side::Host, peer: addr.clone() });
+                self.clipboard.reset();
+                if let Screen::Hosting(state) = &mut self.screen {
+                    state.client_connected(addr);
+                }
+            }
+            NetworkEvent::ControlRequested { addr, reply } => {
+                if let Screen::Hosting(state) = &mut self.screen {
+                    state.control_requested(addr);
+                    self.host.control_requested(reply);
+                }
+            }
+            NetworkEvent::HostCursor { x, y } => {
+                if let Screen::Viewer(state) = &mut self.screen {
+                    state.host_cursor = Some((x, y));
+                }
+            }
+            NetworkEvent::HostInfo(info) => {
+                let Screen::Viewer(state) = &mut self.screen else { return Task::none() };
+                state.timeline.record(format!(
+                    "Host runs {} on {}, protocol {}",
+                    info.app_version, info.os, info.protocol
+                ));
+                state.host_info = Some(info);
+                if let Some(note) = state.host_compat_note() {
+                    state.timeline.record(note);
+                }
+                let greyscale = state.greyscale;
+                let mut tasks = Vec::new();
+                if !greyscale && self.color_mode() != ColorMode::Full {
+                    tasks.push(self.send_to_peer(ProtocolMessage::SetColorMode(self.color_mode())));
+                }
+                if self.graphics_options().is_some_and(|p| !p.partial_updates) {
+                    tasks.push(self.send_to_peer(ProtocolMessage::SetPartialUpdates(false)));
+                }
+                return Task::batch(tasks);
+            }
+            NetworkEvent::SecureDesktop(secure) => {
+                if let Screen::Viewer(state) = &mut self.screen
+                    && state.secure_desktop != secure
+                {
+                    state.secure_desktop = secure;
+                    state.timeline.record(if secure {
+                        "Host switched to the secure desktop"
+                    } else {
+                        "Host left the secure desktop"
+                    });
+                }
+            }
+            NetworkEvent::Displays(displays) => {
+                if let Screen::Viewer(state) = &mut self.screen
+                    && let Some(index) = state.set_displays(displays)
+                {
+                    return self.send_to_peer(ProtocolMessage::SelectDisplay(index));
+                }
+            }
+            NetworkEvent::CursorShape(shape) => {
+                if let Screen::Viewer(state) = &mut self.screen {
+                    state.remote_cursor = RemoteCursor::from_shape(shape);
+                }
+            }
+            NetworkEvent::FileList { path, entries } => {
+                if let Screen::Viewer(state) = &mut self.screen
+                    && let Some(browser) = &mut state.file_browser
+                {
+                    browser.listed(path, entries);
+                }
+            }
+            NetworkEvent::FileChunk { name, offset, total, data } => {
+                if let Screen::Viewer(state) = &mut self.screen {
+                    let commands = state.transfers.chunk(&name, offset, total, &data);
+                    return self.transfer_commands(commands);
+                }
+            }
+            NetworkEvent::FileDigest(sha256) => {
+                if let Screen::Viewer(state) = &mut self.screen {
+                    let commands = state.transfers.digest(sha256);
+                    return self.transfer_commands(commands);
+                }
+            }
+            NetworkEvent::TransferFailed(e) => {
+                if let Screen::Viewer(state) = &mut self.screen {
+                    let commands = state.transfers.host_failed(e);
+                    return self.transfer_commands(commands);
+                }
+            }
+            NetworkEvent::FileError(e) => {
+                if let Screen::Viewer(state) = &mut self.screen
+                    && let Some(browser) = &mut state.file_browser
+                {
+                    browser.failed(e);
+                }
+            }
+            NetworkEvent::Stage(stage) => {
+                if let Screen::Connecting(state) = &mut self.screen {
+                    state.advance(stage, std::time::Instant::now());
+                }
+            }
+            NetworkEvent::Queued => {
+                if let Screen::Viewer(state) = &mut self.screen {
+                    state.waiting_for_host = true;
+                    state.timeline.record("Host is busy; waiting to be let in");
+                }
+            }
+            NetworkEvent::Suspended => {
+                if let Screen::Viewer(state) = &mut self.screen {
+                    state.suspended = true;
+                    state.timeline.record("Paused: this computer is going to sleep");
+                }
+            }
+            NetworkEvent::ClipboardText(text) => {
+                if let Screen::Viewer(state) = &mut self.screen {
+                    state.timeline.record("Clipboard received from host");
+                    state.clipboard_history.record(HistoryDirection::Received, text.clone());
+                }
+                if self.settings.clipboard.enabled
+                    && let Some(text) = self.clipboard.remote_received(text)
+                {
+                    return iced::clipboard::write(text);
+                }
+            }
+            NetworkEvent::ClipboardSecret { text, clear_after_secs } => {
+                if !self.settings.clipboard.enabled {
+                    tracing::info!("Ignored a credential from the viewer: clipboard sync is off");
+                    return Task::none();
+                }
+                let secs = clear_after_secs.clamp(*CREDENTIAL_SECS.start(), *CREDENTIAL_SECS.end());
+                let text = SecretString::from(text);
+                let write = match clipboard::write_secret(text.expose()) {
+                    Ok(()) => {
+                        self.clipboard.native_written();
+                        Task::none()
+                    }
+                    Err(e) => {
+                        tracing::debug!("Credential written as plain text: {e}");
+                        iced::clipboard::write(text.expose().to_string())
+                    }
+                };
+                return Task::batch([write, self.hold_secret(text, secs)]);
+            }
+            NetworkEvent::ClipboardImage(dib) => {
+                if !self.settings.clipboard.enabled || dib.len() > self.settings.clipboard.max_image_bytes() {
+                    return Task::none();
+                }
+                match clipboard::write_image(&dib) {
+                    Ok(()) => {
+                        self.clipboard.native_written();
+                        if let Screen::Viewer(state) = &mut self.screen {
+                            state.timeline.record("Clipboard image received from host");
+                        }
+                    }
+                    Err(e) => tracing::warn!("Could not paste the received image: {e}"),
+                }
+            }
+            NetworkEvent::ClipboardFiles(files) => {
+                let total: u64 = files.iter().map(|(_, data)| data.len() as u64).sum();
+                if !self.settings.clipboard.enabled || total > self.settings.clipboard.max_files_bytes() {
+                    return Task::none();
+                }
+                let count = files.len();
+                let result = clipboard::save_received_files(&app_data_dir().join("clipboard"), files)
+                    .and_then(|paths| clipboard::write_files(&paths));
+                match result {
+                    Ok(()) => {
+                        self.clipboard.native_written();
+                        if let Screen::Viewer(state) = &mut self.screen {
+                            state.timeline.record(format!("{count} copied file(s) received from host"));
+                        }
+                    }
+                    Err(e) => tracing::warn!("Could not paste the received files: {e}"),
+                }
+            }
+            NetworkEvent::Error(e) if role == Role::Host => {
+                self.emit(Event::Error { side: Side::Host, message: e.clone() });
+                self.host.stop();
+                // A failed host must not take down a session we are viewing.
+                if self.client.is_active() {
+                    tracing::warn!("Hosting stopped: {e}");
+                } else {
+                    self.screen = Screen::Error(e);
+                }
+            }
+            NetworkEvent::AccessDenied(reason) => {
+                self.emit(Event::Error { side: Side::Viewer, message: reason.clone() });
+                self.recorder = None;
+                self.client.end();
+                self.end_timeline(&reason);
+                // Ask for the code in place; the retry is a fresh connection.
+                if let Screen::Viewer(state) = &mut self.screen
+                    && self.last_session.is_some()
+                {
+                    state.code_prompt = Some(CodePromptState::new(reason));
+                    return Task::none();
+                }
+                return self.back_to_login_denied(reason);
+            }
+            NetworkEvent::Error(e) => {
+                self.emit(Event::Error { side: Side::Viewer, message: e.clone() });
+                self.record_session(false);
+                self.save_viewer_prefs();
+                self.recorder = None;
+                let leave_fullscreen = self.leave_fullscreen();
+                self.client.end();
+                self.end_timeline(&e);
+                self.screen = Screen::Error(e);
+                return leave_fullscreen;
+            }
+            NetworkEvent::Stopped => {
+                if role == Role::Client && self.client.is_active() {
+                    let reason = Some("Connection closed".to_string());
+                    self.emit(Event::Disconnected { side: Side::Viewer, reason });
+                    self.save_viewer_prefs();
+                    self.recorder = None;
+                    self.client.end();
+                    self.end_timeline("Connection closed");
+                    self.screen = Screen::Error("Connection closed".to_string());
+                }
+            }
+        }
+        Task::none()
+    }
+
+    /// Back to the form as it was, minus the refused code.
+    pub(super) fn back_to_login_denied(&mut self, reason: String) -> Task<Message> {
+        let leave_fullscreen = self.leave_fullscreen();
+        let mut login = LoginState {
+            known_hosts: self.known_hosts.entries.clone(),
+            access_error: Some(reason),
+            ..LoginState::with_profiles(self.profiles.profiles.clone())
+        };
+        if let Some(profile) = self.last_profile() {
+            login.fill(&profile);
+        }
+        self.last_session = None;
+        self.screen = Screen::Login(login);
+        leave_fullscreen
+    }
+}
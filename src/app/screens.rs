@@ -0,0 +1,240 @@
+use super::*;
+
+impl App {
+    pub(super) fn on_crash(&mut self, msg: CrashMessage) -> Task<Message> {
+        match msg {
+            CrashMessage::Export => {
+                self.crash_banner.exporting = true;
+                let reports = self.crash_banner.reports.clone();
+                return Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || crate::crash::export_bundle(&reports))
+                            .await
+                            .unwrap_or_else(|e| Err(e.to_string()))
+                    },
+                    |result| Message::Crash(CrashMessage::Exported(result)),
+                );
+            }
+            CrashMessage::Exported(result) => {
+                self.crash_banner.exporting = false;
+                match result {
+                    Ok(path) => {
+                        crate::crash::mark_seen(&self.crash_banner.reports);
+                        self.crash_banner.note = Some(format!("Report saved to {}", path.display()));
+                        if let Some(dir) = path.parent()
+                            && let Err(e) = crate::logging::open_folder(dir)
+                        {
+                            tracing::warn!("{e}");
+                        }
+                    }
+                    Err(e) => self.crash_banner.note = Some(format!("Export failed: {e}")),
+                }
+            }
+            CrashMessage::Dismiss => {
+                crate::crash::mark_seen(&self.crash_banner.reports);
+                self.crash_banner = CrashBannerState::default();
+            }
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_mode_select(&mut self, msg: ModeSelectMessage) -> Task<Message> {
+        match msg {
+            ModeSelectMessage::ConnectSelected => {
+                self.screen = self.login_screen();
+            }
+            ModeSelectMessage::HostSelected => {
+                // A bind error is shown on the host screen.
+                let _ = self.start_hosting();
+            }
+            ModeSelectMessage::OpenSettings => {
+                let displays = crate::capture::displays::list();
+                self.screen = Screen::Settings(SettingsState::new(&self.settings).with_displays(&displays));
+                return scan_storage();
+            }
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_tray(&mut self, event: TrayEvent) -> Task<Message> {
+        match event {
+            TrayEvent::Activate => self.leave_tray(),
+            TrayEvent::CopyUrl => self.on_host(HostMessage::CopyUrl),
+            TrayEvent::StopHosting if self.host.is_running() => self.stop_hosting(),
+            TrayEvent::StopHosting => self.leave_tray(),
+        }
+    }
+
+    pub(super) fn on_settings(&mut self, msg: SettingsMessage) -> Task<Message> {
+        let Screen::Settings(state) = &mut self.screen else {
+            return Task::none();
+        };
+        match msg {
+            SettingsMessage::Back => self.screen = self.mode_select_screen(),
+            SettingsMessage::ViewLogs => self.screen = Screen::Logs(LogsState::new(self.settings.general.log_level)),
+            SettingsMessage::CleanUp => {
+                state.update(SettingsMessage::CleanUp);
+                return Task::perform(
+                    async {
+                        tokio::task::spawn_blocking(|| storage::trim(&app_data_dir(), |_| 0))
+                            .await
+                            .unwrap_or(0)
+                    },
+                    |freed| Message::Settings(SettingsMessage::CleanedUp(freed)),
+                );
+            }
+            SettingsMessage::RollBack => {
+                let Some(backup) = &state.rollback_to else {
+                    return Task::none();
+                };
+                match updater::roll_back(backup) {
+                    Ok(()) => std::process::exit(0),
+                    Err(e) => state.error = Some(e),
+                }
+            }
+            SettingsMessage::CleanedUp(freed) => {
+                state.update(SettingsMessage::CleanedUp(freed));
+                return scan_storage();
+            }
+            SettingsMessage::Save => {
+                let mut settings = self.settings.clone();
+                let saved = state
+                    .apply(&mut settings)
+                    .and_then(|()| settings.save(&AppSettings::default_path()).map_err(|e| e.to_string()));
+                match saved {
+                    Ok(()) => {
+                        self.settings = settings;
+                        self.screen = self.mode_select_screen();
+                    }
+                    Err(e) => state.error = Some(e),
+                }
+            }
+            msg => state.update(msg),
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_logs(&mut self, msg: LogsMessage) -> Task<Message> {
+        let Screen::Logs(state) = &mut self.screen else {
+            return Task::none();
+        };
+        match msg {
+            LogsMessage::Back => self.screen = self.mode_select_screen(),
+            msg => state.update(msg),
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_login(&mut self, msg: LoginMessage) -> Task<Message> {
+        match msg {
+            LoginMessage::BackToModeSelect => self.screen = self.mode_select_screen(),
+            LoginMessage::ForgetHost(index) => {
+                if let Some(entry) = self.known_hosts.entries.get(index).cloned() {
+                    self.known_hosts.remove(&entry.host);
+                    self.save_known_hosts();
+                }
+                if let Screen::Login(state) = &mut self.screen {
+                    state.known_hosts = self.known_hosts.entries.clone();
+                }
+            }
+            LoginMessage::Reconnect => return self.reconnect(),
+            LoginMessage::CopyKnownHosts => return iced::clipboard::write(self.known_hosts.to_text()),
+            LoginMessage::TestLink => {
+                let Screen::Login(state) = &mut self.screen else { return Task::none() };
+                let host = state.host_ip.trim().to_string();
+                let port = state.port.trim().parse::<u16>().unwrap_or(DEFAULT_PORT);
+                let code = state.access_code();
+                state.link_status = Some(format!("Testing the link to {host}\u{2026}"));
+                return Task::perform(crate::probe::test_link(host, port, code), Message::LinkTested);
+            }
+            LoginMessage::Import => {
+                let path = match &self.screen {
+                    Screen::Login(state) => state.import_path.clone(),
+                    _ => return Task::none(),
+                };
+                let status = self.import_profiles(&path);
+                if let Screen::Login(state) = &mut self.screen {
+                    state.saved_profiles = self.profiles.profiles.clone();
+                    state.import_status = Some(status);
+                }
+            }
+            msg => {
+                if let Screen::Login(state) = &mut self.screen
+                    && let Some(profile) = state.update(msg)
+                {
+                    let code = state.access_code();
+                    self.start_connecting(profile, code);
+                }
+            }
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_link_tested(&mut self, result: Result<LinkReport, String>) -> Task<Message> {
+        if let Screen::Login(state) = &mut self.screen {
+            state.link_status = Some(match result {
+                Ok(report) => report.summary(),
+                Err(e) => format!("Link test failed: {e}"),
+            });
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_connecting(&mut self, msg: ConnectingMessage) -> Task<Message> {
+        match msg {
+            // Redraws the stage timer.
+            ConnectingMessage::Tick => Task::none(),
+            ConnectingMessage::Cancel => {
+                if !matches!(self.screen, Screen::Connecting(_)) {
+                    return Task::none();
+                }
+                tracing::info!("Connection attempt cancelled");
+                let task = self.disconnect();
+                self.screen = self.login_screen();
+                task
+            }
+        }
+    }
+
+    pub(super) fn on_reboot_wait(&mut self, msg: RebootWaitMessage) -> Task<Message> {
+        let Screen::RebootWait(state) = &mut self.screen else {
+            return Task::none();
+        };
+        match msg {
+            RebootWaitMessage::Tick => {
+                return Task::perform(probe::port_open(state.host.clone(), state.port), |open| {
+                    Message::RebootWait(RebootWaitMessage::Checked(open))
+                });
+            }
+            RebootWaitMessage::Checked(open) => match state.checked(open, std::time::Instant::now()) {
+                Some(RebootOutcome::Back) => {
+                    tracing::info!("{} is back after restarting; reconnecting", state.host);
+                    return Task::done(Message::Reconnect);
+                }
+                Some(RebootOutcome::GaveUp(e)) => self.screen = Screen::Error(e),
+                None => {}
+            },
+            RebootWaitMessage::Cancel => self.screen = self.login_screen(),
+        }
+        Task::none()
+    }
+
+    pub(super) fn reconnect(&mut self) -> Task<Message> {
+        if !self.client.is_active()
+            && let Some(profile) = self.last_profile()
+        {
+            let code = self.last_session.as_ref().and_then(|(_, code)| code.clone());
+            self.start_connecting(profile, code);
+        }
+        Task::none()
+    }
+
+    pub(super) fn copy_error(&self) -> Task<Message> {
+        let Screen::Error(e) = &self.screen else { return Task::none() };
+        let text = match &self.last_timeline {
+            Some(timeline) => format!("{e}\n\nSession timeline:\n{timeline}"),
+            None => e.clone(),
+        };
+        iced::clipboard::write(text)
+    }
+}
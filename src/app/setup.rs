@@ -0,0 +1,26 @@
+use super::*;
+
+impl App {
+    pub(super) fn on_tailscale_check(&mut self, status: TailscaleStatus) -> Task<Message> {
+        if status.is_running {
+            self.tailscale_status = status;
+            self.screen = self.mode_select_screen();
+            self.run_launch();
+        } else {
+            let is_installed = status.is_installed;
+            self.tailscale_status = status;
+            self.screen = Screen::TailscaleSetup(TailscaleSetupState::new(is_installed));
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_tailscale_setup(&mut self, msg: TailscaleSetupMessage) -> Task<Message> {
+        let Screen::TailscaleSetup(state) = &mut self.screen else { return Task::none() };
+        match state.update(msg) {
+            Some(SetupAction::CheckTailscale) => {
+                Task::perform(crate::tailscale::check_tailscale(), Message::TailscaleCheck)
+            }
+            None => Task::none(),
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use super::*;
+
+impl App {
+    pub(super) fn on_update_banner(&mut self, msg: UpdateMessage) -> Task<Message> {
+        match self.update_banner.update(msg) {
+            Some(UpdateAction::CheckForUpdates) => {
+                let channel = self.settings.general.update_channel;
+                Task::perform(
+                    async move { updater::check_for_update(channel).await.ok().flatten() },
+                    Message::UpdateCheckResult,
+                )
+            }
+            Some(UpdateAction::Verify { path, checksum_url, signature_url, version }) => Task::perform(
+                async move {
+                    updater::verify_checksum(&path, checksum_url.as_deref(), signature_url.as_deref()).await?;
+                    Ok((path, version))
+                },
+                |result| Message::Update(UpdateMessage::VerifyComplete(result)),
+            ),
+            Some(UpdateAction::Install(path, version)) => {
+                if let Err(e) = updater::apply_update(&path, &version) {
+                    self.update_banner = UpdateBannerState::Error(e);
+                    return Task::none();
+                }
+                std::process::exit(0);
+            }
+            None => Task::none(),
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use super::*;
+
+impl App {
+    pub(super) fn on_viewer(&mut self, msg: ViewerMessage) -> Task<Message> {
+        let Screen::Viewer(state) = &mut self.screen else { return Task::none() };
+        let Some(action) = state.update(msg) else { return Task::none() };
+        match action {
+            ViewerAction::Send(msg) => self.send_to_peer(msg),
+            ViewerAction::Input(msgs) => {
+                let Some(handle) = self.client.handle().cloned() else { return Task::none() };
+                Task::perform(
+                    async move {
+                        for msg in msgs {
+                            handle.send_input(msg).await?;
+                        }
+                        Ok(())
+                    },
+                    Message::InputSent,
+                )
+            }
+            ViewerAction::MovePointer { x, y } => {
+                if let Some(handle) = self.client.handle() {
+                    let _ = handle.try_send_input(ProtocolMessage::MouseMove { x, y });
+                }
+                Task::none()
+            }
+            ViewerAction::Delivery(saver) => self.send_delivery(saver),
+            ViewerAction::Greyscale(greyscale) => {
+                let mode = if greyscale { ColorMode::Greyscale } else { self.color_mode() };
+                self.send_to_peer(ProtocolMessage::SetColorMode(mode))
+            }
+            ViewerAction::Idle { lock_remote, disconnect } => {
+                let lock = if lock_remote {
+                    self.send_to_peer(ProtocolMessage::KeyCombo(combo::KeyCombo::WinL.scancodes()))
+                } else {
+                    Task::none()
+                };
+                if !disconnect {
+                    return lock;
+                }
+                // Give the lock a moment to go out before the goodbye.
+                lock.chain(Task::perform(
+                    tokio::time::sleep(std::time::Duration::from_millis(300)),
+                    |()| Message::IdleDisconnect,
+                ))
+            }
+            ViewerAction::TogglePresentation => self.toggle_presentation(),
+            ViewerAction::Fullscreen(fullscreen) => {
+                let mode = if fullscreen { iced::window::Mode::Fullscreen } else { iced::window::Mode::Windowed };
+                iced::window::set_mode(self.main_window, mode)
+            }
+            ViewerAction::StopRecording => {
+                let note = match self.recorder.take().map(Recorder::stop) {
+                    Some(Ok(path)) => format!("Saved {}", path.display()),
+                    Some(Err(e)) => e,
+                    None => "nothing was recorded".to_string(),
+                };
+                state.recording_stopped(note, false);
+                Task::none()
+            }
+            ViewerAction::StartRecording(editor) => {
+                let label = self.client.host().unwrap_or_default().to_string();
+                match Recorder::start(&self.settings.recording, &label, editor.width, editor.height, editor.regions) {
+                    Ok(recorder) => {
+                        state.recording_started(RecordingClock::start(std::time::Instant::now()), &recorder.path());
+                        self.recorder = Some(recorder);
+                    }
+                    Err(e) => state.recording_stopped(e, true),
+                }
+                Task::none()
+            }
+            ViewerAction::SaveScreenshot(editor) => {
+                let label = self.client.host().unwrap_or_default().to_string();
+                let settings = self.settings.recording.clone();
+                let (width, height, pixels) = (editor.width, editor.height, editor.redacted());
+                Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || {
+                            recording::save_screenshot(&settings, &label, width, height, pixels)
+                        })
+                        .await
+                        .map_err(|e| e.to_string())?
+                    },
+                    |result| Message::Viewer(ViewerMessage::ScreenshotSaved(result)),
+                )
+            }
+            ViewerAction::PasswordMode(on) => {
+                crate::logging::set_quiet(on);
+                Task::none()
+            }
+            ViewerAction::RecordFrame => {
+                if let Some(recorder) = &mut self.recorder
+                    && let Err(e) = recorder.push(state.frame_width, state.frame_height, state.frame_pixels.clone())
+                {
+                    self.recorder = None;
+                    state.recording_stopped(e, true);
+                }
+                Task::none()
+            }
+            ViewerAction::Copy(text) => iced::clipboard::write(text),
+            ViewerAction::ReadClipboardForPaste => iced::clipboard::read().map(Message::PasteFileRead),
+            ViewerAction::ReadClipboardForTyping => iced::clipboard::read().map(Message::TypeClipboardRead),
+            ViewerAction::Transfers(commands) => self.transfer_commands(commands),
+            ViewerAction::SendCredential(secret) => {
+                let secs = self.settings.clipboard.credential_secs;
+                state.timeline.record(format!("Credential sent; clipboards clear in {secs}s"));
+                let text = secret.expose().to_string();
+                let send = self.send_to_peer(ProtocolMessage::ClipboardSecret { text, clear_after_secs: secs });
+                // In case it was pasted into the prompt from our clipboard.
+                Task::batch([send, self.hold_secret(secret, secs)])
+            }
+            ViewerAction::UnlockRemote => {
+                let password = self
+                    .client
+                    .host()
+                    .and_then(|host| self.profiles.find(host))
+                    .filter(|p| p.can_unlock())
+                    .map(|p| p.unlock_password.clone());
+                let (Some(password), Some(handle)) = (password, self.client.handle().cloned()) else {
+                    return Task::none();
+                };
+                state.timeline.record("Unlocking the host's lock screen");
+                Task::perform(
+                    async move {
+                        handle.send_input(combo::unlock_wake()).await?;
+                        tokio::time::sleep(combo::UNLOCK_WAKE_DELAY).await;
+                        for msg in combo::unlock_input(password.expose()) {
+                            handle.send_input(msg).await?;
+                        }
+                        Ok(())
+                    },
+                    Message::InputSent,
+                )
+            }
+            ViewerAction::SelectResolution { resolution, changed } => {
+                // An explicit pick replaces the size given on the command line.
+                let overridden = self.size_override.take().is_some();
+                if !changed && !overridden {
+                    return Task::none();
+                }
+                state.timeline.record(format!("Asked the host for {resolution}"));
+                if self
+                    .client
+                    .host()
+                    .and_then(|host| self.profiles.find(host))
+                    .is_some_and(|p| p.failed_with(&resolution, |r| &r.resolution))
+                {
+                    state.timeline.record(format!("Warning: the last session with this host failed at {resolution}"));
+                }
+                // Until the window size is known, the first resize sends it.
+                let Some(viewport) = state.viewport() else { return Task::none() };
+                // Native is sent as 0x0, which clears the host's target size.
+                let (width, height) = resolution.target_size(viewport).unwrap_or((0, 0));
+                self.send_to_peer(ProtocolMessage::ViewportSize { width, height })
+            }
+            ViewerAction::RestartHost => match self.client.handle().cloned() {
+                Some(handle) => Task::perform(
+                    async move { handle.send_input(ProtocolMessage::RestartHost).await },
+                    Message::RestartSent,
+                ),
+                None => Task::none(),
+            },
+            ViewerAction::Disconnect => {
+                let task = self.disconnect();
+                self.screen = self.login_screen();
+                task
+            }
+            ViewerAction::RetryWithCode(code) => {
+                if let Some(profile) = self.last_profile() {
+                    self.start_connecting(profile, Some(code));
+                }
+                Task::none()
+            }
+            ViewerAction::CancelCodePrompt(reason) => self.back_to_login_denied(reason),
+        }
+    }
+}
+
+impl App {
+    pub(super) fn on_idle_disconnect(&mut self) -> Task<Message> {
+        if !matches!(self.screen, Screen::Viewer(_)) {
+            return Task::none();
+        }
+        let task = self.disconnect();
+        self.screen = self.login_screen();
+        task
+    }
+
+    pub(super) fn on_restart_sent(&mut self, result: Result<(), String>) -> Task<Message> {
+        if let Err(e) = result {
+            if let Screen::Viewer(state) = &mut self.screen {
+                state.timeline.record(format!("Restart request failed: {e}"));
+            }
+            return Task::none();
+        }
+        let target = self.last_session.as_ref().map(|(p, _)| (p.host_ip.clone(), p.port));
+        let task = self.disconnect();
+        self.screen = match target {
+            Some((host, port)) if self.settings.general.reconnect_after_restart => {
+                Screen::RebootWait(RebootWaitState::new(host, port, std::time::Instant::now()))
+            }
+            _ => self.login_screen(),
+        };
+        task
+    }
+
+    pub(super) fn on_peer_path_checked(&mut self, host: String, path: Result<PeerPath, String>) -> Task<Message> {
+        match path {
+            Ok(path) if self.client.host() == Some(host.as_str()) => {
+                if let Screen::Viewer(state) = &mut self.screen {
+                    if let PeerPath::Relayed(region) = &path {
+                        state.timeline.record(format!(
+                            "Traffic to the host is relayed via DERP {region}; direct UDP may be blocked on this network"
+                        ));
+                    }
+                    state.path = Some(path);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::debug!("Could not check the Tailscale path: {e}"),
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_host_key_checked(&mut self, host: String, key: Result<String, String>) -> Task<Message> {
+        if self.client.host() != Some(host.as_str()) {
+            return Task::none();
+        }
+        let key = match key {
+            Ok(key) => key,
+            Err(e) => {
+                tracing::warn!("Could not verify host key for {host}: {e}");
+                return Task::none();
+            }
+        };
+        let hash = known_hosts::key_hash(&key);
+        match self.known_hosts.check(&host, &hash) {
+            HostCheck::Trusted => {}
+            HostCheck::New => {
+                tracing::info!("Trusting {host} on first use ({hash})");
+                self.known_hosts.trust(&host, &hash);
+                self.save_known_hosts();
+            }
+            HostCheck::Changed { expected } => {
+                tracing::warn!("Host key for {host} changed: expected {expected}, got {hash}");
+                let task = self.disconnect();
+                self.screen = Screen::Error(format!(
+                    "The identity of {host} has changed since you last connected. \
+                     If this is expected, forget the host under Known hosts and connect again."
+                ));
+                return task;
+            }
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_folder_shared(&mut self, result: Result<usize, String>) -> Task<Message> {
+        let note = match result {
+            Ok(count) => {
+                tracing::info!("Shared {count} files with the host");
+                format!("Shared {count} files with the host")
+            }
+            Err(e) => {
+                tracing::warn!("Folder sharing failed: {e}");
+                format!("Folder sharing failed: {e}")
+            }
+        };
+        if let Screen::Viewer(state) = &mut self.screen {
+            state.timeline.record(note);
+        }
+        Task::none()
+    }
+
+    pub(super) fn transfer_commands(&mut self, commands: Vec<TransferCommand>) -> Task<Message> {
+        let mut tasks = Vec::new();
+        for command in commands {
+            tasks.push(match command {
+                TransferCommand::Download { path, offset } => {
+                    self.send_to_peer(ProtocolMessage::DownloadFile { path, offset })
+                }
+                TransferCommand::CancelDownload => self.send_to_peer(ProtocolMessage::CancelDownload),
+                TransferCommand::Upload { id, source, folder, name, offset } => {
+                    let Some(handle) = self.peer_handle().cloned() else {
+                        let failed = PasteProgress::Failed("Not connected".to_string());
+                        tasks.push(Task::done(Message::Viewer(ViewerMessage::Transfers(TransfersMessage::UploadProgress(
+                            id, failed,
+                        )))));
+                        continue;
+                    };
+                    let upload = files::send_file(handle, source, offset, move |offset, total, data| {
+                        ProtocolMessage::UploadFile { folder: folder.clone(), name: name.clone(), offset, total, data }
+                    });
+                    let (task, abort) = Task::run(upload, move |progress| {
+                        Message::Viewer(ViewerMessage::Transfers(TransfersMessage::UploadProgress(id, progress)))
+                    })
+                    .abortable();
+                    if let Screen::Viewer(state) = &mut self.screen {
+                        state.transfers.upload_task = Some(abort);
+                    }
+                    task
+                }
+                TransferCommand::Hash { id, path } => Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || files::sha256_file(&path))
+                            .await
+                            .unwrap_or_else(|e| Err(e.to_string()))
+                    },
+                    move |hash| Message::Viewer(ViewerMessage::Transfers(TransfersMessage::Hashed(id, hash))),
+                ),
+                TransferCommand::Finished(direction) => {
+                    let Screen::Viewer(state) = &mut self.screen else { continue };
+                    let Some(browser) = &mut state.file_browser else { continue };
+                    match direction {
+                        Direction::Download => {
+                            browser.refresh_local();
+                            Task::none()
+                        }
+                        Direction::Upload => {
+                            let path = browser.remote_dir.clone();
+                            self.send_to_peer(ProtocolMessage::ListFiles(path))
+                        }
+                    }
+                }
+            });
+        }
+        Task::batch(tasks)
+    }
+}
@@ -0,0 +1,91 @@
+use super::*;
+
+impl App {
+    pub(super) fn on_window_resized(&self, id: iced::window::Id, size: iced::Size) -> Task<Message> {
+        if id == self.main_window && matches!(self.screen, Screen::Viewer(_)) {
+            return iced::window::scale_factor(id).map(move |scale| Message::ViewportChanged(size * scale));
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_viewport_changed(&mut self, size: iced::Size) -> Task<Message> {
+        if let Screen::Viewer(state) = &mut self.screen
+            && let Some(viewport) = state.set_viewport(size.width as u32, size.height as u32)
+            && let Some((width, height)) = match &self.size_override {
+                Some(args) => args.target_size(viewport),
+                None => state.resolution.target_size(viewport),
+            }
+        {
+            return self.send_to_peer(ProtocolMessage::ViewportSize { width, height });
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_window_closed(&mut self, id: iced::window::Id) -> Task<Message> {
+        if id == self.main_window {
+            return iced::exit();
+        }
+        if self.presentation.as_ref().is_some_and(|p| p.window == id) {
+            self.presentation = None;
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_main_window_focus(&mut self, focused: bool) -> Task<Message> {
+        if let Screen::Viewer(state) = &mut self.screen {
+            state.focused = focused;
+            if focused {
+                state.touch();
+            }
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_lock(&mut self, msg: LockMessage) -> Task<Message> {
+        let Some(lock) = &mut self.lock else { return Task::none() };
+        match lock.update(msg, &self.settings.lock) {
+            Some(LockOutcome::Unlocked) => self.unlock(),
+            Some(LockOutcome::PinSet(pin)) => {
+                if let Err(e) = self.settings.lock.set_pin(&pin) {
+                    tracing::warn!("Failed to set PIN: {e}");
+                } else if let Err(e) = self.settings.save(&AppSettings::default_path()) {
+                    tracing::warn!("Failed to save settings: {e}");
+                }
+                self.unlock();
+            }
+            None => {}
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_lock_tick(&mut self) -> Task<Message> {
+        let idle = std::time::Duration::from_secs(self.settings.lock.idle_minutes * 60);
+        if self.lock.is_none()
+            && self.settings.lock.enabled
+            && self.settings.lock.has_pin()
+            && self.last_activity.elapsed() >= idle
+        {
+            tracing::info!("Locking after {} idle minutes", self.settings.lock.idle_minutes);
+            self.lock = Some(LockState::default());
+        }
+        Task::none()
+    }
+
+    pub(super) fn on_presentation_drag(&self) -> Task<Message> {
+        match &self.presentation {
+            Some(presentation) => iced::window::drag(presentation.window),
+            None => Task::none(),
+        }
+    }
+
+    pub(super) fn on_presentation_toggle_fullscreen(&mut self) -> Task<Message> {
+        let Some(presentation) = &mut self.presentation else { return Task::none() };
+        presentation.fullscreen = !presentation.fullscreen;
+        let mode = if presentation.fullscreen {
+            iced::window::Mode::Fullscreen
+        } else {
+            iced::window::Mode::Windowed
+        };
+        iced::window::set_mode(presentation.window, mode)
+    }
+}
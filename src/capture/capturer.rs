@@ -1,8 +1,11 @@
 use std::time::Duration;
 use tokio::sync::mpsc;
 
-use crate::capture::encoder::encode_frame;
+use crate::capture::damage::{self, Damage, KEYFRAME_INTERVAL};
+use crate::capture::displays;
+use crate::capture::encoder::{downscale_bgra, encode_frame};
 use crate::capture::{CaptureCommand, CaptureConfig, CaptureEvent};
+use crate::protocol::FrameData;
 
 pub fn capture_loop(
     config: CaptureConfig,
@@ -11,31 +14,61 @@ pub fn capture_loop(
 ) {
     let mut cmd_rx = cmd_rx;
     let mut jpeg_quality = config.jpeg_quality;
+    let mut color_mode = config.color_mode;
+    let mut target_size = None;
+    let mut previous: Option<Vec<u8>> = None;
+    let mut since_keyframe = 0;
+    let mut partial_updates = true;
 
-    let display = match scrap::Display::primary() {
+    let display = match config.display {
+        Some(index) => displays::open(index),
+        None => scrap::Display::primary().map_err(|e| e.to_string()),
+    };
+    let display = match display {
         Ok(d) => d,
         Err(e) => {
-            let _ = event_tx.blocking_send(CaptureEvent::Error(e.to_string()));
+            let _ = event_tx.blocking_send(CaptureEvent::Error(e));
             return;
         }
     };
-    let width = display.width() as u32;
-    let height = display.height() as u32;
-    let mut capturer = match scrap::Capturer::new(display) {
+    let (mut capturer, mut width, mut height) = match start_capturer(display) {
         Ok(c) => c,
         Err(e) => {
-            let _ = event_tx.blocking_send(CaptureEvent::Error(e.to_string()));
+            let _ = event_tx.blocking_send(CaptureEvent::Error(e));
             return;
         }
     };
     let _ = event_tx.blocking_send(CaptureEvent::Started { width, height });
 
-    let frame_interval = Duration::from_secs(1) / config.fps;
+    let mut frame_interval = Duration::from_secs(1) / config.fps;
 
     loop {
         while let Ok(cmd) = cmd_rx.try_recv() {
             match cmd {
                 CaptureCommand::SetQuality(q) => jpeg_quality = q,
+                CaptureCommand::SetFps(fps) => frame_interval = Duration::from_secs(1) / fps.clamp(1, config.fps),
+                CaptureCommand::SetColorMode(mode) => {
+                    color_mode = mode;
+                    previous = None;
+                }
+                CaptureCommand::SetPartialUpdates(enabled) => partial_updates = enabled,
+                CaptureCommand::SetTargetSize(size) => {
+                    target_size = size;
+                    previous = None;
+                }
+                CaptureCommand::SelectDisplay(index) => {
+                    match displays::open(index).and_then(start_capturer) {
+                        Ok(started) => {
+                            (capturer, width, height) = started;
+                            previous = None;
+                            since_keyframe = 0;
+                            let _ = event_tx.blocking_send(CaptureEvent::Started { width, height });
+                        }
+                        Err(e) => {
+                            let _ = event_tx.blocking_send(CaptureEvent::Error(e));
+                        }
+                    }
+                }
                 CaptureCommand::Stop => {
                     let _ = event_tx.blocking_send(CaptureEvent::Stopped);
                     return;
@@ -59,14 +92,58 @@ pub fn capture_loop(
                     pixels
                 };
 
-                match encode_frame(&bgra, width, height, jpeg_quality) {
-                    Ok(frame_data) => {
-                        if event_tx.blocking_send(CaptureEvent::Frame(frame_data)).is_err() {
-                            break;
-                        }
+                let (bgra, out_width, out_height) = match target_size {
+                    Some(size) => downscale_bgra(bgra, width, height, size),
+                    None => (bgra, width, height),
+                };
+
+                // Diffed against the last frame sent, so one dropped below is
+                // covered by the next.
+                let damage = if since_keyframe >= KEYFRAME_INTERVAL {
+                    Damage::Full
+                } else {
+                    match damage::damage(previous.as_deref(), &bgra, out_width, out_height) {
+                        Damage::Partial(_) if !partial_updates => Damage::Full,
+                        damage => damage,
                     }
-                    Err(e) => {
-                        let _ = event_tx.blocking_send(CaptureEvent::Error(e));
+                };
+                since_keyframe += 1;
+                let keyframe = matches!(damage, Damage::Full);
+                let encoded = match damage {
+                    Damage::Unchanged => None,
+                    Damage::Partial(rect) => {
+                        let region = damage::crop(&bgra, out_width, rect);
+                        Some(
+                            encode_frame(&region, rect.width, rect.height, jpeg_quality, color_mode).map(|data| {
+                                FrameData {
+                                    width: out_width,
+                                    height: out_height,
+                                    region: Some(rect),
+                                    ..data
+                                }
+                            }),
+                        )
+                    }
+                    Damage::Full => Some(encode_frame(&bgra, out_width, out_height, jpeg_quality, color_mode)),
+                };
+
+                if let Some(result) = encoded {
+                    match result {
+                        // A viewer that has fallen behind gets the newest frame
+                        // later rather than a backlog of stale ones now.
+                        Ok(frame_data) => match event_tx.try_send(CaptureEvent::Frame(frame_data)) {
+                            Ok(()) => {
+                                previous = Some(bgra);
+                                if keyframe {
+                                    since_keyframe = 0;
+                                }
+                            }
+                            Err(mpsc::error::TrySendError::Full(_)) => {}
+                            Err(mpsc::error::TrySendError::Closed(_)) => break,
+                        },
+                        Err(e) => {
+                            let _ = event_tx.blocking_send(CaptureEvent::Error(e));
+                        }
                     }
                 }
             }
@@ -87,3 +164,10 @@ pub fn capture_loop(
     }
     let _ = event_tx.blocking_send(CaptureEvent::Stopped);
 }
+
+fn start_capturer(display: scrap::Display) -> Result<(scrap::Capturer, u32, u32), String> {
+    let width = display.width() as u32;
+    let height = display.height() as u32;
+    let capturer = scrap::Capturer::new(display).map_err(|e| e.to_string())?;
+    Ok((capturer, width, height))
+}
@@ -0,0 +1,133 @@
+use crate::protocol::DirtyRect;
+
+/// A full frame is sent at least this often so the viewer recovers from any drift.
+pub const KEYFRAME_INTERVAL: u32 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Damage {
+    Unchanged,
+    Partial(DirtyRect),
+    Full,
+}
+
+/// Compares two captures of the same size and returns what has to be resent.
+/// Large changes go out as full frames; one JPEG beats a crop that covers most
+/// of the screen anyway.
+pub fn damage(previous: Option<&[u8]>, current: &[u8], width: u32, height: u32) -> Damage {
+    let Some(previous) = previous.filter(|p| p.len() == current.len()) else {
+        return Damage::Full;
+    };
+    match bounding_rect(previous, current, width, height) {
+        None => Damage::Unchanged,
+        Some(rect) if (rect.width as u64 * rect.height as u64) * 2 > width as u64 * height as u64 => Damage::Full,
+        Some(rect) => Damage::Partial(rect),
+    }
+}
+
+fn bounding_rect(previous: &[u8], current: &[u8], width: u32, height: u32) -> Option<DirtyRect> {
+    let row_len = width as usize * 4;
+    let rows = || previous.chunks_exact(row_len).zip(current.chunks_exact(row_len)).take(height as usize);
+
+    let top = rows().position(|(a, b)| a != b)?;
+    let bottom = height as usize - 1 - rows().rev().position(|(a, b)| a != b)?;
+
+    let mut left = width as usize;
+    let mut right = 0;
+    for (a, b) in rows().skip(top).take(bottom - top + 1) {
+        let mut pixels = a.chunks_exact(4).zip(b.chunks_exact(4));
+        if let Some(first) = pixels.position(|(p, q)| p != q) {
+            left = left.min(first);
+            let last = a.chunks_exact(4).zip(b.chunks_exact(4)).rposition(|(p, q)| p != q).unwrap_or(first);
+            right = right.max(last);
+        }
+    }
+
+    Some(DirtyRect {
+        x: left as u32,
+        y: top as u32,
+        width: (right - left + 1) as u32,
+        height: (bottom - top + 1) as u32,
+    })
+}
+
+/// Copies `rect` out of a 4-byte-per-pixel buffer `width` pixels wide.
+pub fn crop(pixels: &[u8], width: u32, rect: DirtyRect) -> Vec<u8> {
+    let row_len = rect.width as usize * 4;
+    let mut out = Vec::with_capacity(row_len * rect.height as usize);
+    for y in rect.y..rect.y + rect.height {
+        let start = (y as usize * width as usize + rect.x as usize) * 4;
+        out.extend_from_slice(&pixels[start..start + row_len]);
+    }
+    out
+}
+
+/// Writes a cropped region back into a buffer `width` pixels wide.
+pub fn blit(dst: &mut [u8], width: u32, rect: DirtyRect, src: &[u8]) {
+    let row_len = rect.width as usize * 4;
+    for (row, y) in src.chunks_exact(row_len).zip(rect.y..rect.y + rect.height) {
+        let start = (y as usize * width as usize + rect.x as usize) * 4;
+        dst[start..start + row_len].copy_from_slice(row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(width: u32, height: u32) -> Vec<u8> {
+        vec![0u8; (width * height * 4) as usize]
+    }
+
+    fn paint(pixels: &mut [u8], width: u32, x: u32, y: u32) {
+        let i = ((y * width + x) * 4) as usize;
+        pixels[i..i + 4].copy_from_slice(&[255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn first_and_resized_frames_are_full() {
+        let current = frame(8, 8);
+        assert_eq!(damage(None, &current, 8, 8), Damage::Full);
+        assert_eq!(damage(Some(&frame(4, 4)), &current, 8, 8), Damage::Full);
+    }
+
+    #[test]
+    fn identical_frames_are_unchanged() {
+        let current = frame(8, 8);
+        assert_eq!(damage(Some(&current.clone()), &current, 8, 8), Damage::Unchanged);
+    }
+
+    #[test]
+    fn small_change_is_bounded() {
+        let previous = frame(16, 16);
+        let mut current = previous.clone();
+        paint(&mut current, 16, 3, 5);
+        paint(&mut current, 16, 6, 7);
+        assert_eq!(
+            damage(Some(&previous), &current, 16, 16),
+            Damage::Partial(DirtyRect { x: 3, y: 5, width: 4, height: 3 })
+        );
+    }
+
+    #[test]
+    fn large_change_is_full() {
+        let previous = frame(4, 4);
+        let mut current = previous.clone();
+        paint(&mut current, 4, 0, 0);
+        paint(&mut current, 4, 3, 3);
+        assert_eq!(damage(Some(&previous), &current, 4, 4), Damage::Full);
+    }
+
+    #[test]
+    fn crop_then_blit_round_trips() {
+        let width = 6;
+        let source: Vec<u8> = (0..width * 5 * 4).map(|i| i as u8).collect();
+        let rect = DirtyRect { x: 1, y: 2, width: 3, height: 2 };
+        let region = crop(&source, width, rect);
+        assert_eq!(region.len(), 3 * 2 * 4);
+
+        let mut target = vec![0u8; source.len()];
+        blit(&mut target, width, rect, &region);
+        assert_eq!(crop(&target, width, rect), region);
+        assert_eq!(target[0], 0);
+    }
+}
@@ -0,0 +1,108 @@
+/// Each step is one entry in the Windows display-scaling list, 25% at the low end.
+pub const MAX_TEXT_BOOST_STEPS: u8 = 2;
+
+pub fn boost_label(steps: u8) -> String {
+    match steps {
+        0 => "Text: Normal".to_string(),
+        n => format!("Text: +{}%", n as u32 * 25),
+    }
+}
+
+pub fn next_boost(steps: u8) -> u8 {
+    if steps >= MAX_TEXT_BOOST_STEPS { 0 } else { steps + 1 }
+}
+
+#[cfg(windows)]
+mod platform {
+    use windows_sys::Win32::UI::WindowsAndMessaging::SystemParametersInfoW;
+
+    // Undocumented: the DPI override is stored as an index relative to the
+    // recommended scaling of the primary display.
+    const SPI_GETLOGICALDPIOVERRIDE: u32 = 0x009E;
+    const SPI_SETLOGICALDPIOVERRIDE: u32 = 0x009F;
+    const SPIF_UPDATEINIFILE: u32 = 0x0001;
+
+    fn set_override(index: i32) -> Result<(), String> {
+        let ok = unsafe {
+            SystemParametersInfoW(
+                SPI_SETLOGICALDPIOVERRIDE,
+                index as u32,
+                std::ptr::null_mut(),
+                SPIF_UPDATEINIFILE,
+            )
+        };
+        if ok == 0 {
+            return Err("failed to change display scaling".to_string());
+        }
+        Ok(())
+    }
+
+    pub struct TextBoost {
+        original: i32,
+    }
+
+    impl TextBoost {
+        pub fn start() -> Result<Self, String> {
+            let mut original = 0i32;
+            let ok = unsafe {
+                SystemParametersInfoW(
+                    SPI_GETLOGICALDPIOVERRIDE,
+                    0,
+                    &mut original as *mut i32 as *mut _,
+                    0,
+                )
+            };
+            if ok == 0 {
+                return Err("failed to read display scaling".to_string());
+            }
+            Ok(Self { original })
+        }
+
+        pub fn apply(&self, steps: u8) -> Result<(), String> {
+            set_override(self.original + steps as i32)
+        }
+    }
+
+    impl Drop for TextBoost {
+        fn drop(&mut self) {
+            if let Err(e) = set_override(self.original) {
+                tracing::warn!("Restoring display scaling: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    pub struct TextBoost;
+
+    impl TextBoost {
+        pub fn start() -> Result<Self, String> {
+            Err("display scaling changes are only supported on Windows".to_string())
+        }
+
+        pub fn apply(&self, _steps: u8) -> Result<(), String> {
+            Ok(())
+        }
+    }
+}
+
+pub use platform::TextBoost;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boost_cycles_back_to_normal() {
+        assert_eq!(next_boost(0), 1);
+        assert_eq!(next_boost(1), 2);
+        assert_eq!(next_boost(MAX_TEXT_BOOST_STEPS), 0);
+    }
+
+    #[test]
+    fn labels() {
+        assert_eq!(boost_label(0), "Text: Normal");
+        assert_eq!(boost_label(2), "Text: +50%");
+    }
+}
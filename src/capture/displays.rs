@@ -0,0 +1,100 @@
+use crate::protocol::DisplayInfo;
+
+/// Displays in scrap's order, which is also the order `SelectDisplay` indexes;
+/// the first one is the display captured by default.
+pub fn list() -> Vec<DisplayInfo> {
+    let sizes: Vec<(u32, u32)> = scrap::Display::all()
+        .map(|all| all.iter().map(|d| (d.width() as u32, d.height() as u32)).collect())
+        .unwrap_or_default();
+    let origins = assign_origins(&sizes, &monitor_rects());
+    sizes
+        .into_iter()
+        .zip(origins)
+        .map(|((width, height), (x, y))| DisplayInfo { x, y, width, height })
+        .collect()
+}
+
+pub fn open(index: usize) -> Result<scrap::Display, String> {
+    let mut all = scrap::Display::all().map_err(|e| e.to_string())?;
+    if index >= all.len() {
+        return Err(format!("Display {} is not connected", index + 1));
+    }
+    Ok(all.swap_remove(index))
+}
+
+/// scrap does not report where each display sits on the desktop, so match
+/// the OS monitor rectangles to the captured displays by size. Anything
+/// unmatched is assumed to sit to the right of the rest.
+fn assign_origins(sizes: &[(u32, u32)], rects: &[(i32, i32, u32, u32)]) -> Vec<(i32, i32)> {
+    let mut used = vec![false; rects.len()];
+    let mut right_edge = rects.iter().map(|&(x, _, w, _)| x + w as i32).max().unwrap_or(0);
+    sizes
+        .iter()
+        .map(|&(width, height)| {
+            let found = rects
+                .iter()
+                .enumerate()
+                .find(|&(i, &(_, _, w, h))| !used[i] && w == width && h == height);
+            match found {
+                Some((i, &(x, y, _, _))) => {
+                    used[i] = true;
+                    (x, y)
+                }
+                None => {
+                    let origin = (right_edge, 0);
+                    right_edge += width as i32;
+                    origin
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+fn monitor_rects() -> Vec<(i32, i32, u32, u32)> {
+    use windows_sys::Win32::Foundation::{BOOL, LPARAM, RECT};
+    use windows_sys::Win32::Graphics::Gdi::{EnumDisplayMonitors, HDC, HMONITOR};
+
+    unsafe extern "system" fn collect(_: HMONITOR, _: HDC, rect: *mut RECT, data: LPARAM) -> BOOL {
+        // SAFETY: `data` is the Vec passed below and `rect` is valid for the callback.
+        let (rects, r) = unsafe { (&mut *(data as *mut Vec<(i32, i32, u32, u32)>), &*rect) };
+        rects.push((r.left, r.top, (r.right - r.left) as u32, (r.bottom - r.top) as u32));
+        1
+    }
+
+    let mut rects: Vec<(i32, i32, u32, u32)> = Vec::new();
+    // SAFETY: the callback only runs during this call, while `rects` is alive.
+    unsafe {
+        EnumDisplayMonitors(0, std::ptr::null(), Some(collect), &mut rects as *mut _ as LPARAM);
+    }
+    rects
+}
+
+#[cfg(not(windows))]
+fn monitor_rects() -> Vec<(i32, i32, u32, u32)> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn origins_matched_by_size() {
+        let sizes = [(2560, 1440), (1920, 1080)];
+        let rects = [(-1920, 200, 1920, 1080), (0, 0, 2560, 1440)];
+        assert_eq!(assign_origins(&sizes, &rects), vec![(0, 0), (-1920, 200)]);
+    }
+
+    #[test]
+    fn unmatched_displays_placed_to_the_right() {
+        assert_eq!(
+            assign_origins(&[(1920, 1080), (1280, 1024)], &[]),
+            vec![(0, 0), (1920, 0)]
+        );
+        assert_eq!(
+            assign_origins(&[(1920, 1080), (1920, 1080)], &[(0, 0, 1920, 1080)]),
+            vec![(0, 0), (1920, 0)]
+        );
+    }
+}
@@ -1,6 +1,12 @@
-use crate::protocol::FrameData;
-
-pub fn encode_frame(bgra_pixels: &[u8], width: u32, height: u32, quality: u8) -> Result<FrameData, String> {
+use crate::protocol::{ColorMode, FrameData};
+
+pub fn encode_frame(
+    bgra_pixels: &[u8],
+    width: u32,
+    height: u32,
+    quality: u8,
+    color_mode: ColorMode,
+) -> Result<FrameData, String> {
     if width == 0 || height == 0 {
         return Err("width and height must be non-zero".to_string());
     }
@@ -15,19 +21,41 @@ pub fn encode_frame(bgra_pixels: &[u8], width: u32, height: u32, quality: u8) ->
     }
 
     let pixel_count = (width as usize) * (height as usize);
-    let mut rgb_data = Vec::with_capacity(pixel_count * 3);
-    for i in 0..pixel_count {
-        let offset = i * 4;
-        rgb_data.push(bgra_pixels[offset + 2]); // R (from BGRA position)
-        rgb_data.push(bgra_pixels[offset + 1]); // G
-        rgb_data.push(bgra_pixels[offset]);     // B (from BGRA position)
-    }
+    let (pixel_data, color_type) = match color_mode {
+        ColorMode::Full => {
+            let mut rgb_data = Vec::with_capacity(pixel_count * 3);
+            for i in 0..pixel_count {
+                let offset = i * 4;
+                rgb_data.push(bgra_pixels[offset + 2]); // R (from BGRA position)
+                rgb_data.push(bgra_pixels[offset + 1]); // G
+                rgb_data.push(bgra_pixels[offset]);     // B (from BGRA position)
+            }
+            (rgb_data, image::ExtendedColorType::Rgb8)
+        }
+        ColorMode::Reduced => {
+            let mut rgb_data = Vec::with_capacity(pixel_count * 3);
+            for px in bgra_pixels.chunks_exact(4) {
+                // Keep the top 5, 6 and 5 bits, as a 16-bit display would.
+                rgb_data.extend_from_slice(&[px[2] & 0xF8, px[1] & 0xFC, px[0] & 0xF8]);
+            }
+            (rgb_data, image::ExtendedColorType::Rgb8)
+        }
+        ColorMode::Greyscale => {
+            let mut luma_data = Vec::with_capacity(pixel_count);
+            for px in bgra_pixels.chunks_exact(4) {
+                // BT.601 luma weights in fixed point (sum to 256)
+                let luma = (px[2] as u32 * 77 + px[1] as u32 * 150 + px[0] as u32 * 29) >> 8;
+                luma_data.push(luma as u8);
+            }
+            (luma_data, image::ExtendedColorType::L8)
+        }
+    };
 
     let mut jpeg_buf = Vec::new();
     let mut cursor = std::io::Cursor::new(&mut jpeg_buf);
     let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut cursor, quality);
     encoder
-        .encode(&rgb_data, width, height, image::ExtendedColorType::Rgb8)
+        .encode(&pixel_data, width, height, color_type)
         .map_err(|e| format!("JPEG encode failed: {}", e))?;
 
     let compressed = lz4_flex::compress_prepend_size(&jpeg_buf);
@@ -36,10 +64,35 @@ pub fn encode_frame(bgra_pixels: &[u8], width: u32, height: u32, quality: u8) ->
         width,
         height,
         jpeg_quality: quality,
+        region: None,
         compressed_payload: compressed,
     })
 }
 
+/// Largest size with the source aspect ratio that fits inside `max`. Never upscales.
+pub fn fit_within(width: u32, height: u32, max_width: u32, max_height: u32) -> (u32, u32) {
+    if max_width == 0 || max_height == 0 || (width <= max_width && height <= max_height) {
+        return (width, height);
+    }
+    let scale = (max_width as f64 / width as f64).min(max_height as f64 / height as f64);
+    let w = ((width as f64 * scale).round() as u32).max(1);
+    let h = ((height as f64 * scale).round() as u32).max(1);
+    (w, h)
+}
+
+pub fn downscale_bgra(bgra_pixels: Vec<u8>, width: u32, height: u32, target: (u32, u32)) -> (Vec<u8>, u32, u32) {
+    let (w, h) = fit_within(width, height, target.0, target.1);
+    if (w, h) == (width, height) {
+        return (bgra_pixels, width, height);
+    }
+    // Channel order doesn't matter to the filter, so BGRA can pass as RGBA.
+    let Some(src) = image::RgbaImage::from_raw(width, height, bgra_pixels) else {
+        return (Vec::new(), 0, 0);
+    };
+    let scaled = image::imageops::resize(&src, w, h, image::imageops::FilterType::Triangle);
+    (scaled.into_raw(), w, h)
+}
+
 pub fn decode_frame(frame_data: &FrameData) -> Result<Vec<u8>, String> {
     let jpeg_data = lz4_flex::decompress_size_prepended(&frame_data.compressed_payload)
         .map_err(|e| format!("LZ4 decompress failed: {}", e))?;
@@ -47,7 +100,7 @@ pub fn decode_frame(frame_data: &FrameData) -> Result<Vec<u8>, String> {
     let img = image::load_from_memory_with_format(&jpeg_data, image::ImageFormat::Jpeg)
         .map_err(|e| format!("JPEG decode failed: {}", e))?;
 
-    Ok(img.to_rgba8().into_raw())
+    Ok(img.into_rgba8().into_raw())
 }
 
 #[cfg(test)]
@@ -71,7 +124,7 @@ mod tests {
     #[test]
     fn encode_synthetic_frame() {
         let buf = make_bgra_buffer(100, 100);
-        let result = encode_frame(&buf, 100, 100, 75);
+        let result = encode_frame(&buf, 100, 100, 75, ColorMode::Full);
         assert!(result.is_ok());
         let frame = result.unwrap();
         assert_eq!(frame.width, 100);
@@ -85,7 +138,7 @@ mod tests {
         let width = 64;
         let height = 64;
         let buf = make_bgra_buffer(width, height);
-        let frame = encode_frame(&buf, width, height, 90).unwrap();
+        let frame = encode_frame(&buf, width, height, 90, ColorMode::Full).unwrap();
         let rgba = decode_frame(&frame).unwrap();
         assert_eq!(rgba.len(), (width * height * 4) as usize);
     }
@@ -93,11 +146,47 @@ mod tests {
     #[test]
     fn quality_affects_size() {
         let buf = make_bgra_buffer(100, 100);
-        let low = encode_frame(&buf, 100, 100, 10).unwrap();
-        let high = encode_frame(&buf, 100, 100, 90).unwrap();
+        let low = encode_frame(&buf, 100, 100, 10, ColorMode::Full).unwrap();
+        let high = encode_frame(&buf, 100, 100, 90, ColorMode::Full).unwrap();
         assert!(high.compressed_payload.len() > low.compressed_payload.len());
     }
 
+    #[test]
+    fn greyscale_is_smaller_and_decodes_to_rgba() {
+        let buf = make_bgra_buffer(100, 100);
+        let full = encode_frame(&buf, 100, 100, 75, ColorMode::Full).unwrap();
+        let grey = encode_frame(&buf, 100, 100, 75, ColorMode::Greyscale).unwrap();
+        assert!(grey.compressed_payload.len() < full.compressed_payload.len());
+
+        let rgba = decode_frame(&grey).unwrap();
+        assert_eq!(rgba.len(), 100 * 100 * 4);
+        assert_eq!(rgba[0], rgba[1]);
+        assert_eq!(rgba[1], rgba[2]);
+    }
+
+    #[test]
+    fn reduced_colour_decodes_to_rgba() {
+        let buf = make_bgra_buffer(100, 100);
+        let reduced = encode_frame(&buf, 100, 100, 75, ColorMode::Reduced).unwrap();
+        assert_eq!(decode_frame(&reduced).unwrap().len(), 100 * 100 * 4);
+    }
+
+    #[test]
+    fn fit_within_keeps_aspect_and_never_upscales() {
+        assert_eq!(fit_within(1920, 1080, 960, 1000), (960, 540));
+        assert_eq!(fit_within(1920, 1080, 3000, 540), (960, 540));
+        assert_eq!(fit_within(800, 600, 1920, 1080), (800, 600));
+        assert_eq!(fit_within(800, 600, 0, 0), (800, 600));
+    }
+
+    #[test]
+    fn downscale_produces_matching_buffer() {
+        let buf = make_bgra_buffer(100, 50);
+        let (pixels, w, h) = downscale_bgra(buf, 100, 50, (50, 50));
+        assert_eq!((w, h), (50, 25));
+        assert_eq!(pixels.len(), 50 * 25 * 4);
+    }
+
     #[test]
     fn config_defaults() {
         let config = CaptureConfig::default();
@@ -107,7 +196,7 @@ mod tests {
 
     #[test]
     fn encode_empty_fails() {
-        let result = encode_frame(&[], 0, 0, 75);
+        let result = encode_frame(&[], 0, 0, 75, ColorMode::Full);
         assert!(result.is_err());
     }
 }
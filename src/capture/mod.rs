@@ -1,19 +1,28 @@
 pub mod capturer;
+pub mod damage;
+pub mod displays;
+pub mod display_scale;
 pub mod encoder;
+pub mod secure_desktop;
 
-use crate::protocol::FrameData;
+use crate::protocol::{ColorMode, FrameData};
 
 #[derive(Debug, Clone)]
 pub struct CaptureConfig {
     pub fps: u32,
     pub jpeg_quality: u8,
+    pub color_mode: ColorMode,
+    /// Display to capture; `None` starts on the primary one.
+    pub display: Option<usize>,
 }
 
 impl Default for CaptureConfig {
     fn default() -> Self {
         Self {
             fps: 30,
-            jpeg_quality: 75,
+            jpeg_quality: crate::config::settings::DEFAULT_JPEG_QUALITY,
+            color_mode: ColorMode::Full,
+            display: None,
         }
     }
 }
@@ -28,5 +37,13 @@ pub enum CaptureEvent {
 
 pub enum CaptureCommand {
     SetQuality(u8),
+    /// Frames per second, at most `CaptureConfig::fps`.
+    SetFps(u32),
+    SetColorMode(ColorMode),
+    /// Off sends every change as a full frame.
+    SetPartialUpdates(bool),
+    SetTargetSize(Option<(u32, u32)>),
+    /// Index into `displays::list()`.
+    SelectDisplay(usize),
     Stop,
 }
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+
+const SECURE_DESKTOP_POLL: Duration = Duration::from_secs(1);
+
+/// Sends whether the secure desktop is up each time that changes, starting
+/// with the current state. Runs on a blocking thread until the receiver is
+/// dropped.
+pub fn watch(tx: mpsc::Sender<bool>) {
+    let mut last = None;
+    while !tx.is_closed() {
+        let secure = active();
+        if last != Some(secure) {
+            last = Some(secure);
+            if tx.blocking_send(secure).is_err() {
+                return;
+            }
+        }
+        std::thread::sleep(SECURE_DESKTOP_POLL);
+    }
+}
+
+/// Whether Windows has switched input to the secure desktop, as it does for
+/// UAC prompts, Ctrl+Alt+Del and the lock screen. Screen capture from the
+/// user's session only sees black or stale frames until it switches back.
+#[cfg(windows)]
+pub fn active() -> bool {
+    use windows_sys::Win32::Foundation::{ERROR_ACCESS_DENIED, GetLastError};
+    use windows_sys::Win32::System::StationsAndDesktops::{
+        CloseDesktop, DESKTOP_READOBJECTS, GetUserObjectInformationW, OpenInputDesktop, UOI_NAME,
+    };
+
+    unsafe {
+        let desktop = OpenInputDesktop(0, 0, DESKTOP_READOBJECTS);
+        if desktop == 0 {
+            // A normal process is not allowed to open the secure desktop.
+            return GetLastError() == ERROR_ACCESS_DENIED;
+        }
+        let mut name = [0u16; 64];
+        let mut needed = 0u32;
+        let ok = GetUserObjectInformationW(
+            desktop,
+            UOI_NAME,
+            name.as_mut_ptr().cast(),
+            (name.len() * 2) as u32,
+            &mut needed,
+        );
+        CloseDesktop(desktop);
+        let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+        ok != 0 && is_secure_name(&String::from_utf16_lossy(&name[..len]))
+    }
+}
+
+#[cfg(not(windows))]
+pub fn active() -> bool {
+    false
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+fn is_secure_name(name: &str) -> bool {
+    name.eq_ignore_ascii_case("Winlogon")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_winlogon_is_secure() {
+        assert!(is_secure_name("Winlogon"));
+        assert!(is_secure_name("WINLOGON"));
+        assert!(!is_secure_name("Default"));
+    }
+}
@@ -0,0 +1,251 @@
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+
+use crate::probe::{ProbeArgs, split_target};
+
+#[derive(Debug, Parser)]
+#[command(name = "rust-rdp", version, about = "Remote desktop over Tailscale")]
+pub struct Cli {
+    /// Keep all data beside the exe (a portable.flag file next to it does the same)
+    #[arg(long, global = true)]
+    pub portable: bool,
+    /// Print results and session events as JSON, one object per line
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// Connect to this host at startup; takes host[:port] or an rdp:// address
+    #[arg(long, value_name = "HOST[:PORT]", value_parser = connect_target, conflicts_with = "host")]
+    pub connect: Option<(String, u16)>,
+    /// Start hosting at startup
+    #[arg(long)]
+    pub host: bool,
+    /// Label for the connection
+    #[arg(long, requires = "connect")]
+    pub name: Option<String>,
+    /// Width to ask the host for; without --height it follows the window's aspect
+    #[arg(long, value_name = "PX", requires = "connect", value_parser = pixels())]
+    pub width: Option<u32>,
+    /// Height to ask the host for; without --width it follows the window's aspect
+    #[arg(long, value_name = "PX", requires = "connect", value_parser = pixels())]
+    pub height: Option<u32>,
+    /// Only fill in the connect form
+    #[arg(long, requires = "connect")]
+    pub edit: bool,
+    /// Connections are authorised by Tailscale, so this is refused with a reason
+    #[arg(long, hide = true, value_parser = no_user)]
+    pub user: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Check that a host answers, without starting a session. Exits 0 when it
+    /// is reachable and compatible, 1 when not.
+    Probe {
+        #[arg(value_name = "HOST[:PORT]", value_parser = split_target)]
+        target: (String, u16),
+        /// Seconds to wait for the host
+        #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+        timeout: u64,
+    },
+}
+
+/// Which end of a session an event is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Host,
+    Viewer,
+}
+
+/// A session event, printed as one line of JSON with `--json` so scripts can
+/// follow the app without reading its log.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    /// Hosting is up; viewers connect to `address`.
+    Listening { address: String },
+    /// A viewer came in, or we reached the host; `peer` is the other end.
+    Connected { side: Side, peer: String },
+    Disconnected { side: Side, reason: Option<String> },
+    Error { side: Side, message: String },
+}
+
+impl Event {
+    pub fn to_line(&self) -> String {
+        serde_json::to_string(self).expect("events serialize")
+    }
+
+    /// Writes the event to stdout; a closed stdout is not worth failing over.
+    pub fn print(&self) {
+        use std::io::Write;
+        let _ = writeln!(std::io::stdout().lock(), "{}", self.to_line());
+    }
+}
+
+/// What to do once Tailscale is up, instead of showing the mode picker.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Launch {
+    Connect(ConnectArgs),
+    Host,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectArgs {
+    pub host: String,
+    pub port: u16,
+    pub name: Option<String>,
+    /// Resolution to ask the host for; a missing side follows the window's aspect.
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Only fill in the connect form.
+    pub edit: bool,
+}
+
+impl ConnectArgs {
+    pub fn target_size(&self, viewport: (u32, u32)) -> Option<(u32, u32)> {
+        let (vw, vh) = viewport;
+        match (self.width, self.height) {
+            (Some(w), Some(h)) => Some((w, h)),
+            (Some(w), None) if vw > 0 => Some((w, (vh as u64 * w as u64 / vw as u64) as u32)),
+            (None, Some(h)) if vh > 0 => Some(((vw as u64 * h as u64 / vh as u64) as u32, h)),
+            _ => None,
+        }
+    }
+}
+
+impl Cli {
+    /// Parses the arguments after the program name.
+    pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Self, clap::Error> {
+        Self::try_parse_from(std::iter::once("rust-rdp".to_string()).chain(args))
+    }
+
+    /// What a normal (windowed) start should open with.
+    pub fn launch(&self) -> Option<Launch> {
+        if self.host {
+            return Some(Launch::Host);
+        }
+        let (host, port) = self.connect.clone()?;
+        Some(Launch::Connect(ConnectArgs {
+            host,
+            port,
+            name: self.name.clone(),
+            width: self.width,
+            height: self.height,
+            edit: self.edit,
+        }))
+    }
+
+    pub fn probe_args(&self) -> Option<ProbeArgs> {
+        let Command::Probe { target: (host, port), timeout } = self.command.as_ref()?;
+        Some(ProbeArgs { host: host.clone(), port: *port, timeout: Duration::from_secs(*timeout), json: self.json })
+    }
+}
+
+
+fn pixels() -> clap::builder::RangedI64ValueParser<u32> {
+    clap::value_parser!(u32).range(64..=8192)
+}
+
+/// Accepts addresses copied as URLs, e.g. `rdp://100.64.0.1:9867/`.
+fn connect_target(target: &str) -> Result<(String, u16), String> {
+    let rest = target.split_once("://").map_or(target, |(_, rest)| rest);
+    split_target(rest.trim_end_matches('/'))
+}
+
+fn no_user(_: &str) -> Result<String, String> {
+    Err("--user is not supported: connections are authorised by Tailscale, not accounts".to_string())
+}
+
+/// Prints help, the version or a usage error to the parent console and exits:
+/// 0 for help and version, 2 for a usage error.
+pub fn exit_with(error: clap::Error) -> ! {
+    crate::probe::attach_console();
+    error.exit()
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    fn parse(list: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::parse_args(list.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn parses_connect() {
+        let launch = parse(&["--connect", "rdp://100.64.0.1:7000/", "--width", "1600"]).unwrap().launch();
+        let Some(Launch::Connect(connect)) = launch else { panic!("{launch:?}") };
+        assert_eq!((connect.host.as_str(), connect.port), ("100.64.0.1", 7000));
+        assert_eq!(connect.width, Some(1600));
+        assert!(!connect.edit);
+        assert_eq!(connect.target_size((1000, 500)), Some((1600, 800)));
+    }
+
+    #[test]
+    fn portable_goes_with_anything() {
+        assert!(parse(&["probe", "pc", "--portable"]).unwrap().portable);
+        assert!(parse(&["--portable", "--host"]).unwrap().portable);
+        assert!(!parse(&["--host"]).unwrap().portable);
+    }
+
+    #[test]
+    fn parses_host_and_nothing() {
+        assert_eq!(parse(&["--host"]).unwrap().launch(), Some(Launch::Host));
+        assert_eq!(parse(&[]).unwrap().launch(), None);
+    }
+
+    #[test]
+    fn rejects_bad_combinations() {
+        assert!(parse(&["--connect", "pc", "--host"]).is_err());
+        assert!(parse(&["--width", "1600"]).is_err());
+        assert!(parse(&["--connect", "pc", "--width", "10"]).is_err());
+        assert!(parse(&["--connect"]).is_err());
+        let user = parse(&["--connect", "pc", "--user", "bob"]).unwrap_err();
+        assert!(user.to_string().contains("authorised by Tailscale"));
+    }
+
+    #[test]
+    fn parses_probe() {
+        let cli = parse(&["probe", "100.64.0.1:7000", "--timeout", "3"]).unwrap();
+        let args = cli.probe_args().unwrap();
+        assert_eq!((args.host.as_str(), args.port), ("100.64.0.1", 7000));
+        assert_eq!(args.timeout, Duration::from_secs(3));
+        assert!(!args.json);
+        assert!(parse(&["probe", "--json", "my-pc"]).unwrap().probe_args().unwrap().json);
+        assert!(parse(&["--json", "probe", "my-pc"]).unwrap().probe_args().unwrap().json);
+        assert!(parse(&["--host"]).unwrap().probe_args().is_none());
+
+        let args = parse(&["probe", "my-pc"]).unwrap().probe_args().unwrap();
+        assert_eq!(args.timeout, Duration::from_secs(10));
+        assert!(parse(&["probe"]).is_err());
+        assert!(parse(&["probe", "host", "--timeout", "0"]).is_err());
+        assert!(parse(&["probe", "host", "--verbose"]).is_err());
+        assert!(parse(&["probe", "host:port"]).is_err());
+    }
+
+    #[test]
+    fn events_are_one_json_object_per_line() {
+        assert!(parse(&["--host", "--json"]).unwrap().json);
+        let listening = Event::Listening { address: "100.64.0.1:9867".to_string() };
+        assert_eq!(listening.to_line(), r#"{"event":"listening","address":"100.64.0.1:9867"}"#);
+        let connected = Event::Connected { side: Side::Host, peer: "100.64.0.2:50123".to_string() };
+        assert_eq!(connected.to_line(), r#"{"event":"connected","side":"host","peer":"100.64.0.2:50123"}"#);
+        let disconnected = Event::Disconnected { side: Side::Viewer, reason: None };
+        assert_eq!(disconnected.to_line(), r#"{"event":"disconnected","side":"viewer","reason":null}"#);
+        let error = Event::Error { side: Side::Viewer, message: "Connect failed:\nrefused".to_string() };
+        assert_eq!(error.to_line(), r#"{"event":"error","side":"viewer","message":"Connect failed:\nrefused"}"#);
+    }
+
+    #[test]
+    fn help_is_generated() {
+        Cli::command().debug_assert();
+        let help = Cli::command().render_help().to_string();
+        assert!(help.contains("--connect") && help.contains("probe"));
+        assert!(!help.contains("--user"));
+    }
+}
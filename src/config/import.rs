@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+
+use super::ConnectionProfile;
+use crate::error::{AppError, Result};
+
+pub fn default_ssh_config_path() -> Option<PathBuf> {
+    dirs_next::home_dir().map(|home| home.join(".ssh").join("config"))
+}
+
+pub fn import_file(path: &Path) -> Result<Vec<ConnectionProfile>> {
+    let content = std::fs::read_to_string(path)?;
+    let is_csv = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    let profiles = if is_csv {
+        parse_csv(&content)
+    } else {
+        parse_ssh_config(&content)
+    };
+    if profiles.is_empty() {
+        return Err(AppError::Config(format!(
+            "no hosts found in {}",
+            path.display()
+        )));
+    }
+    Ok(profiles)
+}
+
+/// Parses `hostname,label,username` rows. The username column is accepted for
+/// compatibility with exported host lists but is not used by the app.
+pub fn parse_csv(content: &str) -> Vec<ConnectionProfile> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split(',').map(|f| f.trim().trim_matches('"'));
+            let host = fields.next().filter(|h| !h.is_empty())?;
+            if host.eq_ignore_ascii_case("hostname") {
+                return None;
+            }
+            let label = fields.next().unwrap_or_default();
+            Some(imported_profile(host, label))
+        })
+        .collect()
+}
+
+/// Turns `Host` blocks into profiles, using `HostName` as the address when present.
+/// Wildcard patterns are skipped since they do not name a single machine.
+pub fn parse_ssh_config(content: &str) -> Vec<ConnectionProfile> {
+    let mut profiles = Vec::new();
+    let mut aliases: Vec<String> = Vec::new();
+    let mut host_name: Option<String> = None;
+
+    let mut flush = |aliases: &mut Vec<String>, host_name: &mut Option<String>| {
+        for alias in aliases.drain(..) {
+            let host = host_name.clone().unwrap_or_else(|| alias.clone());
+            profiles.push(imported_profile(&host, &alias));
+        }
+        *host_name = None;
+    };
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = match line.split_once(|c: char| c.is_whitespace() || c == '=') {
+            Some((k, v)) => (k, v.trim().trim_start_matches('=').trim()),
+            None => continue,
+        };
+        if key.eq_ignore_ascii_case("Host") || key.eq_ignore_ascii_case("Match") {
+            flush(&mut aliases, &mut host_name);
+            if key.eq_ignore_ascii_case("Host") {
+                aliases = value
+                    .split_whitespace()
+                    .filter(|a| !a.contains(['*', '?', '!']))
+                    .map(str::to_string)
+                    .collect();
+            }
+        } else if key.eq_ignore_ascii_case("HostName") && !aliases.is_empty() {
+            host_name = Some(value.to_string());
+        }
+    }
+    flush(&mut aliases, &mut host_name);
+
+    profiles
+}
+
+fn imported_profile(host: &str, label: &str) -> ConnectionProfile {
+    ConnectionProfile {
+        host_ip: host.to_string(),
+        display_name: label.to_string(),
+        enabled: false,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_rows_become_disabled_profiles() {
+        let csv = "hostname,label,username\n100.64.0.1,Office PC,alice\n\n# comment\npc-2\n";
+        let profiles = parse_csv(csv);
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].host_ip, "100.64.0.1");
+        assert_eq!(profiles[0].display_name, "Office PC");
+        assert!(!profiles[0].enabled);
+        assert_eq!(profiles[1].host_ip, "pc-2");
+        assert!(profiles[1].display_name.is_empty());
+    }
+
+    #[test]
+    fn ssh_config_uses_hostname_and_skips_wildcards() {
+        let config = "\
+Host *
+    User admin
+
+Host office lab
+    HostName 100.64.0.7
+    Port 2222
+
+Host nas
+    User root
+";
+        let profiles = parse_ssh_config(config);
+        assert_eq!(profiles.len(), 3);
+        assert_eq!(profiles[0].host_ip, "100.64.0.7");
+        assert_eq!(profiles[0].display_name, "office");
+        assert_eq!(profiles[1].host_ip, "100.64.0.7");
+        assert_eq!(profiles[1].display_name, "lab");
+        assert_eq!(profiles[2].host_ip, "nas");
+        assert!(profiles.iter().all(|p| !p.enabled));
+    }
+
+    #[test]
+    fn ssh_config_equals_syntax() {
+        let profiles = parse_ssh_config("Host=box\nHostName=10.0.0.5\n");
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].host_ip, "10.0.0.5");
+    }
+}
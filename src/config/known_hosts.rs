@@ -0,0 +1,128 @@
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+use super::app_data_dir;
+use crate::error::Result;
+
+/// Pinned host identities, one `<host> sha256:<hex>` line per host, in the
+/// spirit of OpenSSH's known_hosts. The file can be copied between machines.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KnownHosts {
+    pub entries: Vec<KnownHost>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnownHost {
+    pub host: String,
+    pub key_hash: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum HostCheck {
+    Trusted,
+    New,
+    Changed { expected: String },
+}
+
+pub fn key_hash(key: &str) -> String {
+    format!("sha256:{:x}", Sha256::digest(key.as_bytes()))
+}
+
+impl KnownHosts {
+    pub fn default_path() -> PathBuf {
+        app_data_dir().join("known_hosts")
+    }
+
+    pub fn load_or_default() -> Self {
+        Self::load(&Self::default_path()).unwrap_or_default()
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_text())?;
+        Ok(())
+    }
+
+    /// Blank lines, `#` comments and malformed lines are skipped.
+    pub fn parse(content: &str) -> Self {
+        let entries = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let host = parts.next()?;
+                let key_hash = parts.next().filter(|h| h.starts_with("sha256:"))?;
+                Some(KnownHost {
+                    host: host.to_string(),
+                    key_hash: key_hash.to_string(),
+                })
+            })
+            .collect();
+        Self { entries }
+    }
+
+    pub fn to_text(&self) -> String {
+        self.entries
+            .iter()
+            .map(|e| format!("{} {}\n", e.host, e.key_hash))
+            .collect()
+    }
+
+    pub fn check(&self, host: &str, key_hash: &str) -> HostCheck {
+        match self.entries.iter().find(|e| e.host.eq_ignore_ascii_case(host)) {
+            None => HostCheck::New,
+            Some(e) if e.key_hash == key_hash => HostCheck::Trusted,
+            Some(e) => HostCheck::Changed {
+                expected: e.key_hash.clone(),
+            },
+        }
+    }
+
+    pub fn trust(&mut self, host: &str, key_hash: &str) {
+        self.remove(host);
+        self.entries.push(KnownHost {
+            host: host.to_string(),
+            key_hash: key_hash.to_string(),
+        });
+    }
+
+    pub fn remove(&mut self, host: &str) {
+        self.entries.retain(|e| !e.host.eq_ignore_ascii_case(host));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_skips_comments_and_junk() {
+        let hosts = KnownHosts::parse("# team hosts\n100.64.0.1 sha256:ab\n\nbroken-line\n100.64.0.2 md5:xx\n");
+        assert_eq!(hosts.entries.len(), 1);
+        assert_eq!(hosts.to_text(), "100.64.0.1 sha256:ab\n");
+    }
+
+    #[test]
+    fn first_use_then_pinned() {
+        let mut hosts = KnownHosts::default();
+        let hash = key_hash("nodekey:1111");
+        assert_eq!(hosts.check("pc-1", &hash), HostCheck::New);
+
+        hosts.trust("pc-1", &hash);
+        assert_eq!(hosts.check("PC-1", &hash), HostCheck::Trusted);
+        assert_eq!(
+            hosts.check("pc-1", &key_hash("nodekey:2222")),
+            HostCheck::Changed { expected: hash.clone() }
+        );
+
+        hosts.remove("pc-1");
+        assert_eq!(hosts.check("pc-1", &hash), HostCheck::New);
+    }
+}
@@ -1,3 +1,46 @@
+pub mod import;
+pub mod known_hosts;
 mod profile;
+pub mod secret;
+pub mod settings;
+pub mod store;
 
-pub use profile::ConnectionProfile;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+pub use profile::{ColorDepth, ConnectionProfile, SessionRecord, ViewerPrefs};
+pub use store::ProfileStore;
+
+/// A file of this name next to the executable turns on portable mode.
+pub const PORTABLE_FLAG: &str = "portable.flag";
+
+static PORTABLE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Keeps settings, profiles and logs in a `rust-rdp` folder next to the
+/// executable, for running from a USB stick. Must run before anything reads
+/// `app_data_dir`.
+pub fn init_portable(forced: bool) -> bool {
+    let Some(exe_dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(PathBuf::from)) else {
+        return false;
+    };
+    if !forced && !exe_dir.join(PORTABLE_FLAG).exists() {
+        return false;
+    }
+    PORTABLE_DIR.set(exe_dir.join("rust-rdp")).is_ok()
+}
+
+pub fn is_portable() -> bool {
+    PORTABLE_DIR.get().is_some()
+}
+
+pub fn app_data_dir() -> PathBuf {
+    if let Some(dir) = PORTABLE_DIR.get() {
+        dir.clone()
+    } else if let Some(data_dir) = dirs_next::data_dir() {
+        data_dir.join("rust-rdp")
+    } else if let Ok(appdata) = std::env::var("APPDATA") {
+        PathBuf::from(appdata).join("rust-rdp")
+    } else {
+        PathBuf::from(".").join("rust-rdp")
+    }
+}
@@ -2,7 +2,11 @@ use serde::{Deserialize, Serialize};
 use std::path::Path;
 
 use crate::error::{AppError, Result};
-use crate::protocol::DEFAULT_PORT;
+use crate::config::secret::SecretString;
+use crate::config::settings::Resolution;
+use crate::protocol::{ColorMode, DEFAULT_PORT};
+use crate::ui::color_adjust::ColorAdjust;
+use crate::ui::scaling::{Rotation, ScaleMode};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectionProfile {
@@ -11,23 +15,181 @@ pub struct ConnectionProfile {
     pub port: u16,
     #[serde(default)]
     pub display_name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub viewer: ViewerPrefs,
+    /// Local folder copied to the host for the session when `share_folder` is on.
+    #[serde(default)]
+    pub shared_folder: String,
+    #[serde(default)]
+    pub share_folder: bool,
+    /// Opt-in: type `unlock_password` at the host's lock screen on request.
+    /// Only meant for machines the user owns; the password is DPAPI-protected.
+    #[serde(default)]
+    pub unlock_enabled: bool,
+    #[serde(default)]
+    pub unlock_password: SecretString,
+    /// Overrides `general.keyboard_layout` for this host; same values.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyboard_layout: Option<String>,
+    /// Colour depth the host encodes with.
+    #[serde(default)]
+    pub color_depth: ColorDepth,
+    /// Lets the host send only the changed part of a frame. Off means every
+    /// change is a full frame, which smears less on lossy links.
+    #[serde(default = "default_enabled")]
+    pub partial_updates: bool,
+    /// What the last session that got connected ran with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_good: Option<SessionRecord>,
+    /// Settings a session failed with; cleared once they work.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_failed: Option<SessionRecord>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorDepth {
+    #[default]
+    Full,
+    /// 16-bit colour: cheaper to encode and send, with visible banding.
+    Reduced,
+}
+
+impl ColorDepth {
+    pub const ALL: [ColorDepth; 2] = [ColorDepth::Full, ColorDepth::Reduced];
+
+    pub fn color_mode(self) -> ColorMode {
+        match self {
+            Self::Full => ColorMode::Full,
+            Self::Reduced => ColorMode::Reduced,
+        }
+    }
+}
+
+impl std::fmt::Display for ColorDepth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Full => "32-bit colour",
+            Self::Reduced => "16-bit colour",
+        })
+    }
+}
+
+/// Session settings remembered per host, so the next connection starts
+/// from what worked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    #[serde(default)]
+    pub resolution: Resolution,
+    /// Same values as `ConnectionProfile::keyboard_layout`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyboard_layout: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewerPrefs {
+    #[serde(default = "default_show_stats")]
+    pub show_stats: bool,
+    #[serde(default)]
+    pub color: ColorAdjust,
+    #[serde(default)]
+    pub greyscale: bool,
+    #[serde(default)]
+    pub scale: ScaleMode,
+    #[serde(default)]
+    pub rotation: Rotation,
+    /// Where "Paste as file" saves on the host, relative to its desktop.
+    #[serde(default)]
+    pub paste_folder: String,
+    /// Ask the host for fewer, lower-quality frames.
+    #[serde(default)]
+    pub bandwidth_saver: bool,
+    #[serde(default)]
+    pub fullscreen: bool,
+}
+
+fn default_show_stats() -> bool {
+    true
+}
+
+impl Default for ViewerPrefs {
+    fn default() -> Self {
+        Self {
+            show_stats: default_show_stats(),
+            color: ColorAdjust::default(),
+            greyscale: false,
+            scale: ScaleMode::default(),
+            rotation: Rotation::default(),
+            paste_folder: String::new(),
+            bandwidth_saver: false,
+            fullscreen: false,
+        }
+    }
 }
 
 fn default_port() -> u16 {
     DEFAULT_PORT
 }
 
+fn default_enabled() -> bool {
+    true
+}
+
 impl Default for ConnectionProfile {
     fn default() -> Self {
         Self {
             host_ip: String::new(),
             port: default_port(),
             display_name: String::new(),
+            enabled: default_enabled(),
+            viewer: ViewerPrefs::default(),
+            shared_folder: String::new(),
+            share_folder: false,
+            unlock_enabled: false,
+            unlock_password: SecretString::default(),
+            keyboard_layout: None,
+            color_depth: ColorDepth::default(),
+            partial_updates: default_enabled(),
+            last_good: None,
+            last_failed: None,
         }
     }
 }
 
 impl ConnectionProfile {
+    pub fn can_unlock(&self) -> bool {
+        self.unlock_enabled && !self.unlock_password.is_empty()
+    }
+
+    /// Returns whether anything changed, so the store is only saved then.
+    pub fn record_success(&mut self, record: SessionRecord) -> bool {
+        let mut changed = self.last_good.as_ref() != Some(&record);
+        if self.last_failed.as_ref() == Some(&record) {
+            self.last_failed = None;
+            changed = true;
+        }
+        self.last_good = Some(record);
+        changed
+    }
+
+    /// A failure with the settings that last worked is put down to the
+    /// network rather than the settings, and not recorded.
+    pub fn record_failure(&mut self, record: SessionRecord) -> bool {
+        if self.last_good.as_ref() == Some(&record) || self.last_failed.as_ref() == Some(&record) {
+            return false;
+        }
+        self.last_failed = Some(record);
+        true
+    }
+
+    /// The setting failed last time and differs from what last worked.
+    pub fn failed_with<T: PartialEq>(&self, value: &T, field: impl Fn(&SessionRecord) -> &T) -> bool {
+        self.last_failed.as_ref().is_some_and(|r| field(r) == value)
+            && self.last_good.as_ref().is_none_or(|r| field(r) != value)
+    }
+
     pub fn server_addr(&self) -> String {
         format!("{}:{}", self.host_ip, self.port)
     }
@@ -79,6 +241,7 @@ mod tests {
         assert_eq!(deserialized.host_ip, "100.64.0.1");
         assert_eq!(deserialized.display_name, "My PC");
         assert_eq!(deserialized.port, 9867);
+        assert!(deserialized.last_good.is_none());
     }
 
     #[test]
@@ -88,5 +251,54 @@ mod tests {
         assert_eq!(profile.host_ip, "10.0.0.1");
         assert_eq!(profile.port, DEFAULT_PORT);
         assert!(profile.display_name.is_empty());
+        assert!(profile.enabled);
+        assert_eq!(profile.viewer, ViewerPrefs::default());
+        assert_eq!(profile.color_depth, ColorDepth::Full);
+        assert!(profile.partial_updates);
+    }
+
+    #[test]
+    fn viewer_prefs_round_trip() {
+        let profile = ConnectionProfile {
+            host_ip: "100.64.0.1".to_string(),
+            viewer: ViewerPrefs {
+                show_stats: false,
+                color: ColorAdjust {
+                    gamma: 1.4,
+                    ..Default::default()
+                },
+                greyscale: true,
+                scale: ScaleMode::Zoom(150),
+                rotation: Rotation::Cw90,
+                paste_folder: "Scripts".to_string(),
+                bandwidth_saver: true,
+                fullscreen: true,
+            },
+            ..Default::default()
+        };
+        let serialized = toml::to_string(&profile).unwrap();
+        let deserialized: ConnectionProfile = toml::from_str(&serialized).unwrap();
+        assert!(!deserialized.viewer.show_stats);
+        assert_eq!(deserialized.viewer.color.gamma, 1.4);
+        assert_eq!(deserialized.viewer.rotation, Rotation::Cw90);
+        assert!(deserialized.viewer.greyscale);
+        assert!(deserialized.viewer.bandwidth_saver);
+        assert!(deserialized.viewer.fullscreen);
+    }
+
+    #[test]
+    fn session_records() {
+        let good = SessionRecord { resolution: Resolution::Window, keyboard_layout: None };
+        let bad = SessionRecord { resolution: Resolution::Native, keyboard_layout: None };
+        let mut profile = ConnectionProfile::default();
+        assert!(profile.record_success(good.clone()));
+        assert!(!profile.record_success(good.clone()));
+        // Failing with what worked is not blamed on the settings.
+        assert!(!profile.record_failure(good.clone()));
+        assert!(profile.record_failure(bad.clone()));
+        assert!(profile.failed_with(&Resolution::Native, |r| &r.resolution));
+        assert!(!profile.failed_with(&None, |r| &r.keyboard_layout));
+        assert!(profile.record_success(bad));
+        assert!(profile.last_failed.is_none());
     }
 }
@@ -0,0 +1,234 @@
+//! Secrets stored in the config files. On Windows they are encrypted with DPAPI
+//! for the current user and written as `dpapi:<hex>`; elsewhere they stay plain.
+//! Plain values are always accepted on load so older files keep working.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroize;
+
+const PREFIX: &str = "dpapi:";
+
+/// A password held in memory. Its bytes are wiped when it is dropped, and
+/// Debug never shows it. Stored with `protect` like the other secrets.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl std::fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(if self.0.is_empty() { "SecretString(empty)" } else { "SecretString(<redacted>)" })
+    }
+}
+
+impl Serialize for SecretString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(Self)
+    }
+}
+
+pub fn is_protected(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+pub fn protect(value: &str) -> Result<String, String> {
+    if value.is_empty() || !cfg!(windows) {
+        return Ok(value.to_string());
+    }
+    let blob = dpapi::encrypt(value.as_bytes())?;
+    Ok(format!("{PREFIX}{}", to_hex(&blob)))
+}
+
+pub fn unprotect(value: &str) -> Result<String, String> {
+    let Some(hex) = value.strip_prefix(PREFIX) else {
+        return Ok(value.to_string());
+    };
+    let blob = from_hex(hex).ok_or("secret is not valid hex")?;
+    let plain = dpapi::decrypt(&blob)?;
+    String::from_utf8(plain).map_err(|e| e.to_string())
+}
+
+/// Whether a plaintext secret would be stored differently once saved again.
+pub fn needs_migration(value: &str) -> bool {
+    cfg!(windows) && !value.is_empty() && !is_protected(value)
+}
+
+pub fn serialize<S: Serializer>(value: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut stored = protect(value).map_err(serde::ser::Error::custom)?;
+    let result = serializer.serialize_str(&stored);
+    stored.zeroize();
+    result
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let mut stored = String::deserialize(deserializer)?;
+    let plain = unprotect(&stored).map_err(serde::de::Error::custom);
+    stored.zeroize();
+    plain
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(windows)]
+mod dpapi {
+    use windows_sys::Win32::Foundation::LocalFree;
+    use windows_sys::Win32::Security::Cryptography::{
+        CRYPT_INTEGER_BLOB, CRYPTPROTECT_UI_FORBIDDEN, CryptProtectData, CryptUnprotectData,
+    };
+
+    fn blob(data: &[u8]) -> CRYPT_INTEGER_BLOB {
+        CRYPT_INTEGER_BLOB {
+            cbData: data.len() as u32,
+            pbData: data.as_ptr() as *mut u8,
+        }
+    }
+
+    fn take(out: CRYPT_INTEGER_BLOB) -> Vec<u8> {
+        // SAFETY: DPAPI allocated `cbData` bytes at `pbData` with LocalAlloc.
+        unsafe {
+            let data = std::slice::from_raw_parts(out.pbData, out.cbData as usize).to_vec();
+            LocalFree(out.pbData as _);
+            data
+        }
+    }
+
+    pub fn encrypt(data: &[u8]) -> Result<Vec<u8>, String> {
+        let input = blob(data);
+        let mut out = CRYPT_INTEGER_BLOB {
+            cbData: 0,
+            pbData: std::ptr::null_mut(),
+        };
+        // SAFETY: all pointers are valid for the duration of the call.
+        let ok = unsafe {
+            CryptProtectData(
+                &input,
+                std::ptr::null(),
+                std::ptr::null(),
+                std::ptr::null(),
+                std::ptr::null(),
+                CRYPTPROTECT_UI_FORBIDDEN,
+                &mut out,
+            )
+        };
+        if ok == 0 {
+            return Err(format!("CryptProtectData failed: {}", std::io::Error::last_os_error()));
+        }
+        Ok(take(out))
+    }
+
+    pub fn decrypt(data: &[u8]) -> Result<Vec<u8>, String> {
+        let input = blob(data);
+        let mut out = CRYPT_INTEGER_BLOB {
+            cbData: 0,
+            pbData: std::ptr::null_mut(),
+        };
+        // SAFETY: all pointers are valid for the duration of the call.
+        let ok = unsafe {
+            CryptUnprotectData(
+                &input,
+                std::ptr::null_mut(),
+                std::ptr::null(),
+                std::ptr::null(),
+                std::ptr::null(),
+                CRYPTPROTECT_UI_FORBIDDEN,
+                &mut out,
+            )
+        };
+        if ok == 0 {
+            return Err(format!("CryptUnprotectData failed: {}", std::io::Error::last_os_error()));
+        }
+        Ok(take(out))
+    }
+}
+
+#[cfg(not(windows))]
+mod dpapi {
+    pub fn encrypt(_data: &[u8]) -> Result<Vec<u8>, String> {
+        Err("DPAPI is only available on Windows".to_string())
+    }
+
+    pub fn decrypt(_data: &[u8]) -> Result<Vec<u8>, String> {
+        Err("DPAPI is only available on Windows".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_round_trip() {
+        let bytes = [0u8, 1, 0xab, 0xff];
+        assert_eq!(from_hex(&to_hex(&bytes)).unwrap(), bytes);
+        assert!(from_hex("abc").is_none());
+        assert!(from_hex("zz").is_none());
+    }
+
+    #[test]
+    fn plain_values_load_unchanged() {
+        assert_eq!(unprotect("plain-token").unwrap(), "plain-token");
+        assert!(!is_protected("plain-token"));
+    }
+
+    #[test]
+    fn secret_strings_hide_their_value() {
+        let secret = SecretString::from("hunter2");
+        assert_eq!(format!("{secret:?}"), "SecretString(<redacted>)");
+        assert_eq!(secret.expose(), "hunter2");
+        let stored = toml::to_string(&std::collections::BTreeMap::from([("password", secret.clone())])).unwrap();
+        assert_eq!(stored.contains("hunter2"), !cfg!(windows));
+        let loaded: std::collections::BTreeMap<String, SecretString> = toml::from_str(&stored).unwrap();
+        assert_eq!(loaded["password"], secret);
+    }
+
+    #[test]
+    fn protect_round_trips() {
+        let stored = protect("s3cret").unwrap();
+        assert_eq!(is_protected(&stored), cfg!(windows));
+        assert_eq!(unprotect(&stored).unwrap(), "s3cret");
+        assert_eq!(protect("").unwrap(), "");
+    }
+}
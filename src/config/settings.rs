@@ -0,0 +1,729 @@
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+use super::{app_data_dir, secret};
+use crate::error::{AppError, Result};
+
+pub const DEFAULT_CONTROL_PORT: u16 = 9868;
+pub const DEFAULT_METRICS_PORT: u16 = 9869;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AppSettings {
+    #[serde(default)]
+    pub general: GeneralSettings,
+    #[serde(default)]
+    pub control: ControlSettings,
+    #[serde(default)]
+    pub notify: NotifySettings,
+    #[serde(default)]
+    pub metrics: MetricsSettings,
+    #[serde(default)]
+    pub clipboard: ClipboardSettings,
+    #[serde(default)]
+    pub privacy: PrivacySettings,
+    #[serde(default)]
+    pub lock: LockSettings,
+    #[serde(default)]
+    pub idle: SessionIdleSettings,
+    #[serde(default)]
+    pub host: HostSettings,
+    #[serde(default)]
+    pub recording: RecordingSettings,
+}
+
+pub const DEFAULT_MAX_FPS: u32 = 30;
+pub const MAX_FPS_LIMIT: u32 = 60;
+pub const DEFAULT_JPEG_QUALITY: u8 = 75;
+pub const SAVER_FPS: u32 = 10;
+pub const SAVER_JPEG_QUALITY: u8 = 40;
+
+/// Defaults edited on the Settings screen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneralSettings {
+    #[serde(default)]
+    pub resolution: Resolution,
+    /// Layout the host switches to while we are connected: empty means this
+    /// machine's layout, `host` keeps the host's own, otherwise a KLID.
+    #[serde(default)]
+    pub keyboard_layout: String,
+    #[serde(default = "default_check_updates")]
+    pub check_updates: bool,
+    #[serde(default)]
+    pub update_channel: UpdateChannel,
+    /// Minutes a freshly updated build must run before old backups are pruned.
+    #[serde(default = "default_update_trial_minutes")]
+    pub update_trial_minutes: u64,
+    /// Frame-rate cap for the capture loop when hosting.
+    #[serde(default = "default_max_fps")]
+    pub max_fps: u32,
+    /// Frame rate asked of hosts when viewing; 0 takes what the host sends.
+    #[serde(default)]
+    pub viewer_max_fps: u32,
+    #[serde(default)]
+    pub log_level: LogLevel,
+    /// After restarting a host from the viewer, wait for it and reconnect.
+    #[serde(default = "default_reconnect_after_restart")]
+    pub reconnect_after_restart: bool,
+    /// Pause between characters for Type Clipboard; slow login prompts
+    /// drop keys sent too quickly.
+    #[serde(default = "default_type_delay_ms")]
+    pub type_delay_ms: u64,
+}
+
+pub const MAX_TYPE_DELAY_MS: u64 = 1000;
+
+fn default_type_delay_ms() -> u64 {
+    30
+}
+
+fn default_check_updates() -> bool {
+    true
+}
+
+fn default_update_trial_minutes() -> u64 {
+    10
+}
+
+fn default_reconnect_after_restart() -> bool {
+    true
+}
+
+fn default_max_fps() -> u32 {
+    DEFAULT_MAX_FPS
+}
+
+impl Default for GeneralSettings {
+    fn default() -> Self {
+        Self {
+            resolution: Resolution::default(),
+            keyboard_layout: String::new(),
+            check_updates: default_check_updates(),
+            update_channel: UpdateChannel::default(),
+            update_trial_minutes: default_update_trial_minutes(),
+            max_fps: default_max_fps(),
+            viewer_max_fps: 0,
+            log_level: LogLevel::default(),
+            reconnect_after_restart: default_reconnect_after_restart(),
+            type_delay_ms: default_type_delay_ms(),
+        }
+    }
+}
+
+impl GeneralSettings {
+    /// Layout to announce in the Hello; `None` leaves the host's layout alone.
+    pub fn hello_layout(&self) -> Option<String> {
+        crate::input_handler::layout::hello_layout(&self.keyboard_layout)
+    }
+
+    pub fn capture_fps(&self) -> u32 {
+        self.max_fps.clamp(1, MAX_FPS_LIMIT)
+    }
+
+    /// Frame rate and JPEG quality to ask a host for; bandwidth saver trades
+    /// both down for slow links.
+    pub fn delivery(&self, bandwidth_saver: bool) -> (u32, u8) {
+        match (bandwidth_saver, self.viewer_max_fps) {
+            (false, fps) => (fps, DEFAULT_JPEG_QUALITY),
+            (true, 0) => (SAVER_FPS, SAVER_JPEG_QUALITY),
+            (true, fps) => (fps.min(SAVER_FPS), SAVER_JPEG_QUALITY),
+        }
+    }
+}
+
+/// Resolution the viewer asks the host to send.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolution {
+    /// Scale to the viewer window.
+    #[default]
+    Window,
+    /// The host's own resolution.
+    Native,
+    #[serde(rename = "1280x720")]
+    Hd720,
+    #[serde(rename = "1920x1080")]
+    Hd1080,
+}
+
+impl Resolution {
+    pub const ALL: [Resolution; 4] = [Resolution::Window, Resolution::Native, Resolution::Hd720, Resolution::Hd1080];
+
+    /// Size to request given the viewer's viewport; `None` sends nothing.
+    pub fn target_size(self, viewport: (u32, u32)) -> Option<(u32, u32)> {
+        match self {
+            Resolution::Window => Some(viewport),
+            Resolution::Native => None,
+            Resolution::Hd720 => Some((1280, 720)),
+            Resolution::Hd1080 => Some((1920, 1080)),
+        }
+    }
+}
+
+impl std::fmt::Display for Resolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Resolution::Window => "Fit window",
+            Resolution::Native => "Host native",
+            Resolution::Hd720 => "1280 x 720",
+            Resolution::Hd1080 => "1920 x 1080",
+        })
+    }
+}
+
+/// Which releases the update check offers. Each channel also gets the
+/// releases of the ones above it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+    Nightly,
+}
+
+impl UpdateChannel {
+    pub const ALL: [UpdateChannel; 3] = [UpdateChannel::Stable, UpdateChannel::Beta, UpdateChannel::Nightly];
+}
+
+impl std::fmt::Display for UpdateChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            UpdateChannel::Stable => "Stable",
+            UpdateChannel::Beta => "Beta",
+            UpdateChannel::Nightly => "Nightly",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 5] = [LogLevel::Error, LogLevel::Warn, LogLevel::Info, LogLevel::Debug, LogLevel::Trace];
+
+    pub fn filter(self) -> tracing::level_filters::LevelFilter {
+        use tracing::level_filters::LevelFilter;
+        match self {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+impl std::fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LogLevel::Error => "Error",
+            LogLevel::Warn => "Warning",
+            LogLevel::Info => "Info",
+            LogLevel::Debug => "Debug",
+            LogLevel::Trace => "Trace",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostSettings {
+    /// Address the host server listens on. Empty means the Tailscale address.
+    #[serde(default)]
+    pub bind_address: String,
+    #[serde(default = "default_host_port")]
+    pub port: u16,
+    /// Viewers must also type the code shown on the host screen, which is
+    /// new each time hosting starts.
+    #[serde(default)]
+    pub require_code: bool,
+    /// Share only this display (0-based); the viewer cannot switch to the
+    /// others. `None` shares all of them.
+    #[serde(default)]
+    pub shared_display: Option<u32>,
+    /// Lets a connected viewer restart this machine. Off unless turned on.
+    #[serde(default)]
+    pub allow_restart: bool,
+}
+
+fn default_host_port() -> u16 {
+    crate::protocol::DEFAULT_PORT
+}
+
+impl Default for HostSettings {
+    fn default() -> Self {
+        Self {
+            bind_address: String::new(),
+            port: default_host_port(),
+            require_code: false,
+            shared_display: None,
+            allow_restart: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordingSettings {
+    /// ffmpeg executable; empty means `ffmpeg` on PATH.
+    #[serde(default)]
+    pub ffmpeg: String,
+    /// Where recordings are written; empty means the user's Videos folder.
+    #[serde(default)]
+    pub directory: String,
+    /// Stamps who saved a screenshot or recording, and when, into a corner.
+    #[serde(default)]
+    pub watermark: bool,
+    /// Identity in the watermark; empty means the local user name.
+    #[serde(default)]
+    pub watermark_text: String,
+    #[serde(default)]
+    pub watermark_corner: Corner,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+impl Corner {
+    pub const ALL: [Corner; 4] = [Corner::TopLeft, Corner::TopRight, Corner::BottomLeft, Corner::BottomRight];
+}
+
+impl std::fmt::Display for Corner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Corner::TopLeft => "Top left",
+            Corner::TopRight => "Top right",
+            Corner::BottomLeft => "Bottom left",
+            Corner::BottomRight => "Bottom right",
+        })
+    }
+}
+
+impl RecordingSettings {
+    pub fn ffmpeg_program(&self) -> String {
+        match self.ffmpeg.trim() {
+            "" => "ffmpeg".to_string(),
+            path => path.to_string(),
+        }
+    }
+
+    pub fn output_dir(&self) -> PathBuf {
+        match self.directory.trim() {
+            "" => dirs_next::video_dir()
+                .map(|dir| dir.join("rust-rdp"))
+                .unwrap_or_else(|| app_data_dir().join("recordings")),
+            dir => PathBuf::from(dir),
+        }
+    }
+
+    /// Who the watermark names.
+    pub fn watermark_identity(&self) -> String {
+        match self.watermark_text.trim() {
+            "" => std::env::var("USERNAME").or_else(|_| std::env::var("USER")).unwrap_or_else(|_| "unknown".to_string()),
+            text => text.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_control_port")]
+    pub port: u16,
+    #[serde(default, with = "super::secret")]
+    pub token: String,
+}
+
+fn default_control_port() -> u16 {
+    DEFAULT_CONTROL_PORT
+}
+
+impl Default for ControlSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_control_port(),
+            token: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotifySettings {
+    #[serde(default, with = "super::secret")]
+    pub webhook_url: String,
+    #[serde(default)]
+    pub script: String,
+}
+
+impl NotifySettings {
+    pub fn is_configured(&self) -> bool {
+        !self.webhook_url.trim().is_empty() || !self.script.trim().is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_metrics_port")]
+    pub port: u16,
+}
+
+fn default_metrics_port() -> u16 {
+    DEFAULT_METRICS_PORT
+}
+
+impl Default for MetricsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_metrics_port(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipboardSettings {
+    #[serde(default = "default_clipboard_enabled")]
+    pub enabled: bool,
+    /// Largest copied image sent or accepted, in MB.
+    #[serde(default = "default_clipboard_image_mb")]
+    pub max_image_mb: u32,
+    /// Largest total size of copied files sent or accepted, in MB.
+    #[serde(default = "default_clipboard_files_mb")]
+    pub max_files_mb: u32,
+    /// How long a sent credential stays on either clipboard.
+    #[serde(default = "default_credential_secs")]
+    pub credential_secs: u16,
+}
+
+pub const MAX_CLIPBOARD_MB: u32 = 512;
+pub const CREDENTIAL_SECS: std::ops::RangeInclusive<u16> = 5..=600;
+
+fn default_clipboard_enabled() -> bool {
+    true
+}
+
+fn default_clipboard_image_mb() -> u32 {
+    16
+}
+
+fn default_clipboard_files_mb() -> u32 {
+    64
+}
+
+fn default_credential_secs() -> u16 {
+    30
+}
+
+impl ClipboardSettings {
+    pub fn max_image_bytes(&self) -> usize {
+        self.max_image_mb.min(MAX_CLIPBOARD_MB) as usize * 1024 * 1024
+    }
+
+    pub fn max_files_bytes(&self) -> u64 {
+        self.max_files_mb.min(MAX_CLIPBOARD_MB) as u64 * 1024 * 1024
+    }
+}
+
+impl Default for ClipboardSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_clipboard_enabled(),
+            max_image_mb: default_clipboard_image_mb(),
+            max_files_mb: default_clipboard_files_mb(),
+            credential_secs: default_credential_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrivacyMode {
+    #[default]
+    Off,
+    Dim,
+    Blur,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivacySettings {
+    #[serde(default)]
+    pub mode: PrivacyMode,
+    #[serde(default = "default_on_focus_loss")]
+    pub on_focus_loss: bool,
+    #[serde(default)]
+    pub idle_secs: u64,
+}
+
+fn default_on_focus_loss() -> bool {
+    true
+}
+
+impl Default for PrivacySettings {
+    fn default() -> Self {
+        Self {
+            mode: PrivacyMode::Off,
+            on_focus_loss: default_on_focus_loss(),
+            idle_secs: 0,
+        }
+    }
+}
+
+pub const MIN_PIN_LEN: usize = 4;
+
+/// Longest idle timeout the settings screen accepts: a day.
+pub const MAX_IDLE_MINUTES: u64 = 24 * 60;
+
+/// What a viewer session does after `minutes` without local input. Nothing
+/// happens unless `minutes` is set and at least one action is on.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct SessionIdleSettings {
+    #[serde(default)]
+    pub minutes: u64,
+    /// Lock the host with Win+L.
+    #[serde(default)]
+    pub lock_remote: bool,
+    #[serde(default)]
+    pub disconnect: bool,
+}
+
+impl SessionIdleSettings {
+    pub fn enabled(&self) -> bool {
+        self.minutes > 0 && (self.lock_remote || self.disconnect)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_idle_minutes")]
+    pub idle_minutes: u64,
+    #[serde(default)]
+    pub pin_salt: String,
+    #[serde(default)]
+    pub pin_hash: String,
+}
+
+fn default_idle_minutes() -> u64 {
+    10
+}
+
+impl Default for LockSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_minutes: default_idle_minutes(),
+            pin_salt: String::new(),
+            pin_hash: String::new(),
+        }
+    }
+}
+
+impl LockSettings {
+    pub fn has_pin(&self) -> bool {
+        !self.pin_hash.is_empty()
+    }
+
+    pub fn set_pin(&mut self, pin: &str) -> Result<()> {
+        self.pin_salt = generate_token()?;
+        self.pin_hash = hash_pin(&self.pin_salt, pin);
+        Ok(())
+    }
+
+    pub fn verify_pin(&self, pin: &str) -> bool {
+        self.has_pin() && hash_pin(&self.pin_salt, pin) == self.pin_hash
+    }
+}
+
+fn hash_pin(salt: &str, pin: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(pin.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+impl AppSettings {
+    pub fn default_path() -> PathBuf {
+        app_data_dir().join("settings.toml")
+    }
+
+    /// Loads the settings file, filling in anything that must exist before use
+    /// (such as the control API token) and writing it back when it changed.
+    /// Files with plaintext secrets are rewritten so the secrets get encrypted.
+    pub fn load_or_default() -> Self {
+        let path = Self::default_path();
+        let mut settings = Self::load(&path).unwrap_or_default();
+        let mut dirty = std::fs::read_to_string(&path).is_ok_and(|c| has_plaintext_secrets(&c));
+        if settings.control.enabled && settings.control.token.is_empty() {
+            match generate_token() {
+                Ok(token) => {
+                    settings.control.token = token;
+                    dirty = true;
+                }
+                Err(e) => tracing::warn!("Failed to generate control API token: {e}"),
+            }
+        }
+        if dirty
+            && let Err(e) = settings.save(&path)
+        {
+            tracing::warn!("Failed to save settings: {e}");
+        }
+        settings
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        toml::from_str(&content).map_err(|e| AppError::Config(e.to_string()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string_pretty(self).map_err(|e| AppError::Config(e.to_string()))?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+fn has_plaintext_secrets(content: &str) -> bool {
+    let Ok(table) = content.parse::<toml::Table>() else {
+        return false;
+    };
+    [("control", "token"), ("notify", "webhook_url")].iter().any(|(section, key)| {
+        table
+            .get(*section)
+            .and_then(|s| s.get(*key))
+            .and_then(|v| v.as_str())
+            .is_some_and(secret::needs_migration)
+    })
+}
+
+/// 16 bytes from the system CSPRNG, hex-encoded.
+pub fn generate_token() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| AppError::Config("no randomness available for a token".to_string()))?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_disable_control_api() {
+        let settings = AppSettings::default();
+        assert!(!settings.control.enabled);
+        assert_eq!(settings.control.port, DEFAULT_CONTROL_PORT);
+        assert!(settings.control.token.is_empty());
+    }
+
+    #[test]
+    fn remote_restart_is_off_unless_turned_on() {
+        assert!(!AppSettings::default().host.allow_restart);
+        let settings: AppSettings = toml::from_str("[host]\nport = 9867\n").unwrap();
+        assert!(!settings.host.allow_restart);
+    }
+
+    #[test]
+    fn deserialize_partial_file() {
+        let settings: AppSettings = toml::from_str("[control]\nenabled = true\n").unwrap();
+        assert!(settings.control.enabled);
+        assert_eq!(settings.control.port, DEFAULT_CONTROL_PORT);
+        assert!(!settings.metrics.enabled);
+        assert_eq!(settings.metrics.port, DEFAULT_METRICS_PORT);
+        assert!(settings.clipboard.enabled);
+        assert_eq!(settings.clipboard.max_image_bytes(), 16 * 1024 * 1024);
+    }
+
+    #[test]
+    fn legacy_plaintext_token_still_loads() {
+        let content = "[control]\nenabled = true\ntoken = \"abc123\"\n";
+        let settings: AppSettings = toml::from_str(content).unwrap();
+        assert_eq!(settings.control.token, "abc123");
+        assert_eq!(has_plaintext_secrets(content), cfg!(windows));
+
+        let saved = toml::to_string_pretty(&settings).unwrap();
+        let reloaded: AppSettings = toml::from_str(&saved).unwrap();
+        assert_eq!(reloaded.control.token, "abc123");
+        assert!(!has_plaintext_secrets(&saved));
+    }
+
+    #[test]
+    fn privacy_mode_parses_lowercase() {
+        let settings: AppSettings = toml::from_str("[privacy]\nmode = \"blur\"\nidle_secs = 120\n").unwrap();
+        assert_eq!(settings.privacy.mode, PrivacyMode::Blur);
+        assert!(settings.privacy.on_focus_loss);
+        assert_eq!(settings.privacy.idle_secs, 120);
+    }
+
+    #[test]
+    fn general_settings_parse() {
+        let settings: AppSettings = toml::from_str(
+            "[general]\nresolution = \"1920x1080\"\nlog_level = \"debug\"\nmax_fps = 500\nkeyboard_layout = \"host\"\nupdate_channel = \"beta\"\nviewer_max_fps = 30\n",
+        )
+        .unwrap();
+        assert_eq!(settings.general.resolution, Resolution::Hd1080);
+        assert_eq!(settings.general.log_level, LogLevel::Debug);
+        assert_eq!(settings.general.capture_fps(), MAX_FPS_LIMIT);
+        assert_eq!(settings.general.hello_layout(), None);
+        assert!(settings.general.check_updates);
+        assert_eq!(settings.general.update_channel, UpdateChannel::Beta);
+        assert_eq!(settings.general.delivery(false), (30, DEFAULT_JPEG_QUALITY));
+        assert_eq!(settings.general.delivery(true), (SAVER_FPS, SAVER_JPEG_QUALITY));
+        assert_eq!(Resolution::Native.target_size((800, 600)), None);
+        assert_eq!(Resolution::Window.target_size((800, 600)), Some((800, 600)));
+    }
+
+    #[test]
+    fn notify_configured_when_either_target_set() {
+        let mut notify = NotifySettings::default();
+        assert!(!notify.is_configured());
+        notify.script = "post-url.cmd".to_string();
+        assert!(notify.is_configured());
+        notify.script.clear();
+        notify.webhook_url = "https://example.com/hook".to_string();
+        assert!(notify.is_configured());
+    }
+
+    #[test]
+    fn pin_verification() {
+        let mut lock = LockSettings::default();
+        assert!(!lock.verify_pin(""));
+        lock.set_pin("2468").unwrap();
+        assert!(lock.has_pin());
+        assert_ne!(lock.pin_hash, "2468");
+        assert!(lock.verify_pin("2468"));
+        assert!(!lock.verify_pin("1357"));
+    }
+
+    #[test]
+    fn generated_tokens_are_unique() {
+        let a = generate_token().unwrap();
+        let b = generate_token().unwrap();
+        assert_eq!(a.len(), 32);
+        assert!(a.bytes().all(|b| b.is_ascii_hexdigit()));
+        assert_ne!(a, b);
+    }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::{ConnectionProfile, app_data_dir};
+use crate::error::{AppError, Result};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfileStore {
+    #[serde(default)]
+    pub profiles: Vec<ConnectionProfile>,
+}
+
+impl ProfileStore {
+    pub fn default_path() -> PathBuf {
+        app_data_dir().join("profiles.toml")
+    }
+
+    pub fn load_or_default() -> Self {
+        Self::load(&Self::default_path()).unwrap_or_default()
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        toml::from_str(&content).map_err(|e| AppError::Config(e.to_string()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string_pretty(self).map_err(|e| AppError::Config(e.to_string()))?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn find(&self, host_ip: &str) -> Option<&ConnectionProfile> {
+        self.profiles.iter().find(|p| p.host_ip.eq_ignore_ascii_case(host_ip))
+    }
+
+    /// Adds profiles whose host is not already known and returns how many were added.
+    pub fn merge(&mut self, imported: Vec<ConnectionProfile>) -> usize {
+        let mut added = 0;
+        for profile in imported {
+            if self.find(&profile.host_ip).is_none() {
+                self.profiles.push(profile);
+                added += 1;
+            }
+        }
+        added
+    }
+
+    pub fn upsert(&mut self, profile: ConnectionProfile) {
+        match self
+            .profiles
+            .iter_mut()
+            .find(|p| p.host_ip.eq_ignore_ascii_case(&profile.host_ip))
+        {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(host_ip: &str) -> ConnectionProfile {
+        ConnectionProfile {
+            host_ip: host_ip.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn merge_skips_known_hosts() {
+        let mut store = ProfileStore::default();
+        store.profiles.push(profile("100.64.0.1"));
+        let added = store.merge(vec![profile("100.64.0.1"), profile("100.64.0.2")]);
+        assert_eq!(added, 1);
+        assert_eq!(store.profiles.len(), 2);
+    }
+
+    #[test]
+    fn upsert_replaces_existing() {
+        let mut store = ProfileStore::default();
+        let mut disabled = profile("pc-1");
+        disabled.enabled = false;
+        store.profiles.push(disabled);
+
+        store.upsert(profile("PC-1"));
+        assert_eq!(store.profiles.len(), 1);
+        assert!(store.profiles[0].enabled);
+    }
+
+    #[test]
+    fn save_load_round_trip() {
+        let dir = std::env::temp_dir().join("rust-rdp-test-store");
+        let path = dir.join("profiles.toml");
+        let mut store = ProfileStore::default();
+        store.profiles.push(profile("100.64.0.9"));
+        store.save(&path).unwrap();
+
+        let loaded = ProfileStore::load(&path).unwrap();
+        assert_eq!(loaded.profiles.len(), 1);
+        assert_eq!(loaded.profiles[0].host_ip, "100.64.0.9");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,245 @@
+use std::pin::Pin;
+use std::time::Duration;
+
+use futures::{SinkExt, Stream};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+use crate::network::guard::codes_match;
+
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlCommand {
+    Status,
+    StartHost,
+    StopHost,
+    GetTunnelUrl,
+    Connect(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum ControlResponse {
+    Ok(String),
+    Error(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct ControlRequest {
+    pub command: ControlCommand,
+    reply_tx: mpsc::Sender<ControlResponse>,
+}
+
+impl ControlRequest {
+    pub fn reply(&self, response: ControlResponse) {
+        let _ = self.reply_tx.try_send(response);
+    }
+}
+
+pub fn control_api_subscription(port: u16, token: String) -> iced::Subscription<ControlRequest> {
+    iced::Subscription::run_with((port, token), |(port, token)| {
+        control_api_stream(*port, token.clone())
+    })
+}
+
+fn control_api_stream(port: u16, token: String) -> Pin<Box<dyn Stream<Item = ControlRequest> + Send>> {
+    Box::pin(iced::stream::channel(16, move |output: futures::channel::mpsc::Sender<ControlRequest>| async move {
+        let listener = match TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(l) => l,
+            Err(e) => {
+                tracing::warn!("Control API bind failed on 127.0.0.1:{port}: {e}");
+                std::future::pending::<()>().await;
+                return;
+            }
+        };
+        tracing::info!("Control API listening on 127.0.0.1:{port}");
+
+        loop {
+            let (stream, _) = match listener.accept().await {
+                Ok(s) => s,
+                Err(e) => {
+                    tracing::warn!("Control API accept failed: {e}");
+                    continue;
+                }
+            };
+            let output = output.clone();
+            let token = token.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(stream, &token, output).await {
+                    tracing::warn!("Control API request failed: {e}");
+                }
+            });
+        }
+    }))
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    token: &str,
+    mut output: futures::channel::mpsc::Sender<ControlRequest>,
+) -> std::io::Result<()> {
+    let raw = match tokio::time::timeout(Duration::from_secs(5), read_request(&mut stream)).await {
+        Ok(r) => r?,
+        Err(_) => return Ok(()),
+    };
+
+    let (status, body) = match parse_request(&raw, token) {
+        Err((status, msg)) => (status, error_json(&msg)),
+        Ok(command) => {
+            let (reply_tx, mut reply_rx) = mpsc::channel(1);
+            let request = ControlRequest { command, reply_tx };
+            if output.send(request).await.is_err() {
+                (503, error_json("app is shutting down"))
+            } else {
+                match tokio::time::timeout(Duration::from_secs(5), reply_rx.recv()).await {
+                    Ok(Some(ControlResponse::Ok(result))) => (200, ok_json(&result)),
+                    Ok(Some(ControlResponse::Error(e))) => (409, error_json(&e)),
+                    Ok(None) | Err(_) => (504, error_json("no response from app")),
+                }
+            }
+        }
+    };
+
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        409 => "Conflict",
+        503 => "Service Unavailable",
+        _ => "Gateway Timeout",
+    };
+    let response = format!(
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+pub async fn read_request(stream: &mut TcpStream) -> std::io::Result<String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") && buf.len() < MAX_REQUEST_BYTES {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+fn parse_request(raw: &str, token: &str) -> Result<ControlCommand, (u16, String)> {
+    let mut lines = raw.lines();
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+
+    let authorized = !token.is_empty()
+        && lines.take_while(|l| !l.is_empty()).any(|line| {
+            line.split_once(':').is_some_and(|(name, value)| {
+                name.trim().eq_ignore_ascii_case("authorization")
+                    && value.trim().strip_prefix("Bearer ").is_some_and(|given| codes_match(token, given))
+            })
+        });
+    if !authorized {
+        return Err((401, "missing or invalid bearer token".to_string()));
+    }
+
+    let path = path.trim_start_matches('/');
+    match (method, path) {
+        ("GET", "status") => Ok(ControlCommand::Status),
+        ("GET", "get-tunnel-url") => Ok(ControlCommand::GetTunnelUrl),
+        ("POST", "start-host") => Ok(ControlCommand::StartHost),
+        ("POST", "stop-host") => Ok(ControlCommand::StopHost),
+        ("POST", p) if p.starts_with("connect/") => {
+            let profile = percent_decode(&p["connect/".len()..]);
+            if profile.is_empty() {
+                Err((400, "missing profile name".to_string()))
+            } else {
+                Ok(ControlCommand::Connect(profile))
+            }
+        }
+        _ => Err((404, format!("unknown command: {method} /{path}"))),
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && i + 2 < bytes.len()
+            && let Ok(hex) = std::str::from_utf8(&bytes[i + 1..i + 3])
+            && let Ok(b) = u8::from_str_radix(hex, 16)
+        {
+            out.push(b);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn ok_json(result: &str) -> String {
+    serde_json::json!({ "ok": true, "result": result }).to_string()
+}
+
+fn error_json(error: &str) -> String {
+    serde_json::json!({ "ok": false, "error": error }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, path: &str, token: Option<&str>) -> String {
+        let auth = token
+            .map(|t| format!("Authorization: Bearer {t}\r\n"))
+            .unwrap_or_default();
+        format!("{method} {path} HTTP/1.1\r\nHost: 127.0.0.1\r\n{auth}\r\n")
+    }
+
+    #[test]
+    fn rejects_missing_token() {
+        let err = parse_request(&request("GET", "/status", None), "secret").unwrap_err();
+        assert_eq!(err.0, 401);
+    }
+
+    #[test]
+    fn rejects_wrong_token() {
+        let err = parse_request(&request("GET", "/status", Some("nope")), "secret").unwrap_err();
+        assert_eq!(err.0, 401);
+    }
+
+    #[test]
+    fn empty_configured_token_rejects_everything() {
+        let err = parse_request(&request("GET", "/status", Some("")), "").unwrap_err();
+        assert_eq!(err.0, 401);
+    }
+
+    #[test]
+    fn parses_commands() {
+        let ok = |m, p| parse_request(&request(m, p, Some("secret")), "secret").unwrap();
+        assert_eq!(ok("GET", "/status"), ControlCommand::Status);
+        assert_eq!(ok("POST", "/start-host"), ControlCommand::StartHost);
+        assert_eq!(ok("POST", "/stop-host"), ControlCommand::StopHost);
+        assert_eq!(ok("GET", "/get-tunnel-url"), ControlCommand::GetTunnelUrl);
+        assert_eq!(
+            ok("POST", "/connect/Office%20PC"),
+            ControlCommand::Connect("Office PC".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_route_is_not_found() {
+        let err = parse_request(&request("DELETE", "/start-host", Some("secret")), "secret").unwrap_err();
+        assert_eq!(err.0, 404);
+    }
+}
@@ -0,0 +1,194 @@
+//! Crash reports: a panic hook that saves what it can to `crash_dir()`, and
+//! a zip of the reports and logs to send along with a bug report.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::app_data_dir;
+use crate::logging;
+
+/// Log lines copied into a report.
+const LOG_LINES: usize = 200;
+
+pub fn crash_dir() -> PathBuf {
+    app_data_dir().join("crashes")
+}
+
+/// Stamp of the newest report the user has already been told about.
+fn seen_marker() -> PathBuf {
+    app_data_dir().join(".crash-seen")
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Writes a report for every panic before the default hook prints it.
+pub fn install_hook() {
+    let default = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = info.payload_as_str().unwrap_or("Box<dyn Any>");
+        let location = info.location().map(|l| l.to_string()).unwrap_or_default();
+        let thread = std::thread::current().name().unwrap_or("unnamed").to_string();
+        let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+        let log = logging::tail(&logging::current_log(), LOG_LINES).unwrap_or_default();
+        let report = format_report(&format!("{message} at {location} (thread {thread})"), &backtrace, &log);
+        if let Err(e) = write_report(&crash_dir(), now(), &report) {
+            eprintln!("Cannot save crash report: {e}");
+        }
+        default(info);
+    }));
+}
+
+fn format_report(panic: &str, backtrace: &str, log: &[String]) -> String {
+    let mut report = format!(
+        "rust-rdp {} on {} {}\nPanic: {panic}\n\nBacktrace:\n{backtrace}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+    );
+    report.push_str(&format!("\nLast {} log lines:\n", log.len()));
+    for line in log {
+        report.push_str(line);
+        report.push('\n');
+    }
+    report
+}
+
+fn write_report(dir: &Path, stamp: u64, report: &str) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("crash-{stamp}.txt"));
+    fs::write(&path, report).map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(path)
+}
+
+fn report_stamp(path: &Path) -> Option<u64> {
+    path.file_name()?.to_str()?.strip_prefix("crash-")?.strip_suffix(".txt")?.parse().ok()
+}
+
+/// Reports newer than `seen`, oldest first.
+fn reports_since(dir: &Path, seen: u64) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else { return Vec::new() };
+    let mut reports: Vec<(u64, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter_map(|path| Some((report_stamp(&path)?, path)))
+        .filter(|(stamp, _)| *stamp > seen)
+        .collect();
+    reports.sort();
+    reports.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Reports from crashes since the user last dismissed the banner.
+pub fn pending_reports() -> Vec<PathBuf> {
+    let seen = fs::read_to_string(seen_marker()).ok().and_then(|s| s.trim().parse().ok()).unwrap_or(0);
+    reports_since(&crash_dir(), seen)
+}
+
+pub fn mark_seen(reports: &[PathBuf]) {
+    if let Some(newest) = reports.iter().filter_map(|p| report_stamp(p)).max() {
+        let _ = fs::write(seen_marker(), newest.to_string());
+    }
+}
+
+/// Zips `reports` and the log files into the downloads folder.
+pub fn export_bundle(reports: &[PathBuf]) -> Result<PathBuf, String> {
+    let dir = dirs_next::download_dir().unwrap_or_else(app_data_dir);
+    let path = dir.join(format!("rust-rdp-crash-{}.zip", now()));
+    let mut files: Vec<PathBuf> = reports.to_vec();
+    if let Ok(entries) = fs::read_dir(logging::log_dir()) {
+        files.extend(entries.flatten().map(|e| e.path()).filter(|p| p.extension().is_some_and(|e| e == "log")));
+    }
+    let entries = files
+        .iter()
+        .filter_map(|file| {
+            let name = file.file_name()?.to_string_lossy().into_owned();
+            Some(fs::read(file).map(|data| (name, data)).map_err(|e| format!("{}: {e}", file.display())))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let zip = zip_stored(&entries)?;
+    fs::write(&path, zip).map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(path)
+}
+
+/// An uncompressed zip archive of `entries`.
+fn zip_stored(entries: &[(String, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    // Flag bit 11: names are UTF-8. The date is 1980-01-01.
+    const FLAGS: u16 = 0x0800;
+    const DATE: u16 = 0x0021;
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+    for (name, data) in entries {
+        let size = u32::try_from(data.len()).map_err(|_| format!("{name} is too large to zip"))?;
+        let offset = u32::try_from(out.len()).map_err(|_| "Crash bundle is too large".to_string())?;
+        let crc = crc32fast::hash(data);
+        let mut common = Vec::new();
+        for field in [20u16, FLAGS, 0, 0, DATE] {
+            common.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [crc, size, size] {
+            common.extend_from_slice(&field.to_le_bytes());
+        }
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+
+        out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        out.extend_from_slice(&common);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(data);
+
+        central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        central.extend_from_slice(&20u16.to_le_bytes());
+        central.extend_from_slice(&common);
+        // Comment length, disk, internal and external attributes.
+        central.extend_from_slice(&[0; 10]);
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+    }
+    let count = entries.len() as u16;
+    let central_offset = out.len() as u32;
+    out.extend_from_slice(&central);
+    out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&count.to_le_bytes());
+    out.extend_from_slice(&count.to_le_bytes());
+    out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+    out.extend_from_slice(&central_offset.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_newer_than_the_marker_are_pending() {
+        let dir = std::env::temp_dir().join(format!("rust-rdp-crash-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let report = format_report("boom at src/main.rs:1:1", "0: main", &["INFO started".to_string()]);
+        assert!(report.contains("Panic: boom") && report.ends_with("INFO started\n"));
+        let first = write_report(&dir, 100, &report).unwrap();
+        let second = write_report(&dir, 200, &report).unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+
+        assert_eq!(reports_since(&dir, 0), [first, second.clone()]);
+        assert_eq!(reports_since(&dir, 100), [second]);
+        assert!(reports_since(&dir, 200).is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stored_zip_layout() {
+        let zip = zip_stored(&[("a.txt".to_string(), b"abc".to_vec())]).unwrap();
+        assert_eq!(&zip[..4], b"PK\x03\x04");
+        // CRC-32 of "abc".
+        assert_eq!(&zip[14..18], &0x3524_41c2u32.to_le_bytes());
+        assert_eq!(&zip[30..38], b"a.txtabc");
+        let end = zip.len() - 22;
+        assert_eq!(&zip[end..end + 4], b"PK\x05\x06");
+        assert_eq!(u16::from_le_bytes([zip[end + 10], zip[end + 11]]), 1);
+        assert_eq!(u32::from_le_bytes(zip[end + 16..end + 20].try_into().unwrap()), 38);
+    }
+}
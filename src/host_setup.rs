@@ -0,0 +1,91 @@
+//! Pre-flight for hosting: viewers can only reach the listening port if
+//! Windows Firewall lets them in.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FirewallCheck {
+    #[cfg_attr(not(windows), allow(dead_code))]
+    Allowed,
+    /// No rule of ours for the port; Windows may block viewers.
+    Missing,
+    /// Not Windows, or `netsh` could not be asked.
+    Unknown,
+}
+
+/// One rule per port, so changing the port never leaves a stale rule looking valid.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn rule_name(port: u16) -> String {
+    format!("Rust RDP (TCP {port})")
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+fn add_rule_args(port: u16) -> String {
+    format!(
+        "advfirewall firewall add rule name=\"{}\" dir=in action=allow protocol=TCP localport={port}",
+        rule_name(port)
+    )
+}
+
+#[cfg(windows)]
+fn hidden(program: &str) -> tokio::process::Command {
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let mut cmd = tokio::process::Command::new(program);
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+#[cfg(windows)]
+pub async fn check_firewall(port: u16) -> FirewallCheck {
+    let name = format!("name={}", rule_name(port));
+    match hidden("netsh").args(["advfirewall", "firewall", "show", "rule", &name]).output().await {
+        Ok(output) if output.status.success() => FirewallCheck::Allowed,
+        // netsh exits non-zero when no rule matches.
+        Ok(_) => FirewallCheck::Missing,
+        Err(e) => {
+            tracing::warn!("Could not check the firewall: {e}");
+            FirewallCheck::Unknown
+        }
+    }
+}
+
+#[cfg(not(windows))]
+pub async fn check_firewall(_port: u16) -> FirewallCheck {
+    FirewallCheck::Unknown
+}
+
+/// Adds an inbound rule for `port`, asking for elevation through UAC.
+#[cfg(windows)]
+pub async fn allow_port(port: u16) -> Result<(), String> {
+    // Single quotes for PowerShell; the rule name keeps its double quotes for netsh.
+    let script = format!(
+        "Start-Process netsh -Verb RunAs -Wait -WindowStyle Hidden -ArgumentList '{}'",
+        add_rule_args(port)
+    );
+    let status = hidden("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .status()
+        .await
+        .map_err(|e| format!("Could not start PowerShell: {e}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err("Windows did not allow the firewall change".to_string())
+    }
+}
+
+#[cfg(not(windows))]
+pub async fn allow_port(_port: u16) -> Result<(), String> {
+    Err("Firewall rules are only managed on Windows".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rule_is_named_after_its_port() {
+        let args = add_rule_args(9867);
+        assert!(args.contains("name=\"Rust RDP (TCP 9867)\""));
+        assert!(args.ends_with("localport=9867"));
+        assert!(!args.contains('\''));
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::protocol::ProtocolMessage;
+
+pub const CTRL: u32 = 0x1D;
+pub const SHIFT: u32 = 0x2A;
+pub const ALT: u32 = 0x38;
+pub const WIN: u32 = 0xE05B;
+pub const ESCAPE: u32 = 0x01;
+pub const TAB: u32 = 0x0F;
+pub const DELETE: u32 = 0xE053;
+pub const KEY_L: u32 = 0x26;
+pub const KEY_R: u32 = 0x13;
+pub const ENTER: u32 = 0x1C;
+
+/// Time for the lock screen to show the password box after it is woken.
+pub const UNLOCK_WAKE_DELAY: Duration = Duration::from_millis(1500);
+
+/// A bare Ctrl lifts the lock screen curtain without typing anything.
+pub fn unlock_wake() -> ProtocolMessage {
+    ProtocolMessage::KeyCombo(vec![CTRL])
+}
+
+/// The password typed as text, then Enter; sent after `UNLOCK_WAKE_DELAY`.
+pub fn unlock_input(password: &str) -> Vec<ProtocolMessage> {
+    password
+        .chars()
+        .map(ProtocolMessage::UnicodeChar)
+        .chain(std::iter::once(ProtocolMessage::KeyCombo(vec![ENTER])))
+        .collect()
+}
+
+/// Shortcuts the local OS grabs before the viewer sees them, sent from the
+/// toolbar instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCombo {
+    CtrlAltDel,
+    CtrlShiftEsc,
+    AltTab,
+    WinL,
+    WinR,
+}
+
+impl KeyCombo {
+    pub const ALL: [Self; 5] = [Self::CtrlAltDel, Self::CtrlShiftEsc, Self::AltTab, Self::WinL, Self::WinR];
+
+    /// Scancodes in press order; they are released in reverse.
+    pub fn scancodes(self) -> Vec<u32> {
+        match self {
+            Self::CtrlAltDel => vec![CTRL, ALT, DELETE],
+            Self::CtrlShiftEsc => vec![CTRL, SHIFT, ESCAPE],
+            Self::AltTab => vec![ALT, TAB],
+            Self::WinL => vec![WIN, KEY_L],
+            Self::WinR => vec![WIN, KEY_R],
+        }
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::CtrlAltDel => "Ctrl+Alt+Del",
+            Self::CtrlShiftEsc => "Ctrl+Shift+Esc",
+            Self::AltTab => "Alt+Tab",
+            Self::WinL => "Win+L (lock)",
+            Self::WinR => "Win+R (run)",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combos_start_with_a_modifier() {
+        for combo in KeyCombo::ALL {
+            let codes = combo.scancodes();
+            assert!(codes.len() >= 2);
+            assert!([CTRL, ALT, WIN].contains(&codes[0]), "{combo}");
+        }
+    }
+
+    #[test]
+    fn unlock_types_password_then_enter() {
+        let input = unlock_input("pä1");
+        assert_eq!(input.len(), 4);
+        assert!(matches!(input[1], ProtocolMessage::UnicodeChar('ä')));
+        assert!(matches!(&input[3], ProtocolMessage::KeyCombo(codes) if codes == &[ENTER]));
+    }
+}
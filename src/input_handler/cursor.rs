@@ -0,0 +1,254 @@
+use std::time::Duration;
+
+use enigo::{Enigo, Mouse, Settings};
+use tokio::sync::mpsc;
+
+use crate::protocol::CursorImage;
+
+const CURSOR_POLL: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub enum CursorUpdate {
+    Moved((i32, i32)),
+    Shape(Option<CursorImage>),
+}
+
+/// Sends the host pointer's absolute position whenever it moves, so the
+/// viewer can show where the person at the host is pointing, and its image
+/// whenever that changes. Runs on a blocking thread until the receiver is
+/// dropped.
+pub fn watch(tx: mpsc::Sender<CursorUpdate>) {
+    let enigo = match Enigo::new(&Settings::default()) {
+        Ok(enigo) => enigo,
+        Err(e) => {
+            tracing::warn!("Host cursor unavailable: {e}");
+            return;
+        }
+    };
+    let mut last = None;
+    let mut shapes = ShapeWatch::default();
+    while !tx.is_closed() {
+        if let Some(shape) = shapes.poll()
+            && tx.blocking_send(CursorUpdate::Shape(shape)).is_err()
+        {
+            return;
+        }
+        if let Ok(position) = enigo.location()
+            && last != Some(position)
+        {
+            last = Some(position);
+            if tx.blocking_send(CursorUpdate::Moved(position)).is_err() {
+                return;
+            }
+        }
+        std::thread::sleep(CURSOR_POLL);
+    }
+}
+
+/// Reports the pointer image when it differs from the last poll.
+#[derive(Default)]
+struct ShapeWatch {
+    /// The cursor handle, or 0 while hidden; `None` until the first poll.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    last: Option<isize>,
+}
+
+impl ShapeWatch {
+    #[cfg(windows)]
+    fn poll(&mut self) -> Option<Option<CursorImage>> {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{CURSOR_SHOWING, CURSORINFO, GetCursorInfo};
+
+        let mut info: CURSORINFO = unsafe { std::mem::zeroed() };
+        info.cbSize = std::mem::size_of::<CURSORINFO>() as u32;
+        // SAFETY: `info` is a CURSORINFO with cbSize set, as the call requires.
+        if unsafe { GetCursorInfo(&mut info) } == 0 {
+            return None;
+        }
+        let handle = if info.flags & CURSOR_SHOWING != 0 { info.hCursor } else { 0 };
+        if self.last == Some(handle) {
+            return None;
+        }
+        self.last = Some(handle);
+        if handle == 0 {
+            return Some(None);
+        }
+        match read_shape(handle) {
+            Some(image) => Some(Some(image)),
+            None => {
+                // Leave the viewer's pointer as it was rather than hide it.
+                tracing::debug!("Could not read the host pointer image");
+                None
+            }
+        }
+    }
+
+    #[cfg(not(windows))]
+    fn poll(&mut self) -> Option<Option<CursorImage>> {
+        None
+    }
+}
+
+/// Reads a bitmap as top-down BGRA, whatever its own format.
+#[cfg(windows)]
+fn bitmap_bgra(
+    dc: windows_sys::Win32::Graphics::Gdi::HDC,
+    bitmap: windows_sys::Win32::Graphics::Gdi::HBITMAP,
+) -> Option<(Vec<u8>, u32, u32)> {
+    use windows_sys::Win32::Graphics::Gdi::{
+        BI_RGB, BITMAP, BITMAPINFO, BITMAPINFOHEADER, DIB_RGB_COLORS, GetDIBits, GetObjectW,
+    };
+
+    let mut bm: BITMAP = unsafe { std::mem::zeroed() };
+    // SAFETY: `bm` is a BITMAP of the size passed.
+    if unsafe { GetObjectW(bitmap, std::mem::size_of::<BITMAP>() as i32, &mut bm as *mut _ as *mut _) } == 0 {
+        return None;
+    }
+    let (width, height) = (bm.bmWidth.max(0) as u32, bm.bmHeight.max(0) as u32);
+    if width == 0 || height == 0 || width > 512 || height > 1024 {
+        return None;
+    }
+    let mut info: BITMAPINFO = unsafe { std::mem::zeroed() };
+    info.bmiHeader = BITMAPINFOHEADER {
+        biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+        biWidth: width as i32,
+        biHeight: -(height as i32),
+        biPlanes: 1,
+        biBitCount: 32,
+        biCompression: BI_RGB,
+        ..unsafe { std::mem::zeroed() }
+    };
+    let mut pixels = vec![0u8; (width * height * 4) as usize];
+    // SAFETY: `pixels` holds `height` rows of 32-bit pixels, as `info` describes.
+    let lines = unsafe {
+        GetDIBits(dc, bitmap, 0, height, pixels.as_mut_ptr() as *mut _, &mut info, DIB_RGB_COLORS)
+    };
+    (lines == height as i32).then_some((pixels, width, height))
+}
+
+#[cfg(windows)]
+fn read_shape(handle: isize) -> Option<CursorImage> {
+    use windows_sys::Win32::Graphics::Gdi::{DeleteObject, GetDC, ReleaseDC};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetIconInfo, ICONINFO};
+
+    let mut icon: ICONINFO = unsafe { std::mem::zeroed() };
+    // SAFETY: `handle` came from GetCursorInfo and `icon` is an ICONINFO.
+    if unsafe { GetIconInfo(handle, &mut icon) } == 0 {
+        return None;
+    }
+    // SAFETY: a screen DC, released below.
+    let dc = unsafe { GetDC(0) };
+    let mask = bitmap_bgra(dc, icon.hbmMask);
+    let color = if icon.hbmColor != 0 { bitmap_bgra(dc, icon.hbmColor) } else { None };
+    // SAFETY: GetIconInfo hands over both bitmaps; they are ours to delete.
+    unsafe {
+        ReleaseDC(0, dc);
+        DeleteObject(icon.hbmMask);
+        if icon.hbmColor != 0 {
+            DeleteObject(icon.hbmColor);
+        }
+    }
+
+    let (mask, mask_width, mask_height) = mask?;
+    let (rgba, width, height) = match color {
+        Some((bgra, width, height)) => (color_cursor_rgba(&bgra, &mask), width, height),
+        None => (mono_cursor_rgba(&mask), mask_width, mask_height / 2),
+    };
+    Some(CursorImage {
+        width: width as u16,
+        height: height as u16,
+        hot_x: icon.xHotspot as u16,
+        hot_y: icon.yHotspot as u16,
+        rgba,
+    })
+}
+
+/// A colour cursor: its own alpha when it has any, otherwise opaque wherever
+/// the AND mask is clear.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn color_cursor_rgba(bgra: &[u8], mask: &[u8]) -> Vec<u8> {
+    let has_alpha = bgra.chunks_exact(4).any(|p| p[3] != 0);
+    bgra.chunks_exact(4)
+        .enumerate()
+        .flat_map(|(i, p)| {
+            let alpha = if has_alpha {
+                p[3]
+            } else if mask.get(i * 4).is_some_and(|&m| m != 0) {
+                0
+            } else {
+                255
+            };
+            [p[2], p[1], p[0], alpha]
+        })
+        .collect()
+}
+
+/// A monochrome cursor: the mask is twice as tall, AND bits over XOR bits.
+/// Inverted pixels, which an image cannot show, are drawn black.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn mono_cursor_rgba(mask: &[u8]) -> Vec<u8> {
+    let (and, xor) = mask.split_at(mask.len() / 2);
+    and.chunks_exact(4)
+        .zip(xor.chunks_exact(4))
+        .flat_map(|(a, x)| match (a[0] != 0, x[0] != 0) {
+            (false, false) => [0, 0, 0, 255],
+            (false, true) => [255, 255, 255, 255],
+            (true, false) => [0, 0, 0, 0],
+            (true, true) => [0, 0, 0, 255],
+        })
+        .collect()
+}
+
+/// `position` on `screen` (left, top, width, height) as 0..=u16::MAX on each
+/// axis, like `MouseMove`; `None` when it is on another monitor.
+pub fn normalize(position: (i32, i32), screen: (i32, i32, i32, i32)) -> Option<(u16, u16)> {
+    let (left, top, width, height) = screen;
+    let axis = |value: i32, start: i32, extent: i32| {
+        let offset = value - start;
+        if !(0..extent).contains(&offset) {
+            return None;
+        }
+        let max = (extent - 1).max(1) as i64;
+        Some((offset as i64 * u16::MAX as i64 / max) as u16)
+    };
+    Some((axis(position.0, left, width)?, axis(position.1, top, height)?))
+}
+
+/// Whether two normalized positions land on the same screen pixel, give or
+/// take rounding. Used to skip echoes of the viewer's own pointer moves.
+pub fn same_pixel(a: (u16, u16), b: (u16, u16), screen: (i32, i32, i32, i32)) -> bool {
+    let (_, _, width, height) = screen;
+    let close = |a: u16, b: u16, extent: i32| a.abs_diff(b) as i32 <= u16::MAX as i32 / extent.max(1) + 1;
+    close(a.0, b.0, width) && close(a.1, b.1, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_on_the_shared_monitor_only() {
+        let screen = (1920, 0, 1920, 1080);
+        assert_eq!(normalize((1920, 0), screen), Some((0, 0)));
+        assert_eq!(normalize((3839, 1079), screen), Some((u16::MAX, u16::MAX)));
+        assert_eq!(normalize((100, 100), screen), None);
+        assert!(same_pixel((1000, 1000), (1030, 1050), screen));
+        assert!(!same_pixel((1000, 1000), (2000, 1000), screen));
+    }
+
+    #[test]
+    fn cursor_bitmaps_become_rgba() {
+        // One pixel of each AND/XOR combination: black, white, clear, inverted.
+        let and = [0, 0, 0, 0, 0, 0, 0, 0, 255, 255, 255, 0, 255, 255, 255, 0];
+        let xor = [0, 0, 0, 0, 255, 255, 255, 0, 0, 0, 0, 0, 255, 255, 255, 0];
+        let mask = [&and[..], &xor[..]].concat();
+        assert_eq!(
+            mono_cursor_rgba(&mask),
+            [0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 255]
+        );
+
+        let bgra = [10, 20, 30, 0, 10, 20, 30, 0];
+        let mask = [0, 0, 0, 0, 255, 255, 255, 0];
+        assert_eq!(color_cursor_rgba(&bgra, &mask), [30, 20, 10, 255, 30, 20, 10, 0]);
+        assert_eq!(color_cursor_rgba(&[10, 20, 30, 128], &[255; 4]), [30, 20, 10, 128]);
+    }
+}
@@ -1,22 +1,35 @@
 use enigo::{Button, Coordinate, Direction, Enigo, Key, Keyboard, Mouse, Settings};
 
-use crate::protocol::ProtocolMessage;
+use super::combo;
+use crate::protocol::{DisplayInfo, ProtocolMessage};
 
 pub struct InputHandler {
     enigo: Enigo,
+    displays: Vec<DisplayInfo>,
+    /// Desktop rectangle of the streamed display as (x, y, width, height).
+    screen: (i32, i32, i32, i32),
 }
 
 impl InputHandler {
-    pub fn new() -> Result<Self, String> {
+    pub fn new(displays: Vec<DisplayInfo>) -> Result<Self, String> {
         let enigo = Enigo::new(&Settings::default())
             .map_err(|e| format!("Failed to create Enigo: {e}"))?;
-        Ok(Self { enigo })
+        let (width, height) = enigo.main_display().unwrap_or((1920, 1080));
+        Ok(Self { enigo, displays, screen: (0, 0, width, height) })
     }
 
     pub fn apply(&mut self, msg: &ProtocolMessage) {
         match msg {
             ProtocolMessage::MouseMove { x, y } => {
-                let _ = self.enigo.move_mouse(*x as i32, *y as i32, Coordinate::Abs);
+                let (left, top, width, height) = self.screen;
+                let x = left + denormalize_axis(*x, width);
+                let y = top + denormalize_axis(*y, height);
+                let _ = self.enigo.move_mouse(x, y, Coordinate::Abs);
+            }
+            ProtocolMessage::SelectDisplay(index) => {
+                if let Some(d) = self.displays.get(*index as usize) {
+                    self.screen = (d.x, d.y, d.width as i32, d.height as i32);
+                }
             }
             ProtocolMessage::MouseButton { button, pressed } => {
                 if let Some(btn) = protocol_btn_to_enigo(button) {
@@ -50,16 +63,48 @@ impl InputHandler {
                     let _ = self.enigo.key(key, dir);
                 }
             }
+            ProtocolMessage::KeyCombo(codes) => self.key_combo(codes),
+            ProtocolMessage::UnicodeChar(c) => {
+                let _ = self.enigo.key(Key::Unicode(*c), Direction::Click);
+            }
             _ => {}
         }
     }
+
+    fn key_combo(&mut self, codes: &[u32]) {
+        // Windows ignores a synthesized Win+L, so lock the session directly.
+        if cfg!(windows) && codes == [combo::WIN, combo::KEY_L] {
+            lock_workstation();
+            return;
+        }
+        let keys: Vec<Key> = codes.iter().filter_map(|&code| scancode_to_enigo_key(code)).collect();
+        for key in &keys {
+            let _ = self.enigo.key(*key, Direction::Press);
+        }
+        for key in keys.iter().rev() {
+            let _ = self.enigo.key(*key, Direction::Release);
+        }
+    }
+}
+
+#[cfg(windows)]
+fn lock_workstation() {
+    // SAFETY: LockWorkStation takes no arguments.
+    unsafe {
+        windows_sys::Win32::System::Shutdown::LockWorkStation();
+    }
 }
 
+#[cfg(not(windows))]
+fn lock_workstation() {}
+
 fn protocol_btn_to_enigo(btn: &crate::protocol::MouseBtn) -> Option<Button> {
     match btn {
         crate::protocol::MouseBtn::Left => Some(Button::Left),
         crate::protocol::MouseBtn::Right => Some(Button::Right),
         crate::protocol::MouseBtn::Middle => Some(Button::Middle),
+        crate::protocol::MouseBtn::Back => Some(Button::Back),
+        crate::protocol::MouseBtn::Forward => Some(Button::Forward),
     }
 }
 
@@ -146,7 +191,14 @@ fn scancode_to_enigo_key(keycode: u32) -> Option<Key> {
         0xE051 => Some(Key::PageDown),
         0xE052 => Some(Key::Insert),
         0xE053 => Some(Key::Delete),
+        0xE05B => Some(Key::Meta),
         0xE11D => Some(Key::Pause),
         _ => None,
     }
 }
+
+/// Maps a normalized 0..=u16::MAX coordinate onto `extent` screen pixels.
+fn denormalize_axis(value: u16, extent: i32) -> i32 {
+    let max = (extent - 1).max(0) as i64;
+    (value as i64 * max / u16::MAX as i64) as i32
+}
@@ -0,0 +1,194 @@
+/// Keyboard layouts are exchanged as Windows KLID strings (e.g. `00000409` for US English),
+/// the same identifiers `LoadKeyboardLayoutW` accepts.
+pub fn is_valid_klid(klid: &str) -> bool {
+    klid.len() == 8 && klid.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Layouts offered by name in the connect form.
+pub const COMMON_LAYOUTS: &[(&str, &str)] = &[
+    ("00000409", "English (US)"),
+    ("00010409", "English (US, Dvorak)"),
+    ("00000809", "English (UK)"),
+    ("00000407", "German"),
+    ("00000807", "German (Switzerland)"),
+    ("0000040C", "French"),
+    ("0000100C", "French (Switzerland)"),
+    ("0000080C", "French (Belgium)"),
+    ("0000040A", "Spanish"),
+    ("00000410", "Italian"),
+    ("00000816", "Portuguese"),
+    ("00000416", "Portuguese (Brazil)"),
+    ("00000413", "Dutch"),
+    ("00000406", "Danish"),
+    ("0000041D", "Swedish"),
+    ("00000414", "Norwegian"),
+    ("0000040B", "Finnish"),
+    ("00000415", "Polish"),
+    ("00000405", "Czech"),
+    ("0000040E", "Hungarian"),
+    ("0000041F", "Turkish"),
+    ("00000419", "Russian"),
+    ("00000422", "Ukrainian"),
+    ("00000411", "Japanese"),
+    ("00000412", "Korean"),
+    ("00000804", "Chinese (Simplified)"),
+];
+
+/// Layout to announce in the Hello for a layout setting: empty detects this
+/// PC's layout, `host` leaves the host's alone, anything else is a KLID.
+pub fn hello_layout(setting: &str) -> Option<String> {
+    match setting.trim() {
+        "" => local_layout_id(),
+        "host" => None,
+        klid => Some(klid.to_string()),
+    }
+}
+
+/// A connection's layout, stored in its profile as a layout setting string,
+/// or nothing to follow the app-wide setting.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum LayoutChoice {
+    #[default]
+    FollowSettings,
+    Detect,
+    Host,
+    Klid(String),
+}
+
+impl LayoutChoice {
+    pub fn options() -> Vec<Self> {
+        let mut options = vec![Self::FollowSettings, Self::Detect, Self::Host];
+        options.extend(COMMON_LAYOUTS.iter().map(|(klid, _)| Self::Klid(klid.to_string())));
+        options
+    }
+
+    pub fn from_setting(setting: Option<&str>) -> Self {
+        match setting.map(str::trim) {
+            None => Self::FollowSettings,
+            Some("") => Self::Detect,
+            Some("host") => Self::Host,
+            Some(klid) => Self::Klid(klid.to_ascii_uppercase()),
+        }
+    }
+
+    pub fn to_setting(&self) -> Option<String> {
+        match self {
+            Self::FollowSettings => None,
+            Self::Detect => Some(String::new()),
+            Self::Host => Some("host".to_string()),
+            Self::Klid(klid) => Some(klid.clone()),
+        }
+    }
+}
+
+impl std::fmt::Display for LayoutChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FollowSettings => f.write_str("App setting"),
+            Self::Detect => f.write_str("This PC's layout"),
+            Self::Host => f.write_str("Keep the host's layout"),
+            Self::Klid(klid) => match COMMON_LAYOUTS.iter().find(|(k, _)| k == klid) {
+                Some((_, name)) => f.write_str(name),
+                None => write!(f, "Layout {klid}"),
+            },
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+        GetKeyboardLayout, GetKeyboardLayoutNameW, KLF_ACTIVATE, LoadKeyboardLayoutW,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetForegroundWindow, KL_NAMELENGTH, PostMessageW, WM_INPUTLANGCHANGEREQUEST,
+    };
+
+    /// The full KLID, so variants such as US-Dvorak (`00010409`) are not
+    /// reported as their base language's layout.
+    pub fn local_layout_id() -> Option<String> {
+        let mut name = [0u16; KL_NAMELENGTH as usize];
+        if unsafe { GetKeyboardLayoutNameW(name.as_mut_ptr()) } == 0 {
+            return None;
+        }
+        let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+        let klid = String::from_utf16(&name[..len]).ok()?.to_ascii_uppercase();
+        super::is_valid_klid(&klid).then_some(klid)
+    }
+
+    fn request_layout(hkl: isize) {
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd != 0 {
+                PostMessageW(hwnd, WM_INPUTLANGCHANGEREQUEST, 0, hkl);
+            }
+        }
+    }
+
+    pub struct LayoutGuard {
+        previous: isize,
+    }
+
+    impl Drop for LayoutGuard {
+        fn drop(&mut self) {
+            request_layout(self.previous);
+        }
+    }
+
+    pub fn activate_layout(klid: &str) -> Result<LayoutGuard, String> {
+        let previous = unsafe { GetKeyboardLayout(0) };
+        let wide: Vec<u16> = klid.encode_utf16().chain(std::iter::once(0)).collect();
+        let hkl = unsafe { LoadKeyboardLayoutW(wide.as_ptr(), KLF_ACTIVATE) };
+        if hkl == 0 {
+            return Err(format!("keyboard layout {klid} is not installed"));
+        }
+        request_layout(hkl);
+        Ok(LayoutGuard { previous })
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    pub fn local_layout_id() -> Option<String> {
+        None
+    }
+
+    pub struct LayoutGuard;
+
+    pub fn activate_layout(_klid: &str) -> Result<LayoutGuard, String> {
+        Err("keyboard layout switching is only supported on Windows".to_string())
+    }
+}
+
+pub use platform::{activate_layout, local_layout_id};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn klid_validation() {
+        assert!(is_valid_klid("00000409"));
+        assert!(is_valid_klid("0000040c"));
+        assert!(!is_valid_klid("409"));
+        assert!(!is_valid_klid("0000040G"));
+    }
+
+    #[test]
+    fn choice_round_trips_through_profile_setting() {
+        for choice in LayoutChoice::options() {
+            assert_eq!(LayoutChoice::from_setting(choice.to_setting().as_deref()), choice);
+        }
+        assert_eq!(LayoutChoice::from_setting(Some("0000040c")), LayoutChoice::Klid("0000040C".to_string()));
+        assert_eq!(LayoutChoice::Klid("0000040C".to_string()).to_string(), "French");
+        assert!(COMMON_LAYOUTS.iter().all(|(klid, _)| is_valid_klid(klid)));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn local_layout_is_a_full_klid() {
+        if let Some(klid) = local_layout_id() {
+            assert!(is_valid_klid(&klid));
+        }
+    }
+}
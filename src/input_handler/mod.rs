@@ -1,2 +1,5 @@
+pub mod combo;
+pub mod cursor;
 pub mod handler;
+pub mod layout;
 pub mod translate;
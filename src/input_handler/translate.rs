@@ -8,6 +8,15 @@ pub fn iced_key_to_keycode(key: &iced::keyboard::Key) -> Option<u32> {
     }
 }
 
+/// Characters with no US scancode (ä, é, ß, ...), sent as text instead of
+/// key presses.
+pub fn unmapped_text(key: &iced::keyboard::Key) -> Option<&str> {
+    match key {
+        iced::keyboard::Key::Character(c) if char_to_keycode(c.as_str()).is_none() => Some(c.as_str()),
+        _ => None,
+    }
+}
+
 fn named_key_to_keycode(key: &iced::keyboard::key::Named) -> Option<u32> {
     use iced::keyboard::key::Named;
     let code = match key {
@@ -30,6 +39,7 @@ fn named_key_to_keycode(key: &iced::keyboard::key::Named) -> Option<u32> {
         Named::Shift => 0x2A,
         Named::Control => 0x1D,
         Named::Alt => 0x38,
+        Named::Super => 0xE05B,
         Named::CapsLock => 0x3A,
         Named::Space => 0x39,
         Named::PageUp => 0xE049,
@@ -114,7 +124,9 @@ pub fn mouse_button_to_protocol(btn: &iced::mouse::Button) -> Option<MouseBtn> {
         iced::mouse::Button::Left => Some(MouseBtn::Left),
         iced::mouse::Button::Right => Some(MouseBtn::Right),
         iced::mouse::Button::Middle => Some(MouseBtn::Middle),
-        _ => None,
+        iced::mouse::Button::Back => Some(MouseBtn::Back),
+        iced::mouse::Button::Forward => Some(MouseBtn::Forward),
+        iced::mouse::Button::Other(_) => None,
     }
 }
 
@@ -124,6 +136,13 @@ mod tests {
     use iced::keyboard::key::Named;
     use iced::keyboard::Key;
 
+    #[test]
+    fn unmapped_characters_fall_back_to_text() {
+        assert_eq!(unmapped_text(&Key::Character("ä".into())), Some("ä"));
+        assert_eq!(unmapped_text(&Key::Character("a".into())), None);
+        assert_eq!(unmapped_text(&Key::Named(Named::Enter)), None);
+    }
+
     #[test]
     fn keycode_enter() {
         assert_eq!(
@@ -225,6 +244,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn mouse_button_side_buttons() {
+        assert_eq!(
+            mouse_button_to_protocol(&iced::mouse::Button::Back),
+            Some(MouseBtn::Back)
+        );
+        assert_eq!(
+            mouse_button_to_protocol(&iced::mouse::Button::Forward),
+            Some(MouseBtn::Forward)
+        );
+    }
+
     #[test]
     fn mouse_button_other_returns_none() {
         assert_eq!(
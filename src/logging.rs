@@ -0,0 +1,193 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tracing::Level;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::prelude::*;
+
+use crate::config::app_data_dir;
+use crate::config::settings::LogLevel;
+
+const LOG_NAME: &str = "rust-rdp";
+/// The current file is rotated once it grows past this.
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+/// Rotated files kept next to the current one.
+const KEEP_ROTATED: usize = 4;
+/// How much of the end of the current file the log screen reads.
+const TAIL_BYTES: u64 = 256 * 1024;
+
+/// Set while the viewer is in password mode.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn log_dir() -> PathBuf {
+    app_data_dir().join("logs")
+}
+
+pub fn current_log() -> PathBuf {
+    log_dir().join(format!("{LOG_NAME}.log"))
+}
+
+/// Logs to stdout and, since the release build has no console, to a
+/// rotating file under `log_dir()`.
+pub fn init(level: LevelFilter) {
+    let file = match RollingFile::open(&log_dir()) {
+        Ok(file) => Some(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(Mutex::new(file)),
+        ),
+        Err(e) => {
+            eprintln!("File logging disabled: {e}");
+            None
+        }
+    };
+    tracing_subscriber::registry()
+        .with(level)
+        .with(tracing_subscriber::filter::filter_fn(|meta| passes(*meta.level(), QUIET.load(Ordering::Relaxed))))
+        .with(tracing_subscriber::fmt::layer())
+        .with(file)
+        .init();
+}
+
+/// Drops everything below warnings until turned off again, so nothing
+/// logged while a secret is typed can carry it into the log.
+pub fn set_quiet(quiet: bool) {
+    if QUIET.swap(quiet, Ordering::Relaxed) != quiet {
+        match quiet {
+            true => tracing::warn!("Password mode on; only warnings and errors are logged"),
+            false => tracing::warn!("Password mode off; logging resumed"),
+        }
+    }
+}
+
+fn passes(level: Level, quiet: bool) -> bool {
+    !quiet || level <= Level::WARN
+}
+
+/// `rust-rdp.log`, moved to `rust-rdp.1.log` (and so on) when it fills up.
+struct RollingFile {
+    dir: PathBuf,
+    file: File,
+    written: u64,
+}
+
+impl RollingFile {
+    fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = OpenOptions::new().create(true).append(true).open(rotated_path(dir, 0))?;
+        let written = file.metadata()?.len();
+        Ok(Self { dir: dir.to_path_buf(), file, written })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let _ = fs::remove_file(rotated_path(&self.dir, KEEP_ROTATED));
+        for index in (0..KEEP_ROTATED).rev() {
+            let from = rotated_path(&self.dir, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.dir, index + 1))?;
+            }
+        }
+        self.file = OpenOptions::new().create(true).append(true).open(rotated_path(&self.dir, 0))?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RollingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > MAX_LOG_BYTES {
+            self.rotate()?;
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn rotated_path(dir: &Path, index: usize) -> PathBuf {
+    match index {
+        0 => dir.join(format!("{LOG_NAME}.log")),
+        n => dir.join(format!("{LOG_NAME}.{n}.log")),
+    }
+}
+
+/// The last `max_lines` lines of `path`.
+pub fn tail(path: &Path, max_lines: usize) -> Result<Vec<String>, String> {
+    let mut file = File::open(path).map_err(|e| format!("Cannot open {}: {e}", path.display()))?;
+    let len = file.metadata().map_err(|e| e.to_string())?.len();
+    let start = len.saturating_sub(TAIL_BYTES);
+    file.seek(SeekFrom::Start(start)).map_err(|e| e.to_string())?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+    let text = String::from_utf8_lossy(&bytes);
+    let mut lines: Vec<&str> = text.lines().collect();
+    // The first line is usually cut in half when reading from the middle.
+    if start > 0 && !lines.is_empty() {
+        lines.remove(0);
+    }
+    let skip = lines.len().saturating_sub(max_lines);
+    Ok(lines[skip..].iter().map(|l| l.to_string()).collect())
+}
+
+/// The level `fmt` wrote on `line`; `None` for continuation lines.
+pub fn line_level(line: &str) -> Option<LogLevel> {
+    line.split_whitespace().take(3).find_map(|word| match word {
+        "ERROR" => Some(LogLevel::Error),
+        "WARN" => Some(LogLevel::Warn),
+        "INFO" => Some(LogLevel::Info),
+        "DEBUG" => Some(LogLevel::Debug),
+        "TRACE" => Some(LogLevel::Trace),
+        _ => None,
+    })
+}
+
+pub fn open_folder(dir: &Path) -> Result<(), String> {
+    let program = if cfg!(windows) { "explorer" } else { "xdg-open" };
+    std::process::Command::new(program)
+        .arg(dir)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Cannot open {}: {e}", dir.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quiet_keeps_only_warnings_and_errors() {
+        assert!(passes(Level::DEBUG, false));
+        assert!(!passes(Level::INFO, true));
+        assert!(!passes(Level::TRACE, true));
+        assert!(passes(Level::WARN, true) && passes(Level::ERROR, true));
+    }
+
+    #[test]
+    fn rotates_and_drops_the_oldest_file() {
+        let dir = std::env::temp_dir().join(format!("rust-rdp-logs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut file = RollingFile::open(&dir).unwrap();
+        let line = vec![b'x'; MAX_LOG_BYTES as usize];
+        for _ in 0..KEEP_ROTATED + 3 {
+            file.write_all(&line).unwrap();
+        }
+        file.write_all(b"2026-01-01T00:00:00Z  WARN rust_rdp: last\n").unwrap();
+        file.flush().unwrap();
+        assert!(rotated_path(&dir, KEEP_ROTATED).exists());
+        assert!(!rotated_path(&dir, KEEP_ROTATED + 1).exists());
+
+        let lines = tail(&rotated_path(&dir, 0), 10).unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(line_level(&lines[0]), Some(LogLevel::Warn));
+        assert_eq!(line_level("    at src/app.rs:10"), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
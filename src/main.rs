@@ -2,12 +2,24 @@
 
 mod app;
 mod capture;
+mod cli;
 mod config;
+mod control;
+mod crash;
 mod error;
+mod host_setup;
 mod input_handler;
+mod logging;
+mod metrics;
 mod network;
+mod notify;
+mod power;
+mod probe;
 mod process;
 mod protocol;
+mod recording;
+mod session;
+mod storage;
 mod tailscale;
 mod ui;
 mod updater;
@@ -15,12 +27,29 @@ mod updater;
 use app::App;
 
 fn main() -> iced::Result {
-    tracing_subscriber::fmt::init();
+    let cli = cli::Cli::parse_args(std::env::args().skip(1)).unwrap_or_else(|e| cli::exit_with(e));
+    let portable = config::init_portable(cli.portable);
 
-    iced::application(App::new, App::update, App::view)
-        .title("Rust RDP")
+    let log_level = config::settings::AppSettings::load_or_default().general.log_level;
+    logging::init(log_level.filter());
+    crash::install_hook();
+    if portable {
+        tracing::info!("Portable mode: data in {}", config::app_data_dir().display());
+    }
+
+    std::thread::spawn(|| storage::enforce_quotas(&config::app_data_dir()));
+
+    if let Some(args) = cli.probe_args() {
+        std::process::exit(probe::run(&args));
+    }
+
+    let (launch, json) = (cli.launch(), cli.json);
+    if json {
+        probe::attach_console();
+    }
+    iced::daemon(move || App::new(launch.clone(), json), App::update, App::view)
+        .title(App::title)
         .subscription(App::subscription)
         .theme(App::theme)
-        .centered()
         .run()
 }
@@ -0,0 +1,192 @@
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use futures::{SinkExt, Stream};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+
+#[derive(Default)]
+pub struct HostMetrics {
+    server_starts: AtomicU64,
+    listening: AtomicU64,
+    active_clients: AtomicU64,
+    client_connections: AtomicU64,
+    frames_sent: AtomicU64,
+    bytes_sent: AtomicU64,
+    input_events: AtomicU64,
+}
+
+pub static HOST_METRICS: HostMetrics = HostMetrics {
+    server_starts: AtomicU64::new(0),
+    listening: AtomicU64::new(0),
+    active_clients: AtomicU64::new(0),
+    client_connections: AtomicU64::new(0),
+    frames_sent: AtomicU64::new(0),
+    bytes_sent: AtomicU64::new(0),
+    input_events: AtomicU64::new(0),
+};
+
+/// Keeps `rust_rdp_host_listening` at 1 until dropped. Stopping hosting drops
+/// the server stream mid-await, so the reset cannot wait for the loop to end.
+pub struct ListeningGuard<'a>(&'a HostMetrics);
+
+impl Drop for ListeningGuard<'_> {
+    fn drop(&mut self) {
+        self.0.server_stopped();
+    }
+}
+
+/// Counts one active client until dropped.
+pub struct ClientGuard<'a>(&'a HostMetrics);
+
+impl Drop for ClientGuard<'_> {
+    fn drop(&mut self) {
+        self.0.client_disconnected();
+    }
+}
+
+impl HostMetrics {
+    pub fn listening(&self) -> ListeningGuard<'_> {
+        self.server_started();
+        ListeningGuard(self)
+    }
+
+    pub fn client(&self) -> ClientGuard<'_> {
+        self.client_connected();
+        ClientGuard(self)
+    }
+
+    pub fn server_started(&self) {
+        self.server_starts.fetch_add(1, Ordering::Relaxed);
+        self.listening.store(1, Ordering::Relaxed);
+    }
+
+    pub fn server_stopped(&self) {
+        self.listening.store(0, Ordering::Relaxed);
+    }
+
+    pub fn client_connected(&self) {
+        self.client_connections.fetch_add(1, Ordering::Relaxed);
+        self.active_clients.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn client_disconnected(&self) {
+        let _ = self
+            .active_clients
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+    }
+
+    pub fn frame_sent(&self, bytes: usize) {
+        self.frames_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn input_received(&self) {
+        self.input_events.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn render(&self) -> String {
+        let metrics: [(&str, &str, &str, &AtomicU64); 7] = [
+            ("rust_rdp_host_listening", "gauge", "Whether the host server is accepting connections", &self.listening),
+            ("rust_rdp_host_server_starts_total", "counter", "Number of times the host server was started", &self.server_starts),
+            ("rust_rdp_host_active_clients", "gauge", "Currently connected clients", &self.active_clients),
+            ("rust_rdp_host_client_connections_total", "counter", "Client connections accepted", &self.client_connections),
+            ("rust_rdp_host_frames_sent_total", "counter", "Frames sent to clients", &self.frames_sent),
+            ("rust_rdp_host_bytes_sent_total", "counter", "Compressed frame bytes sent to clients", &self.bytes_sent),
+            ("rust_rdp_host_input_events_total", "counter", "Input events received from clients", &self.input_events),
+        ];
+
+        let mut out = String::new();
+        for (name, kind, help, value) in metrics {
+            out.push_str(&format!(
+                "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {}\n",
+                value.load(Ordering::Relaxed)
+            ));
+        }
+        out
+    }
+}
+
+pub fn metrics_subscription(port: u16) -> iced::Subscription<String> {
+    iced::Subscription::run_with(port, |port| metrics_stream(*port))
+}
+
+fn metrics_stream(port: u16) -> Pin<Box<dyn Stream<Item = String> + Send>> {
+    Box::pin(iced::stream::channel(4, move |mut output: futures::channel::mpsc::Sender<String>| async move {
+        let listener = match TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(l) => l,
+            Err(e) => {
+                let _ = output.send(format!("Metrics bind failed on 127.0.0.1:{port}: {e}")).await;
+                std::future::pending::<()>().await;
+                return;
+            }
+        };
+        tracing::info!("Metrics endpoint listening on 127.0.0.1:{port}");
+
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                continue;
+            };
+            tokio::spawn(async move {
+                let request = crate::control::read_request(&mut stream).await.unwrap_or_default();
+                let (status, body) = if request.starts_with("GET /metrics ") {
+                    ("200 OK", HOST_METRICS.render())
+                } else {
+                    ("404 Not Found", "not found\n".to_string())
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_exposition_format() {
+        let metrics = HostMetrics::default();
+        metrics.server_started();
+        metrics.client_connected();
+        metrics.frame_sent(1500);
+        metrics.frame_sent(500);
+
+        let text = metrics.render();
+        assert!(text.contains("# TYPE rust_rdp_host_listening gauge\nrust_rdp_host_listening 1\n"));
+        assert!(text.contains("rust_rdp_host_active_clients 1\n"));
+        assert!(text.contains("rust_rdp_host_frames_sent_total 2\n"));
+        assert!(text.contains("rust_rdp_host_bytes_sent_total 2000\n"));
+    }
+
+    #[test]
+    fn active_clients_never_underflow() {
+        let metrics = HostMetrics::default();
+        metrics.client_disconnected();
+        metrics.client_connected();
+        metrics.client_disconnected();
+        metrics.client_disconnected();
+        assert!(metrics.render().contains("rust_rdp_host_active_clients 0\n"));
+        assert!(metrics.render().contains("rust_rdp_host_client_connections_total 1\n"));
+    }
+
+    #[test]
+    fn guards_reset_gauges_on_drop() {
+        let metrics = HostMetrics::default();
+        let listening = metrics.listening();
+        let client = metrics.client();
+        assert!(metrics.render().contains("rust_rdp_host_listening 1\n"));
+        assert!(metrics.render().contains("rust_rdp_host_active_clients 1\n"));
+
+        drop(listening);
+        drop(client);
+        assert!(metrics.render().contains("rust_rdp_host_listening 0\n"));
+        assert!(metrics.render().contains("rust_rdp_host_active_clients 0\n"));
+        assert!(metrics.render().contains("rust_rdp_host_client_connections_total 1\n"));
+    }
+}
@@ -0,0 +1,83 @@
+use std::net::IpAddr;
+
+/// Address the host server listens on, plus a warning when that address is
+/// reachable from outside the tailnet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BindPlan {
+    pub addr: IpAddr,
+    pub warning: Option<String>,
+}
+
+/// Resolves the configured bind address. An empty setting means "the Tailscale
+/// address"; there is no silent fallback to all interfaces.
+pub fn resolve_bind_address(configured: &str, tailscale_ip: Option<&str>) -> Result<BindPlan, String> {
+    let configured = configured.trim();
+    if configured.is_empty() {
+        let ip = tailscale_ip.ok_or("No Tailscale address yet; connect Tailscale or set host.bind_address")?;
+        let addr = ip.parse().map_err(|_| format!("Tailscale reported an invalid address: {ip}"))?;
+        return Ok(BindPlan { addr, warning: None });
+    }
+
+    let addr: IpAddr = configured
+        .parse()
+        .map_err(|_| format!("host.bind_address \"{configured}\" is not an IP address"))?;
+    let warning = if addr.is_unspecified() {
+        Some(format!(
+            "Listening on {addr} exposes this machine to every network it is on, including the local LAN"
+        ))
+    } else if !addr.is_loopback() && !is_tailnet(addr) {
+        Some(format!("{addr} is outside the tailnet; devices on that network can reach this machine"))
+    } else {
+        None
+    };
+    Ok(BindPlan { addr, warning })
+}
+
+/// Tailscale hands out 100.64.0.0/10 and fd7a:115c:a1e0::/48.
+pub fn is_tailnet(addr: IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            a == 100 && (64..128).contains(&b)
+        }
+        IpAddr::V6(v6) => {
+            let s = v6.segments();
+            s[0] == 0xfd7a && s[1] == 0x115c && s[2] == 0xa1e0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_uses_tailscale_address() {
+        let plan = resolve_bind_address("", Some("100.101.102.103")).unwrap();
+        assert_eq!(plan.addr.to_string(), "100.101.102.103");
+        assert!(plan.warning.is_none());
+        assert!(resolve_bind_address(" ", None).is_err());
+    }
+
+    #[test]
+    fn all_interfaces_and_lan_warn() {
+        assert!(resolve_bind_address("0.0.0.0", None).unwrap().warning.is_some());
+        assert!(resolve_bind_address("::", None).unwrap().warning.is_some());
+        assert!(resolve_bind_address("192.168.1.20", None).unwrap().warning.is_some());
+        assert!(resolve_bind_address("127.0.0.1", None).unwrap().warning.is_none());
+        assert!(resolve_bind_address("fd7a:115c:a1e0::5", None).unwrap().warning.is_none());
+    }
+
+    #[test]
+    fn rejects_hostnames() {
+        assert!(resolve_bind_address("localhost", None).is_err());
+    }
+
+    #[test]
+    fn tailnet_range() {
+        assert!(is_tailnet("100.64.0.1".parse().unwrap()));
+        assert!(is_tailnet("100.127.255.255".parse().unwrap()));
+        assert!(!is_tailnet("100.128.0.1".parse().unwrap()));
+        assert!(!is_tailnet("10.0.0.1".parse().unwrap()));
+    }
+}
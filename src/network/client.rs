@@ -1,14 +1,24 @@
 use std::pin::Pin;
+use std::sync::Arc;
 use std::time::Duration;
 use futures::Stream;
 use tokio::net::TcpStream;
+use tokio::sync::broadcast;
 use tokio::time;
 use tokio_util::codec::Framed;
 use futures::StreamExt;
 use futures::SinkExt;
 use crate::protocol::{ProtocolMessage, PROTOCOL_VERSION};
 use crate::protocol::codec::MessageCodec;
-use super::{NetworkEvent, ConnectionHandle};
+use crate::power::PowerEvent;
+use super::{ConnectStage, NetworkEvent, ConnectionHandle};
+use crate::session::SessionId;
+use crate::config::secret::SecretString;
+use super::coalesce;
+use super::route::{self, ROUTE_CHECK_INTERVAL, RouteWatch};
+use super::stats::{ByteCounters, CountingStream, SessionStats};
+
+type ClientTransport = Framed<CountingStream<TcpStream>, MessageCodec>;
 
 fn now_ms() -> u64 {
     std::time::SystemTime::now()
@@ -17,14 +27,44 @@ fn now_ms() -> u64 {
         .as_millis() as u64
 }
 
-pub fn access_client_subscription(host: String, port: u16) -> iced::Subscription<NetworkEvent> {
+/// `layout` is the keyboard layout announced to the host, see `GeneralSettings::hello_layout`.
+/// `access_code` is sent after the Hello for hosts that require one.
+///
+/// The stream retries dropped connections by itself, so it is only replaced
+/// when an argument changes. `session` is new for every connect from the UI,
+/// which makes a fresh connect always start a fresh stream, even to the same
+/// host with the same settings, instead of picking up the previous one.
+pub fn access_client_subscription(
+    session: SessionId,
+    host: String,
+    port: u16,
+    layout: Option<String>,
+    access_code: Option<SecretString>,
+) -> iced::Subscription<NetworkEvent> {
     iced::Subscription::run_with(
-        (host.clone(), port),
-        move |(host, port)| access_client_stream(host.clone(), *port),
+        (session, host.clone(), port, layout, access_code),
+        move |(_, host, port, layout, access_code)| {
+            access_client_stream(host.clone(), *port, layout.clone(), access_code.clone())
+        },
     )
 }
 
-fn access_client_stream(host: String, port: u16) -> Pin<Box<dyn Stream<Item = NetworkEvent> + Send>> {
+pub const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+
+enum SessionEnd {
+    Closed,
+    Declined,
+    /// The host refused the access code; retrying would not help.
+    Denied(String),
+    Lost(String),
+}
+
+fn access_client_stream(
+    host: String,
+    port: u16,
+    layout: Option<String>,
+    access_code: Option<SecretString>,
+) -> Pin<Box<dyn Stream<Item = NetworkEvent> + Send>> {
     Box::pin(iced::stream::channel(100, move |mut output: futures::channel::mpsc::Sender<NetworkEvent>| async move {
         let addr = format!("{host}:{port}");
 
@@ -32,144 +72,262 @@ fn access_client_stream(host: String, port: u16) -> Pin<Box<dyn Stream<Item = Ne
             .map(|d| (d.width() as u32, d.height() as u32))
             .unwrap_or((1920, 1080));
 
-        let mut framed = None;
-        let max_attempts = 3u32;
-
-        for attempt in 1..=max_attempts {
-            let stream = match time::timeout(
-                Duration::from_secs(10),
-                TcpStream::connect(&addr),
-            ).await {
-                Ok(Ok(s)) => s,
-                Ok(Err(e)) => {
-                    if attempt < max_attempts {
-                        let _ = output.send(NetworkEvent::Error(
-                            format!("Connect failed: {e} — Retrying (attempt {attempt}/{max_attempts})...")
-                        )).await;
-                        time::sleep(Duration::from_secs(1 << (attempt - 1))).await;
-                        continue;
-                    }
-                    let _ = output.send(NetworkEvent::Error(format!("Connect failed after {max_attempts} attempts: {e}"))).await;
-                    let _ = output.send(NetworkEvent::Stopped).await;
-                    std::future::pending::<()>().await;
-                    return;
-                }
-                Err(_) => {
-                    if attempt < max_attempts {
-                        let _ = output.send(NetworkEvent::Error(
-                            format!("Connection timed out — Retrying (attempt {attempt}/{max_attempts})...")
-                        )).await;
-                        time::sleep(Duration::from_secs(1 << (attempt - 1))).await;
-                        continue;
-                    }
-                    let _ = output.send(NetworkEvent::Error(format!("Connection timed out after {max_attempts} attempts"))).await;
-                    let _ = output.send(NetworkEvent::Stopped).await;
-                    std::future::pending::<()>().await;
-                    return;
+        let mut power = crate::power::subscribe();
+        let mut attempt = 0u32;
+        loop {
+            let last_error = match connect(&addr, sw, sh, layout.clone(), access_code.clone(), &mut output).await {
+                Ok((framed, counters, watch)) => {
+                    attempt = 0;
+                    match run_session(framed, counters, watch, &mut power, &mut output).await {
+                        SessionEnd::Closed => break,
+                        SessionEnd::Declined => {
+                            let _ = output.send(NetworkEvent::Error(
+                                "The host did not accept the connection; someone else is using it".to_string(),
+                            )).await;
+                            break;
+                        }
+                        SessionEnd::Denied(reason) => {
+                            let _ = output.send(NetworkEvent::AccessDenied(reason)).await;
+                            break;
+                        }
+                        SessionEnd::Lost(reason) => {
+                            tracing::warn!("Connection lost: {reason}");
+                            reason
+                        }
+                    }
                 }
+                Err(e) => e,
             };
 
-            let mut f = Framed::new(stream, MessageCodec);
-
-            let hello = ProtocolMessage::Hello {
-                version: PROTOCOL_VERSION,
-                screen_width: sw,
-                screen_height: sh,
-            };
-            if let Err(e) = f.send(hello).await {
-                if attempt < max_attempts {
-                    let _ = output.send(NetworkEvent::Error(
-                        format!("Send Hello failed: {e} — Retrying (attempt {attempt}/{max_attempts})...")
-                    )).await;
-                    time::sleep(Duration::from_secs(1 << (attempt - 1))).await;
-                    continue;
-                }
-                let _ = output.send(NetworkEvent::Error(format!("Send Hello failed after {max_attempts} attempts: {e}"))).await;
-                let _ = output.send(NetworkEvent::Stopped).await;
-                std::future::pending::<()>().await;
-                return;
+            attempt += 1;
+            if attempt > MAX_RECONNECT_ATTEMPTS {
+                let _ = output.send(NetworkEvent::Error(
+                    format!("{last_error} (gave up after {MAX_RECONNECT_ATTEMPTS} attempts)")
+                )).await;
+                break;
             }
-
-            framed = Some(f);
-            break;
+            let _ = output.send(NetworkEvent::Reconnecting { attempt, max: MAX_RECONNECT_ATTEMPTS }).await;
+            time::sleep(reconnect_delay(attempt)).await;
         }
 
-        let framed = match framed {
-            Some(f) => f,
-            None => {
-                let _ = output.send(NetworkEvent::Error("Connection failed after all retries".to_string())).await;
-                let _ = output.send(NetworkEvent::Stopped).await;
-                std::future::pending::<()>().await;
-                return;
-            }
-        };
+        let _ = output.send(NetworkEvent::Stopped).await;
+        std::future::pending::<()>().await;
+    }))
+}
 
-        let (input_tx, mut input_rx) = tokio::sync::mpsc::channel::<ProtocolMessage>(100);
-        let handle = ConnectionHandle::new(input_tx);
-        let _ = output.send(NetworkEvent::Connected(handle)).await;
+fn reconnect_delay(attempt: u32) -> Duration {
+    Duration::from_secs(1 << (attempt.saturating_sub(1)).min(4))
+}
 
-        let (mut sink, mut stream_reader) = framed.split();
+async fn connect(
+    addr: &str,
+    sw: u32,
+    sh: u32,
+    layout: Option<String>,
+    access_code: Option<SecretString>,
+    output: &mut futures::channel::mpsc::Sender<NetworkEvent>,
+) -> Result<(ClientTransport, Arc<ByteCounters>, Option<RouteWatch>), String> {
+    let _ = output.send(NetworkEvent::Stage(ConnectStage::Dialing)).await;
+    let stream = match time::timeout(Duration::from_secs(10), TcpStream::connect(addr)).await {
+        Ok(Ok(s)) => s,
+        Ok(Err(e)) => return Err(format!("Connect failed: {e}")),
+        Err(_) => return Err("Connection timed out".to_string()),
+    };
+    let watch = match (stream.local_addr(), stream.peer_addr()) {
+        (Ok(local), Ok(peer)) => Some(RouteWatch { local: local.ip(), peer }),
+        _ => None,
+    };
 
-        let mut heartbeat = time::interval(Duration::from_secs(5));
-        heartbeat.tick().await;
-        let mut last_pong = time::Instant::now();
+    let _ = output.send(NetworkEvent::Stage(ConnectStage::Handshake)).await;
 
-        loop {
-            tokio::select! {
-                msg = stream_reader.next() => {
-                    match msg {
-                        Some(Ok(ProtocolMessage::Frame(frame_data))) => {
-                            match crate::capture::encoder::decode_frame(&frame_data) {
-                                Ok(pixels) => {
-                                    let _ = output.send(NetworkEvent::Frame {
-                                        width: frame_data.width,
-                                        height: frame_data.height,
-                                        pixels,
-                                    }).await;
-                                }
-                                Err(e) => {
-                                    tracing::warn!("Frame decode error: {e}");
-                                }
-                            }
-                        }
-                        Some(Ok(ProtocolMessage::Pong(ts))) => {
+    let counters = Arc::new(ByteCounters::default());
+    let mut framed = Framed::new(CountingStream::new(stream, counters.clone()), MessageCodec);
+    let hello = ProtocolMessage::Hello {
+        version: PROTOCOL_VERSION,
+        screen_width: sw,
+        screen_height: sh,
+        keyboard_layout: layout,
+    };
+    framed.send(hello).await.map_err(|e| format!("Send Hello failed: {e}"))?;
+    if let Some(code) = access_code {
+        framed
+            .send(ProtocolMessage::AccessCode(code.expose().to_string()))
+            .await
+            .map_err(|e| format!("Send access code failed: {e}"))?;
+    }
+    Ok((framed, counters, watch))
+}
+
+async fn run_session(
+    framed: ClientTransport,
+    counters: Arc<ByteCounters>,
+    watch: Option<RouteWatch>,
+    power: &mut broadcast::Receiver<PowerEvent>,
+    output: &mut futures::channel::mpsc::Sender<NetworkEvent>,
+) -> SessionEnd {
+    let (input_tx, mut input_rx) = tokio::sync::mpsc::channel::<ProtocolMessage>(100);
+    let handle = ConnectionHandle::new(input_tx);
+    let _ = output.send(NetworkEvent::Connected(handle)).await;
+
+    let (mut sink, mut stream_reader) = framed.split();
+
+    let mut heartbeat = time::interval(Duration::from_secs(5));
+    heartbeat.tick().await;
+    let mut last_pong = time::Instant::now();
+    // The host holds queued viewers without reading from them, so pongs only
+    // resume once it lets us in.
+    let mut queued = false;
+    // Set before the machine sleeps: stop reading and pinging so the session
+    // is not torn down mid-suspend, then reconnect once it resumes.
+    let mut suspended = false;
+    let started = time::Instant::now();
+    let mut route_tick = time::interval(ROUTE_CHECK_INTERVAL);
+    route_tick.tick().await;
+    let mut stats_tick = time::interval(Duration::from_secs(1));
+    stats_tick.tick().await;
+    counters.take();
+
+    loop {
+        tokio::select! {
+            msg = stream_reader.next(), if !suspended => {
+                match msg {
+                    Some(Ok(ProtocolMessage::Frame(frame_data))) => {
+                        if queued {
+                            queued = false;
                             last_pong = time::Instant::now();
-                            let rtt_ms = now_ms().saturating_sub(ts);
-                            let _ = output.send(NetworkEvent::LatencyUpdate { rtt_ms }).await;
                         }
-                        Some(Ok(ProtocolMessage::Disconnect)) | None => break,
-                        Some(Err(e)) => {
-                            let _ = output.send(NetworkEvent::Error(e.to_string())).await;
-                            break;
+                        match crate::capture::encoder::decode_frame(&frame_data) {
+                            Ok(pixels) => {
+                                let _ = output.send(NetworkEvent::Frame {
+                                    width: frame_data.width,
+                                    height: frame_data.height,
+                                    region: frame_data.region,
+                                    pixels,
+                                }).await;
+                            }
+                            Err(e) => {
+                                tracing::warn!("Frame decode error: {e}");
+                            }
                         }
-                        _ => {}
                     }
+                    Some(Ok(ProtocolMessage::Pong(ts))) => {
+                        last_pong = time::Instant::now();
+                        let rtt_ms = now_ms().saturating_sub(ts);
+                        let _ = output.send(NetworkEvent::LatencyUpdate { rtt_ms }).await;
+                    }
+                    Some(Ok(ProtocolMessage::ClipboardText(text))) => {
+                        let _ = output.send(NetworkEvent::ClipboardText(text)).await;
+                    }
+                    Some(Ok(ProtocolMessage::ClipboardImage(dib))) => {
+                        let _ = output.send(NetworkEvent::ClipboardImage(dib)).await;
+                    }
+                    Some(Ok(ProtocolMessage::ClipboardFiles(files))) => {
+                        let _ = output.send(NetworkEvent::ClipboardFiles(files)).await;
+                    }
+                    Some(Ok(ProtocolMessage::SecureDesktop(secure))) => {
+                        let _ = output.send(NetworkEvent::SecureDesktop(secure)).await;
+                    }
+                    Some(Ok(ProtocolMessage::HostInfo(info))) => {
+                        let _ = output.send(NetworkEvent::HostInfo(info)).await;
+                    }
+                    Some(Ok(ProtocolMessage::HostCursor { x, y })) => {
+                        let _ = output.send(NetworkEvent::HostCursor { x, y }).await;
+                    }
+                    Some(Ok(ProtocolMessage::CursorShape(shape))) => {
+                        let _ = output.send(NetworkEvent::CursorShape(shape)).await;
+                    }
+                    Some(Ok(ProtocolMessage::FileList { path, entries })) => {
+                        let _ = output.send(NetworkEvent::FileList { path, entries }).await;
+                    }
+                    Some(Ok(ProtocolMessage::FileChunk { name, offset, total, data })) => {
+                        let _ = output.send(NetworkEvent::FileChunk { name, offset, total, data }).await;
+                    }
+                    Some(Ok(ProtocolMessage::FileError(e))) => {
+                        let _ = output.send(NetworkEvent::FileError(e)).await;
+                    }
+                    Some(Ok(ProtocolMessage::TransferFailed(e))) => {
+                        let _ = output.send(NetworkEvent::TransferFailed(e)).await;
+                    }
+                    Some(Ok(ProtocolMessage::FileDigest { sha256, .. })) => {
+                        let _ = output.send(NetworkEvent::FileDigest(sha256)).await;
+                    }
+                    Some(Ok(ProtocolMessage::Displays(displays))) => {
+                        let _ = output.send(NetworkEvent::Displays(displays)).await;
+                    }
+                    Some(Ok(ProtocolMessage::ControlQueued)) => {
+                        queued = true;
+                        let _ = output.send(NetworkEvent::Queued).await;
+                    }
+                    Some(Ok(ProtocolMessage::ControlDeclined)) => return SessionEnd::Declined,
+                    Some(Ok(ProtocolMessage::AccessDenied(reason))) => return SessionEnd::Denied(reason),
+                    Some(Ok(ProtocolMessage::Disconnect)) => return SessionEnd::Closed,
+                    None => return SessionEnd::Lost("Server closed the connection".to_string()),
+                    Some(Err(e)) => return SessionEnd::Lost(e.to_string()),
+                    _ => {}
                 }
-                input = input_rx.recv() => {
-                    match input {
-                        Some(msg) => {
-                            if let Err(e) = sink.send(msg).await {
-                                let _ = output.send(NetworkEvent::Error(e.to_string())).await;
-                                break;
+            }
+            input = input_rx.recv() => {
+                match input {
+                    Some(msg) => {
+                        // Moves queued up behind this one collapse into the
+                        // latest, and the batch goes out in a single write.
+                        for msg in coalesce::drain(msg, &mut input_rx) {
+                            if let Err(e) = sink.feed(msg).await {
+                                return SessionEnd::Lost(e.to_string());
                             }
                         }
-                        None => break,
+                        if let Err(e) = sink.flush().await {
+                            return SessionEnd::Lost(e.to_string());
+                        }
                     }
+                    None => return SessionEnd::Closed,
                 }
-                _ = heartbeat.tick() => {
-                    if last_pong.elapsed() > Duration::from_secs(15) {
-                        let _ = output.send(NetworkEvent::Error("Server heartbeat timeout".to_string())).await;
-                        break;
-                    }
-                    if let Err(e) = sink.send(ProtocolMessage::Ping(now_ms())).await {
-                        let _ = output.send(NetworkEvent::Error(format!("Ping failed: {e}"))).await;
-                        break;
+            }
+            _ = stats_tick.tick() => {
+                let (received, sent) = counters.take();
+                let _ = output.send(NetworkEvent::Stats(SessionStats {
+                    received_per_sec: received,
+                    sent_per_sec: sent,
+                    connected_for: started.elapsed(),
+                })).await;
+            }
+            event = power.recv() => match event {
+                Ok(PowerEvent::Suspending) => {
+                    suspended = true;
+                    let _ = output.send(NetworkEvent::Suspended).await;
+                }
+                Ok(PowerEvent::Resumed) => return SessionEnd::Lost("System resumed from sleep".to_string()),
+                Err(_) => {}
+            },
+            _ = route_tick.tick(), if watch.is_some() && !suspended => {
+                if let Some(watch) = watch {
+                    let now = tokio::task::spawn_blocking(move || route::source_for(watch.peer)).await.ok().flatten();
+                    if let Some(reason) = watch.changed(now) {
+                        return SessionEnd::Lost(reason);
                     }
                 }
             }
+            _ = heartbeat.tick(), if !suspended => {
+                if !queued && last_pong.elapsed() > Duration::from_secs(15) {
+                    return SessionEnd::Lost("Server heartbeat timeout".to_string());
+                }
+                if let Err(e) = sink.send(ProtocolMessage::Ping(now_ms())).await {
+                    return SessionEnd::Lost(format!("Ping failed: {e}"));
+                }
+            }
         }
+    }
+}
 
-        let _ = output.send(NetworkEvent::Stopped).await;
-        std::future::pending::<()>().await;
-    }))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnect_delay_backs_off_and_caps() {
+        assert_eq!(reconnect_delay(1), Duration::from_secs(1));
+        assert_eq!(reconnect_delay(2), Duration::from_secs(2));
+        assert_eq!(reconnect_delay(3), Duration::from_secs(4));
+        assert_eq!(reconnect_delay(10), Duration::from_secs(16));
+    }
 }
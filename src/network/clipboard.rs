@@ -0,0 +1,401 @@
+use std::path::{Path, PathBuf};
+
+pub const MAX_CLIPBOARD_BYTES: usize = 1024 * 1024;
+
+/// Clipboard content iced cannot read: a `CF_DIB` bitmap (BITMAPINFO header
+/// followed by the pixels) or files copied in Explorer.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub enum NativeContent {
+    Image(Vec<u8>),
+    Files(Vec<PathBuf>),
+}
+
+/// Tracks the last clipboard text seen on either side so that text we just
+/// received from the peer is not echoed straight back to it. Whatever is on
+/// the clipboard when a session starts is not sent; only later copies are.
+#[derive(Debug, Default)]
+pub struct ClipboardSync {
+    last: Option<String>,
+    primed: bool,
+    /// Clipboard sequence number at the last native check.
+    sequence: Option<u32>,
+}
+
+impl ClipboardSync {
+    /// Called with the result of polling the local clipboard. Returns the text
+    /// to send to the peer when it changed since the last sync.
+    pub fn local_changed(&mut self, text: Option<String>) -> Option<String> {
+        if !self.primed {
+            self.primed = true;
+            self.last = text;
+            return None;
+        }
+        let text = text.filter(|t| !t.is_empty() && t.len() <= MAX_CLIPBOARD_BYTES)?;
+        if self.last.as_deref() == Some(text.as_str()) {
+            return None;
+        }
+        self.last = Some(text.clone());
+        Some(text)
+    }
+
+    /// Called with text received from the peer. Returns the text to write to
+    /// the local clipboard.
+    pub fn remote_received(&mut self, text: String) -> Option<String> {
+        if text.is_empty() || text.len() > MAX_CLIPBOARD_BYTES {
+            return None;
+        }
+        if self.last.as_deref() == Some(text.as_str()) {
+            return None;
+        }
+        self.primed = true;
+        self.last = Some(text.clone());
+        Some(text)
+    }
+
+    /// Reads an image or files from the local clipboard when it changed since
+    /// the last poll. As with text, what is there at session start is not sent.
+    pub fn poll_native(&mut self) -> Option<NativeContent> {
+        let now = platform::sequence();
+        match self.sequence.replace(now) {
+            Some(previous) if previous != now => platform::read(),
+            _ => None,
+        }
+    }
+
+    /// Call after writing to the clipboard natively, so the write is not
+    /// read back and sent to the peer.
+    pub fn native_written(&mut self) {
+        self.sequence = Some(platform::sequence());
+    }
+
+    /// Treats `text` as synced already, so it is never sent to the peer.
+    pub fn ignore(&mut self, text: &str) {
+        self.primed = true;
+        self.last = Some(text.to_string());
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+pub fn write_image(dib: &[u8]) -> Result<(), String> {
+    platform::write(platform::CF_DIB, dib)
+}
+
+/// Puts `text` on the clipboard marked so Windows keeps it out of clipboard
+/// history and cloud sync.
+pub fn write_secret(text: &str) -> Result<(), String> {
+    platform::write_secret(text)
+}
+
+/// Puts `paths` on the clipboard so they can be pasted in Explorer.
+pub fn write_files(paths: &[PathBuf]) -> Result<(), String> {
+    platform::write(platform::CF_HDROP, &drop_files(paths))
+}
+
+/// A `DROPFILES` block: the header, then each path as a NUL-terminated UTF-16
+/// string, then one more NUL.
+fn drop_files(paths: &[PathBuf]) -> Vec<u8> {
+    const HEADER: u32 = 20;
+    let mut blob = Vec::new();
+    blob.extend_from_slice(&HEADER.to_le_bytes());
+    // Drop point and fNC, unused for the clipboard.
+    blob.extend_from_slice(&[0; 12]);
+    // fWide: the names are UTF-16.
+    blob.extend_from_slice(&1u32.to_le_bytes());
+    for path in paths {
+        for unit in path.to_string_lossy().encode_utf16().chain([0]) {
+            blob.extend_from_slice(&unit.to_le_bytes());
+        }
+    }
+    blob.extend_from_slice(&[0, 0]);
+    blob
+}
+
+/// Reads copied files to send to the peer. Folders are skipped; going over
+/// `limit` bytes in total fails the whole copy.
+pub async fn read_files(paths: Vec<PathBuf>, limit: u64) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut files = Vec::new();
+    let mut total = 0u64;
+    for path in paths {
+        let Ok(metadata) = tokio::fs::metadata(&path).await else { continue };
+        if !metadata.is_file() {
+            continue;
+        }
+        total += metadata.len();
+        if total > limit {
+            return Err(format!("Copied files are larger than {} MB", limit / (1024 * 1024)));
+        }
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().into_owned()) else { continue };
+        let data = tokio::fs::read(&path)
+            .await
+            .map_err(|e| format!("Read {}: {e}", path.display()))?;
+        files.push((name, data));
+    }
+    Ok(files)
+}
+
+/// A received file name, or None if it is not a plain name.
+fn safe_file_name(name: &str) -> Option<&str> {
+    let bad = name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\', ':']);
+    (!bad).then_some(name)
+}
+
+/// Writes files received from the peer into `dir`, replacing whatever an
+/// earlier copy left there, and returns their paths.
+pub fn save_received_files(dir: &Path, files: Vec<(String, Vec<u8>)>) -> Result<Vec<PathBuf>, String> {
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir).map_err(|e| format!("Create {}: {e}", dir.display()))?;
+    let mut paths = Vec::new();
+    for (name, data) in files {
+        let Some(name) = safe_file_name(&name) else {
+            tracing::warn!("Skipping clipboard file with unsafe name");
+            continue;
+        };
+        let path = dir.join(name);
+        std::fs::write(&path, data).map_err(|e| format!("Write {}: {e}", path.display()))?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::path::PathBuf;
+
+    use windows_sys::Win32::System::DataExchange::{
+        CloseClipboard, EmptyClipboard, GetClipboardData, GetClipboardSequenceNumber, IsClipboardFormatAvailable,
+        OpenClipboard, RegisterClipboardFormatW, SetClipboardData,
+    };
+    use windows_sys::Win32::System::Memory::{GMEM_MOVEABLE, GlobalAlloc, GlobalFree, GlobalLock, GlobalSize, GlobalUnlock};
+    use windows_sys::Win32::UI::Shell::DragQueryFileW;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{CreateWindowExW, DestroyWindow, HWND_MESSAGE};
+
+    use super::NativeContent;
+
+    pub const CF_DIB: u32 = 8;
+    pub const CF_HDROP: u32 = 15;
+    const CF_UNICODETEXT: u32 = 13;
+
+    /// Holds the clipboard open, and the window that owns it while writing.
+    struct Open(isize);
+
+    impl Open {
+        fn new(owner: isize) -> Option<Self> {
+            // SAFETY: plain Win32 call; a zero owner is allowed for reading.
+            (unsafe { OpenClipboard(owner) } != 0).then_some(Open(owner))
+        }
+    }
+
+    impl Drop for Open {
+        fn drop(&mut self) {
+            // SAFETY: the clipboard was opened by this thread in `new`.
+            unsafe {
+                CloseClipboard();
+                if self.0 != 0 {
+                    DestroyWindow(self.0);
+                }
+            }
+        }
+    }
+
+    pub fn sequence() -> u32 {
+        // SAFETY: no arguments; never fails.
+        unsafe { GetClipboardSequenceNumber() }
+    }
+
+    pub fn read() -> Option<NativeContent> {
+        let _open = Open::new(0)?;
+        // SAFETY: the clipboard is open; handles it returns stay valid until
+        // it is closed, and each is locked only while copying from it.
+        unsafe {
+            if IsClipboardFormatAvailable(CF_HDROP) != 0 {
+                let files = GetClipboardData(CF_HDROP);
+                if files == 0 {
+                    return None;
+                }
+                let count = DragQueryFileW(files as _, u32::MAX, std::ptr::null_mut(), 0);
+                let mut paths = Vec::new();
+                for i in 0..count {
+                    let len = DragQueryFileW(files as _, i, std::ptr::null_mut(), 0) as usize;
+                    let mut buf = vec![0u16; len + 1];
+                    DragQueryFileW(files as _, i, buf.as_mut_ptr(), buf.len() as u32);
+                    paths.push(PathBuf::from(String::from_utf16_lossy(&buf[..len])));
+                }
+                return Some(NativeContent::Files(paths));
+            }
+            if IsClipboardFormatAvailable(CF_DIB) != 0 {
+                let handle = GetClipboardData(CF_DIB);
+                let ptr = GlobalLock(handle as _) as *const u8;
+                if ptr.is_null() {
+                    return None;
+                }
+                let bytes = std::slice::from_raw_parts(ptr, GlobalSize(handle as _)).to_vec();
+                GlobalUnlock(handle as _);
+                return Some(NativeContent::Image(bytes));
+            }
+        }
+        None
+    }
+
+    pub fn write(format: u32, data: &[u8]) -> Result<(), String> {
+        write_formats(&[(format, data.to_vec())])
+    }
+
+    pub fn write_secret(text: &str) -> Result<(), String> {
+        let utf16: Vec<u8> = text.encode_utf16().chain([0]).flat_map(u16::to_le_bytes).collect();
+        let mut formats = vec![(CF_UNICODETEXT, utf16)];
+        // Clipboard history and cloud clipboard skip content carrying these.
+        for (name, value) in [
+            ("ExcludeClipboardContentFromMonitorProcessing", 1u32),
+            ("CanIncludeInClipboardHistory", 0),
+            ("CanUploadToCloudClipboard", 0),
+        ] {
+            let name: Vec<u16> = name.encode_utf16().chain([0]).collect();
+            // SAFETY: `name` is NUL-terminated and outlives the call.
+            let format = unsafe { RegisterClipboardFormatW(name.as_ptr()) };
+            if format != 0 {
+                formats.push((format, value.to_le_bytes().to_vec()));
+            }
+        }
+        write_formats(&formats)
+    }
+
+    fn write_formats(formats: &[(u32, Vec<u8>)]) -> Result<(), String> {
+        // EmptyClipboard makes the opener the owner, and SetClipboardData
+        // fails without one, so writes open it with a message-only window.
+        let class: Vec<u16> = "STATIC\0".encode_utf16().collect();
+        // SAFETY: `class` outlives the call; the window is destroyed by `Open`.
+        let owner = unsafe {
+            CreateWindowExW(0, class.as_ptr(), std::ptr::null(), 0, 0, 0, 0, 0, HWND_MESSAGE, 0, 0, std::ptr::null())
+        };
+        if owner == 0 {
+            return Err("could not create a clipboard window".to_string());
+        }
+        let Some(_open) = Open::new(owner) else {
+            // SAFETY: the window was created above and is not used again.
+            unsafe { DestroyWindow(owner) };
+            return Err("the clipboard is in use by another program".to_string());
+        };
+        // SAFETY: the clipboard is open and owned by us; the memory block is
+        // filled while locked and handed to the system on success.
+        unsafe {
+            EmptyClipboard();
+            for (format, data) in formats {
+                let mem = GlobalAlloc(GMEM_MOVEABLE, data.len());
+                let ptr = GlobalLock(mem) as *mut u8;
+                if ptr.is_null() {
+                    GlobalFree(mem);
+                    return Err("out of memory for the clipboard".to_string());
+                }
+                std::ptr::copy_nonoverlapping(data.as_ptr(), ptr, data.len());
+                GlobalUnlock(mem);
+                if SetClipboardData(*format, mem as _) == 0 {
+                    GlobalFree(mem);
+                    return Err("the clipboard refused the data".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Only text is synced elsewhere; iced handles that on every platform.
+#[cfg(not(windows))]
+mod platform {
+    use super::NativeContent;
+
+    pub const CF_DIB: u32 = 8;
+    pub const CF_HDROP: u32 = 15;
+
+    pub fn sequence() -> u32 {
+        0
+    }
+
+    pub fn read() -> Option<NativeContent> {
+        None
+    }
+
+    pub fn write(_format: u32, _data: &[u8]) -> Result<(), String> {
+        Err("images and files on the clipboard are only supported on Windows".to_string())
+    }
+
+    pub fn write_secret(_text: &str) -> Result<(), String> {
+        Err("keeping text out of clipboard history is only supported on Windows".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_change_sent_once() {
+        let mut sync = ClipboardSync::default();
+        assert_eq!(sync.local_changed(Some("before".to_string())), None);
+        assert_eq!(sync.local_changed(Some("hello".to_string())), Some("hello".to_string()));
+        assert_eq!(sync.local_changed(Some("hello".to_string())), None);
+        assert_eq!(sync.local_changed(None), None);
+        assert_eq!(sync.local_changed(Some("world".to_string())), Some("world".to_string()));
+    }
+
+    #[test]
+    fn remote_text_not_echoed_back() {
+        let mut sync = ClipboardSync::default();
+        assert_eq!(sync.remote_received("from host".to_string()), Some("from host".to_string()));
+        assert_eq!(sync.local_changed(Some("from host".to_string())), None);
+    }
+
+    #[test]
+    fn ignored_secret_never_sent() {
+        let mut sync = ClipboardSync::default();
+        sync.ignore("hunter2");
+        assert_eq!(sync.local_changed(Some("hunter2".to_string())), None);
+        // Cleared afterwards: nothing to send either.
+        assert_eq!(sync.local_changed(Some(String::new())), None);
+    }
+
+    #[test]
+    fn oversized_text_ignored() {
+        let mut sync = ClipboardSync::default();
+        let big = "x".repeat(MAX_CLIPBOARD_BYTES + 1);
+        assert_eq!(sync.local_changed(Some(big.clone())), None);
+        assert_eq!(sync.remote_received(big), None);
+    }
+
+    #[test]
+    fn drop_files_layout() {
+        let blob = drop_files(&[PathBuf::from("a"), PathBuf::from("bc")]);
+        assert_eq!(&blob[..4], &20u32.to_le_bytes());
+        assert_eq!(&blob[16..20], &1u32.to_le_bytes());
+        assert_eq!(&blob[20..], &[b'a', 0, 0, 0, b'b', 0, b'c', 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn received_files_stay_in_dir() {
+        let dir = std::env::temp_dir().join("rust-rdp-test-clipboard");
+        let files = vec![
+            ("notes.txt".to_string(), b"hi".to_vec()),
+            ("../escape.txt".to_string(), b"no".to_vec()),
+            ("C:evil".to_string(), b"no".to_vec()),
+        ];
+        let paths = save_received_files(&dir, files).unwrap();
+        assert_eq!(paths, vec![dir.join("notes.txt")]);
+        assert_eq!(std::fs::read(&paths[0]).unwrap(), b"hi");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn read_files_enforces_limit() {
+        let dir = std::env::temp_dir().join("rust-rdp-test-clipboard-read");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("data.bin");
+        std::fs::write(&file, vec![0u8; 100]).unwrap();
+        let paths = vec![file.clone(), dir.clone()];
+        assert_eq!(read_files(paths.clone(), 1000).await.unwrap().len(), 1);
+        assert!(read_files(paths, 50).await.is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
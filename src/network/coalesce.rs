@@ -0,0 +1,67 @@
+use tokio::sync::mpsc;
+
+use crate::protocol::ProtocolMessage;
+
+/// Most input messages taken from the queue for one write.
+pub const MAX_BATCH: usize = 64;
+
+/// `first` plus whatever input is already queued behind it, up to `MAX_BATCH`.
+pub fn drain(first: ProtocolMessage, rx: &mut mpsc::Receiver<ProtocolMessage>) -> Vec<ProtocolMessage> {
+    let mut batch = vec![first];
+    while batch.len() < MAX_BATCH {
+        match rx.try_recv() {
+            Ok(msg) => batch.push(msg),
+            Err(_) => break,
+        }
+    }
+    coalesce(batch)
+}
+
+/// Keeps only the last of each run of consecutive mouse moves. Everything
+/// else stays in order, and moves are never merged across a click or key,
+/// so a click still lands where the pointer was when it was made.
+pub fn coalesce(batch: Vec<ProtocolMessage>) -> Vec<ProtocolMessage> {
+    let mut out: Vec<ProtocolMessage> = Vec::with_capacity(batch.len());
+    for msg in batch {
+        if matches!(msg, ProtocolMessage::MouseMove { .. })
+            && let Some(last @ ProtocolMessage::MouseMove { .. }) = out.last_mut()
+        {
+            *last = msg;
+        } else {
+            out.push(msg);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::MouseBtn;
+
+    fn at(x: u16) -> ProtocolMessage {
+        ProtocolMessage::MouseMove { x, y: 0 }
+    }
+
+    #[test]
+    fn merges_runs_of_moves_only() {
+        let click = ProtocolMessage::MouseButton { button: MouseBtn::Left, pressed: true };
+        let batch = coalesce(vec![at(1), at(2), at(3), click, at(4), at(5)]);
+        assert_eq!(batch.len(), 3);
+        assert!(matches!(batch[0], ProtocolMessage::MouseMove { x: 3, .. }));
+        assert!(matches!(batch[1], ProtocolMessage::MouseButton { pressed: true, .. }));
+        assert!(matches!(batch[2], ProtocolMessage::MouseMove { x: 5, .. }));
+    }
+
+    #[test]
+    fn drains_what_is_queued() {
+        let (tx, mut rx) = mpsc::channel(8);
+        for x in 2..6 {
+            tx.try_send(at(x)).unwrap();
+        }
+        let batch = drain(at(1), &mut rx);
+        assert_eq!(batch.len(), 1);
+        assert!(matches!(batch[0], ProtocolMessage::MouseMove { x: 5, .. }));
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use futures::{SinkExt, Stream};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use super::ConnectionHandle;
+use super::paste::PasteProgress;
+use super::share::safe_relative_path;
+use crate::protocol::{FileEntry, ProtocolMessage};
+
+pub const MAX_TRANSFER_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+const CHUNK_BYTES: usize = 256 * 1024;
+
+/// What the remote file browser can reach on the host.
+pub fn host_root() -> Option<PathBuf> {
+    dirs_next::home_dir()
+}
+
+/// `path` under `root`; the empty path is `root` itself.
+pub fn resolve(root: &Path, path: &str) -> Option<PathBuf> {
+    match path.trim_matches('/') {
+        "" => Some(root.to_path_buf()),
+        path => safe_relative_path(path).map(|p| root.join(p)),
+    }
+}
+
+/// Folders first, then files, each by name.
+pub fn list_dir(dir: &Path) -> Result<Vec<FileEntry>, String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    let mut list: Vec<FileEntry> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            Some(FileEntry {
+                name: entry.file_name().into_string().ok()?,
+                is_dir: meta.is_dir(),
+                size: if meta.is_dir() { 0 } else { meta.len() },
+            })
+        })
+        .collect();
+    list.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+    Ok(list)
+}
+
+/// The folder above a browser path.
+pub fn parent(path: &str) -> String {
+    match path.trim_matches('/').rsplit_once('/') {
+        Some((up, _)) => up.to_string(),
+        None => String::new(),
+    }
+}
+
+pub fn child(path: &str, name: &str) -> String {
+    match path.trim_matches('/') {
+        "" => name.to_string(),
+        path => format!("{path}/{name}"),
+    }
+}
+
+/// Hex SHA-256 of the file at `path`. Blocks while it reads the file.
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Streams the file at `path` from `start` in chunks, each wrapped by
+/// `chunk(offset, total, data)`.
+pub fn send_file(
+    handle: ConnectionHandle,
+    path: PathBuf,
+    start: u64,
+    chunk: impl Fn(u64, u64, Vec<u8>) -> ProtocolMessage + Send + Sync + 'static,
+) -> Pin<Box<dyn Stream<Item = PasteProgress> + Send>> {
+    Box::pin(iced::stream::channel(8, async move |mut output| {
+        let result: Result<(), String> = async {
+            let mut file = tokio::fs::File::open(&path).await.map_err(|e| format!("{}: {e}", path.display()))?;
+            let total = file.metadata().await.map_err(|e| e.to_string())?.len();
+            if total > MAX_TRANSFER_BYTES {
+                return Err(format!("Files over {} GB are not transferred", MAX_TRANSFER_BYTES >> 30));
+            }
+            if start > total {
+                return Err(format!("{} shrank while it was sent", path.display()));
+            }
+            file.seek(std::io::SeekFrom::Start(start)).await.map_err(|e| e.to_string())?;
+            let mut offset = start;
+            let mut buf = vec![0u8; CHUNK_BYTES];
+            // An empty file still needs one chunk to be created.
+            while offset < total || total == 0 {
+                let read = file.read(&mut buf).await.map_err(|e| e.to_string())?;
+                if read == 0 && offset < total {
+                    return Err(format!("{} shrank while it was sent", path.display()));
+                }
+                handle.send_input(chunk(offset, total, buf[..read].to_vec())).await?;
+                offset += read as u64;
+                let _ = output.send(PasteProgress::Sent { sent: offset, total }).await;
+                if total == 0 {
+                    break;
+                }
+            }
+            Ok(())
+        }
+        .await;
+        if let Err(e) = result {
+            let _ = output.send(PasteProgress::Failed(e)).await;
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn browser_paths_stay_under_the_root() {
+        let root = Path::new("home");
+        assert_eq!(resolve(root, ""), Some(root.to_path_buf()));
+        assert_eq!(resolve(root, "docs/a"), Some(root.join("docs").join("a")));
+        assert_eq!(resolve(root, "docs/../.."), None);
+        assert_eq!(parent("docs/a"), "docs");
+        assert_eq!(parent("docs"), "");
+        assert_eq!(child("", "docs"), "docs");
+        assert_eq!(child("docs", "a"), "docs/a");
+    }
+
+    #[test]
+    fn folders_list_before_files() {
+        let dir = std::env::temp_dir().join(format!("rust-rdp-files-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("zeta")).unwrap();
+        std::fs::write(dir.join("Alpha.txt"), b"abc").unwrap();
+        std::fs::write(dir.join("beta.txt"), b"").unwrap();
+        let names: Vec<_> = list_dir(&dir).unwrap().into_iter().map(|e| (e.name, e.size)).collect();
+        assert_eq!(names, [("zeta".to_string(), 0), ("Alpha.txt".to_string(), 3), ("beta.txt".to_string(), 0)]);
+        assert_eq!(
+            sha256_file(&dir.join("Alpha.txt")).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::config::secret::SecretString;
+
+pub const CODE_DIGITS: usize = 6;
+/// Wrong codes allowed from one address before it is locked out for a while.
+const MAX_FAILURES: u32 = 5;
+const LOCKOUT: Duration = Duration::from_secs(60);
+
+/// A fresh code for one hosting run, shown on the host screen.
+pub fn generate_code() -> Result<SecretString, String> {
+    let mut bytes = [0u8; 4];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| "No randomness available for an access code".to_string())?;
+    let code = u32::from_le_bytes(bytes) % 10u32.pow(CODE_DIGITS as u32);
+    Ok(format!("{code:0width$}", width = CODE_DIGITS).into())
+}
+
+/// Checks the access code viewers send after their Hello. Failures are
+/// counted per source address, so one peer guessing cannot lock out another.
+#[derive(Debug)]
+pub struct Guard {
+    code: Option<SecretString>,
+    failures: HashMap<IpAddr, Failures>,
+}
+
+#[derive(Debug, Default)]
+struct Failures {
+    count: u32,
+    locked_until: Option<Instant>,
+}
+
+impl Guard {
+    /// `None` lets every viewer through.
+    pub fn new(code: Option<SecretString>) -> Self {
+        Self { code, failures: HashMap::new() }
+    }
+
+    pub fn required(&self) -> bool {
+        self.code.is_some()
+    }
+
+    pub fn check(&mut self, from: IpAddr, given: &str, now: Instant) -> Result<(), String> {
+        let Some(code) = &self.code else { return Ok(()) };
+        // Forget addresses whose lockout has run out, so the map stays small.
+        self.failures.retain(|_, f| f.locked_until.is_none_or(|until| now < until));
+        let failures = self.failures.entry(from).or_default();
+        if failures.locked_until.is_some() {
+            return Err("Too many wrong access codes; try again in a minute".to_string());
+        }
+        if codes_match(code.expose(), given.trim()) {
+            self.failures.remove(&from);
+            return Ok(());
+        }
+        failures.count += 1;
+        if failures.count >= MAX_FAILURES {
+            failures.locked_until = Some(now + LOCKOUT);
+        }
+        Err("Wrong access code".to_string())
+    }
+}
+
+/// Compares without stopping at the first differing byte.
+pub(crate) fn codes_match(expected: &str, given: &str) -> bool {
+    expected.len() == given.len() && expected.bytes().zip(given.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_codes_are_six_digits() {
+        let code = generate_code().unwrap();
+        assert_eq!(code.expose().len(), CODE_DIGITS);
+        assert!(code.expose().bytes().all(|b| b.is_ascii_digit()));
+    }
+
+    const VIEWER: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(100, 64, 0, 1));
+    const GUESSER: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(100, 64, 0, 2));
+
+    #[test]
+    fn locks_out_after_repeated_failures() {
+        let now = Instant::now();
+        let mut guard = Guard::new(Some("123456".into()));
+        assert!(guard.check(VIEWER, " 123456 ", now).is_ok());
+        for _ in 0..MAX_FAILURES {
+            assert!(guard.check(VIEWER, "000000", now).is_err());
+        }
+        // Even the right code is refused until the lockout ends.
+        assert!(guard.check(VIEWER, "123456", now).is_err());
+        assert!(guard.check(VIEWER, "123456", now + LOCKOUT).is_ok());
+
+        assert!(Guard::new(None).check(VIEWER, "", now).is_ok());
+    }
+
+    #[test]
+    fn lockout_is_per_address() {
+        let now = Instant::now();
+        let mut guard = Guard::new(Some("123456".into()));
+        for _ in 0..MAX_FAILURES {
+            assert!(guard.check(GUESSER, "000000", now).is_err());
+        }
+        assert!(guard.check(GUESSER, "123456", now).is_err());
+        assert!(guard.check(VIEWER, "123456", now).is_ok());
+    }
+}
@@ -1,8 +1,18 @@
+pub mod bind;
 pub mod client;
+pub mod clipboard;
+pub mod coalesce;
+pub mod files;
+pub mod guard;
+pub mod paste;
+pub mod route;
 pub mod server;
+pub mod share;
+pub mod stats;
+pub mod typing;
 
 use tokio::sync::mpsc;
-use crate::protocol::ProtocolMessage;
+use crate::protocol::{CursorImage, DirtyRect, DisplayInfo, FileEntry, HostInfo, ProtocolMessage};
 
 #[derive(Debug, Clone)]
 pub struct ConnectionHandle {
@@ -17,21 +27,104 @@ impl ConnectionHandle {
     pub async fn send_input(&self, msg: ProtocolMessage) -> Result<(), String> {
         self.input_tx.send(msg).await.map_err(|e| e.to_string())
     }
+
+    /// Queues `msg` without waiting, dropping it when the queue is full. For
+    /// pointer moves, which the next move replaces anyway.
+    pub fn try_send_input(&self, msg: ProtocolMessage) -> Result<(), String> {
+        self.input_tx.try_send(msg).map_err(|e| e.to_string())
+    }
+}
+
+/// The host's answer to a viewer waiting for a busy session.
+#[derive(Debug, Clone)]
+pub struct ControlReply {
+    tx: mpsc::Sender<bool>,
+}
+
+impl ControlReply {
+    pub fn new(tx: mpsc::Sender<bool>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn send(&self, accept: bool) -> Result<(), String> {
+        self.tx.send(accept).await.map_err(|e| e.to_string())
+    }
+}
+
+/// Stages a viewer goes through before the session is up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectStage {
+    Dialing,
+    Handshake,
+    Active,
+}
+
+impl ConnectStage {
+    pub const ALL: [ConnectStage; 3] = [ConnectStage::Dialing, ConnectStage::Handshake, ConnectStage::Active];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ConnectStage::Dialing => "Reaching the host over Tailscale",
+            ConnectStage::Handshake => "Hello and access code",
+            ConnectStage::Active => "Session active",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum NetworkEvent {
+    /// Viewer side: how far the connection attempt has got.
+    Stage(ConnectStage),
     Listening { port: u16 },
     ClientConnected,
     Connected(ConnectionHandle),
+    Reconnecting { attempt: u32, max: u32 },
     ClientDisconnected,
+    /// Decoded RGBA pixels, covering only `region` when it is set.
     Frame {
         width: u32,
         height: u32,
+        region: Option<DirtyRect>,
         pixels: Vec<u8>,
     },
     LatencyUpdate { rtt_ms: u64 },
-    ClientInfo { addr: String },
+    Stats(stats::SessionStats),
+    ClientInfo { addr: String, handle: ConnectionHandle },
+    /// Host side: a second viewer asked to take over the session.
+    ControlRequested { addr: String, reply: ControlReply },
+    /// Viewer side: the host's monitors, sent only when it has more than one.
+    Displays(Vec<DisplayInfo>),
+    /// Viewer side: the host is busy and deciding whether to let us in.
+    Queued,
+    /// Viewer side: this machine is going to sleep; the session is held until
+    /// it resumes and then reconnects.
+    Suspended,
+    /// Viewer side: the person at the host moved its pointer here.
+    HostCursor { x: u16, y: u16 },
+    /// Viewer side: the host's pointer image; `None` while it is hidden.
+    CursorShape(Option<CursorImage>),
+    /// Viewer side: a folder listing from the host's file browser.
+    FileList { path: String, entries: Vec<FileEntry> },
+    FileChunk { name: String, offset: u64, total: u64, data: Vec<u8> },
+    FileError(String),
+    TransferFailed(String),
+    /// SHA-256 of the host's copy of the file being transferred.
+    FileDigest(String),
+    /// Viewer side: the host is showing a UAC prompt or its lock screen.
+    SecureDesktop(bool),
+    /// Viewer side: the host's version, sent once it lets us in.
+    HostInfo(HostInfo),
+    ClipboardText(String),
+    /// Host side: a credential from the viewer and when to clear it.
+    ClipboardSecret { text: String, clear_after_secs: u16 },
+    ClipboardImage(Vec<u8>),
+    ClipboardFiles(Vec<(String, Vec<u8>)>),
+    /// Viewer side: the host refused the access code.
+    AccessDenied(String),
     Error(String),
     Stopped,
 }
@@ -0,0 +1,179 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use futures::{SinkExt, Stream};
+
+use super::ConnectionHandle;
+use super::share::safe_relative_path;
+use crate::protocol::ProtocolMessage;
+
+/// Text is sent in pieces this big so the viewer can show progress.
+pub const CHUNK_BYTES: usize = 64 * 1024;
+pub const MAX_PASTE_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PasteProgress {
+    Sent { sent: u64, total: u64 },
+    Failed(String),
+}
+
+/// Sends `text` to be saved as `name` in `folder` under the host's desktop.
+pub fn send(
+    handle: ConnectionHandle,
+    folder: String,
+    name: String,
+    text: String,
+) -> Pin<Box<dyn Stream<Item = PasteProgress> + Send>> {
+    Box::pin(iced::stream::channel(8, async move |mut output| {
+        let data = text.into_bytes();
+        let total = data.len() as u64;
+        if total > MAX_PASTE_BYTES {
+            let _ = output
+                .send(PasteProgress::Failed(format!(
+                    "Text is larger than {} MB",
+                    MAX_PASTE_BYTES / (1024 * 1024)
+                )))
+                .await;
+            return;
+        }
+        let mut offset = 0u64;
+        for chunk in data.chunks(CHUNK_BYTES) {
+            let msg = ProtocolMessage::PasteFile {
+                folder: folder.clone(),
+                name: name.clone(),
+                offset,
+                total,
+                data: chunk.to_vec(),
+            };
+            if let Err(e) = handle.send_input(msg).await {
+                let _ = output.send(PasteProgress::Failed(e)).await;
+                return;
+            }
+            offset += chunk.len() as u64;
+            let _ = output.send(PasteProgress::Sent { sent: offset, total }).await;
+        }
+    }))
+}
+
+/// Where `folder` and `name` from the viewer end up under `root`, or `None`
+/// when either would leave it.
+pub fn target_path(root: &Path, folder: &str, name: &str) -> Option<PathBuf> {
+    let name = safe_relative_path(name).filter(|_| !name.contains('/'))?;
+    let folder = folder.trim_matches('/');
+    let dir = if folder.is_empty() { root.to_path_buf() } else { root.join(safe_relative_path(folder)?) };
+    Some(dir.join(name))
+}
+
+/// `path`, or `name (2).ext` and so on when that is already taken.
+fn unused_path(path: PathBuf) -> PathBuf {
+    if !path.exists() {
+        return path;
+    }
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{stem} ({n}){ext}")))
+        .find(|candidate| !candidate.exists())
+        .unwrap_or(path)
+}
+
+/// Host side: writes pasted text to files as its chunks arrive.
+pub struct PasteWriter {
+    root: PathBuf,
+    limit: u64,
+    current: Option<(String, PathBuf, File, u64)>,
+}
+
+impl PasteWriter {
+    /// Files land on the desktop when there is one.
+    pub fn new() -> Self {
+        let root = dirs_next::desktop_dir().unwrap_or_else(|| crate::config::app_data_dir().join("pasted"));
+        Self::with_root(root, MAX_PASTE_BYTES)
+    }
+
+    /// Also used for file browser transfers, with a larger `limit`.
+    pub fn with_root(root: PathBuf, limit: u64) -> Self {
+        Self { root, limit, current: None }
+    }
+
+    /// Deletes the file being written, if it is unfinished.
+    pub fn abandon(&mut self) {
+        if let Some((_, path, file, _)) = self.current.take() {
+            drop(file);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    /// Returns the file's path once its last chunk is written.
+    pub fn write(&mut self, folder: &str, name: &str, offset: u64, total: u64, data: &[u8]) -> Result<Option<PathBuf>, String> {
+        if offset == 0 {
+            // A file sent again from the start replaces its own unfinished copy.
+            if self.current.as_ref().is_some_and(|(current, ..)| current == name) {
+                self.abandon();
+            }
+            if total > self.limit {
+                return Err(format!("{name:?} exceeds the size limit"));
+            }
+            let target = target_path(&self.root, folder, name)
+                .ok_or_else(|| format!("Rejected paste destination {folder:?}/{name:?}"))?;
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            let target = unused_path(target);
+            let file = File::create(&target).map_err(|e| format!("Create {}: {e}", target.display()))?;
+            self.current = Some((name.to_string(), target, file, 0));
+        }
+        let Some((current, path, file, written)) = &mut self.current else {
+            return Err(format!("{name:?} arrived without its start"));
+        };
+        if current != name || *written != offset || offset + data.len() as u64 > total {
+            self.abandon();
+            return Err(format!("{name:?} arrived out of order"));
+        }
+        file.write_all(data).map_err(|e| e.to_string())?;
+        *written += data.len() as u64;
+        if *written < total {
+            return Ok(None);
+        }
+        let path = path.clone();
+        self.current = None;
+        Ok(Some(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destinations_stay_under_the_root() {
+        let root = Path::new("desk");
+        assert_eq!(target_path(root, "", "a.txt"), Some(root.join("a.txt")));
+        assert_eq!(target_path(root, "logs/today/", "a.txt"), Some(root.join("logs").join("today").join("a.txt")));
+        assert_eq!(target_path(root, "../up", "a.txt"), None);
+        assert_eq!(target_path(root, "", "sub/a.txt"), None);
+        assert_eq!(target_path(root, "", ".."), None);
+    }
+
+    #[test]
+    fn writes_chunks_without_overwriting() {
+        let root = std::env::temp_dir().join(format!("rust-rdp-paste-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let mut writer = PasteWriter::with_root(root.clone(), MAX_PASTE_BYTES);
+        assert_eq!(writer.write("", "a.txt", 0, 6, b"abc").unwrap(), None);
+        let first = writer.write("", "a.txt", 3, 6, b"def").unwrap().unwrap();
+        assert_eq!(std::fs::read(&first).unwrap(), b"abcdef");
+
+        let second = writer.write("", "a.txt", 0, 1, b"x").unwrap().unwrap();
+        assert_eq!(second, root.join("a (2).txt"));
+        assert!(writer.write("", "a.txt", 4, 6, b"zz").is_err());
+
+        assert_eq!(writer.write("", "b.txt", 0, 4, b"ab").unwrap(), None);
+        assert_eq!(writer.write("", "b.txt", 0, 4, b"ab").unwrap(), None);
+        assert_eq!(writer.write("", "b.txt", 2, 4, b"cd").unwrap(), Some(root.join("b.txt")));
+        assert!(!root.join("b (2).txt").exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,56 @@
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::time::Duration;
+
+/// How often the client checks whether the route to the host has moved.
+pub const ROUTE_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The local address the OS would now use to reach `peer`. Connecting a UDP
+/// socket only consults the routing table; nothing is sent.
+pub fn source_for(peer: SocketAddr) -> Option<IpAddr> {
+    let bind: SocketAddr = if peer.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" }.parse().ok()?;
+    let socket = UdpSocket::bind(bind).ok()?;
+    socket.connect(peer).ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip())
+}
+
+/// Remembers which local address a session's connection went out on, so a
+/// roam (Wi-Fi to Ethernet, a VPN coming up or down) is noticed straight away
+/// instead of after the heartbeat times out.
+#[derive(Debug, Clone, Copy)]
+pub struct RouteWatch {
+    pub local: IpAddr,
+    pub peer: SocketAddr,
+}
+
+impl RouteWatch {
+    /// Describes the change when the connection's address is no longer the
+    /// one the OS would pick. A failed lookup counts as no change.
+    pub fn changed(&self, now: Option<IpAddr>) -> Option<String> {
+        match now {
+            Some(ip) if ip != self.local => Some(format!("Network changed ({} is now reached from {ip})", self.peer.ip())),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loopback_routes_from_loopback() {
+        let peer: SocketAddr = "127.0.0.1:9867".parse().unwrap();
+        assert_eq!(source_for(peer), Some(IpAddr::from([127, 0, 0, 1])));
+    }
+
+    #[test]
+    fn only_a_different_source_is_a_change() {
+        let watch = RouteWatch {
+            local: IpAddr::from([192, 168, 1, 20]),
+            peer: "100.64.0.1:9867".parse().unwrap(),
+        };
+        assert!(watch.changed(Some(IpAddr::from([192, 168, 1, 20]))).is_none());
+        assert!(watch.changed(None).is_none());
+        assert!(watch.changed(Some(IpAddr::from([10, 0, 0, 5]))).is_some());
+    }
+}
@@ -1,22 +1,63 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::time::Duration;
 use futures::{Stream, StreamExt, SinkExt};
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
 use tokio_util::codec::Framed;
-use crate::protocol::ProtocolMessage;
+use crate::protocol::{HostInfo, PROTOCOL_VERSION, ProtocolMessage};
 use crate::protocol::codec::MessageCodec;
 use crate::capture::{CaptureConfig, CaptureEvent, CaptureCommand};
 use crate::capture::capturer::capture_loop;
+use crate::capture::display_scale::{MAX_TEXT_BOOST_STEPS, TextBoost};
+use crate::capture::secure_desktop;
+use crate::input_handler::cursor::{self, CursorUpdate};
 use crate::input_handler::handler::InputHandler;
-use super::NetworkEvent;
+use crate::input_handler::layout::{activate_layout, is_valid_klid};
+use crate::config::secret::SecretString;
+use crate::metrics::HOST_METRICS;
+use super::guard::Guard;
+use super::files;
+use super::paste::{PasteProgress, PasteWriter};
+use super::share::SharedDrive;
+use super::{ConnectionHandle, ControlReply, NetworkEvent};
+use crate::session::SessionId;
 
-pub fn host_server_subscription(host: String, port: u16) -> iced::Subscription<NetworkEvent> {
-    iced::Subscription::run_with((host.clone(), port), move |(host, port)| host_server_stream(host.clone(), *port))
+/// `access_code`, when set, must be sent by each viewer after its Hello.
+/// Viewers may restart this machine only with `allow_restart`.
+///
+/// The server is restarted whenever any argument changes, including
+/// `session`, which is new for every start of hosting. Keeping it in the key
+/// stops a stop-and-start within one update from reusing the old listener.
+pub fn host_server_subscription(
+    session: SessionId,
+    host: IpAddr,
+    port: u16,
+    fps: u32,
+    only_display: Option<u32>,
+    access_code: Option<SecretString>,
+    allow_restart: bool,
+) -> iced::Subscription<NetworkEvent> {
+    iced::Subscription::run_with(
+        (session, host, port, fps, only_display, access_code, allow_restart),
+        move |(_, host, port, fps, only_display, access_code, allow_restart)| {
+            host_server_stream(*host, *port, *fps, *only_display, access_code.clone(), *allow_restart)
+        },
+    )
 }
 
-fn host_server_stream(host: String, port: u16) -> Pin<Box<dyn Stream<Item = NetworkEvent> + Send>> {
+fn host_server_stream(
+    host: IpAddr,
+    port: u16,
+    fps: u32,
+    only_display: Option<u32>,
+    access_code: Option<SecretString>,
+    allow_restart: bool,
+) -> Pin<Box<dyn Stream<Item = NetworkEvent> + Send>> {
     Box::pin(iced::stream::channel(100, move |mut output: futures::channel::mpsc::Sender<NetworkEvent>| async move {
-        let addr = format!("{host}:{port}");
+        let addr = SocketAddr::new(host, port);
         let listener = match TcpListener::bind(&addr).await {
             Ok(l) => l,
             Err(e) => {
@@ -27,131 +68,596 @@ fn host_server_stream(host: String, port: u16) -> Pin<Box<dyn Stream<Item = Netw
             }
         };
 
+        let listening = HOST_METRICS.listening();
         let _ = output.send(NetworkEvent::Listening { port }).await;
 
-        let (stream, client_addr) = match listener.accept().await {
-            Ok(s) => s,
-            Err(e) => {
-                let _ = output.send(NetworkEvent::Error(format!("Accept failed: {e}"))).await;
-                let _ = output.send(NetworkEvent::Stopped).await;
-                std::future::pending::<()>().await;
-                return;
-            }
-        };
+        let guard = Arc::new(Mutex::new(Guard::new(access_code)));
+        let mut next: Option<Viewer> = None;
+        loop {
+            let viewer = match next.take() {
+                Some(viewer) => viewer,
+                None => {
+                    let (stream, client_addr) = match listener.accept().await {
+                        Ok(s) => s,
+                        Err(e) => {
+                            let _ = output.send(NetworkEvent::Error(format!("Accept failed: {e}"))).await;
+                            break;
+                        }
+                    };
+                    match greet(stream, client_addr, &guard).await {
+                        Some(viewer) => viewer,
+                        None => continue,
+                    }
+                }
+            };
 
-        let _ = output.send(NetworkEvent::ClientConnected).await;
+            let client = HOST_METRICS.client();
+            let _ = output.send(NetworkEvent::ClientConnected).await;
+            next = serve_client(viewer, fps, only_display, allow_restart, &listener, &guard, &mut output).await;
+            drop(client);
+            let _ = output.send(NetworkEvent::ClientDisconnected).await;
+        }
 
-        let mut framed = Framed::new(stream, MessageCodec);
+        drop(listening);
+        let _ = output.send(NetworkEvent::Stopped).await;
+        std::future::pending::<()>().await;
+    }))
+}
 
-        match framed.next().await {
-            Some(Ok(ProtocolMessage::Hello { version, screen_width, screen_height })) => {
-                tracing::info!(
-                    "Client hello: version={version}, screen={screen_width}x{screen_height}, addr={client_addr}"
-                );
-            }
-            Some(Ok(other)) => {
-                tracing::warn!("Expected Hello, got: {other:?}");
-            }
-            Some(Err(e)) => {
-                let _ = output.send(NetworkEvent::Error(format!("Read hello failed: {e}"))).await;
-                let _ = output.send(NetworkEvent::Stopped).await;
-                std::future::pending::<()>().await;
-                return;
+/// Kept short so a peer that never speaks is dropped quickly.
+const HELLO_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a second viewer waits for the host to answer before being turned away.
+const CONTROL_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Encoded frames waiting for the socket; the capture loop drops frames
+/// rather than queue more behind a slow link.
+const FRAME_QUEUE: usize = 2;
+
+/// Longest a link test may hold its connection open.
+const LINK_TEST_TIMEOUT: Duration = Duration::from_secs(20);
+/// Largest payload sent for one `LinkTestRequest`.
+const MAX_LINK_TEST_BYTES: u32 = 8 * 1024 * 1024;
+/// Link tests running at once; more are turned away until one finishes.
+const MAX_LINK_TESTS: usize = 2;
+static LINK_TESTS: tokio::sync::Semaphore = tokio::sync::Semaphore::const_new(MAX_LINK_TESTS);
+
+/// A viewer that has sent its Hello.
+struct Viewer {
+    framed: Framed<TcpStream, MessageCodec>,
+    addr: SocketAddr,
+    layout: Option<String>,
+}
+
+/// A second viewer waiting on the host's answer.
+struct PendingViewer {
+    viewer: Viewer,
+    reply_rx: tokio::sync::mpsc::Receiver<bool>,
+    deadline: tokio::time::Instant,
+}
+
+async fn greet(stream: TcpStream, addr: SocketAddr, guard: &Mutex<Guard>) -> Option<Viewer> {
+    let mut framed = Framed::new(stream, MessageCodec);
+
+    let mut layout = None;
+    let mut link_test = false;
+    match tokio::time::timeout(HELLO_TIMEOUT, framed.next()).await {
+        Ok(Some(Ok(ProtocolMessage::Hello { version, screen_width, screen_height, keyboard_layout }))) => {
+            tracing::info!(
+                "Client hello: version={version}, screen={screen_width}x{screen_height}, layout={keyboard_layout:?}, addr={addr}"
+            );
+            layout = keyboard_layout.filter(|klid| is_valid_klid(klid));
+        }
+        Ok(Some(Ok(ProtocolMessage::Probe))) => {
+            let _ = framed.send(probe_hello()).await;
+            tracing::info!("Answered probe from {addr}");
+            return None;
+        }
+        // Served once the access code checks out, like a session.
+        Ok(Some(Ok(ProtocolMessage::LinkTest))) => link_test = true,
+        Ok(Some(Ok(other))) => {
+            tracing::warn!("Expected Hello, got: {}", other.kind());
+        }
+        Ok(Some(Err(e))) => {
+            tracing::warn!("Read hello from {addr} failed: {e}");
+            return None;
+        }
+        Ok(None) => return None,
+        Err(_) => {
+            tracing::warn!("No hello from {addr}");
+            return None;
+        }
+    }
+    if guard.lock().await.required() {
+        let checked = match tokio::time::timeout(HELLO_TIMEOUT, framed.next()).await {
+            Ok(Some(Ok(ProtocolMessage::AccessCode(code)))) => {
+                guard.lock().await.check(addr.ip(), &code, std::time::Instant::now())
             }
-            None => {
-                let _ = output.send(NetworkEvent::ClientDisconnected).await;
-                let _ = output.send(NetworkEvent::Stopped).await;
-                std::future::pending::<()>().await;
-                return;
+            _ => Err("This host requires an access code".to_string()),
+        };
+        if let Err(reason) = checked {
+            tracing::warn!("Turned away {addr}: {reason}");
+            let _ = framed.send(ProtocolMessage::AccessDenied(reason)).await;
+            return None;
+        }
+    }
+    if link_test {
+        let Ok(permit) = LINK_TESTS.try_acquire() else {
+            tracing::warn!("Turned away a link test from {addr}: {MAX_LINK_TESTS} already running");
+            let reason = "The host is busy with other link tests; try again shortly".to_string();
+            let _ = framed.send(ProtocolMessage::AccessDenied(reason)).await;
+            return None;
+        };
+        // Off the accept path, so a test never stalls a running session.
+        tokio::spawn(async move {
+            let _ = tokio::time::timeout(LINK_TEST_TIMEOUT, serve_link_test(framed)).await;
+            drop(permit);
+            tracing::info!("Link test from {addr} finished");
+        });
+        return None;
+    }
+    Some(Viewer { framed, addr, layout })
+}
+
+fn probe_hello() -> ProtocolMessage {
+    let (screen_width, screen_height) = crate::capture::displays::list()
+        .first()
+        .map_or((0, 0), |d| (d.width, d.height));
+    ProtocolMessage::Hello {
+        version: PROTOCOL_VERSION,
+        screen_width,
+        screen_height,
+        keyboard_layout: None,
+    }
+}
+
+async fn serve_link_test(mut framed: Framed<TcpStream, MessageCodec>) {
+    if framed.send(probe_hello()).await.is_err() {
+        return;
+    }
+    while let Some(Ok(msg)) = framed.next().await {
+        let reply = match msg {
+            ProtocolMessage::Ping(ts) => ProtocolMessage::Pong(ts),
+            ProtocolMessage::LinkTestRequest(bytes) => {
+                ProtocolMessage::LinkTestPayload(vec![0; bytes.min(MAX_LINK_TEST_BYTES) as usize])
             }
+            _ => return,
+        };
+        if framed.send(reply).await.is_err() {
+            return;
         }
+    }
+}
+
+/// Resolves with the host's answer, or `false` once the request times out.
+async fn control_decision(pending: &mut Option<PendingViewer>) -> bool {
+    match pending {
+        Some(p) => tokio::time::timeout_at(p.deadline, p.reply_rx.recv())
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or(false),
+        None => std::future::pending().await,
+    }
+}
 
-        let _ = output.send(NetworkEvent::ClientInfo { addr: client_addr.to_string() }).await;
+/// Runs one client session to completion. The listener goes back to accepting
+/// afterwards so a viewer that dropped off can reconnect. A viewer that arrives
+/// while the session is running is greeted in its own task, so a silent peer
+/// cannot stall this one, then queued until the host accepts or declines it;
+/// an accepted one is returned so it is served next.
+async fn serve_client(
+    viewer: Viewer,
+    fps: u32,
+    only_display: Option<u32>,
+    allow_restart: bool,
+    listener: &TcpListener,
+    guard: &Arc<Mutex<Guard>>,
+    output: &mut futures::channel::mpsc::Sender<NetworkEvent>,
+) -> Option<Viewer> {
+    let Viewer { framed, addr: client_addr, layout: client_layout } = viewer;
 
-        let config = CaptureConfig::default();
-        let (capture_tx, mut capture_rx) = tokio::sync::mpsc::channel::<CaptureEvent>(30);
-        let (cmd_tx, cmd_rx) = tokio::sync::mpsc::channel::<CaptureCommand>(10);
+    let (outbound_tx, mut outbound_rx) = tokio::sync::mpsc::channel::<ProtocolMessage>(16);
+    let files_handle = ConnectionHandle::new(outbound_tx.clone());
+    let _ = output.send(NetworkEvent::ClientInfo {
+        addr: client_addr.to_string(),
+        handle: ConnectionHandle::new(outbound_tx),
+    }).await;
 
-        tokio::task::spawn_blocking(move || capture_loop(config, capture_tx, cmd_rx));
+    let config = CaptureConfig { fps, display: only_display.map(|i| i as usize), ..Default::default() };
+    let (capture_tx, mut capture_rx) = tokio::sync::mpsc::channel::<CaptureEvent>(FRAME_QUEUE);
+    let (cmd_tx, cmd_rx) = tokio::sync::mpsc::channel::<CaptureCommand>(10);
 
-        let (input_tx, mut input_rx) = tokio::sync::mpsc::channel::<ProtocolMessage>(100);
+    tokio::task::spawn_blocking(move || capture_loop(config, capture_tx, cmd_rx));
 
-        tokio::task::spawn_blocking(move || {
-            let mut handler = match InputHandler::new() {
-                Ok(h) => h,
-                Err(e) => {
-                    tracing::warn!("Failed to create InputHandler: {e}");
-                    return;
-                }
-            };
-            while let Some(msg) = input_rx.blocking_recv() {
-                handler.apply(&msg);
+    let (input_tx, mut input_rx) = tokio::sync::mpsc::channel::<ProtocolMessage>(100);
+
+    let displays = crate::capture::displays::list();
+    let input_displays = displays.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut handler = match InputHandler::new(input_displays) {
+            Ok(h) => h,
+            Err(e) => {
+                tracing::warn!("Failed to create InputHandler: {e}");
+                return;
+            }
+        };
+        let _layout_guard = client_layout.and_then(|klid| match activate_layout(&klid) {
+            Ok(guard) => {
+                tracing::info!("Switched keyboard layout to {klid} to match the client");
+                Some(guard)
+            }
+            Err(e) => {
+                tracing::warn!("Keeping host keyboard layout: {e}");
+                None
             }
         });
+        while let Some(msg) = input_rx.blocking_recv() {
+            handler.apply(&msg);
+        }
+    });
 
-        let mut heartbeat = tokio::time::interval(Duration::from_secs(5));
-        let mut last_pong = tokio::time::Instant::now();
+    let (cursor_tx, mut cursor_rx) = tokio::sync::mpsc::channel::<CursorUpdate>(8);
+    tokio::task::spawn_blocking(move || cursor::watch(cursor_tx));
+    let first = only_display.unwrap_or(0) as usize;
+    let mut screen = displays.get(first).map(|d| (d.x, d.y, d.width as i32, d.height as i32));
+    if let Some(index) = only_display {
+        tracing::info!("Sharing display {} only", index + 1);
+        let _ = input_tx.send(ProtocolMessage::SelectDisplay(index)).await;
+    }
+    let mut last_pointer: Option<(u16, u16)> = None;
 
-        let (mut sink, mut stream_reader) = framed.split();
+    let (secure_tx, mut secure_rx) = tokio::sync::mpsc::channel::<bool>(2);
+    tokio::task::spawn_blocking(move || secure_desktop::watch(secure_tx));
 
-        loop {
-            tokio::select! {
-                frame = capture_rx.recv() => {
-                    match frame {
-                        Some(CaptureEvent::Frame(data)) => {
-                            if let Err(e) = sink.send(ProtocolMessage::Frame(data)).await {
-                                tracing::warn!("Send frame error: {e}");
-                                break;
-                            }
+    let mut heartbeat = tokio::time::interval(Duration::from_secs(5));
+    let mut last_pong = tokio::time::Instant::now();
+
+    let (mut sink, mut stream_reader) = framed.split();
+    let _ = sink.send(ProtocolMessage::HostInfo(HostInfo::local(guard.lock().await.required()))).await;
+    // With one display shared, the viewer is not told about the others.
+    if displays.len() > 1 && only_display.is_none() {
+        let _ = sink.send(ProtocolMessage::Displays(displays.clone())).await;
+    }
+    let mut text_boost: Option<TextBoost> = None;
+    let mut shared_drive: Option<SharedDrive> = None;
+    let mut pasted = PasteWriter::new();
+    let files_root = files::host_root();
+    // One per file, so a paused upload can carry on after others.
+    let mut uploads: HashMap<String, PasteWriter> = HashMap::new();
+    let mut download: Option<tokio::task::JoinHandle<()>> = None;
+    let mut pending: Option<PendingViewer> = None;
+    let mut handover: Option<Viewer> = None;
+    let (greeted_tx, mut greeted_rx) = tokio::sync::mpsc::channel::<Viewer>(4);
+
+    loop {
+        tokio::select! {
+            incoming = listener.accept(), if pending.is_none() => {
+                let (stream, addr) = match incoming {
+                    Ok(s) => s,
+                    Err(e) => {
+                        tracing::warn!("Accept failed: {e}");
+                        continue;
+                    }
+                };
+                let (guard, greeted_tx) = (guard.clone(), greeted_tx.clone());
+                tokio::spawn(async move {
+                    if let Some(viewer) = greet(stream, addr, &guard).await {
+                        let _ = greeted_tx.send(viewer).await;
+                    }
+                });
+            }
+            Some(mut viewer) = greeted_rx.recv(), if pending.is_none() => {
+                let addr = viewer.addr;
+                if viewer.framed.send(ProtocolMessage::ControlQueued).await.is_err() {
+                    continue;
+                }
+                tracing::info!("{addr} asked to take over the session from {client_addr}");
+                let (reply_tx, reply_rx) = tokio::sync::mpsc::channel(1);
+                let _ = output.send(NetworkEvent::ControlRequested {
+                    addr: addr.to_string(),
+                    reply: ControlReply::new(reply_tx),
+                }).await;
+                pending = Some(PendingViewer {
+                    viewer,
+                    reply_rx,
+                    deadline: tokio::time::Instant::now() + CONTROL_REQUEST_TIMEOUT,
+                });
+            }
+            accept = control_decision(&mut pending) => {
+                let Some(mut waiting) = pending.take() else { continue };
+                if accept {
+                    tracing::info!("Handing the session over to {}", waiting.viewer.addr);
+                    let _ = sink.send(ProtocolMessage::Disconnect).await;
+                    handover = Some(waiting.viewer);
+                    break;
+                }
+                tracing::info!("Declined {}", waiting.viewer.addr);
+                let _ = waiting.viewer.framed.send(ProtocolMessage::ControlDeclined).await;
+            }
+            frame = capture_rx.recv() => {
+                match frame {
+                    Some(CaptureEvent::Frame(data)) => {
+                        HOST_METRICS.frame_sent(data.compressed_payload.len());
+                        if let Err(e) = sink.send(ProtocolMessage::Frame(data)).await {
+                            tracing::warn!("Send frame error: {e}");
+                            break;
                         }
-                        Some(CaptureEvent::Started { width, height }) => {
-                            tracing::info!("Capture started: {width}x{height}");
+                    }
+                    Some(CaptureEvent::Started { width, height }) => {
+                        tracing::info!("Capture started: {width}x{height}");
+                    }
+                    Some(CaptureEvent::Error(e)) => {
+                        tracing::warn!("Capture error: {e}");
+                    }
+                    Some(CaptureEvent::Stopped) | None => break,
+                }
+            }
+            msg = stream_reader.next() => {
+                match msg {
+                    Some(Ok(ProtocolMessage::Disconnect)) => break,
+                    Some(Ok(ProtocolMessage::Ping(ts))) => {
+                        let _ = sink.send(ProtocolMessage::Pong(ts)).await;
+                    }
+                    Some(Ok(ProtocolMessage::Pong(_))) => {
+                        last_pong = tokio::time::Instant::now();
+                    }
+                    Some(Ok(ProtocolMessage::SetColorMode(mode))) => {
+                        tracing::info!("Client requested color mode {mode:?}");
+                        let _ = cmd_tx.send(CaptureCommand::SetColorMode(mode)).await;
+                    }
+                    Some(Ok(ProtocolMessage::SetPartialUpdates(enabled))) => {
+                        tracing::info!("Client turned partial updates {}", if enabled { "on" } else { "off" });
+                        let _ = cmd_tx.send(CaptureCommand::SetPartialUpdates(enabled)).await;
+                    }
+                    Some(Ok(ProtocolMessage::SetFrameRate(rate))) => {
+                        let rate = if rate == 0 { fps } else { rate.min(fps) };
+                        tracing::info!("Client requested {rate} fps");
+                        let _ = cmd_tx.send(CaptureCommand::SetFps(rate)).await;
+                    }
+                    Some(Ok(ProtocolMessage::SetQuality(quality))) => {
+                        let _ = cmd_tx.send(CaptureCommand::SetQuality(quality.clamp(1, 100))).await;
+                    }
+                    Some(Ok(ProtocolMessage::ViewportSize { width, height })) => {
+                        let size = (width > 0 && height > 0).then_some((width, height));
+                        let _ = cmd_tx.send(CaptureCommand::SetTargetSize(size)).await;
+                    }
+                    Some(Ok(ProtocolMessage::SetTextBoost(steps))) => {
+                        let steps = steps.min(MAX_TEXT_BOOST_STEPS);
+                        if steps == 0 {
+                            text_boost = None;
+                        } else {
+                            if text_boost.is_none() {
+                                text_boost = TextBoost::start()
+                                    .map_err(|e| tracing::warn!("Text boost unavailable: {e}"))
+                                    .ok();
+                            }
+                            if let Some(boost) = &text_boost
+                                && let Err(e) = boost.apply(steps)
+                            {
+                                tracing::warn!("Text boost failed: {e}");
+                            }
                         }
-                        Some(CaptureEvent::Error(e)) => {
-                            tracing::warn!("Capture error: {e}");
+                    }
+                    Some(Ok(ProtocolMessage::ClipboardText(text))) => {
+                        let _ = output.send(NetworkEvent::ClipboardText(text)).await;
+                    }
+                    Some(Ok(ProtocolMessage::ClipboardSecret { text, clear_after_secs })) => {
+                        tracing::info!("{client_addr} sent a credential for the clipboard");
+                        let _ = output.send(NetworkEvent::ClipboardSecret { text, clear_after_secs }).await;
+                    }
+                    Some(Ok(ProtocolMessage::ClipboardImage(dib))) => {
+                        let _ = output.send(NetworkEvent::ClipboardImage(dib)).await;
+                    }
+                    Some(Ok(ProtocolMessage::ClipboardFiles(files))) => {
+                        let _ = output.send(NetworkEvent::ClipboardFiles(files)).await;
+                    }
+                    Some(Ok(ProtocolMessage::SelectDisplay(index))) => {
+                        if only_display.is_some_and(|only| only != index) {
+                            tracing::warn!("Client asked for display {}, but only one display is shared", index + 1);
+                        } else if (index as usize) < displays.len() {
+                            tracing::info!("Client switched to display {}", index + 1);
+                            let _ = cmd_tx.send(CaptureCommand::SelectDisplay(index as usize)).await;
+                            let d = &displays[index as usize];
+                            screen = Some((d.x, d.y, d.width as i32, d.height as i32));
+                            let _ = input_tx.send(ProtocolMessage::SelectDisplay(index)).await;
                         }
-                        Some(CaptureEvent::Stopped) | None => break,
                     }
-                }
-                msg = stream_reader.next() => {
-                    match msg {
-                        Some(Ok(ProtocolMessage::Disconnect)) => break,
-                        Some(Ok(ProtocolMessage::Ping(ts))) => {
-                            let _ = sink.send(ProtocolMessage::Pong(ts)).await;
+                    Some(Ok(ProtocolMessage::SharedFile { path, data })) => {
+                        let drive = shared_drive.get_or_insert_with(|| {
+                            let client = client_addr.ip().to_string().replace(':', "_");
+                            SharedDrive::new(crate::config::app_data_dir().join("shared").join(client))
+                        });
+                        if let Err(e) = drive.write(&path, &data) {
+                            tracing::warn!("Shared file from {client_addr}: {e}");
                         }
-                        Some(Ok(ProtocolMessage::Pong(_))) => {
-                            last_pong = tokio::time::Instant::now();
+                    }
+                    Some(Ok(ProtocolMessage::RestartHost)) if !allow_restart => {
+                        tracing::warn!("Ignored a restart request from {client_addr}: remote restart is off");
+                    }
+                    Some(Ok(ProtocolMessage::RestartHost)) => {
+                        tracing::info!("{client_addr} asked to restart this machine");
+                        if let Err(e) = crate::power::restart() {
+                            tracing::warn!("{e}");
                         }
-                        Some(Ok(input_msg)) => {
-                            let _ = input_tx.send(input_msg).await;
+                    }
+                    Some(Ok(ProtocolMessage::PasteFile { folder, name, offset, total, data })) => {
+                        match pasted.write(&folder, &name, offset, total, &data) {
+                            Ok(Some(path)) => tracing::info!("Saved pasted text from {client_addr} to {}", path.display()),
+                            Ok(None) => {}
+                            Err(e) => tracing::warn!("Pasted text from {client_addr}: {e}"),
                         }
-                        Some(Err(e)) => {
-                            tracing::warn!("Client read error: {e}");
+                    }
+                    Some(Ok(ProtocolMessage::ListFiles(path))) => {
+                        let listed = files_root
+                            .as_deref()
+                            .and_then(|root| files::resolve(root, &path))
+                            .ok_or_else(|| format!("Cannot browse {path:?}"))
+                            .and_then(|dir| files::list_dir(&dir));
+                        let reply = match listed {
+                            Ok(entries) => ProtocolMessage::FileList { path, entries },
+                            Err(e) => ProtocolMessage::FileError(e),
+                        };
+                        if let Err(e) = sink.send(reply).await {
+                            tracing::warn!("Send to client error: {e}");
                             break;
                         }
-                        None => break,
                     }
-                }
-                _ = heartbeat.tick() => {
-                    let ts = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap_or_default()
-                        .as_millis() as u64;
-                    let _ = sink.send(ProtocolMessage::Ping(ts)).await;
-                    if last_pong.elapsed() > Duration::from_secs(15) {
-                        tracing::warn!("Client heartbeat timeout");
+                    Some(Ok(ProtocolMessage::CancelDownload)) => {
+                        if let Some(task) = download.take() {
+                            task.abort();
+                        }
+                    }
+                    Some(Ok(ProtocolMessage::DownloadFile { path, offset })) => {
+                        if let Some(task) = download.take() {
+                            task.abort();
+                        }
+                        let Some(file) = files_root.as_deref().and_then(|root| files::resolve(root, &path)) else {
+                            let _ = sink.send(ProtocolMessage::TransferFailed(format!("Cannot download {path:?}"))).await;
+                            continue;
+                        };
+                        tracing::info!("{client_addr} is downloading {}", file.display());
+                        let name = file.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                        let handle = files_handle.clone();
+                        download = Some(tokio::spawn(async move {
+                            let chunk = {
+                                let name = name.clone();
+                                move |offset, total, data| ProtocolMessage::FileChunk { name: name.clone(), offset, total, data }
+                            };
+                            let mut sending = files::send_file(handle.clone(), file.clone(), offset, chunk);
+                            while let Some(progress) = sending.next().await {
+                                if let PasteProgress::Failed(e) = progress {
+                                    let _ = handle.send_input(ProtocolMessage::TransferFailed(e)).await;
+                                    return;
+                                }
+                            }
+                            send_digest(&handle, name, file).await;
+                        }));
+                    }
+                    Some(Ok(ProtocolMessage::UploadFile { folder, name, offset, total, data })) => {
+                        let Some(root) = &files_root else { continue };
+                        let key = files::child(&folder, &name);
+                        let writer = uploads
+                            .entry(key.clone())
+                            .or_insert_with(|| PasteWriter::with_root(root.clone(), files::MAX_TRANSFER_BYTES));
+                        let written = writer.write(&folder, &name, offset, total, &data);
+                        if !matches!(written, Ok(None)) {
+                            uploads.remove(&key);
+                        }
+                        match written {
+                            Ok(Some(path)) => {
+                                tracing::info!("Saved {} from {client_addr}", path.display());
+                                let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                                let handle = files_handle.clone();
+                                tokio::spawn(async move { send_digest(&handle, name, path).await });
+                            }
+                            Ok(None) => {}
+                            Err(e) => {
+                                tracing::warn!("Upload from {client_addr}: {e}");
+                                let _ = sink.send(ProtocolMessage::TransferFailed(e)).await;
+                            }
+                        }
+                    }
+                    Some(Ok(input_msg)) => {
+                        HOST_METRICS.input_received();
+                        if let ProtocolMessage::MouseMove { x, y } = input_msg {
+                            last_pointer = Some((x, y));
+                        }
+                        let _ = input_tx.send(input_msg).await;
+                    }
+                    Some(Err(e)) => {
+                        tracing::warn!("Client read error: {e}");
                         break;
                     }
+                    None => break,
+                }
+            }
+            Some(update) = cursor_rx.recv() => {
+                let msg = match update {
+                    CursorUpdate::Shape(shape) => ProtocolMessage::CursorShape(shape),
+                    CursorUpdate::Moved(position) => {
+                        let Some(screen) = screen else { continue };
+                        let Some(point) = cursor::normalize(position, screen) else { continue };
+                        if last_pointer.is_some_and(|last| cursor::same_pixel(last, point, screen)) {
+                            continue;
+                        }
+                        ProtocolMessage::HostCursor { x: point.0, y: point.1 }
+                    }
+                };
+                if let Err(e) = sink.send(msg).await {
+                    tracing::warn!("Send to client error: {e}");
+                    break;
+                }
+            }
+            Some(secure) = secure_rx.recv() => {
+                if secure {
+                    tracing::info!("Secure desktop is up; frames are paused");
+                }
+                if let Err(e) = sink.send(ProtocolMessage::SecureDesktop(secure)).await {
+                    tracing::warn!("Send to client error: {e}");
+                    break;
+                }
+            }
+            Some(msg) = outbound_rx.recv() => {
+                if let Err(e) = sink.send(msg).await {
+                    tracing::warn!("Send to client error: {e}");
+                    break;
+                }
+            }
+            _ = heartbeat.tick() => {
+                let ts = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as u64;
+                let _ = sink.send(ProtocolMessage::Ping(ts)).await;
+                if last_pong.elapsed() > Duration::from_secs(15) {
+                    tracing::warn!("Client heartbeat timeout");
+                    break;
                 }
             }
         }
+    }
+
+    let _ = cmd_tx.send(CaptureCommand::Stop).await;
+    if let Some(task) = download {
+        task.abort();
+    }
+    // A viewer still waiting when the session ends simply gets the now idle host.
+    handover.or(pending.map(|p| p.viewer))
+}
 
-        let _ = cmd_tx.send(CaptureCommand::Stop).await;
-        drop(input_tx);
+/// Lets the viewer check its copy of a file the browser moved.
+async fn send_digest(handle: &ConnectionHandle, name: String, path: std::path::PathBuf) {
+    let msg = match tokio::task::spawn_blocking(move || files::sha256_file(&path)).await {
+        Ok(Ok(sha256)) => ProtocolMessage::FileDigest { name, sha256 },
+        Ok(Err(e)) => ProtocolMessage::TransferFailed(e),
+        Err(e) => ProtocolMessage::TransferFailed(e.to_string()),
+    };
+    let _ = handle.send_input(msg).await;
+}
 
-        let _ = output.send(NetworkEvent::ClientDisconnected).await;
-        let _ = output.send(NetworkEvent::Stopped).await;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        std::future::pending::<()>().await;
-    }))
+    /// Greets one connection on a local port, with `code` required.
+    async fn greet_once(code: &str) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let guard = Mutex::new(Guard::new(Some(code.into())));
+        tokio::spawn(async move {
+            let (stream, addr) = listener.accept().await.unwrap();
+            greet(stream, addr, &guard).await;
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn link_test_needs_the_access_code() {
+        let port = greet_once("482913").await;
+        let wrong = crate::probe::test_link("127.0.0.1".to_string(), port, Some("000000".into())).await;
+        assert_eq!(wrong.unwrap_err(), "Wrong access code");
+
+        let port = greet_once("482913").await;
+        assert!(crate::probe::test_link("127.0.0.1".to_string(), port, Some("482913".into())).await.is_ok());
+    }
 }
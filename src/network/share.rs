@@ -0,0 +1,204 @@
+use std::path::{Path, PathBuf};
+
+use super::ConnectionHandle;
+use crate::protocol::ProtocolMessage;
+
+/// Drive letter the shared folder is mapped to on a Windows host.
+pub const SHARE_DRIVE_LETTER: char = 'S';
+pub const MAX_SHARED_FILES: usize = 2000;
+pub const MAX_SHARED_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Lists the files under `root` as (`/`-separated relative path, full path).
+/// Fails rather than sending a partial folder when it is over the limits.
+pub fn collect_files(root: &Path) -> Result<Vec<(String, PathBuf)>, String> {
+    let mut files = Vec::new();
+    let mut total = 0u64;
+    let mut pending = vec![(root.to_path_buf(), String::new())];
+    while let Some((dir, prefix)) = pending.pop() {
+        let entries = std::fs::read_dir(&dir).map_err(|e| format!("Read {}: {e}", dir.display()))?;
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else { continue };
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let relative = if prefix.is_empty() { name } else { format!("{prefix}/{name}") };
+            if file_type.is_dir() {
+                pending.push((entry.path(), relative));
+            } else if file_type.is_file() {
+                total += entry.metadata().map(|m| m.len()).unwrap_or(0);
+                files.push((relative, entry.path()));
+                if files.len() > MAX_SHARED_FILES || total > MAX_SHARED_BYTES {
+                    return Err(format!(
+                        "Shared folder is larger than {MAX_SHARED_FILES} files or {} MB",
+                        MAX_SHARED_BYTES / (1024 * 1024)
+                    ));
+                }
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Sends every file under `root` to the host, returning how many were sent.
+pub async fn send_folder(handle: ConnectionHandle, root: PathBuf) -> Result<usize, String> {
+    let files = tokio::task::spawn_blocking(move || collect_files(&root))
+        .await
+        .map_err(|e| e.to_string())??;
+    for (path, full_path) in &files {
+        let data = tokio::fs::read(full_path)
+            .await
+            .map_err(|e| format!("Read {}: {e}", full_path.display()))?;
+        handle
+            .send_input(ProtocolMessage::SharedFile { path: path.clone(), data })
+            .await?;
+    }
+    Ok(files.len())
+}
+
+/// Turns a path received from the viewer into one that stays inside the share.
+pub fn safe_relative_path(path: &str) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for part in path.split('/') {
+        if part.is_empty() || part == "." || part == ".." || part.contains(['\\', ':']) {
+            return None;
+        }
+        out.push(part);
+    }
+    (!out.as_os_str().is_empty()).then_some(out)
+}
+
+/// Host-side copy of the viewer's shared folder. It is mapped to a drive
+/// letter once the first file arrives and removed when the session ends.
+pub struct SharedDrive {
+    root: PathBuf,
+    received: u64,
+    mapped: bool,
+}
+
+impl SharedDrive {
+    pub fn new(root: PathBuf) -> Self {
+        let _ = std::fs::remove_dir_all(&root);
+        Self {
+            root,
+            received: 0,
+            mapped: false,
+        }
+    }
+
+    pub fn write(&mut self, path: &str, data: &[u8]) -> Result<(), String> {
+        let relative = safe_relative_path(path).ok_or_else(|| format!("Rejected shared file path {path:?}"))?;
+        self.received += data.len() as u64;
+        if self.received > MAX_SHARED_BYTES {
+            return Err("Shared folder exceeds the size limit".to_string());
+        }
+
+        let target = self.root.join(relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(&target, data).map_err(|e| e.to_string())?;
+
+        if !self.mapped {
+            self.mapped = true;
+            match map_drive(&self.root) {
+                Ok(()) => tracing::info!("Shared folder available at {}", self.location()),
+                Err(e) => tracing::warn!("Shared folder left at {}: {e}", self.root.display()),
+            }
+        }
+        Ok(())
+    }
+
+    fn location(&self) -> String {
+        if cfg!(windows) {
+            format!("{SHARE_DRIVE_LETTER}:")
+        } else {
+            self.root.display().to_string()
+        }
+    }
+}
+
+impl Drop for SharedDrive {
+    fn drop(&mut self) {
+        if self.mapped {
+            unmap_drive();
+        }
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+#[cfg(windows)]
+fn map_drive(root: &Path) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let status = std::process::Command::new("subst")
+        .arg(format!("{SHARE_DRIVE_LETTER}:"))
+        .arg(root)
+        .creation_flags(CREATE_NO_WINDOW)
+        .status()
+        .map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("drive {SHARE_DRIVE_LETTER}: is already in use"))
+    }
+}
+
+#[cfg(windows)]
+fn unmap_drive() {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let _ = std::process::Command::new("subst")
+        .arg(format!("{SHARE_DRIVE_LETTER}:"))
+        .arg("/D")
+        .creation_flags(CREATE_NO_WINDOW)
+        .status();
+}
+
+#[cfg(not(windows))]
+fn map_drive(_root: &Path) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn unmap_drive() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_paths_cannot_escape() {
+        assert_eq!(safe_relative_path("docs/a.txt"), Some(PathBuf::from("docs").join("a.txt")));
+        assert_eq!(safe_relative_path("../a.txt"), None);
+        assert_eq!(safe_relative_path("/etc/passwd"), None);
+        assert_eq!(safe_relative_path("C:/x"), None);
+        assert_eq!(safe_relative_path("a\\..\\b"), None);
+        assert_eq!(safe_relative_path(""), None);
+    }
+
+    #[test]
+    fn collect_then_write_mirrors_folder() {
+        let base = std::env::temp_dir().join(format!("rdp-share-test-{}", std::process::id()));
+        let source = base.join("source");
+        std::fs::create_dir_all(source.join("sub")).unwrap();
+        std::fs::write(source.join("top.txt"), b"top").unwrap();
+        std::fs::write(source.join("sub").join("inner.txt"), b"inner").unwrap();
+
+        let mut files = collect_files(&source).unwrap();
+        files.sort();
+        let names: Vec<_> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["sub/inner.txt", "top.txt"]);
+
+        let target = base.join("target");
+        let mut drive = SharedDrive::new(target.clone());
+        for (name, path) in &files {
+            drive.write(name, &std::fs::read(path).unwrap()).unwrap();
+        }
+        assert_eq!(std::fs::read(target.join("sub").join("inner.txt")).unwrap(), b"inner");
+        assert!(drive.write("../escape.txt", b"x").is_err());
+
+        drop(drive);
+        assert!(!target.exists());
+        let _ = std::fs::remove_dir_all(&base);
+    }
+}
@@ -0,0 +1,126 @@
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Per-second transfer figures for a viewer session, sent to the UI once a second.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SessionStats {
+    pub received_per_sec: u64,
+    pub sent_per_sec: u64,
+    pub connected_for: Duration,
+}
+
+#[derive(Debug, Default)]
+pub struct ByteCounters {
+    received: AtomicU64,
+    sent: AtomicU64,
+}
+
+impl ByteCounters {
+    /// Returns the bytes read and written since the previous call.
+    pub fn take(&self) -> (u64, u64) {
+        (
+            self.received.swap(0, Ordering::Relaxed),
+            self.sent.swap(0, Ordering::Relaxed),
+        )
+    }
+}
+
+/// Wraps a transport and counts the raw bytes going through it, framing included.
+pub struct CountingStream<S> {
+    inner: S,
+    counters: Arc<ByteCounters>,
+}
+
+impl<S> CountingStream<S> {
+    pub fn new(inner: S, counters: Arc<ByteCounters>) -> Self {
+        Self { inner, counters }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for CountingStream<S> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let read = (buf.filled().len() - before) as u64;
+        self.counters.received.fetch_add(read, Ordering::Relaxed);
+        result
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for CountingStream<S> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = result {
+            self.counters.sent.fetch_add(written as u64, Ordering::Relaxed);
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+pub fn format_rate(bytes_per_sec: u64) -> String {
+    match bytes_per_sec {
+        b if b >= 1024 * 1024 => format!("{:.1} MB/s", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{:.0} KB/s", b as f64 / 1024.0),
+        b => format!("{b} B/s"),
+    }
+}
+
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs >= 3600 {
+        format!("{}h {}m", secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{secs}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn counts_both_directions() {
+        let (a, mut b) = tokio::io::duplex(64);
+        let counters = Arc::new(ByteCounters::default());
+        let mut counted = CountingStream::new(a, counters.clone());
+
+        counted.write_all(b"hello").await.unwrap();
+        b.write_all(b"abc").await.unwrap();
+        let mut buf = [0u8; 3];
+        counted.read_exact(&mut buf).await.unwrap();
+
+        assert_eq!(counters.take(), (3, 5));
+        assert_eq!(counters.take(), (0, 0));
+    }
+
+    #[test]
+    fn rates_pick_a_unit() {
+        assert_eq!(format_rate(512), "512 B/s");
+        assert_eq!(format_rate(2048), "2 KB/s");
+        assert_eq!(format_rate(3 * 1024 * 1024 / 2), "1.5 MB/s");
+    }
+
+    #[test]
+    fn elapsed_formats() {
+        assert_eq!(format_elapsed(Duration::from_secs(42)), "42s");
+        assert_eq!(format_elapsed(Duration::from_secs(125)), "2m 5s");
+        assert_eq!(format_elapsed(Duration::from_secs(3720)), "1h 2m");
+    }
+}
@@ -0,0 +1,75 @@
+use std::pin::Pin;
+use std::time::Duration;
+
+use futures::{SinkExt, Stream};
+
+use super::ConnectionHandle;
+use super::paste::PasteProgress;
+use crate::protocol::ProtocolMessage;
+
+/// Longer text belongs in Paste as File.
+pub const MAX_TYPED_CHARS: usize = 4096;
+
+const ENTER: u32 = 0x1C;
+const TAB: u32 = 0x0F;
+
+fn tap(keycode: u32) -> [ProtocolMessage; 2] {
+    [
+        ProtocolMessage::KeyEvent { keycode, pressed: true },
+        ProtocolMessage::KeyEvent { keycode, pressed: false },
+    ]
+}
+
+/// One group of input events per character; line breaks and tabs are real
+/// key presses so they submit forms and move between fields.
+pub fn keystrokes(text: &str) -> Vec<Vec<ProtocolMessage>> {
+    text.chars()
+        .filter(|&c| c != '\r')
+        .map(|c| match c {
+            '\n' => tap(ENTER).to_vec(),
+            '\t' => tap(TAB).to_vec(),
+            c => vec![ProtocolMessage::UnicodeChar(c)],
+        })
+        .collect()
+}
+
+/// Types `text` into the session, waiting `delay` between characters.
+pub fn type_text(handle: ConnectionHandle, text: String, delay: Duration) -> Pin<Box<dyn Stream<Item = PasteProgress> + Send>> {
+    Box::pin(iced::stream::channel(8, async move |mut output| {
+        let keys = keystrokes(&text);
+        let total = keys.len() as u64;
+        if keys.len() > MAX_TYPED_CHARS {
+            let _ = output
+                .send(PasteProgress::Failed(format!("Text is longer than {MAX_TYPED_CHARS} characters")))
+                .await;
+            return;
+        }
+        for (typed, events) in keys.into_iter().enumerate() {
+            for event in events {
+                if let Err(e) = handle.send_input(event).await {
+                    let _ = output.send(PasteProgress::Failed(e)).await;
+                    return;
+                }
+            }
+            let _ = output.send(PasteProgress::Sent { sent: typed as u64 + 1, total }).await;
+            tokio::time::sleep(delay).await;
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_breaks_and_tabs_are_key_presses() {
+        let keys = keystrokes("a\tb\r\n");
+        assert_eq!(keys.len(), 4);
+        assert!(matches!(keys[0][..], [ProtocolMessage::UnicodeChar('a')]));
+        assert!(matches!(
+            keys[1][..],
+            [ProtocolMessage::KeyEvent { keycode: TAB, pressed: true }, ProtocolMessage::KeyEvent { keycode: TAB, pressed: false }]
+        ));
+        assert!(matches!(keys[3][..], [ProtocolMessage::KeyEvent { keycode: ENTER, pressed: true }, _]));
+    }
+}
@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use crate::config::settings::NotifySettings;
+
+pub fn host_ready_payload(address: &str, hostname: Option<&str>) -> serde_json::Value {
+    serde_json::json!({
+        "event": "host-ready",
+        "address": address,
+        "hostname": hostname,
+        "version": env!("CARGO_PKG_VERSION"),
+    })
+}
+
+/// Posts the host address to the configured webhook and/or runs the configured
+/// script with the address as its only argument. Both targets are attempted even
+/// if the first one fails.
+pub async fn announce_host_address(
+    settings: NotifySettings,
+    address: String,
+    hostname: Option<String>,
+) -> Result<(), String> {
+    let mut errors = Vec::new();
+
+    let webhook = settings.webhook_url.trim();
+    if !webhook.is_empty() {
+        let payload = host_ready_payload(&address, hostname.as_deref());
+        if let Err(e) = post_webhook(webhook, &payload).await {
+            errors.push(e);
+        }
+    }
+
+    let script = settings.script.trim();
+    if !script.is_empty()
+        && let Err(e) = run_script(script, &address).await
+    {
+        errors.push(e);
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+async fn post_webhook(url: &str, payload: &serde_json::Value) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .user_agent("rust-rdp")
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {e}"))?;
+
+    let response = client
+        .post(url)
+        .json(payload)
+        .send()
+        .await
+        .map_err(|e| format!("Webhook request failed: {e}"))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Webhook returned status: {}", response.status()));
+    }
+    Ok(())
+}
+
+/// Longest a notify script may run before it is killed.
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(30);
+
+async fn run_script(script: &str, address: &str) -> Result<(), String> {
+    run_script_within(script, address, SCRIPT_TIMEOUT).await
+}
+
+async fn run_script_within(script: &str, address: &str, limit: Duration) -> Result<(), String> {
+    // Dropped on any early return too, so a hung script never outlives us.
+    let mut child = tokio::process::Command::new(script)
+        .arg(address)
+        .env("RUST_RDP_HOST_ADDRESS", address)
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to run notify script: {e}"))?;
+
+    match tokio::time::timeout(limit, child.wait()).await {
+        Ok(Ok(s)) if s.success() => Ok(()),
+        Ok(Ok(s)) => Err(format!("Notify script exited with {s}")),
+        Ok(Err(e)) => Err(format!("Failed to run notify script: {e}")),
+        Err(_) => {
+            if let Err(e) = child.kill().await {
+                tracing::warn!("Could not stop the notify script: {e}");
+            }
+            Err(format!("Notify script timed out after {}s and was stopped", limit.as_secs_f32()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_contains_address() {
+        let payload = host_ready_payload("100.64.0.1:9867", Some("my-pc"));
+        assert_eq!(payload["event"], "host-ready");
+        assert_eq!(payload["address"], "100.64.0.1:9867");
+        assert_eq!(payload["hostname"], "my-pc");
+    }
+
+    #[tokio::test]
+    async fn unconfigured_is_noop() {
+        let result = announce_host_address(NotifySettings::default(), "x".to_string(), None).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn missing_script_reports_error() {
+        let settings = NotifySettings {
+            script: "rust-rdp-definitely-missing-script".to_string(),
+            ..Default::default()
+        };
+        let result = announce_host_address(settings, "x".to_string(), None).await;
+        assert!(result.unwrap_err().contains("notify script"));
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn hung_script_is_killed() {
+        let started = std::time::Instant::now();
+        let result = run_script_within("sleep", "30", Duration::from_millis(200)).await;
+        assert!(result.unwrap_err().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,137 @@
+use std::sync::OnceLock;
+
+use tokio::sync::broadcast;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerEvent {
+    /// The machine is about to sleep or hibernate. Only Windows reports
+    /// this ahead of time.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    Suspending,
+    Resumed,
+}
+
+/// Local suspend/resume notifications. The watcher starts on first use and
+/// lives for the rest of the process.
+pub fn subscribe() -> broadcast::Receiver<PowerEvent> {
+    static EVENTS: OnceLock<broadcast::Sender<PowerEvent>> = OnceLock::new();
+    EVENTS
+        .get_or_init(|| {
+            let (tx, _) = broadcast::channel(8);
+            platform::watch(tx.clone());
+            tx
+        })
+        .subscribe()
+}
+
+/// Restarts this machine after a short delay, leaving time for the session
+/// to close cleanly. Asked for by a viewer.
+pub fn restart() -> Result<(), String> {
+    if !cfg!(windows) {
+        return Err("Remote restart is only supported on Windows hosts".to_string());
+    }
+    std::process::Command::new("shutdown")
+        .args(["/r", "/t", "5", "/c", "Restart requested over Rust RDP"])
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Could not restart: {e}"))
+}
+
+/// Whether a tick that should have taken `expected` took long enough on the
+/// wall clock that the machine must have been asleep in between.
+#[cfg(any(not(windows), test))]
+fn slept_through(expected: std::time::Duration, wall_elapsed: std::time::Duration) -> bool {
+    wall_elapsed > expected + std::time::Duration::from_secs(10)
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::ffi::c_void;
+
+    use tokio::sync::broadcast;
+    use windows_sys::Win32::System::Power::{DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS, PowerRegisterSuspendResumeNotification};
+
+    use super::PowerEvent;
+
+    const DEVICE_NOTIFY_CALLBACK: u32 = 2;
+    const PBT_APMSUSPEND: u32 = 0x4;
+    const PBT_APMRESUMEAUTOMATIC: u32 = 0x12;
+
+    unsafe extern "system" fn on_power(context: *const c_void, kind: u32, _setting: *const c_void) -> u32 {
+        // SAFETY: `context` is the sender leaked in `watch`, valid for the process lifetime.
+        let tx = unsafe { &*(context as *const broadcast::Sender<PowerEvent>) };
+        let event = match kind {
+            PBT_APMSUSPEND => Some(PowerEvent::Suspending),
+            // Automatic resume always arrives; the user-present resume that
+            // may follow it is ignored so the session reconnects only once.
+            PBT_APMRESUMEAUTOMATIC => Some(PowerEvent::Resumed),
+            _ => None,
+        };
+        if let Some(event) = event {
+            let _ = tx.send(event);
+        }
+        0
+    }
+
+    pub fn watch(tx: broadcast::Sender<PowerEvent>) {
+        let context: &'static broadcast::Sender<PowerEvent> = Box::leak(Box::new(tx));
+        let params: &'static DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS = Box::leak(Box::new(DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS {
+            Callback: Some(on_power),
+            Context: context as *const _ as *mut c_void,
+        }));
+        let mut registration = std::ptr::null_mut();
+        // SAFETY: `params` and its context are leaked, so they outlive the registration.
+        let status = unsafe {
+            PowerRegisterSuspendResumeNotification(
+                DEVICE_NOTIFY_CALLBACK,
+                params as *const _ as isize,
+                &mut registration,
+            )
+        };
+        if status != 0 {
+            tracing::warn!("Power notifications unavailable (error {status}); sleep will not pause sessions");
+        }
+    }
+}
+
+/// Without power notifications, notice sleep afterwards from a jump in the
+/// wall clock between ticks.
+#[cfg(not(windows))]
+mod platform {
+    use std::time::{Duration, SystemTime};
+
+    use tokio::sync::broadcast;
+
+    use super::{PowerEvent, slept_through};
+
+    const TICK: Duration = Duration::from_secs(2);
+
+    pub fn watch(tx: broadcast::Sender<PowerEvent>) {
+        std::thread::spawn(move || {
+            let mut last = SystemTime::now();
+            loop {
+                std::thread::sleep(TICK);
+                let now = SystemTime::now();
+                if slept_through(TICK, now.duration_since(last).unwrap_or_default()) {
+                    let _ = tx.send(PowerEvent::Resumed);
+                }
+                last = now;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn long_gaps_mean_sleep() {
+        let tick = Duration::from_secs(2);
+        assert!(!slept_through(tick, Duration::from_secs(3)));
+        assert!(!slept_through(tick, Duration::from_secs(11)));
+        assert!(slept_through(tick, Duration::from_secs(600)));
+    }
+}
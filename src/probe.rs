@@ -0,0 +1,293 @@
+use std::time::{Duration, Instant};
+
+use futures::{SinkExt, StreamExt};
+use serde::Serialize;
+use tokio::net::TcpStream;
+use tokio_util::codec::Framed;
+
+use crate::config::secret::SecretString;
+use crate::config::settings::Resolution;
+use crate::protocol::codec::MessageCodec;
+use crate::protocol::{DEFAULT_PORT, PROTOCOL_VERSION, ProtocolMessage};
+
+#[derive(Debug, PartialEq)]
+pub struct ProbeArgs {
+    pub host: String,
+    pub port: u16,
+    pub timeout: Duration,
+    pub json: bool,
+}
+
+/// Result printed on stdout, as one JSON object with `--json`.
+#[derive(Debug, Default, Serialize)]
+pub struct ProbeReport {
+    pub target: String,
+    pub ok: bool,
+    pub connect_ms: Option<u64>,
+    pub handshake_ms: Option<u64>,
+    pub host_version: Option<u32>,
+    pub host_screen: Option<(u32, u32)>,
+    pub error: Option<String>,
+}
+
+/// Accepts `host`, `host:port`, `[v6]:port` and bare IPv6 addresses.
+pub fn split_target(target: &str) -> Result<(String, u16), String> {
+    if let Ok(addr) = target.parse::<std::net::SocketAddr>() {
+        return Ok((addr.ip().to_string(), addr.port()));
+    }
+    match target.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => {
+            let port = port.parse().map_err(|_| format!("invalid port in {target}"))?;
+            Ok((host.to_string(), port))
+        }
+        _ => Ok((target.trim_matches(['[', ']']).to_string(), DEFAULT_PORT)),
+    }
+}
+
+/// Connects and exchanges a Probe for the host's Hello. The host answers
+/// without starting a session, so probing a busy host does not disturb it.
+pub async fn probe(args: &ProbeArgs) -> ProbeReport {
+    let mut report = ProbeReport {
+        target: format!("{}:{}", args.host, args.port),
+        ..Default::default()
+    };
+    let started = Instant::now();
+    let deadline = tokio::time::Instant::now() + args.timeout;
+
+    let stream = match tokio::time::timeout_at(deadline, TcpStream::connect((args.host.as_str(), args.port))).await {
+        Ok(Ok(s)) => s,
+        Ok(Err(e)) => {
+            report.error = Some(format!("Connect failed: {e}"));
+            return report;
+        }
+        Err(_) => {
+            report.error = Some("Connection timed out".to_string());
+            return report;
+        }
+    };
+    report.connect_ms = Some(started.elapsed().as_millis() as u64);
+
+    let mut framed = Framed::new(stream, MessageCodec);
+    if let Err(e) = framed.send(ProtocolMessage::Probe).await {
+        report.error = Some(format!("Send probe failed: {e}"));
+        return report;
+    }
+    match tokio::time::timeout_at(deadline, framed.next()).await {
+        Ok(Some(Ok(ProtocolMessage::Hello { version, screen_width, screen_height, .. }))) => {
+            report.handshake_ms = Some(started.elapsed().as_millis() as u64);
+            report.host_version = Some(version);
+            report.host_screen = Some((screen_width, screen_height));
+            if version == PROTOCOL_VERSION {
+                report.ok = true;
+            } else {
+                report.error = Some(format!("Host speaks protocol {version}, this build speaks {PROTOCOL_VERSION}"));
+            }
+        }
+        Ok(Some(Ok(other))) => report.error = Some(format!("Unexpected reply: {}", other.kind())),
+        Ok(Some(Err(e))) => report.error = Some(format!("Read failed: {e}")),
+        Ok(None) => report.error = Some("Host closed the connection".to_string()),
+        Err(_) => report.error = Some("Handshake timed out".to_string()),
+    }
+    report
+}
+
+/// Entry point for `rust-rdp probe ...`. Exit codes: 0 reachable and
+/// compatible, 1 probe failed, 2 usage error.
+pub fn run(args: &ProbeArgs) -> i32 {
+    attach_console();
+    let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("Failed to start runtime: {e}");
+            return 1;
+        }
+    };
+    let report = runtime.block_on(probe(args));
+    if args.json {
+        match serde_json::to_string(&report) {
+            Ok(json) => println!("{json}"),
+            Err(e) => eprintln!("Failed to encode report: {e}"),
+        }
+    } else {
+        println!("{}", report.summary());
+    }
+    if report.ok { 0 } else { 1 }
+}
+
+impl ProbeReport {
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(ms) = self.connect_ms {
+            parts.push(format!("connect {ms} ms"));
+        }
+        if let Some(ms) = self.handshake_ms {
+            parts.push(format!("handshake {ms} ms"));
+        }
+        if let Some(version) = self.host_version {
+            parts.push(format!("protocol {version}"));
+        }
+        if let Some((w, h)) = self.host_screen {
+            parts.push(format!("screen {w}x{h}"));
+        }
+        if let Some(e) = &self.error {
+            parts.push(e.clone());
+        }
+        let verdict = if self.ok { "OK" } else { "FAILED" };
+        format!("{} {verdict}: {}", self.target, parts.join(", "))
+    }
+}
+
+const LINK_TEST_TIMEOUT: Duration = Duration::from_secs(15);
+const PORT_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+const LINK_TEST_PINGS: usize = 5;
+const LINK_TEST_BYTES: u32 = 2 * 1024 * 1024;
+
+/// Latency and throughput to a host, measured before connecting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkReport {
+    pub rtt_ms: u64,
+    pub mbps: f64,
+}
+
+impl LinkReport {
+    /// Resolution and whether to use greyscale for a link this fast.
+    pub fn recommendation(&self) -> (Resolution, bool) {
+        match self.mbps {
+            m if m < 3.0 => (Resolution::Hd720, true),
+            m if m < 10.0 => (Resolution::Hd720, false),
+            m if m < 30.0 => (Resolution::Hd1080, false),
+            _ => (Resolution::Native, false),
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let (resolution, greyscale) = self.recommendation();
+        let color = if greyscale { "greyscale" } else { "full color" };
+        let mut summary =
+            format!("RTT {} ms, {:.1} Mbit/s. Recommended: {resolution}, {color}", self.rtt_ms, self.mbps);
+        if self.rtt_ms > 150 {
+            summary.push_str("; expect noticeable input lag");
+        }
+        summary
+    }
+}
+
+/// Measures the link to a host without starting a session: a few pings for
+/// latency, then one timed transfer for throughput. A host with an access
+/// code only runs the test for a viewer that sends it.
+pub async fn test_link(host: String, port: u16, code: Option<SecretString>) -> Result<LinkReport, String> {
+    tokio::time::timeout(LINK_TEST_TIMEOUT, run_link_test(&host, port, code))
+        .await
+        .map_err(|_| "Link test timed out".to_string())?
+}
+
+async fn run_link_test(host: &str, port: u16, code: Option<SecretString>) -> Result<LinkReport, String> {
+    let stream = TcpStream::connect((host, port)).await.map_err(|e| format!("Connect failed: {e}"))?;
+    let mut framed = Framed::new(stream, MessageCodec);
+    framed.send(ProtocolMessage::LinkTest).await.map_err(|e| e.to_string())?;
+    if let Some(code) = code {
+        framed
+            .send(ProtocolMessage::AccessCode(code.expose().to_string()))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    match framed.next().await {
+        Some(Ok(ProtocolMessage::Hello { version, .. })) if version == PROTOCOL_VERSION => {}
+        Some(Ok(ProtocolMessage::Hello { version, .. })) => {
+            return Err(format!("Host speaks protocol {version}, this build speaks {PROTOCOL_VERSION}"));
+        }
+        Some(Ok(ProtocolMessage::AccessDenied(reason))) => return Err(reason),
+        _ => return Err("Host does not support link tests".to_string()),
+    }
+
+    let mut rtts = Vec::with_capacity(LINK_TEST_PINGS);
+    for seq in 0..LINK_TEST_PINGS as u64 {
+        let sent = Instant::now();
+        framed.send(ProtocolMessage::Ping(seq)).await.map_err(|e| e.to_string())?;
+        match framed.next().await {
+            Some(Ok(ProtocolMessage::Pong(ts))) if ts == seq => rtts.push(sent.elapsed()),
+            _ => return Err("Host stopped answering pings".to_string()),
+        }
+    }
+    rtts.sort();
+    let rtt = rtts[rtts.len() / 2];
+
+    let sent = Instant::now();
+    framed
+        .send(ProtocolMessage::LinkTestRequest(LINK_TEST_BYTES))
+        .await
+        .map_err(|e| e.to_string())?;
+    let bytes = match framed.next().await {
+        Some(Ok(ProtocolMessage::LinkTestPayload(data))) => data.len(),
+        _ => return Err("Host did not send the test payload".to_string()),
+    };
+    Ok(LinkReport {
+        rtt_ms: rtt.as_millis() as u64,
+        mbps: throughput_mbps(bytes, sent.elapsed(), rtt),
+    })
+}
+
+/// Whether something accepts connections on `port`, within `PORT_CHECK_TIMEOUT`.
+pub async fn port_open(host: String, port: u16) -> bool {
+    matches!(
+        tokio::time::timeout(PORT_CHECK_TIMEOUT, TcpStream::connect((host.as_str(), port))).await,
+        Ok(Ok(_))
+    )
+}
+
+/// The request's own round trip is not transfer time.
+fn throughput_mbps(bytes: usize, elapsed: Duration, rtt: Duration) -> f64 {
+    let secs = elapsed.saturating_sub(rtt).max(elapsed / 2).as_secs_f64().max(0.001);
+    bytes as f64 * 8.0 / secs / 1_000_000.0
+}
+
+/// The release build uses the windows subsystem, so it has no console of its
+/// own; borrow the one of the shell that started it.
+#[cfg(windows)]
+pub fn attach_console() {
+    use windows_sys::Win32::System::Console::{ATTACH_PARENT_PROCESS, AttachConsole};
+    // SAFETY: plain Win32 call; failure just means there is no parent console.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+pub fn attach_console() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ipv6_targets() {
+        assert_eq!(split_target("[fd7a::1]:7000").unwrap(), ("fd7a::1".to_string(), 7000));
+        assert_eq!(split_target("fd7a::1").unwrap(), ("fd7a::1".to_string(), DEFAULT_PORT));
+    }
+
+    #[test]
+    fn summary_reads_as_one_line() {
+        let report = ProbeReport {
+            target: "pc:9867".to_string(),
+            connect_ms: Some(12),
+            error: Some("Handshake timed out".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(report.summary(), "pc:9867 FAILED: connect 12 ms, Handshake timed out");
+    }
+
+    #[test]
+    fn link_recommendation_follows_throughput() {
+        let report = |mbps| LinkReport { rtt_ms: 20, mbps };
+        assert_eq!(report(1.5).recommendation(), (Resolution::Hd720, true));
+        assert_eq!(report(20.0).recommendation(), (Resolution::Hd1080, false));
+        assert_eq!(report(200.0).recommendation(), (Resolution::Native, false));
+        assert_eq!(
+            LinkReport { rtt_ms: 200, mbps: 5.0 }.summary(),
+            "RTT 200 ms, 5.0 Mbit/s. Recommended: 1280 x 720, full color; expect noticeable input lag"
+        );
+        // 2 MB in one second after a 200 ms round trip: 16.8 Mbit over 0.8 s.
+        let mbps = throughput_mbps(2_097_152, Duration::from_secs(1), Duration::from_millis(200));
+        assert!((mbps - 20.97).abs() < 0.01);
+    }
+}
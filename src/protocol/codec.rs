@@ -50,7 +50,7 @@ impl Encoder<ProtocolMessage> for MessageCodec {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::protocol::{FrameData, MouseBtn, PROTOCOL_VERSION};
+    use crate::protocol::{ColorMode, CursorImage, DirtyRect, DisplayInfo, FrameData, MouseBtn, PROTOCOL_VERSION};
 
     fn roundtrip(msg: ProtocolMessage) -> ProtocolMessage {
         let mut codec = MessageCodec;
@@ -65,13 +65,15 @@ mod tests {
             version: PROTOCOL_VERSION,
             screen_width: 1920,
             screen_height: 1080,
+            keyboard_layout: Some("00000407".to_string()),
         };
         let decoded = roundtrip(msg);
         match decoded {
-            ProtocolMessage::Hello { version, screen_width, screen_height } => {
+            ProtocolMessage::Hello { version, screen_width, screen_height, keyboard_layout } => {
                 assert_eq!(version, PROTOCOL_VERSION);
                 assert_eq!(screen_width, 1920);
                 assert_eq!(screen_height, 1080);
+                assert_eq!(keyboard_layout.as_deref(), Some("00000407"));
             }
             _ => panic!("expected Hello"),
         }
@@ -83,6 +85,7 @@ mod tests {
             width: 800,
             height: 600,
             jpeg_quality: 75,
+            region: Some(DirtyRect { x: 10, y: 20, width: 30, height: 40 }),
             compressed_payload: vec![1, 2, 3, 4],
         });
         let decoded = roundtrip(msg);
@@ -91,12 +94,104 @@ mod tests {
                 assert_eq!(f.width, 800);
                 assert_eq!(f.height, 600);
                 assert_eq!(f.jpeg_quality, 75);
+                assert_eq!(f.region, Some(DirtyRect { x: 10, y: 20, width: 30, height: 40 }));
                 assert_eq!(f.compressed_payload, vec![1, 2, 3, 4]);
             }
             _ => panic!("expected Frame"),
         }
     }
 
+    #[test]
+    fn roundtrip_clipboard_text() {
+        let decoded = roundtrip(ProtocolMessage::ClipboardText("héllo\nworld".to_string()));
+        match decoded {
+            ProtocolMessage::ClipboardText(text) => assert_eq!(text, "héllo\nworld"),
+            _ => panic!("expected ClipboardText"),
+        }
+    }
+
+    #[test]
+    fn roundtrip_set_color_mode() {
+        match roundtrip(ProtocolMessage::SetColorMode(ColorMode::Greyscale)) {
+            ProtocolMessage::SetColorMode(mode) => assert_eq!(mode, ColorMode::Greyscale),
+            _ => panic!("expected SetColorMode"),
+        }
+    }
+
+    #[test]
+    fn roundtrip_set_text_boost() {
+        match roundtrip(ProtocolMessage::SetTextBoost(2)) {
+            ProtocolMessage::SetTextBoost(steps) => assert_eq!(steps, 2),
+            _ => panic!("expected SetTextBoost"),
+        }
+    }
+
+    #[test]
+    fn roundtrip_viewport_size() {
+        match roundtrip(ProtocolMessage::ViewportSize { width: 1280, height: 720 }) {
+            ProtocolMessage::ViewportSize { width, height } => {
+                assert_eq!(width, 1280);
+                assert_eq!(height, 720);
+            }
+            _ => panic!("expected ViewportSize"),
+        }
+    }
+
+    #[test]
+    fn roundtrip_displays() {
+        let display = DisplayInfo { x: -1920, y: 120, width: 1920, height: 1080 };
+        match roundtrip(ProtocolMessage::Displays(vec![display])) {
+            ProtocolMessage::Displays(list) => assert_eq!(list, vec![display]),
+            _ => panic!("expected Displays"),
+        }
+    }
+
+    #[test]
+    fn roundtrip_cursor_shape() {
+        let image = CursorImage { width: 2, height: 1, hot_x: 1, hot_y: 0, rgba: vec![0, 0, 0, 255, 255, 255, 255, 128] };
+        match roundtrip(ProtocolMessage::CursorShape(Some(image.clone()))) {
+            ProtocolMessage::CursorShape(shape) => assert_eq!(shape, Some(image)),
+            _ => panic!("expected CursorShape"),
+        }
+    }
+
+    #[test]
+    fn roundtrip_control_messages() {
+        assert!(matches!(roundtrip(ProtocolMessage::ControlQueued), ProtocolMessage::ControlQueued));
+        assert!(matches!(roundtrip(ProtocolMessage::ControlDeclined), ProtocolMessage::ControlDeclined));
+    }
+
+    #[test]
+    fn roundtrip_unicode_char() {
+        match roundtrip(ProtocolMessage::UnicodeChar('ä')) {
+            ProtocolMessage::UnicodeChar(c) => assert_eq!(c, 'ä'),
+            _ => panic!("expected UnicodeChar"),
+        }
+    }
+
+    #[test]
+    fn roundtrip_key_combo() {
+        match roundtrip(ProtocolMessage::KeyCombo(vec![0x1D, 0x38, 0xE053])) {
+            ProtocolMessage::KeyCombo(codes) => assert_eq!(codes, vec![0x1D, 0x38, 0xE053]),
+            _ => panic!("expected KeyCombo"),
+        }
+    }
+
+    #[test]
+    fn roundtrip_shared_file() {
+        let msg = ProtocolMessage::SharedFile {
+            path: "docs/notes.txt".to_string(),
+            data: vec![7, 8, 9],
+        };
+        match roundtrip(msg) {
+            ProtocolMessage::SharedFile { path, data } => {
+                assert_eq!(path, "docs/notes.txt");
+                assert_eq!(data, vec![7, 8, 9]);
+            }
+            _ => panic!("expected SharedFile"),
+        }
+    }
+
     #[test]
     fn roundtrip_mouse_move() {
         let msg = ProtocolMessage::MouseMove { x: 100, y: 200 };
@@ -179,6 +274,7 @@ mod tests {
             version: PROTOCOL_VERSION,
             screen_width: 1920,
             screen_height: 1080,
+            keyboard_layout: None,
         };
         codec.encode(msg, &mut buf).unwrap();
 
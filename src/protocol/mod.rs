@@ -3,22 +3,112 @@ pub mod compress;
 
 use serde::{Deserialize, Serialize};
 
-pub const PROTOCOL_VERSION: u32 = 1;
+pub const PROTOCOL_VERSION: u32 = 16;
 pub const DEFAULT_PORT: u16 = 9867;
 
+/// Part of the frame covered by a partial update, in frame pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirtyRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FrameData {
     pub width: u32,
     pub height: u32,
     pub jpeg_quality: u8,
+    /// When set, the payload only holds this region of a `width`x`height` frame.
+    pub region: Option<DirtyRect>,
     pub compressed_payload: Vec<u8>,
 }
 
+/// What a host runs, sent to each viewer it lets in. Hosts before protocol 9
+/// send none.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostInfo {
+    pub protocol: u32,
+    pub app_version: String,
+    pub os: String,
+    /// Frame encodings the host can send.
+    pub codecs: Vec<String>,
+    pub access_code: bool,
+}
+
+impl HostInfo {
+    pub fn local(access_code: bool) -> Self {
+        Self {
+            protocol: PROTOCOL_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            codecs: vec![
+                "JPEG+LZ4".to_string(),
+                "JPEG+LZ4 greyscale".to_string(),
+                "JPEG+LZ4 16-bit".to_string(),
+                "partial updates".to_string(),
+            ],
+            access_code,
+        }
+    }
+
+    /// Whether the host takes `ColorMode::Reduced` and `SetPartialUpdates`.
+    pub fn graphics_options(&self) -> bool {
+        self.protocol >= 16
+    }
+
+    /// Security in effect, for display: the tailnet always, plus the code.
+    pub fn security(&self) -> &'static str {
+        if self.access_code { "Tailscale + access code" } else { "Tailscale" }
+    }
+}
+
+/// The host's pointer image, RGBA with straight alpha.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CursorImage {
+    pub width: u16,
+    pub height: u16,
+    /// The pixel that points, from the image's top-left corner.
+    pub hot_x: u16,
+    pub hot_y: u16,
+    pub rgba: Vec<u8>,
+}
+
+/// A file or folder in the remote file browser.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub size: u64,
+}
+
+/// One of the host's monitors, positioned on its virtual desktop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisplayInfo {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MouseBtn {
     Left,
     Right,
     Middle,
+    /// The side buttons usually bound to Back and Forward.
+    Back,
+    Forward,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorMode {
+    #[default]
+    Full,
+    Greyscale,
+    /// Colour cut to 16 bits (5-6-5) before encoding. Protocol 16 and later.
+    Reduced,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,8 +117,10 @@ pub enum ProtocolMessage {
         version: u32,
         screen_width: u32,
         screen_height: u32,
+        keyboard_layout: Option<String>,
     },
     Frame(FrameData),
+    /// Pointer position across the shared screen, 0..=u16::MAX on each axis.
     MouseMove {
         x: u16,
         y: u16,
@@ -45,7 +137,179 @@ pub enum ProtocolMessage {
         keycode: u32,
         pressed: bool,
     },
+    /// Presses the scancodes in order, then releases them in reverse.
+    KeyCombo(Vec<u32>),
+    /// A typed character with no scancode mapping, injected as text.
+    UnicodeChar(char),
     Ping(u64),
     Pong(u64),
+    ClipboardText(String),
+    /// A copied bitmap in `CF_DIB` layout.
+    ClipboardImage(Vec<u8>),
+    /// Copied files as (file name, contents).
+    ClipboardFiles(Vec<(String, Vec<u8>)>),
+    /// A password for the host's clipboard, kept out of clipboard history
+    /// and cleared after `clear_after_secs`. Viewer to host.
+    ClipboardSecret {
+        text: String,
+        clear_after_secs: u16,
+    },
+    SetColorMode(ColorMode),
+    /// Whether the host may send only the changed part of a frame. Protocol
+    /// 16 and later; older hosts always do.
+    SetPartialUpdates(bool),
+    /// Frames per second the viewer wants, up to the host's cap; 0 is the cap.
+    SetFrameRate(u32),
+    /// JPEG quality, 1..=100.
+    SetQuality(u8),
+    SetTextBoost(u8),
+    ViewportSize {
+        width: u32,
+        height: u32,
+    },
+    /// One file of the viewer's shared folder; `path` is relative and `/`-separated.
+    SharedFile {
+        path: String,
+        data: Vec<u8>,
+    },
+    /// Part of some text the viewer saves as a file on the host. `folder` is
+    /// relative to the host's desktop and `/`-separated; empty is the desktop.
+    PasteFile {
+        folder: String,
+        name: String,
+        offset: u64,
+        total: u64,
+        data: Vec<u8>,
+    },
+    /// Sent instead of Hello by `rust-rdp probe`; the host answers with its own
+    /// Hello and closes the connection without starting a session.
+    Probe,
+    /// Sent instead of Hello to measure the link. The host answers with its
+    /// Hello, then Pongs to Pings and a payload to each `LinkTestRequest`.
+    LinkTest,
+    /// Asks for a `LinkTestPayload` of this many bytes.
+    LinkTestRequest(u32),
+    LinkTestPayload(Vec<u8>),
+    /// The host's monitors; frames come from the first until the viewer picks another.
+    Displays(Vec<DisplayInfo>),
+    /// Index into the host's `Displays` list.
+    SelectDisplay(u32),
+    /// Another viewer holds the session; the host has been asked to hand it over.
+    ControlQueued,
+    ControlDeclined,
+    /// Where the person at the host moved its pointer, 0..=u16::MAX on each
+    /// axis of the shared screen. Not sent for the viewer's own moves.
+    HostCursor {
+        x: u16,
+        y: u16,
+    },
+    /// The host's pointer changed shape; `None` while it hides the pointer.
+    /// Hosts that cannot read the shape never send this.
+    CursorShape(Option<CursorImage>),
+    /// Remote file browser. Paths are relative to the host user's home
+    /// folder with `/` separators; the empty path is the home folder.
+    ListFiles(String),
+    FileList {
+        path: String,
+        entries: Vec<FileEntry>,
+    },
+    /// Starts sending a file from `offset`, which is non-zero when a
+    /// paused download resumes.
+    DownloadFile {
+        path: String,
+        offset: u64,
+    },
+    /// Stops the download in progress.
+    CancelDownload,
+    /// A piece of a downloaded file, host to viewer.
+    FileChunk {
+        name: String,
+        offset: u64,
+        total: u64,
+        data: Vec<u8>,
+    },
+    /// A piece of a file sent into the browsed folder, viewer to host.
+    UploadFile {
+        folder: String,
+        name: String,
+        offset: u64,
+        total: u64,
+        data: Vec<u8>,
+    },
+    FileError(String),
+    /// The download or upload in progress failed on the host.
+    TransferFailed(String),
+    /// SHA-256 of a file the host finished sending or saving, so the
+    /// viewer can check its copy.
+    FileDigest {
+        name: String,
+        sha256: String,
+    },
+    /// Asks the host to restart its machine.
+    RestartHost,
+    /// The host switched to or from the secure desktop (a UAC prompt or the
+    /// lock screen), which it cannot stream.
+    SecureDesktop(bool),
+    /// Sent right after Hello to a host that requires an access code.
+    AccessCode(String),
+    HostInfo(HostInfo),
+    /// The host turned the viewer away; carries the reason to show.
+    AccessDenied(String),
     Disconnect,
 }
+
+impl ProtocolMessage {
+    /// Variant name without its payload. Use this when logging messages from
+    /// a peer: key events and clipboard text may hold credentials.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Hello { .. } => "Hello",
+            Self::Frame(_) => "Frame",
+            Self::MouseMove { .. } => "MouseMove",
+            Self::MouseButton { .. } => "MouseButton",
+            Self::MouseScroll { .. } => "MouseScroll",
+            Self::KeyEvent { .. } => "KeyEvent",
+            Self::KeyCombo(_) => "KeyCombo",
+            Self::UnicodeChar(_) => "UnicodeChar",
+            Self::Ping(_) => "Ping",
+            Self::Pong(_) => "Pong",
+            Self::ClipboardText(_) => "ClipboardText",
+            Self::ClipboardImage(_) => "ClipboardImage",
+            Self::ClipboardFiles(_) => "ClipboardFiles",
+            Self::ClipboardSecret { .. } => "ClipboardSecret",
+            Self::SetColorMode(_) => "SetColorMode",
+            Self::SetPartialUpdates(_) => "SetPartialUpdates",
+            Self::SetFrameRate(_) => "SetFrameRate",
+            Self::SetQuality(_) => "SetQuality",
+            Self::SetTextBoost(_) => "SetTextBoost",
+            Self::ViewportSize { .. } => "ViewportSize",
+            Self::SharedFile { .. } => "SharedFile",
+            Self::PasteFile { .. } => "PasteFile",
+            Self::Probe => "Probe",
+            Self::LinkTest => "LinkTest",
+            Self::LinkTestRequest(_) => "LinkTestRequest",
+            Self::LinkTestPayload(_) => "LinkTestPayload",
+            Self::Displays(_) => "Displays",
+            Self::SelectDisplay(_) => "SelectDisplay",
+            Self::ControlQueued => "ControlQueued",
+            Self::ControlDeclined => "ControlDeclined",
+            Self::HostCursor { .. } => "HostCursor",
+            Self::AccessCode(_) => "AccessCode",
+            Self::AccessDenied(_) => "AccessDenied",
+            Self::SecureDesktop(_) => "SecureDesktop",
+            Self::HostInfo(_) => "HostInfo",
+            Self::CursorShape(_) => "CursorShape",
+            Self::ListFiles(_) => "ListFiles",
+            Self::FileList { .. } => "FileList",
+            Self::DownloadFile { .. } => "DownloadFile",
+            Self::CancelDownload => "CancelDownload",
+            Self::FileChunk { .. } => "FileChunk",
+            Self::UploadFile { .. } => "UploadFile",
+            Self::FileError(_) => "FileError",
+            Self::TransferFailed(_) => "TransferFailed",
+            Self::FileDigest { .. } => "FileDigest",
+            Self::RestartHost => "RestartHost",
+            Self::Disconnect => "Disconnect",
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+
+use crate::config::settings::RecordingSettings;
+
+mod watermark;
+
+pub use watermark::Watermark;
+
+/// The host only sends changes, so the recorder samples the viewer's current
+/// frame at a fixed rate instead of writing frames as they arrive.
+pub const RECORDING_FPS: u32 = 15;
+
+/// Side of the squares a redacted region is averaged over; big enough that
+/// text under it cannot be read.
+const REDACT_BLOCK: u32 = 16;
+
+/// Part of a frame to blur out, in frame pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Region {
+    /// The rectangle with corners `a` and `b`.
+    pub fn between(a: (u32, u32), b: (u32, u32)) -> Self {
+        Self { x: a.0.min(b.0), y: a.1.min(b.1), width: a.0.abs_diff(b.0), height: a.1.abs_diff(b.1) }
+    }
+
+    /// The same part of a frame resized from `from` to `to`.
+    pub fn scaled(self, from: (u32, u32), to: (u32, u32)) -> Self {
+        if from == to || from.0 == 0 || from.1 == 0 {
+            return self;
+        }
+        let sx = |v: u32| (v as u64 * to.0 as u64 / from.0 as u64) as u32;
+        let sy = |v: u32| (v as u64 * to.1 as u64 / from.1 as u64) as u32;
+        // Rounded outwards so nothing at the edges slips through.
+        Self { x: sx(self.x), y: sy(self.y), width: sx(self.width) + 1, height: sy(self.height) + 1 }
+    }
+}
+
+/// Blurs `regions` of an RGBA frame by replacing each block with its
+/// average colour.
+pub fn redact(pixels: &mut [u8], width: u32, height: u32, regions: &[Region]) {
+    if pixels.len() < (width * height * 4) as usize {
+        return;
+    }
+    let index = |x: u32, y: u32| ((y * width + x) * 4) as usize;
+    for region in regions {
+        let x_end = region.x.saturating_add(region.width).min(width);
+        let y_end = region.y.saturating_add(region.height).min(height);
+        for by in (region.y..y_end).step_by(REDACT_BLOCK as usize) {
+            for bx in (region.x..x_end).step_by(REDACT_BLOCK as usize) {
+                let (xs, ys) = (bx..(bx + REDACT_BLOCK).min(x_end), by..(by + REDACT_BLOCK).min(y_end));
+                let mut sum = [0u64; 4];
+                for y in ys.clone() {
+                    for x in xs.clone() {
+                        let i = index(x, y);
+                        for (total, value) in sum.iter_mut().zip(&pixels[i..i + 4]) {
+                            *total += *value as u64;
+                        }
+                    }
+                }
+                let count = (xs.len() * ys.len()).max(1) as u64;
+                let average = sum.map(|total| (total / count) as u8);
+                for y in ys {
+                    for x in xs.clone() {
+                        let i = index(x, y);
+                        pixels[i..i + 4].copy_from_slice(&average);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Saves an RGBA frame as a PNG next to the recordings, watermarked if
+/// that is on.
+pub fn save_screenshot(settings: &RecordingSettings, label: &str, width: u32, height: u32, mut pixels: Vec<u8>) -> Result<PathBuf, String> {
+    let dir = settings.output_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create {}: {e}", dir.display()))?;
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = dir.join(format!("{}-{stamp}.png", file_label(label)));
+    if let Some(watermark) = Watermark::from_settings(settings) {
+        watermark.apply(&mut pixels, width, height);
+    }
+    let image = image::RgbaImage::from_raw(width, height, pixels).ok_or("Screenshot has the wrong size")?;
+    image.save_with_format(&path, image::ImageFormat::Png).map_err(|e| format!("Cannot save {}: {e}", path.display()))?;
+    Ok(path)
+}
+
+/// Encodes frames to WebM through an `ffmpeg` child process. A size change
+/// (window resize, display switch) closes the file and continues in a new part.
+pub struct Recorder {
+    settings: RecordingSettings,
+    base: PathBuf,
+    part: u32,
+    size: (u32, u32),
+    encoder: Option<Encoder>,
+    /// Blurred in every frame; drawn on a frame of `redaction_size`.
+    redaction: Vec<Region>,
+    redaction_size: (u32, u32),
+    watermark: Option<Watermark>,
+}
+
+struct Encoder {
+    tx: SyncSender<Bytes>,
+    worker: JoinHandle<Result<(), String>>,
+}
+
+impl Recorder {
+    /// `label` names the file, e.g. the host being viewed.
+    pub fn start(
+        settings: &RecordingSettings,
+        label: &str,
+        width: u32,
+        height: u32,
+        redaction: Vec<Region>,
+    ) -> Result<Self, String> {
+        let dir = settings.output_dir();
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create {}: {e}", dir.display()))?;
+        let stamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let base = dir.join(format!("{}-{stamp}", file_label(label)));
+        let mut recorder = Self {
+            settings: settings.clone(),
+            base,
+            part: 1,
+            size: (width, height),
+            encoder: None,
+            redaction,
+            redaction_size: (width, height),
+            watermark: Watermark::from_settings(settings),
+        };
+        recorder.encoder = Some(recorder.spawn_encoder()?);
+        Ok(recorder)
+    }
+
+    pub fn path(&self) -> PathBuf {
+        part_path(&self.base, self.part)
+    }
+
+    /// Queues one RGBA frame. Frames are dropped rather than stalling the UI
+    /// when ffmpeg falls behind.
+    pub fn push(&mut self, width: u32, height: u32, pixels: Bytes) -> Result<(), String> {
+        if (width, height) != self.size {
+            self.finish_encoder()?;
+            self.part += 1;
+            self.size = (width, height);
+            self.encoder = Some(self.spawn_encoder()?);
+        }
+        let Some(encoder) = &self.encoder else { return Ok(()) };
+        let pixels = if self.redaction.is_empty() && self.watermark.is_none() {
+            pixels
+        } else {
+            let regions: Vec<Region> =
+                self.redaction.iter().map(|r| r.scaled(self.redaction_size, (width, height))).collect();
+            let mut frame = pixels.to_vec();
+            redact(&mut frame, width, height, &regions);
+            // After redacting, so the stamp itself is never blurred.
+            if let Some(watermark) = &self.watermark {
+                watermark.apply(&mut frame, width, height);
+            }
+            Bytes::from(frame)
+        };
+        match encoder.tx.try_send(pixels) {
+            Ok(()) | Err(TrySendError::Full(_)) => Ok(()),
+            Err(TrySendError::Disconnected(_)) => {
+                let result = self.finish_encoder();
+                result.and(Err("ffmpeg stopped unexpectedly".to_string()))
+            }
+        }
+    }
+
+    /// Closes the current file, returning the path of the last part written.
+    pub fn stop(mut self) -> Result<PathBuf, String> {
+        self.finish_encoder()?;
+        Ok(self.path())
+    }
+
+    fn spawn_encoder(&self) -> Result<Encoder, String> {
+        let path = self.path();
+        let mut child = ffmpeg_command(&self.settings.ffmpeg_program(), self.size, &path)
+            .spawn()
+            .map_err(|e| format!("Failed to start ffmpeg: {e}"))?;
+        let mut stdin = child.stdin.take().ok_or("ffmpeg has no stdin")?;
+        let (tx, rx) = mpsc::sync_channel::<Bytes>(RECORDING_FPS as usize);
+        let worker = std::thread::spawn(move || {
+            while let Ok(frame) = rx.recv() {
+                if stdin.write_all(&frame).is_err() {
+                    break;
+                }
+            }
+            drop(stdin);
+            wait_for(child)
+        });
+        Ok(Encoder { tx, worker })
+    }
+
+    fn finish_encoder(&mut self) -> Result<(), String> {
+        let Some(Encoder { tx, worker }) = self.encoder.take() else { return Ok(()) };
+        drop(tx);
+        worker.join().map_err(|_| "Recording thread panicked".to_string())?
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        let _ = self.finish_encoder();
+    }
+}
+
+fn wait_for(mut child: Child) -> Result<(), String> {
+    let output = child.wait().map_err(|e| e.to_string())?;
+    if output.success() {
+        Ok(())
+    } else {
+        let mut err = String::new();
+        if let Some(mut stderr) = child.stderr.take() {
+            let _ = std::io::Read::read_to_string(&mut stderr, &mut err);
+        }
+        Err(format!("ffmpeg exited with {output}: {}", err.trim()))
+    }
+}
+
+fn ffmpeg_command(program: &str, (width, height): (u32, u32), path: &Path) -> Command {
+    let mut cmd = Command::new(program);
+    cmd.args(["-hide_banner", "-loglevel", "error", "-y"])
+        .args(["-f", "rawvideo", "-pix_fmt", "rgba"])
+        .args(["-s", &format!("{width}x{height}")])
+        .args(["-r", &RECORDING_FPS.to_string()])
+        .args(["-i", "-"])
+        // yuv420p needs even dimensions.
+        .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"])
+        .args(["-c:v", "libvpx-vp9", "-pix_fmt", "yuv420p"])
+        .args(["-deadline", "realtime", "-cpu-used", "8", "-b:v", "0", "-crf", "40"])
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    cmd
+}
+
+fn part_path(base: &Path, part: u32) -> PathBuf {
+    let mut name = base.file_name().unwrap_or_default().to_os_string();
+    if part > 1 {
+        name.push(format!("-part{part}"));
+    }
+    name.push(".webm");
+    base.with_file_name(name)
+}
+
+fn file_label(label: &str) -> String {
+    let cleaned: String = label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    if cleaned.is_empty() { "session".to_string() } else { format!("session-{cleaned}") }
+}
+
+/// Recording time shown in the viewer, excluding paused stretches.
+#[derive(Debug, Clone, Copy)]
+pub struct RecordingClock {
+    started: Instant,
+    paused_at: Option<Instant>,
+    paused_for: Duration,
+}
+
+impl RecordingClock {
+    pub fn start(now: Instant) -> Self {
+        Self { started: now, paused_at: None, paused_for: Duration::ZERO }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    pub fn toggle_pause(&mut self, now: Instant) {
+        match self.paused_at.take() {
+            Some(at) => self.paused_for += now.saturating_duration_since(at),
+            None => self.paused_at = Some(now),
+        }
+    }
+
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        let end = self.paused_at.unwrap_or(now);
+        end.saturating_duration_since(self.started).saturating_sub(self.paused_for)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parts_get_numbered_after_the_first() {
+        let base = Path::new("/videos/session-pc-1");
+        assert_eq!(part_path(base, 1), Path::new("/videos/session-pc-1.webm"));
+        assert_eq!(part_path(base, 3), Path::new("/videos/session-pc-1-part3.webm"));
+    }
+
+    #[test]
+    fn labels_are_file_safe() {
+        assert_eq!(file_label("100.64.0.1"), "session-100_64_0_1");
+        assert_eq!(file_label(""), "session");
+    }
+
+    #[test]
+    fn clock_excludes_pauses() {
+        let t0 = Instant::now();
+        let mut clock = RecordingClock::start(t0);
+        clock.toggle_pause(t0 + Duration::from_secs(10));
+        assert!(clock.is_paused());
+        assert_eq!(clock.elapsed(t0 + Duration::from_secs(25)), Duration::from_secs(10));
+        clock.toggle_pause(t0 + Duration::from_secs(30));
+        assert_eq!(clock.elapsed(t0 + Duration::from_secs(35)), Duration::from_secs(15));
+    }
+
+    #[test]
+    fn redaction_averages_only_inside_regions() {
+        let (w, h) = (4, 2);
+        let mut pixels: Vec<u8> = (0..w * h).flat_map(|i| [i as u8 * 10, 0, 0, 255]).collect();
+        let region = Region::between((2, 0), (0, 2));
+        assert_eq!(region, Region { x: 0, y: 0, width: 2, height: 2 });
+        redact(&mut pixels, w, h, &[region]);
+        // Pixels 0, 1, 4 and 5 average to 25; the right half is untouched.
+        for i in [0, 1, 4, 5] {
+            assert_eq!(pixels[i * 4], 25);
+        }
+        assert_eq!(pixels[2 * 4], 20);
+        assert_eq!(pixels[7 * 4], 70);
+        assert_eq!(region.scaled((4, 2), (8, 4)), Region { x: 0, y: 0, width: 5, height: 5 });
+    }
+
+    #[test]
+    fn missing_ffmpeg_reports_error() {
+        let settings = RecordingSettings {
+            ffmpeg: "/nonexistent/ffmpeg".to_string(),
+            directory: std::env::temp_dir().join("rdp-recording-test").display().to_string(),
+            ..Default::default()
+        };
+        let err = Recorder::start(&settings, "pc", 64, 48, Vec::new()).err().unwrap();
+        assert!(err.contains("ffmpeg"), "{err}");
+    }
+}
@@ -0,0 +1,171 @@
+//! A corner stamp naming who saved a capture and when, drawn with a built-in
+//! 5x7 font so no font files are needed.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::settings::{Corner, RecordingSettings};
+
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+/// Padding inside the box and gap to the frame edge, in font pixels.
+const MARGIN: u32 = 2;
+
+/// Printable ASCII from 0x20, one byte per column, bit 0 at the top.
+static FONT: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], [0x00, 0x00, 0x5F, 0x00, 0x00], [0x00, 0x07, 0x00, 0x07, 0x00],
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], [0x24, 0x2A, 0x7F, 0x2A, 0x12], [0x23, 0x13, 0x08, 0x64, 0x62],
+    [0x36, 0x49, 0x55, 0x22, 0x50], [0x00, 0x05, 0x03, 0x00, 0x00], [0x00, 0x1C, 0x22, 0x41, 0x00],
+    [0x00, 0x41, 0x22, 0x1C, 0x00], [0x08, 0x2A, 0x1C, 0x2A, 0x08], [0x08, 0x08, 0x3E, 0x08, 0x08],
+    [0x00, 0x50, 0x30, 0x00, 0x00], [0x08, 0x08, 0x08, 0x08, 0x08], [0x00, 0x60, 0x60, 0x00, 0x00],
+    [0x20, 0x10, 0x08, 0x04, 0x02], [0x3E, 0x51, 0x49, 0x45, 0x3E], [0x00, 0x42, 0x7F, 0x40, 0x00],
+    [0x42, 0x61, 0x51, 0x49, 0x46], [0x21, 0x41, 0x45, 0x4B, 0x31], [0x18, 0x14, 0x12, 0x7F, 0x10],
+    [0x27, 0x45, 0x45, 0x45, 0x39], [0x3C, 0x4A, 0x49, 0x49, 0x30], [0x01, 0x71, 0x09, 0x05, 0x03],
+    [0x36, 0x49, 0x49, 0x49, 0x36], [0x06, 0x49, 0x49, 0x29, 0x1E], [0x00, 0x36, 0x36, 0x00, 0x00],
+    [0x00, 0x56, 0x36, 0x00, 0x00], [0x08, 0x14, 0x22, 0x41, 0x00], [0x14, 0x14, 0x14, 0x14, 0x14],
+    [0x00, 0x41, 0x22, 0x14, 0x08], [0x02, 0x01, 0x51, 0x09, 0x06], [0x32, 0x49, 0x79, 0x41, 0x3E],
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], [0x7F, 0x49, 0x49, 0x49, 0x36], [0x3E, 0x41, 0x41, 0x41, 0x22],
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], [0x7F, 0x49, 0x49, 0x49, 0x41], [0x7F, 0x09, 0x09, 0x01, 0x01],
+    [0x3E, 0x41, 0x41, 0x51, 0x32], [0x7F, 0x08, 0x08, 0x08, 0x7F], [0x00, 0x41, 0x7F, 0x41, 0x00],
+    [0x20, 0x40, 0x41, 0x3F, 0x01], [0x7F, 0x08, 0x14, 0x22, 0x41], [0x7F, 0x40, 0x40, 0x40, 0x40],
+    [0x7F, 0x02, 0x04, 0x02, 0x7F], [0x7F, 0x04, 0x08, 0x10, 0x7F], [0x3E, 0x41, 0x41, 0x41, 0x3E],
+    [0x7F, 0x09, 0x09, 0x09, 0x06], [0x3E, 0x41, 0x51, 0x21, 0x5E], [0x7F, 0x09, 0x19, 0x29, 0x46],
+    [0x46, 0x49, 0x49, 0x49, 0x31], [0x01, 0x01, 0x7F, 0x01, 0x01], [0x3F, 0x40, 0x40, 0x40, 0x3F],
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], [0x7F, 0x20, 0x18, 0x20, 0x7F], [0x63, 0x14, 0x08, 0x14, 0x63],
+    [0x03, 0x04, 0x78, 0x04, 0x03], [0x61, 0x51, 0x49, 0x45, 0x43], [0x00, 0x7F, 0x41, 0x41, 0x00],
+    [0x02, 0x04, 0x08, 0x10, 0x20], [0x00, 0x41, 0x41, 0x7F, 0x00], [0x04, 0x02, 0x01, 0x02, 0x04],
+    [0x40, 0x40, 0x40, 0x40, 0x40], [0x00, 0x01, 0x02, 0x04, 0x00], [0x20, 0x54, 0x54, 0x54, 0x78],
+    [0x7F, 0x48, 0x44, 0x44, 0x38], [0x38, 0x44, 0x44, 0x44, 0x20], [0x38, 0x44, 0x44, 0x48, 0x7F],
+    [0x38, 0x54, 0x54, 0x54, 0x18], [0x08, 0x7E, 0x09, 0x01, 0x02], [0x08, 0x14, 0x54, 0x54, 0x3C],
+    [0x7F, 0x08, 0x04, 0x04, 0x78], [0x00, 0x44, 0x7D, 0x40, 0x00], [0x20, 0x40, 0x44, 0x3D, 0x00],
+    [0x00, 0x7F, 0x10, 0x28, 0x44], [0x00, 0x41, 0x7F, 0x40, 0x00], [0x7C, 0x04, 0x18, 0x04, 0x78],
+    [0x7C, 0x08, 0x04, 0x04, 0x78], [0x38, 0x44, 0x44, 0x44, 0x38], [0x7C, 0x14, 0x14, 0x14, 0x08],
+    [0x08, 0x14, 0x14, 0x18, 0x7C], [0x7C, 0x08, 0x04, 0x04, 0x08], [0x48, 0x54, 0x54, 0x54, 0x20],
+    [0x04, 0x3F, 0x44, 0x40, 0x20], [0x3C, 0x40, 0x40, 0x20, 0x7C], [0x1C, 0x20, 0x40, 0x20, 0x1C],
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], [0x44, 0x28, 0x10, 0x28, 0x44], [0x0C, 0x50, 0x50, 0x50, 0x3C],
+    [0x44, 0x64, 0x54, 0x4C, 0x44], [0x00, 0x08, 0x36, 0x41, 0x00], [0x00, 0x00, 0x7F, 0x00, 0x00],
+    [0x00, 0x41, 0x36, 0x08, 0x00], [0x02, 0x01, 0x02, 0x04, 0x02],
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watermark {
+    identity: String,
+    corner: Corner,
+}
+
+impl Watermark {
+    /// `None` when watermarks are off.
+    pub fn from_settings(settings: &RecordingSettings) -> Option<Self> {
+        settings
+            .watermark
+            .then(|| Self { identity: settings.watermark_identity(), corner: settings.watermark_corner })
+    }
+
+    fn text(&self, secs: u64) -> String {
+        format!("{} {}", self.identity, utc_stamp(secs))
+    }
+
+    /// Stamps the current time onto an RGBA frame.
+    pub fn apply(&self, pixels: &mut [u8], width: u32, height: u32) {
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        stamp(pixels, width, height, &self.text(secs), self.corner);
+    }
+}
+
+/// `secs` since the epoch as a UTC date and time, so stamps read the same
+/// wherever they were taken.
+fn utc_stamp(secs: u64) -> String {
+    // Days to a civil date, from Howard Hinnant's date algorithms.
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    let time = secs % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        time / 3_600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+fn glyph(c: char) -> &'static [u8; 5] {
+    let index = match c as u32 {
+        code @ 0x20..=0x7E => code - 0x20,
+        _ => '?' as u32 - 0x20,
+    };
+    &FONT[index as usize]
+}
+
+/// Draws `text` in white on a darkened box, scaled up on tall frames.
+fn stamp(pixels: &mut [u8], width: u32, height: u32, text: &str, corner: Corner) {
+    if text.is_empty() || pixels.len() < (width * height * 4) as usize {
+        return;
+    }
+    let scale = (height / 360).max(1);
+    let glyphs: Vec<&[u8; 5]> = text.chars().map(glyph).collect();
+    let box_width = (glyphs.len() as u32 * (GLYPH_WIDTH + 1) - 1 + 2 * MARGIN) * scale;
+    let box_height = (GLYPH_HEIGHT + 2 * MARGIN) * scale;
+    let edge = MARGIN * scale;
+    let x0 = match corner {
+        Corner::TopLeft | Corner::BottomLeft => edge,
+        Corner::TopRight | Corner::BottomRight => width.saturating_sub(box_width + edge),
+    };
+    let y0 = match corner {
+        Corner::TopLeft | Corner::TopRight => edge,
+        Corner::BottomLeft | Corner::BottomRight => height.saturating_sub(box_height + edge),
+    };
+    let index = |x: u32, y: u32| ((y * width + x) * 4) as usize;
+
+    for y in y0..(y0 + box_height).min(height) {
+        for x in x0..(x0 + box_width).min(width) {
+            let i = index(x, y);
+            for channel in &mut pixels[i..i + 3] {
+                *channel /= 3;
+            }
+        }
+    }
+    for (n, columns) in glyphs.iter().enumerate() {
+        let left = x0 + (MARGIN + n as u32 * (GLYPH_WIDTH + 1)) * scale;
+        for (column, bits) in columns.iter().enumerate() {
+            for row in (0..GLYPH_HEIGHT).filter(|row| bits >> row & 1 == 1) {
+                let (px, py) = (left + column as u32 * scale, y0 + (MARGIN + row) * scale);
+                for y in py..(py + scale).min(height) {
+                    for x in px..(px + scale).min(width) {
+                        let i = index(x, y);
+                        pixels[i..i + 3].fill(255);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stamps_are_utc_dates() {
+        assert_eq!(utc_stamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(utc_stamp(1_700_000_000), "2023-11-14 22:13:20 UTC");
+        assert_eq!(utc_stamp(951_782_400), "2000-02-29 00:00:00 UTC");
+    }
+
+    #[test]
+    fn stamp_stays_in_its_corner() {
+        let (width, height) = (200, 40);
+        let mut pixels = vec![90u8; (width * height * 4) as usize];
+        stamp(&mut pixels, width, height, "ab", Corner::BottomRight);
+        let touched = |x: u32, y: u32| pixels[((y * width + x) * 4) as usize] != 90;
+        assert!(!touched(0, 0) && !touched(0, height - 1) && !touched(width - 1, 0));
+        assert!(touched(width - 3, height - 3));
+        assert!(pixels.chunks_exact(4).any(|px| px[0] == 255));
+        // Alpha is left alone.
+        assert!(pixels.chunks_exact(4).all(|px| px[3] == 90));
+    }
+}
@@ -0,0 +1,303 @@
+//! Lifecycles of the two things the app can be doing: hosting this machine
+//! and viewing a remote one. Each owns the handles that belong to it, so a
+//! teardown drops exactly what that side started.
+
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::config::secret::SecretString;
+use crate::network::{ConnectionHandle, ControlReply};
+
+/// Which subscription a network event came from. Hosting and viewing can run
+/// at the same time, so events that both sides send are told apart by this.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Host,
+    Client,
+}
+
+/// Identifies one start of hosting or viewing. Subscriptions are keyed by it,
+/// so each start gets a fresh stream even when everything else about it
+/// (address, port, code) matches the run it replaces in the same update.
+pub type SessionId = u64;
+
+fn next_session_id() -> SessionId {
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Hosting: the server subscription runs while this is not `Idle`.
+#[derive(Debug, Default)]
+pub enum HostSession {
+    #[default]
+    Idle,
+    Running {
+        id: SessionId,
+        bind: IpAddr,
+        /// Code viewers must send, when the host requires one.
+        access_code: Option<SecretString>,
+        /// The connected viewer, if any.
+        viewer: Option<ConnectionHandle>,
+        /// Answer to a second viewer asking to take over.
+        pending: Option<ControlReply>,
+    },
+}
+
+impl HostSession {
+    pub fn start(&mut self, bind: IpAddr, access_code: Option<SecretString>) {
+        *self = HostSession::Running { id: next_session_id(), bind, access_code, viewer: None, pending: None };
+    }
+
+    /// Returns whether hosting was running.
+    pub fn stop(&mut self) -> bool {
+        !matches!(std::mem::take(self), HostSession::Idle)
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self, HostSession::Running { .. })
+    }
+
+    pub fn id(&self) -> Option<SessionId> {
+        match self {
+            HostSession::Running { id, .. } => Some(*id),
+            HostSession::Idle => None,
+        }
+    }
+
+    pub fn bind(&self) -> Option<IpAddr> {
+        match self {
+            HostSession::Running { bind, .. } => Some(*bind),
+            HostSession::Idle => None,
+        }
+    }
+
+    pub fn access_code(&self) -> Option<&SecretString> {
+        match self {
+            HostSession::Running { access_code, .. } => access_code.as_ref(),
+            HostSession::Idle => None,
+        }
+    }
+
+    pub fn viewer(&self) -> Option<&ConnectionHandle> {
+        match self {
+            HostSession::Running { viewer, .. } => viewer.as_ref(),
+            HostSession::Idle => None,
+        }
+    }
+
+    pub fn viewer_connected(&mut self, handle: ConnectionHandle) {
+        if let HostSession::Running { viewer, .. } = self {
+            *viewer = Some(handle);
+        }
+    }
+
+    /// The viewer left; a takeover request for its session is moot.
+    pub fn viewer_disconnected(&mut self) {
+        if let HostSession::Running { viewer, pending, .. } = self {
+            *viewer = None;
+            *pending = None;
+        }
+    }
+
+    pub fn control_requested(&mut self, reply: ControlReply) {
+        if let HostSession::Running { pending, .. } = self {
+            *pending = Some(reply);
+        }
+    }
+
+    pub fn take_control_reply(&mut self) -> Option<ControlReply> {
+        match self {
+            HostSession::Running { pending, .. } => pending.take(),
+            HostSession::Idle => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClientTarget {
+    /// New for every `start`; kept across the subscription's own reconnects.
+    pub id: SessionId,
+    pub host: String,
+    pub port: u16,
+    /// Sent after the Hello to a host that requires a code.
+    pub access_code: Option<SecretString>,
+}
+
+/// Viewing: the client subscription runs while this is not `Idle`.
+#[derive(Debug, Default)]
+pub enum ClientSession {
+    #[default]
+    Idle,
+    /// Waiting for the first connection or for a reconnect.
+    Connecting {
+        target: ClientTarget,
+        attempt: Option<(u32, u32)>,
+    },
+    Connected {
+        target: ClientTarget,
+        handle: ConnectionHandle,
+    },
+}
+
+impl ClientSession {
+    pub fn start(&mut self, host: String, port: u16, access_code: Option<SecretString>) {
+        *self = ClientSession::Connecting {
+            target: ClientTarget { id: next_session_id(), host, port, access_code },
+            attempt: None,
+        };
+    }
+
+    /// A late event after the session ended is ignored.
+    pub fn connected(&mut self, handle: ConnectionHandle) {
+        if let ClientSession::Connecting { target, .. } | ClientSession::Connected { target, .. } = self {
+            let target = target.clone();
+            *self = ClientSession::Connected { target, handle };
+        }
+    }
+
+    /// The connection dropped; the subscription is retrying.
+    pub fn reconnecting(&mut self, attempt: u32, max: u32) {
+        if let ClientSession::Connecting { target, .. } | ClientSession::Connected { target, .. } = self {
+            let target = target.clone();
+            *self = ClientSession::Connecting { target, attempt: Some((attempt, max)) };
+        }
+    }
+
+    /// Ends the session, handing back the live connection so the caller can
+    /// say goodbye on it.
+    pub fn end(&mut self) -> Option<ConnectionHandle> {
+        match std::mem::take(self) {
+            ClientSession::Connected { handle, .. } => Some(handle),
+            _ => None,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !matches!(self, ClientSession::Idle)
+    }
+
+    pub fn target(&self) -> Option<&ClientTarget> {
+        match self {
+            ClientSession::Connecting { target, .. } | ClientSession::Connected { target, .. } => Some(target),
+            ClientSession::Idle => None,
+        }
+    }
+
+    pub fn host(&self) -> Option<&str> {
+        self.target().map(|t| t.host.as_str())
+    }
+
+    pub fn handle(&self) -> Option<&ConnectionHandle> {
+        match self {
+            ClientSession::Connected { handle, .. } => Some(handle),
+            _ => None,
+        }
+    }
+
+    pub fn attempt(&self) -> Option<(u32, u32)> {
+        match self {
+            ClientSession::Connecting { attempt, .. } => *attempt,
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handle() -> ConnectionHandle {
+        ConnectionHandle::new(tokio::sync::mpsc::channel(1).0)
+    }
+
+    fn reply() -> ControlReply {
+        ControlReply::new(tokio::sync::mpsc::channel(1).0)
+    }
+
+    #[test]
+    fn host_stop_drops_viewer_and_request() {
+        let mut host = HostSession::default();
+        assert!(!host.stop());
+        host.start(IpAddr::from([100, 64, 0, 1]), None);
+        host.viewer_connected(handle());
+        host.control_requested(reply());
+        assert!(host.stop());
+        assert!(!host.is_running());
+        assert!(host.viewer().is_none());
+        assert!(host.take_control_reply().is_none());
+        assert_eq!(host.bind(), None);
+    }
+
+    #[test]
+    fn host_viewer_leaving_clears_request_but_keeps_listening() {
+        let mut host = HostSession::default();
+        host.start(IpAddr::from([100, 64, 0, 1]), None);
+        host.viewer_connected(handle());
+        host.control_requested(reply());
+        host.viewer_disconnected();
+        assert!(host.is_running());
+        assert!(host.viewer().is_none());
+        assert!(host.take_control_reply().is_none());
+    }
+
+    #[test]
+    fn host_ignores_events_while_idle() {
+        let mut host = HostSession::default();
+        host.viewer_connected(handle());
+        host.control_requested(reply());
+        assert!(host.viewer().is_none());
+        assert!(host.take_control_reply().is_none());
+    }
+
+    #[test]
+    fn client_reconnect_drops_stale_handle() {
+        let mut client = ClientSession::default();
+        client.start("100.64.0.2".to_string(), 9867, None);
+        assert!(client.handle().is_none());
+        client.connected(handle());
+        assert!(client.handle().is_some());
+        client.reconnecting(1, 5);
+        assert!(client.handle().is_none());
+        assert_eq!(client.attempt(), Some((1, 5)));
+        assert_eq!(client.host(), Some("100.64.0.2"));
+        client.connected(handle());
+        assert_eq!(client.attempt(), None);
+    }
+
+    #[test]
+    fn every_start_is_a_new_session() {
+        let mut client = ClientSession::default();
+        client.start("pc".to_string(), 9867, None);
+        let first = client.target().unwrap().clone();
+        client.start("pc".to_string(), 9867, None);
+        assert_ne!(client.target(), Some(&first));
+
+        let mut host = HostSession::default();
+        host.start(IpAddr::from([100, 64, 0, 1]), None);
+        let first = host.id();
+        host.start(IpAddr::from([100, 64, 0, 1]), None);
+        assert_ne!(host.id(), first);
+    }
+
+    #[test]
+    fn client_end_returns_live_handle_once() {
+        let mut client = ClientSession::default();
+        client.start("pc".to_string(), 9867, None);
+        assert!(client.end().is_none());
+        assert!(!client.is_active());
+
+        client.start("pc".to_string(), 9867, None);
+        client.connected(handle());
+        assert!(client.end().is_some());
+        assert!(client.end().is_none());
+    }
+
+    #[test]
+    fn client_ignores_late_events_after_end() {
+        let mut client = ClientSession::default();
+        client.connected(handle());
+        client.reconnecting(2, 5);
+        assert!(!client.is_active());
+        assert!(client.target().is_none());
+    }
+}
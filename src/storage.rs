@@ -0,0 +1,153 @@
+//! Quotas for what piles up under the data directory. Over its quota, a
+//! category loses its oldest files first; the newest is always kept, which
+//! also spares the log file being written.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const MB: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Category {
+    pub name: &'static str,
+    /// Folder under the data directory.
+    pub dir: &'static str,
+    pub quota: u64,
+}
+
+pub const CATEGORIES: [Category; 6] = [
+    Category { name: "Logs", dir: "logs", quota: 64 * MB },
+    Category { name: "Received clipboard files", dir: "clipboard", quota: 512 * MB },
+    Category { name: "Shared folders", dir: "shared", quota: 1024 * MB },
+    Category { name: "Pasted text", dir: "pasted", quota: 64 * MB },
+    Category { name: "Update backups", dir: "backups", quota: 256 * MB },
+    Category { name: "Crash reports", dir: "crashes", quota: 16 * MB },
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Usage {
+    pub category: Category,
+    pub bytes: u64,
+}
+
+impl Usage {
+    pub fn over_quota(&self) -> bool {
+        self.bytes > self.category.quota
+    }
+}
+
+fn files(dir: &Path, out: &mut Vec<(PathBuf, u64, SystemTime)>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let Ok(meta) = entry.metadata() else { continue };
+        if meta.is_dir() {
+            files(&entry.path(), out);
+        } else {
+            out.push((entry.path(), meta.len(), meta.modified().unwrap_or(SystemTime::UNIX_EPOCH)));
+        }
+    }
+}
+
+pub fn usage(root: &Path) -> Vec<Usage> {
+    CATEGORIES
+        .iter()
+        .map(|&category| {
+            let mut found = Vec::new();
+            files(&root.join(category.dir), &mut found);
+            Usage { category, bytes: found.iter().map(|(_, len, _)| len).sum() }
+        })
+        .collect()
+}
+
+/// Deletes oldest-first until each category is within `limit(category)`,
+/// then drops folders left empty. Returns the bytes freed.
+pub fn trim(root: &Path, limit: impl Fn(&Category) -> u64) -> u64 {
+    let mut freed = 0;
+    for category in &CATEGORIES {
+        let dir = root.join(category.dir);
+        let mut found = Vec::new();
+        files(&dir, &mut found);
+        let mut total: u64 = found.iter().map(|(_, len, _)| len).sum();
+        found.sort_by_key(|(_, _, modified)| *modified);
+        found.pop();
+        let limit = limit(category);
+        for (path, len, _) in found {
+            if total <= limit {
+                break;
+            }
+            match fs::remove_file(&path) {
+                Ok(()) => {
+                    total -= len;
+                    freed += len;
+                }
+                Err(e) => tracing::debug!("Could not remove {}: {e}", path.display()),
+            }
+        }
+        remove_empty_dirs(&dir);
+    }
+    freed
+}
+
+fn remove_empty_dirs(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            remove_empty_dirs(&path);
+            // Fails, as intended, unless the folder is now empty.
+            let _ = fs::remove_dir(&path);
+        }
+    }
+}
+
+/// Run at startup so no category grows past its quota for long.
+pub fn enforce_quotas(root: &Path) {
+    let freed = trim(root, |category| category.quota);
+    if freed > 0 {
+        tracing::info!("Freed {} of old data", format_size(freed));
+    }
+}
+
+pub fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * MB {
+        format!("{:.1} GB", bytes as f64 / (1024 * MB) as f64)
+    } else if bytes >= MB {
+        format!("{:.1} MB", bytes as f64 / MB as f64)
+    } else {
+        format!("{} KB", bytes.div_ceil(1024))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trims_oldest_first_and_keeps_the_newest() {
+        let root = std::env::temp_dir().join(format!("rust-rdp-storage-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let dir = root.join("clipboard").join("batch");
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["a", "b", "c"] {
+            fs::write(dir.join(name), [0u8; 100]).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        let clipboard = |usage: Vec<Usage>| usage.into_iter().find(|u| u.category.dir == "clipboard").unwrap();
+        assert_eq!(clipboard(usage(&root)).bytes, 300);
+
+        assert_eq!(trim(&root, |_| 150), 200);
+        assert!(dir.join("c").exists() && !dir.join("a").exists());
+        // The newest file stays even when nothing is allowed.
+        assert_eq!(trim(&root, |_| 0), 0);
+        assert_eq!(clipboard(usage(&root)).bytes, 100);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn sizes_read_naturally() {
+        assert_eq!(format_size(1), "1 KB");
+        assert_eq!(format_size(3 * MB / 2), "1.5 MB");
+        assert_eq!(format_size(2048 * MB), "2.0 GB");
+    }
+}
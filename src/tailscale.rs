@@ -1,6 +1,12 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::Duration;
 use serde::Deserialize;
 
+/// Tailscale starts every connection on a DERP relay and upgrades to a direct
+/// path once UDP hole punching works, so the path is checked after a pause.
+pub const PATH_CHECK_DELAY: Duration = Duration::from_secs(5);
+
 #[derive(Debug, Clone, Default)]
 pub struct TailscaleStatus {
     pub is_installed: bool,
@@ -15,6 +21,43 @@ struct TailscaleStatusJson {
     self_node: Option<SelfNode>,
 }
 
+#[derive(Deserialize)]
+struct PeerStatusJson {
+    #[serde(rename = "Peer", default)]
+    peers: HashMap<String, PeerNode>,
+}
+
+#[derive(Deserialize)]
+struct PeerNode {
+    #[serde(rename = "TailscaleIPs", default)]
+    tailscale_ips: Vec<String>,
+    #[serde(rename = "HostName", default)]
+    host_name: String,
+    #[serde(rename = "DNSName", default)]
+    dns_name: String,
+    #[serde(rename = "CurAddr", default)]
+    cur_addr: String,
+    #[serde(rename = "Relay", default)]
+    relay: String,
+}
+
+/// How traffic to a peer is currently carried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PeerPath {
+    Direct,
+    /// Through the named DERP region, usually because UDP is filtered.
+    Relayed(String),
+}
+
+impl std::fmt::Display for PeerPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PeerPath::Direct => f.write_str("Direct"),
+            PeerPath::Relayed(region) => write!(f, "Relayed via DERP {region}"),
+        }
+    }
+}
+
 #[derive(Deserialize)]
 struct SelfNode {
     #[serde(rename = "TailscaleIPs")]
@@ -23,6 +66,18 @@ struct SelfNode {
     host_name: Option<String>,
 }
 
+#[derive(Deserialize)]
+struct WhoIsJson {
+    #[serde(rename = "Node")]
+    node: Option<WhoIsNode>,
+}
+
+#[derive(Deserialize)]
+struct WhoIsNode {
+    #[serde(rename = "Key")]
+    key: Option<String>,
+}
+
 fn find_tailscale_cli() -> Option<PathBuf> {
     let candidates = [
         PathBuf::from(r"C:\Program Files\Tailscale\tailscale.exe"),
@@ -99,6 +154,55 @@ pub async fn check_tailscale() -> TailscaleStatus {
     }
 }
 
+/// WireGuard node key of the tailnet peer at `addr`, as reported by `tailscale whois`.
+pub async fn peer_node_key(addr: String) -> Result<String, String> {
+    let cli = find_tailscale_cli().ok_or("Tailscale CLI not found")?;
+    let output = tokio::process::Command::new(&cli)
+        .args(["whois", "--json", &addr])
+        .output()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!("tailscale whois {addr} failed"));
+    }
+    parse_whois_key(&output.stdout).ok_or_else(|| format!("No node key for {addr}"))
+}
+
+/// Whether the connection to the peer at `addr` (IP or host name) is direct.
+pub async fn peer_path(addr: String) -> Result<PeerPath, String> {
+    let cli = find_tailscale_cli().ok_or("Tailscale CLI not found")?;
+    let output = tokio::process::Command::new(&cli)
+        .args(["status", "--json"])
+        .output()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err("tailscale status failed".to_string());
+    }
+    parse_peer_path(&output.stdout, &addr).ok_or_else(|| format!("{addr} is not a peer in this tailnet"))
+}
+
+fn parse_peer_path(json: &[u8], addr: &str) -> Option<PeerPath> {
+    let parsed: PeerStatusJson = serde_json::from_slice(json).ok()?;
+    let peer = parsed.peers.into_values().find(|peer| {
+        let dns_name = peer.dns_name.trim_end_matches('.');
+        peer.tailscale_ips.iter().any(|ip| ip == addr)
+            || peer.host_name.eq_ignore_ascii_case(addr)
+            || dns_name.eq_ignore_ascii_case(addr)
+            || dns_name.split('.').next().is_some_and(|label| label.eq_ignore_ascii_case(addr))
+    })?;
+    if peer.cur_addr.is_empty() {
+        Some(PeerPath::Relayed(peer.relay))
+    } else {
+        Some(PeerPath::Direct)
+    }
+}
+
+fn parse_whois_key(json: &[u8]) -> Option<String> {
+    let parsed: WhoIsJson = serde_json::from_slice(json).ok()?;
+    parsed.node?.key.filter(|k| !k.is_empty())
+}
+
 pub fn open_install_page() {
     #[cfg(target_os = "windows")]
     {
@@ -126,6 +230,25 @@ mod tests {
         assert_eq!(node.host_name.unwrap(), "my-machine");
     }
 
+    #[test]
+    fn parse_whois_node_key() {
+        let json = br#"{"Node": {"ID": 1, "Key": "nodekey:abc123"}, "UserProfile": {}}"#;
+        assert_eq!(parse_whois_key(json).as_deref(), Some("nodekey:abc123"));
+        assert_eq!(parse_whois_key(b"{}"), None);
+    }
+
+    #[test]
+    fn parse_peer_path_direct_and_relayed() {
+        let json = br#"{"Peer": {
+            "nodekey:a": {"TailscaleIPs": ["100.64.0.2"], "HostName": "office", "DNSName": "office.tail1.ts.net.", "CurAddr": "203.0.113.5:41641", "Relay": "fra"},
+            "nodekey:b": {"TailscaleIPs": ["100.64.0.3"], "HostName": "lab", "DNSName": "lab.tail1.ts.net.", "CurAddr": "", "Relay": "nyc"}
+        }}"#;
+        assert_eq!(parse_peer_path(json, "100.64.0.2"), Some(PeerPath::Direct));
+        assert_eq!(parse_peer_path(json, "lab"), Some(PeerPath::Relayed("nyc".to_string())));
+        assert_eq!(parse_peer_path(json, "lab.tail1.ts.net"), Some(PeerPath::Relayed("nyc".to_string())));
+        assert_eq!(parse_peer_path(json, "100.64.0.9"), None);
+    }
+
     #[test]
     fn parse_invalid_json_returns_default() {
         let result: Result<TailscaleStatusJson, _> = serde_json::from_str("not json");
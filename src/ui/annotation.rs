@@ -0,0 +1,266 @@
+use std::time::{Duration, Instant};
+
+use iced::widget::{Space, container, stack};
+use iced::{Color, Element, Fill, Point, Theme};
+
+const VISIBLE_FOR: Duration = Duration::from_secs(4);
+const FADE_FOR: Duration = Duration::from_secs(1);
+const DOT_SIZE: f32 = 5.0;
+const DOT_SPACING: f32 = 2.5;
+const MAX_DOTS: usize = 4000;
+const ARROW_HEAD: f32 = 18.0;
+const STROKE_COLOR: Color = Color::from_rgb(1.0, 0.25, 0.2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationTool {
+    Freehand,
+    Arrow,
+    Circle,
+}
+
+impl AnnotationTool {
+    pub fn label(tool: Option<Self>) -> &'static str {
+        match tool {
+            None => "Draw: Off",
+            Some(Self::Freehand) => "Draw: Pen",
+            Some(Self::Arrow) => "Draw: Arrow",
+            Some(Self::Circle) => "Draw: Circle",
+        }
+    }
+
+    pub fn next(tool: Option<Self>) -> Option<Self> {
+        match tool {
+            None => Some(Self::Freehand),
+            Some(Self::Freehand) => Some(Self::Arrow),
+            Some(Self::Arrow) => Some(Self::Circle),
+            Some(Self::Circle) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Stroke {
+    tool: AnnotationTool,
+    points: Vec<Point>,
+    finished_at: Option<Instant>,
+}
+
+impl Stroke {
+    fn alpha(&self, now: Instant) -> f32 {
+        let Some(finished) = self.finished_at else {
+            return 1.0;
+        };
+        let age = now.saturating_duration_since(finished);
+        if age <= VISIBLE_FOR {
+            1.0
+        } else {
+            1.0 - ((age - VISIBLE_FOR).as_secs_f32() / FADE_FOR.as_secs_f32()).min(1.0)
+        }
+    }
+
+    fn expired(&self, now: Instant) -> bool {
+        self.finished_at
+            .is_some_and(|t| now.saturating_duration_since(t) >= VISIBLE_FOR + FADE_FOR)
+    }
+
+    fn outline(&self) -> Vec<Point> {
+        match (self.tool, self.points.as_slice()) {
+            (_, []) => Vec::new(),
+            (AnnotationTool::Freehand, points) => points
+                .windows(2)
+                .flat_map(|w| sample_segment(w[0], w[1]))
+                .chain(points.last().copied())
+                .collect(),
+            (_, [only]) => vec![*only],
+            (AnnotationTool::Arrow, [start, .., end]) => arrow_outline(*start, *end),
+            (AnnotationTool::Circle, [center, .., edge]) => circle_outline(*center, *edge),
+        }
+    }
+}
+
+/// Locally drawn marks on top of the remote frame. Nothing here is sent to the host.
+#[derive(Debug, Default)]
+pub struct Annotations {
+    pub tool: Option<AnnotationTool>,
+    strokes: Vec<Stroke>,
+}
+
+impl Annotations {
+    pub fn is_empty(&self) -> bool {
+        self.strokes.is_empty()
+    }
+
+    pub fn begin(&mut self, at: Point) {
+        if let Some(tool) = self.tool {
+            self.strokes.push(Stroke {
+                tool,
+                points: vec![at],
+                finished_at: None,
+            });
+        }
+    }
+
+    pub fn extend(&mut self, to: Point) {
+        if let Some(stroke) = self.strokes.last_mut()
+            && stroke.finished_at.is_none()
+        {
+            match stroke.tool {
+                AnnotationTool::Freehand => stroke.points.push(to),
+                AnnotationTool::Arrow | AnnotationTool::Circle => {
+                    stroke.points.truncate(1);
+                    stroke.points.push(to);
+                }
+            }
+        }
+    }
+
+    pub fn finish(&mut self, now: Instant) {
+        if let Some(stroke) = self.strokes.last_mut()
+            && stroke.finished_at.is_none()
+        {
+            stroke.finished_at = Some(now);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.strokes.clear();
+    }
+
+    pub fn prune(&mut self, now: Instant) {
+        self.strokes.retain(|s| !s.expired(now));
+    }
+
+    fn dots(&self, now: Instant) -> Vec<(Point, f32)> {
+        let mut dots = Vec::new();
+        for stroke in self.strokes.iter().rev() {
+            let alpha = stroke.alpha(now);
+            dots.extend(stroke.outline().into_iter().map(|p| (p, alpha)));
+            if dots.len() >= MAX_DOTS {
+                dots.truncate(MAX_DOTS);
+                break;
+            }
+        }
+        dots
+    }
+
+    pub fn view<'a, Message: 'a>(&self, now: Instant) -> Element<'a, Message> {
+        let layers = self.dots(now).into_iter().map(|(p, alpha)| {
+            let color = Color { a: alpha, ..STROKE_COLOR };
+            let dot = container(Space::new().width(DOT_SIZE).height(DOT_SIZE)).style(move |_theme: &Theme| {
+                container::Style {
+                    background: Some(color.into()),
+                    border: iced::Border {
+                        radius: (DOT_SIZE / 2.0).into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }
+            });
+            container(dot)
+                .padding(iced::Padding {
+                    top: (p.y - DOT_SIZE / 2.0).max(0.0),
+                    left: (p.x - DOT_SIZE / 2.0).max(0.0),
+                    right: 0.0,
+                    bottom: 0.0,
+                })
+                .into()
+        });
+        stack(layers).width(Fill).height(Fill).into()
+    }
+}
+
+fn sample_segment(a: Point, b: Point) -> Vec<Point> {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let steps = ((dx * dx + dy * dy).sqrt() / DOT_SPACING).ceil().max(1.0) as usize;
+    (0..steps)
+        .map(|i| {
+            let t = i as f32 / steps as f32;
+            Point::new(a.x + dx * t, a.y + dy * t)
+        })
+        .collect()
+}
+
+fn arrow_outline(start: Point, end: Point) -> Vec<Point> {
+    let mut points = sample_segment(start, end);
+    let angle = (end.y - start.y).atan2(end.x - start.x);
+    for side in [-1.0f32, 1.0] {
+        let wing = angle + std::f32::consts::PI + side * 0.45;
+        let tip = Point::new(end.x + ARROW_HEAD * wing.cos(), end.y + ARROW_HEAD * wing.sin());
+        points.extend(sample_segment(end, tip));
+    }
+    points.push(end);
+    points
+}
+
+fn circle_outline(center: Point, edge: Point) -> Vec<Point> {
+    let radius = center.distance(edge);
+    let steps = ((2.0 * std::f32::consts::PI * radius) / DOT_SPACING).ceil().max(8.0) as usize;
+    (0..steps)
+        .map(|i| {
+            let theta = i as f32 / steps as f32 * 2.0 * std::f32::consts::PI;
+            Point::new(center.x + radius * theta.cos(), center.y + radius * theta.sin())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tool_cycle_returns_to_off() {
+        let mut tool = None;
+        for _ in 0..4 {
+            tool = AnnotationTool::next(tool);
+        }
+        assert_eq!(tool, None);
+    }
+
+    #[test]
+    fn nothing_drawn_without_a_tool() {
+        let mut annotations = Annotations::default();
+        annotations.begin(Point::new(1.0, 1.0));
+        assert!(annotations.is_empty());
+    }
+
+    #[test]
+    fn arrow_keeps_only_endpoints() {
+        let mut annotations = Annotations {
+            tool: Some(AnnotationTool::Arrow),
+            ..Default::default()
+        };
+        annotations.begin(Point::new(0.0, 0.0));
+        annotations.extend(Point::new(10.0, 0.0));
+        annotations.extend(Point::new(50.0, 0.0));
+        assert_eq!(annotations.strokes[0].points.len(), 2);
+        annotations.finish(Instant::now());
+        assert!(annotations.strokes[0].finished_at.is_some());
+    }
+
+    #[test]
+    fn strokes_fade_then_expire() {
+        let mut annotations = Annotations {
+            tool: Some(AnnotationTool::Freehand),
+            ..Default::default()
+        };
+        let start = Instant::now();
+        annotations.begin(Point::new(0.0, 0.0));
+        annotations.extend(Point::new(20.0, 20.0));
+        annotations.finish(start);
+
+        assert_eq!(annotations.strokes[0].alpha(start + Duration::from_secs(1)), 1.0);
+        let fading = annotations.strokes[0].alpha(start + VISIBLE_FOR + FADE_FOR / 2);
+        assert!(fading > 0.0 && fading < 1.0);
+
+        annotations.prune(start + VISIBLE_FOR + FADE_FOR);
+        assert!(annotations.is_empty());
+    }
+
+    #[test]
+    fn circle_outline_stays_on_radius() {
+        let center = Point::new(50.0, 50.0);
+        for p in circle_outline(center, Point::new(80.0, 50.0)) {
+            assert!((center.distance(p) - 30.0).abs() < 0.01);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::VecDeque;
+
+use iced::widget::{button, column, container, row, scrollable, text};
+use iced::{Element, Fill};
+
+use crate::ui::theme::*;
+
+/// Oldest snippets are dropped past this many.
+const MAX_ENTRIES: usize = 50;
+/// Characters of a snippet shown in the panel.
+const PREVIEW_CHARS: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClipboardEntry {
+    pub direction: Direction,
+    pub text: String,
+}
+
+/// Text synced through the clipboard during one viewer session, newest last,
+/// so earlier snippets can be copied again.
+#[derive(Debug, Clone, Default)]
+pub struct ClipboardHistory {
+    pub entries: VecDeque<ClipboardEntry>,
+}
+
+impl ClipboardHistory {
+    /// A snippet that is already listed moves to the end instead of repeating.
+    pub fn record(&mut self, direction: Direction, text: String) {
+        if text.trim().is_empty() {
+            return;
+        }
+        self.entries.retain(|e| e.text != text);
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(ClipboardEntry { direction, text });
+    }
+
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(|e| e.text.as_str())
+    }
+
+    pub fn view<'a, M: Clone + 'a>(&'a self, on_copy: impl Fn(usize) -> M) -> Element<'a, M> {
+        let lines = self.entries.iter().enumerate().rev().fold(column![].spacing(4), |col, (index, e)| {
+            let arrow = match e.direction {
+                Direction::Sent => "\u{2191}",
+                Direction::Received => "\u{2193}",
+            };
+            col.push(
+                row![
+                    text(arrow).size(12).color(TEXT_MUTED),
+                    text(preview(&e.text)).size(12).color(TEXT_SECONDARY).width(Fill),
+                    button(text("Copy").size(12))
+                        .on_press(on_copy(index))
+                        .style(secondary_button_style)
+                        .padding([2, 10]),
+                ]
+                .spacing(8)
+                .align_y(iced::Center),
+            )
+        });
+        let body: Element<'a, M> = if self.entries.is_empty() {
+            text("Nothing copied yet this session").size(12).color(TEXT_MUTED).into()
+        } else {
+            scrollable(lines).height(220).into()
+        };
+        container(column![text("Clipboard history").size(14).color(TEXT_PRIMARY), body].spacing(8))
+            .style(card_container_style)
+            .padding(12)
+            .width(360)
+            .into()
+    }
+}
+
+/// First line of `text`, shortened for the panel.
+fn preview(text: &str) -> String {
+    let line = text.trim().lines().next().unwrap_or_default();
+    let mut preview: String = line.chars().take(PREVIEW_CHARS).collect();
+    if line.chars().count() > PREVIEW_CHARS || text.trim().lines().nth(1).is_some() {
+        preview.push('\u{2026}');
+    }
+    preview
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_snippets_move_to_the_end() {
+        let mut history = ClipboardHistory::default();
+        history.record(Direction::Sent, "a".to_string());
+        history.record(Direction::Received, "b".to_string());
+        history.record(Direction::Received, "a".to_string());
+        history.record(Direction::Sent, "  ".to_string());
+        assert_eq!(history.entries.len(), 2);
+        assert_eq!(history.get(1), Some("a"));
+        assert_eq!(history.entries[1].direction, Direction::Received);
+        assert_eq!(preview("first line\nsecond"), "first line\u{2026}");
+    }
+}
@@ -0,0 +1,73 @@
+use iced::widget::{button, column, container, row, text, text_input};
+use iced::{Center, Element};
+
+use crate::config::secret::SecretString;
+use crate::ui::theme::*;
+
+#[derive(Debug, Clone)]
+pub enum CodePromptMessage {
+    CodeChanged(String),
+    Submit,
+    Cancel,
+}
+
+/// Shown over the viewer when the host turns the code away, so the code can
+/// be typed and the connection retried without going back to the form.
+#[derive(Debug, Clone)]
+pub struct CodePromptState {
+    /// What the host said, e.g. that the code was wrong.
+    pub reason: String,
+    pub code: SecretString,
+}
+
+impl CodePromptState {
+    pub fn new(reason: String) -> Self {
+        Self { reason, code: SecretString::default() }
+    }
+
+    pub fn code(&self) -> Option<SecretString> {
+        let code = self.code.expose().trim();
+        (!code.is_empty()).then(|| code.into())
+    }
+
+    pub fn view(&self) -> Element<'_, CodePromptMessage> {
+        let submit = button(text("Connect").size(13)).style(primary_button_style).padding([6, 16]);
+        let submit = if self.code().is_some() { submit.on_press(CodePromptMessage::Submit) } else { submit };
+        let panel = column![
+            text("The host asks for its access code").size(16).color(TEXT_PRIMARY),
+            text(self.reason.as_str()).size(13).color(DANGER),
+            text_input("Code shown on the host", self.code.expose())
+                .on_input(CodePromptMessage::CodeChanged)
+                .on_submit(CodePromptMessage::Submit)
+                .style(input_style)
+                .padding(8)
+                .width(260),
+            row![
+                submit,
+                button(text("Cancel").size(13))
+                    .on_press(CodePromptMessage::Cancel)
+                    .style(secondary_button_style)
+                    .padding([6, 16]),
+            ]
+            .spacing(10),
+        ]
+        .spacing(10)
+        .align_x(Center);
+        container(panel).style(card_container_style).padding([16, 24]).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blank_codes_are_not_submitted() {
+        let mut prompt = CodePromptState::new("Wrong access code".to_string());
+        assert_eq!(prompt.code(), None);
+        prompt.code = "  ".into();
+        assert_eq!(prompt.code(), None);
+        prompt.code = " 4821 ".into();
+        assert_eq!(prompt.code().as_ref().map(SecretString::expose), Some("4821"));
+    }
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+pub const BRIGHTNESS_RANGE: std::ops::RangeInclusive<f32> = -0.5..=0.5;
+pub const CONTRAST_RANGE: std::ops::RangeInclusive<f32> = 0.5..=2.0;
+pub const GAMMA_RANGE: std::ops::RangeInclusive<f32> = 0.5..=2.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ColorAdjust {
+    #[serde(default)]
+    pub brightness: f32,
+    #[serde(default = "default_one")]
+    pub contrast: f32,
+    #[serde(default = "default_one")]
+    pub gamma: f32,
+}
+
+fn default_one() -> f32 {
+    1.0
+}
+
+impl Default for ColorAdjust {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 1.0,
+            gamma: 1.0,
+        }
+    }
+}
+
+impl ColorAdjust {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn lut(&self) -> [u8; 256] {
+        let mut table = [0u8; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            let v = i as f32 / 255.0;
+            let v = ((v - 0.5) * self.contrast + 0.5 + self.brightness).clamp(0.0, 1.0);
+            let v = v.powf(1.0 / self.gamma);
+            *entry = (v * 255.0).round() as u8;
+        }
+        table
+    }
+}
+
+/// Rewrites the RGB channels of an RGBA buffer through `lut`, leaving alpha untouched.
+pub fn apply_lut(rgba: &mut [u8], lut: &[u8; 256]) {
+    for px in rgba.chunks_exact_mut(4) {
+        px[0] = lut[px[0] as usize];
+        px[1] = lut[px[1] as usize];
+        px[2] = lut[px[2] as usize];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_lut_is_identity() {
+        let adjust = ColorAdjust::default();
+        assert!(adjust.is_identity());
+        let lut = adjust.lut();
+        for (i, v) in lut.iter().enumerate() {
+            assert_eq!(*v as usize, i);
+        }
+    }
+
+    #[test]
+    fn brightness_lifts_darks() {
+        let lut = ColorAdjust { brightness: 0.2, ..Default::default() }.lut();
+        assert!(lut[0] >= 50);
+        assert_eq!(lut[255], 255);
+    }
+
+    #[test]
+    fn gamma_above_one_brightens_midtones() {
+        let lut = ColorAdjust { gamma: 2.0, ..Default::default() }.lut();
+        assert!(lut[64] > 64);
+        assert_eq!(lut[0], 0);
+        assert_eq!(lut[255], 255);
+    }
+
+    #[test]
+    fn apply_lut_preserves_alpha() {
+        let lut = ColorAdjust { contrast: 2.0, ..Default::default() }.lut();
+        let mut px = vec![200, 100, 50, 7];
+        apply_lut(&mut px, &lut);
+        assert_eq!(px[3], 7);
+        assert!(px[0] > 200);
+        assert!(px[2] < 50);
+    }
+}
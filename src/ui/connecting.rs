@@ -0,0 +1,99 @@
+use std::time::{Duration, Instant};
+
+use iced::widget::{button, column, container, row, text};
+use iced::{Center, Element, Fill};
+
+use crate::network::ConnectStage;
+use crate::ui::theme::*;
+
+#[derive(Debug, Clone)]
+pub enum ConnectingMessage {
+    Tick,
+    Cancel,
+}
+
+/// Steps through the viewer's connection stages, timing each one.
+#[derive(Debug, Clone)]
+pub struct ConnectingState {
+    pub stage: ConnectStage,
+    pub stage_started: Instant,
+    /// How long each stage before the current one took.
+    pub done: Vec<Duration>,
+}
+
+impl ConnectingState {
+    pub fn new(now: Instant) -> Self {
+        Self { stage: ConnectStage::Dialing, stage_started: now, done: Vec::new() }
+    }
+
+    pub fn advance(&mut self, stage: ConnectStage, now: Instant) {
+        if stage.index() < self.stage.index() {
+            // A retry starts over from the first stage.
+            *self = Self::new(now);
+        }
+        while self.stage.index() < stage.index() {
+            self.done.push(now.duration_since(self.stage_started));
+            self.stage = ConnectStage::ALL[self.stage.index() + 1];
+            self.stage_started = now;
+        }
+    }
+
+    pub fn view(&self, attempt: Option<(u32, u32)>) -> Element<'_, ConnectingMessage> {
+        let mut steps = column![].spacing(8);
+        for (index, stage) in ConnectStage::ALL.into_iter().enumerate() {
+            let (marker, color, time) = match self.done.get(index) {
+                Some(took) => ("\u{2713}", SUCCESS, format!("{} ms", took.as_millis())),
+                None if stage == self.stage => {
+                    ("\u{25B8}", ACCENT, format!("{:.1} s", self.stage_started.elapsed().as_secs_f32()))
+                }
+                None => ("\u{2022}", TEXT_MUTED, String::new()),
+            };
+            steps = steps.push(
+                row![
+                    text(marker).size(14).color(color).width(18),
+                    text(stage.label()).size(14).color(TEXT_PRIMARY).width(Fill),
+                    text(time).size(13).color(TEXT_MUTED),
+                ]
+                .align_y(Center),
+            );
+        }
+
+        let status = match attempt {
+            Some((attempt, max)) => format!("Connection failed - retrying (attempt {attempt}/{max})..."),
+            None => "Establishing connection via Tailscale...".to_string(),
+        };
+        let inner = column![
+            text("Connecting...").size(24).color(TEXT_PRIMARY),
+            text(status).size(14).color(TEXT_SECONDARY),
+            steps,
+            button("Cancel")
+                .on_press(ConnectingMessage::Cancel)
+                .style(secondary_button_style)
+                .padding([10, 24]),
+        ]
+        .spacing(16)
+        .align_x(Center);
+
+        let card = container(inner).style(card_container_style).padding(40).max_width(400);
+        container(card).center_x(Fill).center_y(Fill).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stages_advance_and_restart_on_retry() {
+        let start = Instant::now();
+        let mut state = ConnectingState::new(start);
+        state.advance(ConnectStage::Handshake, start + Duration::from_millis(40));
+        assert_eq!(state.stage, ConnectStage::Handshake);
+        assert_eq!(state.done, [Duration::from_millis(40)]);
+
+        state.advance(ConnectStage::Dialing, start + Duration::from_secs(2));
+        assert_eq!(state.stage, ConnectStage::Dialing);
+        assert!(state.done.is_empty());
+        assert_eq!(state.stage_started, start + Duration::from_secs(2));
+    }
+}
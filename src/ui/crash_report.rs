@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+
+use iced::widget::{Space, button, container, row, text};
+use iced::{Center, Element, Fill, Length};
+
+use crate::ui::theme::*;
+
+#[derive(Debug, Clone)]
+pub enum CrashMessage {
+    Export,
+    Exported(Result<PathBuf, String>),
+    Dismiss,
+}
+
+/// Shown on the launch after a crash, until dismissed.
+#[derive(Debug, Clone, Default)]
+pub struct CrashBannerState {
+    pub reports: Vec<PathBuf>,
+    pub exporting: bool,
+    pub note: Option<String>,
+}
+
+impl CrashBannerState {
+    pub fn view(&self) -> Element<'_, CrashMessage> {
+        if self.reports.is_empty() {
+            return Space::new().into();
+        }
+        let message = match &self.note {
+            Some(note) => note.clone(),
+            None => "The previous session crashed. A report was saved.".to_string(),
+        };
+        let content = row![
+            text(message).size(14).color(TEXT_PRIMARY),
+            Space::new().width(Length::Fill),
+            button(text(if self.exporting { "Exporting\u{2026}" } else { "Export Report" }).size(13))
+                .on_press_maybe((!self.exporting).then_some(CrashMessage::Export))
+                .style(primary_button_style)
+                .padding([6, 16]),
+            button(text("Dismiss").size(13))
+                .on_press(CrashMessage::Dismiss)
+                .style(secondary_button_style)
+                .padding([6, 16]),
+        ]
+        .spacing(12)
+        .align_y(Center);
+        container(content).style(banner_container_style).padding([8, 16]).width(Fill).into()
+    }
+}
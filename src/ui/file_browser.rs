@@ -0,0 +1,234 @@
+use std::path::PathBuf;
+
+use iced::widget::{Column, button, column, row, scrollable, text};
+use iced::{Center, Element, Fill};
+
+use crate::network::files;
+use crate::protocol::FileEntry;
+use crate::storage::format_size;
+use crate::ui::theme::*;
+
+#[derive(Debug, Clone)]
+pub enum FileBrowserMessage {
+    LocalOpen(String),
+    LocalUp,
+    RemoteOpen(String),
+    RemoteUp,
+    Refresh,
+    Upload(String),
+    Download(String),
+}
+
+/// What the app has to do for the browser.
+#[derive(Debug, PartialEq, Eq)]
+pub enum BrowserRequest {
+    List(String),
+    Download { path: String, name: String, size: u64 },
+    Upload { source: PathBuf, name: String, size: u64, folder: String },
+}
+
+/// Side panel with this computer's files on the left and the host's on
+/// the right, for copying files without Explorer inside the session.
+pub struct FileBrowserState {
+    pub local_dir: PathBuf,
+    pub local: Vec<FileEntry>,
+    /// Relative to the host user's home folder.
+    pub remote_dir: String,
+    /// `None` until the host answers.
+    pub remote: Option<Vec<FileEntry>>,
+    pub note: Option<String>,
+}
+
+impl FileBrowserState {
+    pub fn new(local_dir: PathBuf) -> Self {
+        let mut state = Self {
+            local_dir,
+            local: Vec::new(),
+            remote_dir: String::new(),
+            remote: None,
+            note: None,
+        };
+        state.refresh_local();
+        state
+    }
+
+    pub fn refresh_local(&mut self) {
+        match files::list_dir(&self.local_dir) {
+            Ok(entries) => self.local = entries,
+            Err(e) => {
+                self.local.clear();
+                self.note = Some(e);
+            }
+        }
+    }
+
+    pub fn update(&mut self, msg: FileBrowserMessage) -> Option<BrowserRequest> {
+        match msg {
+            FileBrowserMessage::LocalOpen(name) => {
+                self.local_dir.push(name);
+                self.refresh_local();
+            }
+            FileBrowserMessage::LocalUp => {
+                self.local_dir.pop();
+                self.refresh_local();
+            }
+            FileBrowserMessage::RemoteOpen(name) => {
+                self.remote_dir = files::child(&self.remote_dir, &name);
+                self.remote = None;
+                return Some(BrowserRequest::List(self.remote_dir.clone()));
+            }
+            FileBrowserMessage::RemoteUp => {
+                self.remote_dir = files::parent(&self.remote_dir);
+                self.remote = None;
+                return Some(BrowserRequest::List(self.remote_dir.clone()));
+            }
+            FileBrowserMessage::Refresh => {
+                self.refresh_local();
+                return Some(BrowserRequest::List(self.remote_dir.clone()));
+            }
+            FileBrowserMessage::Download(name) => {
+                let size = self.remote.iter().flatten().find(|e| e.name == name).map_or(0, |e| e.size);
+                return Some(BrowserRequest::Download { path: files::child(&self.remote_dir, &name), name, size });
+            }
+            FileBrowserMessage::Upload(name) => {
+                let size = self.local.iter().find(|e| e.name == name).map_or(0, |e| e.size);
+                return Some(BrowserRequest::Upload {
+                    source: self.local_dir.join(&name),
+                    name,
+                    size,
+                    folder: self.remote_dir.clone(),
+                });
+            }
+        }
+        None
+    }
+
+    pub fn listed(&mut self, path: String, entries: Vec<FileEntry>) {
+        if path == self.remote_dir {
+            self.remote = Some(entries);
+        }
+    }
+
+    pub fn failed(&mut self, error: String) {
+        self.note = Some(error);
+    }
+
+    fn pane<'a>(
+        title: String,
+        entries: Option<&'a [FileEntry]>,
+        up: FileBrowserMessage,
+        open: fn(String) -> FileBrowserMessage,
+        action: &'static str,
+        act: fn(String) -> FileBrowserMessage,
+    ) -> Element<'a, FileBrowserMessage> {
+        let header = row![
+            text(title).size(12).color(TEXT_SECONDARY).width(Fill),
+            button(text("Up").size(11)).on_press(up).style(secondary_button_style).padding([2, 8]),
+        ]
+        .spacing(6)
+        .align_y(Center);
+        let list: Element<'a, FileBrowserMessage> = match entries {
+            None => text("Loading\u{2026}").size(12).color(TEXT_MUTED).into(),
+            Some(entries) => {
+                let rows = entries.iter().map(|entry| {
+                    if entry.is_dir {
+                        button(text(format!("{}/", entry.name)).size(12))
+                            .on_press(open(entry.name.clone()))
+                            .style(secondary_button_style)
+                            .padding([2, 6])
+                            .width(Fill)
+                            .into()
+                    } else {
+                        row![
+                            text(&entry.name).size(12).color(TEXT_PRIMARY).width(Fill),
+                            text(format_size(entry.size)).size(11).color(TEXT_MUTED),
+                            button(text(action).size(11))
+                                .on_press(act(entry.name.clone()))
+                                .style(primary_button_style)
+                                .padding([2, 8]),
+                        ]
+                        .spacing(6)
+                        .align_y(Center)
+                        .into()
+                    }
+                });
+                scrollable(Column::with_children(rows).spacing(2)).height(320).into()
+            }
+        };
+        column![header, list].spacing(6).width(Fill).into()
+    }
+
+    pub fn view(&self) -> Element<'_, FileBrowserMessage> {
+        let local = Self::pane(
+            self.local_dir.display().to_string(),
+            Some(&self.local),
+            FileBrowserMessage::LocalUp,
+            FileBrowserMessage::LocalOpen,
+            "Upload",
+            FileBrowserMessage::Upload,
+        );
+        let remote = Self::pane(
+            format!("Host: ~/{}", self.remote_dir),
+            self.remote.as_deref(),
+            FileBrowserMessage::RemoteUp,
+            FileBrowserMessage::RemoteOpen,
+            "Download",
+            FileBrowserMessage::Download,
+        );
+
+        let mut content = column![
+            row![
+                text("Files").size(14).color(TEXT_PRIMARY).width(Fill),
+                button(text("Refresh").size(11))
+                    .on_press(FileBrowserMessage::Refresh)
+                    .style(secondary_button_style)
+                    .padding([2, 8]),
+            ]
+            .align_y(Center),
+            row![local, remote].spacing(12),
+        ]
+        .spacing(8);
+        if let Some(note) = &self.note {
+            content = content.push(text(note).size(12).color(TEXT_SECONDARY));
+        }
+        content.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_navigation_and_transfer_requests() {
+        let dir = std::env::temp_dir().join(format!("rust-rdp-browser-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("b.txt"), b"abc").unwrap();
+        let mut state = FileBrowserState::new(dir.clone());
+
+        assert_eq!(
+            state.update(FileBrowserMessage::RemoteOpen("docs".to_string())),
+            Some(BrowserRequest::List("docs".to_string()))
+        );
+        // A late answer for another folder is ignored.
+        state.listed(String::new(), Vec::new());
+        assert!(state.remote.is_none());
+        state.listed("docs".to_string(), vec![FileEntry { name: "a.txt".to_string(), is_dir: false, size: 4 }]);
+
+        assert_eq!(
+            state.update(FileBrowserMessage::Download("a.txt".to_string())),
+            Some(BrowserRequest::Download { path: "docs/a.txt".to_string(), name: "a.txt".to_string(), size: 4 })
+        );
+        assert_eq!(
+            state.update(FileBrowserMessage::Upload("b.txt".to_string())),
+            Some(BrowserRequest::Upload {
+                source: dir.join("b.txt"),
+                name: "b.txt".to_string(),
+                size: 3,
+                folder: "docs".to_string(),
+            })
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,363 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+use bytes::Bytes;
+use iced::widget::shader::{self, Pipeline, Primitive, Viewport};
+use iced::{Rectangle, mouse, wgpu};
+
+use crate::protocol::DirtyRect;
+use crate::ui::scaling::Rotation;
+
+/// Partial updates remembered for a surface that fell behind; past this the
+/// next draw uploads the whole frame.
+const MAX_DAMAGE: usize = 64;
+
+const SHADER: &str = r#"
+// Texture coordinates of the top-left, top-right, bottom-left and
+// bottom-right corners, two per vector.
+struct Uniforms {
+    top: vec4<f32>,
+    bottom: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+@group(0) @binding(1) var frame: texture_2d<f32>;
+@group(0) @binding(2) var frame_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let corner = vec2<f32>(f32(index & 1u), f32((index >> 1u) & 1u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(corner.x * 2.0 - 1.0, 1.0 - corner.y * 2.0, 0.0, 1.0);
+    let row = select(uniforms.top, uniforms.bottom, corner.y > 0.5);
+    out.uv = select(row.xy, row.zw, corner.x > 0.5);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(frame, frame_sampler, in.uv);
+}
+"#;
+
+/// Tracks which parts of the retained frame changed, so the GPU texture only
+/// receives those regions instead of the whole frame on every update.
+#[derive(Debug, Clone, Default)]
+pub struct FrameDamage {
+    generation: u64,
+    /// Generation of the last full-frame change; a texture older than this
+    /// needs the whole frame.
+    full: u64,
+    regions: VecDeque<(u64, DirtyRect)>,
+}
+
+impl FrameDamage {
+    pub fn full(&mut self) {
+        self.generation += 1;
+        self.full = self.generation;
+        self.regions.clear();
+    }
+
+    pub fn region(&mut self, rect: DirtyRect) {
+        self.generation += 1;
+        self.regions.push_back((self.generation, rect));
+        if self.regions.len() > MAX_DAMAGE {
+            self.regions.pop_front();
+        }
+    }
+
+    /// Regions to upload to a texture holding generation `uploaded`, or
+    /// `None` when it needs the whole frame.
+    fn pending(&self, uploaded: Option<u64>) -> Option<Vec<DirtyRect>> {
+        let uploaded = uploaded.filter(|&uploaded| uploaded >= self.full)?;
+        let regions: Vec<DirtyRect> =
+            self.regions.iter().filter(|(generation, _)| *generation > uploaded).map(|(_, rect)| *rect).collect();
+        (regions.len() as u64 == self.generation - uploaded).then_some(regions)
+    }
+}
+
+/// Draws the retained frame through a persistent texture, showing `crop` (in
+/// pixels of the frame as rotated) stretched over the widget bounds.
+pub struct FrameSurface {
+    pub pixels: Bytes,
+    pub width: u32,
+    pub height: u32,
+    pub crop: Rectangle<u32>,
+    pub rotation: Rotation,
+    pub damage: FrameDamage,
+}
+
+impl<Message> shader::Program<Message> for FrameSurface {
+    type State = ();
+    type Primitive = FramePrimitive;
+
+    fn draw(&self, _state: &(), _cursor: mouse::Cursor, _bounds: Rectangle) -> FramePrimitive {
+        FramePrimitive {
+            pixels: self.pixels.clone(),
+            width: self.width,
+            height: self.height,
+            crop: self.crop,
+            rotation: self.rotation,
+            damage: self.damage.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FramePrimitive {
+    pixels: Bytes,
+    width: u32,
+    height: u32,
+    crop: Rectangle<u32>,
+    rotation: Rotation,
+    damage: FrameDamage,
+}
+
+impl FramePrimitive {
+    fn write(&self, queue: &wgpu::Queue, texture: &wgpu::Texture, rect: DirtyRect) {
+        if rect.width == 0 || rect.height == 0 {
+            return;
+        }
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: rect.x, y: rect.y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &self.pixels,
+            wgpu::TexelCopyBufferLayout {
+                offset: (rect.y as u64 * self.width as u64 + rect.x as u64) * 4,
+                bytes_per_row: Some(self.width * 4),
+                rows_per_image: None,
+            },
+            wgpu::Extent3d { width: rect.width, height: rect.height, depth_or_array_layers: 1 },
+        );
+    }
+}
+
+impl Primitive for FramePrimitive {
+    type Pipeline = FramePipeline;
+
+    fn prepare(
+        &self,
+        pipeline: &mut FramePipeline,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        _bounds: &Rectangle,
+        _viewport: &Viewport,
+    ) {
+        if self.width == 0 || self.height == 0 || self.pixels.len() < (self.width * self.height * 4) as usize {
+            return;
+        }
+        if pipeline.texture.as_ref().is_none_or(|t| (t.width, t.height) != (self.width, self.height)) {
+            pipeline.texture = Some(pipeline.create_texture(device, self.width, self.height));
+            pipeline.generation = None;
+        }
+        let texture = pipeline.texture.as_ref().expect("texture was just created");
+
+        let generation = self.damage.generation;
+        if pipeline.generation != Some(generation) {
+            let whole = vec![DirtyRect { x: 0, y: 0, width: self.width, height: self.height }];
+            for rect in self.damage.pending(pipeline.generation).unwrap_or(whole) {
+                self.write(queue, &texture.texture, rect);
+            }
+            pipeline.generation = Some(generation);
+        }
+
+        let (w, h) = self.rotation.shown_size(self.width, self.height);
+        let (u0, v0) = (self.crop.x as f32 / w as f32, self.crop.y as f32 / h as f32);
+        let (u1, v1) = (
+            (self.crop.x + self.crop.width) as f32 / w as f32,
+            (self.crop.y + self.crop.height) as f32 / h as f32,
+        );
+        let bytes: Vec<u8> = [(u0, v0), (u1, v0), (u0, v1), (u1, v1)]
+            .into_iter()
+            .flat_map(|(u, v)| {
+                let (tu, tv) = self.rotation.texture_uv(u, v);
+                [tu, tv]
+            })
+            .flat_map(f32::to_ne_bytes)
+            .collect();
+        queue.write_buffer(&pipeline.uniforms, 0, &bytes);
+    }
+
+    fn draw(&self, pipeline: &FramePipeline, render_pass: &mut wgpu::RenderPass<'_>) -> bool {
+        let Some(texture) = &pipeline.texture else {
+            return true;
+        };
+        render_pass.set_pipeline(&pipeline.pipeline);
+        render_pass.set_bind_group(0, &texture.bind_group, &[]);
+        render_pass.draw(0..4, 0..1);
+        true
+    }
+}
+
+struct FrameTexture {
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+    width: u32,
+    height: u32,
+}
+
+pub struct FramePipeline {
+    pipeline: wgpu::RenderPipeline,
+    layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    uniforms: wgpu::Buffer,
+    format: wgpu::TextureFormat,
+    texture: Option<FrameTexture>,
+    /// Generation of the frame the texture holds.
+    generation: Option<u64>,
+}
+
+impl FramePipeline {
+    fn create_texture(&self, device: &wgpu::Device, width: u32, height: u32) -> FrameTexture {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("frame texture"),
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("frame bind group"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: self.uniforms.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&view) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&self.sampler) },
+            ],
+        });
+        FrameTexture { texture, bind_group, width, height }
+    }
+}
+
+impl Pipeline for FramePipeline {
+    fn new(device: &wgpu::Device, _queue: &wgpu::Queue, format: wgpu::TextureFormat) -> Self {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("frame shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(SHADER)),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("frame bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("frame pipeline layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("frame pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..wgpu::PrimitiveState::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("frame sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..wgpu::SamplerDescriptor::default()
+        });
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("frame uniforms"),
+            size: 32,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        // Frames arrive as sRGB-encoded RGBA, like images.
+        let format = if format.is_srgb() { wgpu::TextureFormat::Rgba8UnormSrgb } else { wgpu::TextureFormat::Rgba8Unorm };
+
+        Self { pipeline, layout, sampler, uniforms, format, texture: None, generation: None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: u32) -> DirtyRect {
+        DirtyRect { x, y: 0, width: 1, height: 1 }
+    }
+
+    #[test]
+    fn pending_covers_only_what_the_texture_missed() {
+        let mut damage = FrameDamage::default();
+        damage.full();
+        damage.region(rect(1));
+        damage.region(rect(2));
+        assert_eq!(damage.pending(None), None);
+        assert_eq!(damage.pending(Some(1)), Some(vec![rect(1), rect(2)]));
+        assert_eq!(damage.pending(Some(2)), Some(vec![rect(2)]));
+
+        // A texture from before the last full frame needs all of it.
+        damage.full();
+        assert_eq!(damage.pending(Some(3)), None);
+
+        // So does one that fell further behind than the kept regions.
+        for x in 0..=MAX_DAMAGE as u32 {
+            damage.region(rect(x));
+        }
+        assert_eq!(damage.pending(Some(4)), None);
+        assert_eq!(damage.pending(Some(5)).map(|r| r.len()), Some(MAX_DAMAGE));
+    }
+}
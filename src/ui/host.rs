@@ -1,14 +1,42 @@
+use std::collections::VecDeque;
 use std::time::Instant;
 
-use iced::widget::{button, column, container, row, text};
+use iced::widget::{button, column, container, row, text, text_input};
 use iced::{Center, Element, Fill};
 
+use crate::config::secret::SecretString;
+use crate::host_setup::FirewallCheck;
+use crate::network::stats::format_elapsed;
 use crate::ui::theme::*;
 
+/// Entries kept in the host screen's activity list.
+const MAX_ACTIVITY: usize = 8;
+
 #[derive(Debug, Clone)]
 pub enum HostMessage {
     CopyUrl,
     StopHosting,
+    AnswerControlRequest(bool),
+    PortChanged(String),
+    ApplyPort,
+    HideToTray,
+    Tick,
+    FirewallChecked(FirewallCheck),
+    AllowFirewall,
+    FirewallAllowed(Result<(), String>),
+}
+
+/// Work the host screen hands back to the app.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostAction {
+    Copy(String),
+    StopHosting,
+    HideToTray,
+    /// Listen on this port from now on.
+    SetPort(u16),
+    AllowFirewall,
+    CheckFirewall,
+    AnswerControlRequest(bool),
 }
 
 #[derive(Debug, Clone)]
@@ -19,12 +47,41 @@ pub enum HostStatus {
     Error(String),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ActivityKind {
+    Connected,
+    Disconnected,
+    Requested,
+    Declined,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientActivity {
+    pub at: Instant,
+    pub addr: String,
+    pub kind: ActivityKind,
+}
+
 pub struct HostState {
     pub tunnel_url: Option<String>,
     pub status: HostStatus,
     pub copied: bool,
     pub client_addr: Option<String>,
     pub connected_since: Option<Instant>,
+    pub bind_warning: Option<String>,
+    /// Code viewers must type, when hosting requires one.
+    pub access_code: Option<SecretString>,
+    /// Address of a viewer asking to take over the current session.
+    pub control_request: Option<String>,
+    /// Most recent first.
+    pub activity: VecDeque<ClientActivity>,
+    pub sessions_served: u32,
+    /// Listening port as typed in the port field.
+    pub port_input: String,
+    pub port_error: Option<String>,
+    pub firewall: Option<FirewallCheck>,
+    /// Set while the elevated fix runs, then to why it failed, if it did.
+    pub firewall_note: Option<String>,
 }
 
 impl HostState {
@@ -35,7 +92,186 @@ impl HostState {
             copied: false,
             client_addr: None,
             connected_since: None,
+            bind_warning: None,
+            access_code: None,
+            control_request: None,
+            activity: VecDeque::new(),
+            sessions_served: 0,
+            port_input: String::new(),
+            port_error: None,
+            firewall: None,
+            firewall_note: None,
+        }
+    }
+
+    pub fn parse_port(&self) -> Result<u16, String> {
+        match self.port_input.trim().parse::<u16>() {
+            Ok(0) | Err(_) => Err("Port must be a number from 1 to 65535".to_string()),
+            Ok(port) => Ok(port),
+        }
+    }
+
+    /// `port` is the one hosting listens on now.
+    pub fn update(&mut self, msg: HostMessage, port: u16) -> Option<HostAction> {
+        match msg {
+            HostMessage::CopyUrl => {
+                let url = self.tunnel_url.clone()?;
+                self.copied = true;
+                Some(HostAction::Copy(url))
+            }
+            HostMessage::StopHosting => Some(HostAction::StopHosting),
+            HostMessage::Tick => None,
+            HostMessage::HideToTray => Some(HostAction::HideToTray),
+            HostMessage::PortChanged(value) => {
+                self.port_input = value;
+                self.port_error = None;
+                None
+            }
+            HostMessage::ApplyPort => match self.parse_port() {
+                Ok(new) if new == port => None,
+                Ok(new) => {
+                    self.tunnel_url = None;
+                    self.copied = false;
+                    self.status = HostStatus::Starting;
+                    Some(HostAction::SetPort(new))
+                }
+                Err(e) => {
+                    self.port_error = Some(e);
+                    None
+                }
+            },
+            HostMessage::FirewallChecked(check) => {
+                self.firewall = Some(check);
+                None
+            }
+            HostMessage::AllowFirewall => {
+                self.firewall_note = Some("Waiting for Windows to allow the change...".to_string());
+                Some(HostAction::AllowFirewall)
+            }
+            HostMessage::FirewallAllowed(result) => {
+                self.firewall_note = result.err();
+                Some(HostAction::CheckFirewall)
+            }
+            HostMessage::AnswerControlRequest(accept) => {
+                self.control_answered(accept);
+                Some(HostAction::AnswerControlRequest(accept))
+            }
+        }
+    }
+
+    fn port_form(&self) -> Element<'_, HostMessage> {
+        let form = row![
+            text("Port").size(14).color(TEXT_SECONDARY),
+            text_input("9867", &self.port_input)
+                .on_input(HostMessage::PortChanged)
+                .on_submit(HostMessage::ApplyPort)
+                .style(input_style)
+                .padding(6)
+                .width(90),
+            button(text("Apply").size(13))
+                .on_press(HostMessage::ApplyPort)
+                .style(secondary_button_style)
+                .padding([6, 12]),
+        ]
+        .spacing(8)
+        .align_y(Center);
+        match &self.port_error {
+            Some(e) => column![form, text(e).size(12).color(DANGER)].spacing(4).align_x(Center).into(),
+            None => form.into(),
+        }
+    }
+
+    fn firewall_warning(&self) -> Option<Element<'_, HostMessage>> {
+        if self.firewall != Some(FirewallCheck::Missing) {
+            return None;
+        }
+        let warning = row![
+            text("Windows Firewall may block viewers on this port").size(13).color(DANGER),
+            button(text("Allow in Firewall").size(13))
+                .on_press(HostMessage::AllowFirewall)
+                .style(secondary_button_style)
+                .padding([6, 12]),
+        ]
+        .spacing(8)
+        .align_y(Center);
+        Some(match &self.firewall_note {
+            Some(note) => column![warning, text(note).size(12).color(TEXT_MUTED)].spacing(4).align_x(Center).into(),
+            None => warning.into(),
+        })
+    }
+
+    pub fn tray_tooltip(&self) -> String {
+        let status = match (&self.status, &self.client_addr, &self.tunnel_url) {
+            (HostStatus::Error(_), _, _) => "hosting failed".to_string(),
+            (HostStatus::Stopping, _, _) => "stopping".to_string(),
+            (_, Some(addr), _) => format!("{addr} connected"),
+            (HostStatus::Active, None, Some(url)) => format!("hosting at {url}"),
+            _ => "starting".to_string(),
+        };
+        format!("Rust RDP: {status}")
+    }
+
+    /// The viewer in session plus one waiting for an answer.
+    pub fn active_connections(&self) -> usize {
+        usize::from(self.client_addr.is_some()) + usize::from(self.control_request.is_some())
+    }
+
+    pub fn client_connected(&mut self, addr: String) {
+        self.record(ActivityKind::Connected, addr.clone());
+        self.client_addr = Some(addr);
+        self.connected_since = Some(Instant::now());
+        self.sessions_served += 1;
+    }
+
+    pub fn client_disconnected(&mut self) {
+        if let Some(addr) = self.client_addr.take() {
+            self.record(ActivityKind::Disconnected, addr);
+        }
+        self.connected_since = None;
+        self.control_request = None;
+    }
+
+    pub fn control_requested(&mut self, addr: String) {
+        self.record(ActivityKind::Requested, addr.clone());
+        self.control_request = Some(addr);
+    }
+
+    pub fn control_answered(&mut self, accept: bool) {
+        if let Some(addr) = self.control_request.take()
+            && !accept
+        {
+            self.record(ActivityKind::Declined, addr);
+        }
+    }
+
+    fn record(&mut self, kind: ActivityKind, addr: String) {
+        self.activity.push_front(ClientActivity { at: Instant::now(), addr, kind });
+        self.activity.truncate(MAX_ACTIVITY);
+    }
+
+    fn activity_view(&self) -> Element<'_, HostMessage> {
+        let now = Instant::now();
+        let mut list = column![
+            text(format!(
+                "Active connections: {} \u{00b7} sessions since start: {}",
+                self.active_connections(),
+                self.sessions_served
+            ))
+            .size(13)
+            .color(TEXT_SECONDARY)
+        ]
+        .spacing(2);
+        for entry in &self.activity {
+            let what = match entry.kind {
+                ActivityKind::Connected => "connected",
+                ActivityKind::Disconnected => "disconnected",
+                ActivityKind::Requested => "asked to connect",
+                ActivityKind::Declined => "declined",
+            };
+            let ago = format_elapsed(now.saturating_duration_since(entry.at));
+            list = list.push(text(format!("{} {what} \u{2014} {ago} ago", entry.addr)).size(12).color(TEXT_MUTED));
         }
+        list.into()
     }
 
     pub fn view(&self) -> Element<'_, HostMessage> {
@@ -100,15 +336,54 @@ impl HostState {
         let mut stop_button = button(text("Stop Hosting"))
             .style(danger_button_style)
             .padding([10, 20]);
-        if matches!(self.status, HostStatus::Active) {
+        if matches!(self.status, HostStatus::Active | HostStatus::Error(_)) {
             stop_button = stop_button.on_press(HostMessage::StopHosting);
         }
 
-        let buttons = row![copy_button, stop_button].spacing(10);
+        let mut buttons = row![copy_button, stop_button].spacing(10);
+        if crate::ui::tray::SUPPORTED {
+            buttons = buttons.push(
+                button(text("Hide to Tray"))
+                    .on_press(HostMessage::HideToTray)
+                    .style(secondary_button_style)
+                    .padding([10, 20]),
+            );
+        }
 
-        let inner = column![title, status_text, url_display, client_info, buttons]
-            .spacing(20)
+        let mut inner = column![title, status_text, url_display].spacing(20).align_x(Center);
+        if let Some(code) = &self.access_code {
+            inner = inner.push(text(format!("Access code: {}", code.expose())).size(16).color(TEXT_PRIMARY));
+        }
+        let mut inner = inner.push(self.port_form());
+        if let Some(warning) = &self.bind_warning {
+            inner = inner.push(text(warning).size(13).color(DANGER));
+        }
+        if let Some(warning) = self.firewall_warning() {
+            inner = inner.push(warning);
+        }
+        let mut inner = inner.push(client_info).push(self.activity_view());
+        if let Some(addr) = &self.control_request {
+            let request = column![
+                text(format!("{addr} wants to connect. Accepting ends the current session."))
+                    .size(14)
+                    .color(TEXT_PRIMARY),
+                row![
+                    button(text("Accept"))
+                        .on_press(HostMessage::AnswerControlRequest(true))
+                        .style(primary_button_style)
+                        .padding([6, 16]),
+                    button(text("Decline"))
+                        .on_press(HostMessage::AnswerControlRequest(false))
+                        .style(secondary_button_style)
+                        .padding([6, 16]),
+                ]
+                .spacing(10),
+            ]
+            .spacing(8)
             .align_x(Center);
+            inner = inner.push(container(request).style(url_container_style).padding([12, 16]));
+        }
+        let inner = inner.push(buttons);
 
         let card = container(inner)
             .style(card_container_style)
@@ -136,6 +411,17 @@ mod tests {
         assert!(state.connected_since.is_none());
     }
 
+    #[test]
+    fn tray_tooltip_follows_status() {
+        let mut state = HostState::new();
+        assert_eq!(state.tray_tooltip(), "Rust RDP: starting");
+        state.status = HostStatus::Active;
+        state.tunnel_url = Some("100.64.0.1:9867".to_string());
+        assert_eq!(state.tray_tooltip(), "Rust RDP: hosting at 100.64.0.1:9867");
+        state.client_connected("100.64.0.2:5000".to_string());
+        assert_eq!(state.tray_tooltip(), "Rust RDP: 100.64.0.2:5000 connected");
+    }
+
     #[test]
     fn host_state_with_address() {
         let mut state = HostState::new();
@@ -152,6 +438,56 @@ mod tests {
         assert!(matches!(state.status, HostStatus::Error(_)));
     }
 
+    #[test]
+    fn activity_tracks_sessions_and_requests() {
+        let mut state = HostState::new();
+        state.client_connected("100.64.0.1:5000".to_string());
+        state.control_requested("100.64.0.2:5001".to_string());
+        assert_eq!(state.active_connections(), 2);
+
+        state.control_answered(false);
+        state.client_disconnected();
+        assert_eq!(state.active_connections(), 0);
+        assert_eq!(state.sessions_served, 1);
+        let kinds: Vec<_> = state.activity.iter().map(|a| a.kind).collect();
+        assert_eq!(
+            kinds,
+            [ActivityKind::Disconnected, ActivityKind::Declined, ActivityKind::Requested, ActivityKind::Connected]
+        );
+
+        for _ in 0..MAX_ACTIVITY {
+            state.client_connected("100.64.0.3:5002".to_string());
+        }
+        assert_eq!(state.activity.len(), MAX_ACTIVITY);
+    }
+
+    #[test]
+    fn applying_a_new_port_restarts_hosting() {
+        let mut state = HostState::new();
+        state.status = HostStatus::Active;
+        state.tunnel_url = Some("100.64.0.1:9867".to_string());
+        state.update(HostMessage::PortChanged("9867".to_string()), 9867);
+        assert_eq!(state.update(HostMessage::ApplyPort, 9867), None);
+
+        state.update(HostMessage::PortChanged("7000".to_string()), 9867);
+        assert_eq!(state.update(HostMessage::ApplyPort, 9867), Some(HostAction::SetPort(7000)));
+        assert!(state.tunnel_url.is_none());
+        assert!(matches!(state.status, HostStatus::Starting));
+
+        assert_eq!(state.update(HostMessage::CopyUrl, 7000), None);
+    }
+
+    #[test]
+    fn port_input_validation() {
+        let mut state = HostState::new();
+        state.port_input = " 7000 ".to_string();
+        assert_eq!(state.parse_port(), Ok(7000));
+        for bad in ["0", "70000", "abc", ""] {
+            state.port_input = bad.to_string();
+            assert!(state.parse_port().is_err(), "{bad}");
+        }
+    }
+
     #[test]
     fn host_state_with_client() {
         let mut state = HostState::new();
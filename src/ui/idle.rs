@@ -0,0 +1,67 @@
+use std::time::{Duration, Instant};
+
+use iced::widget::{container, text};
+use iced::Element;
+
+use crate::config::settings::SessionIdleSettings;
+use crate::ui::theme::*;
+
+/// How long the countdown shows before the idle action is taken.
+pub const IDLE_WARNING: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleStage {
+    Active,
+    /// Seconds left before acting.
+    Warning(u64),
+    Act,
+}
+
+pub fn stage(settings: &SessionIdleSettings, last_activity: Instant, now: Instant) -> IdleStage {
+    if !settings.enabled() {
+        return IdleStage::Active;
+    }
+    let limit = Duration::from_secs(settings.minutes * 60);
+    let idle = now.saturating_duration_since(last_activity);
+    if idle >= limit {
+        IdleStage::Act
+    } else if idle + IDLE_WARNING >= limit {
+        IdleStage::Warning((limit - idle).as_secs().max(1))
+    } else {
+        IdleStage::Active
+    }
+}
+
+/// Countdown shown over the session while it is about to be locked or closed.
+pub fn warning<'a, M: 'a>(settings: &SessionIdleSettings, seconds: u64) -> Element<'a, M> {
+    let action = match (settings.lock_remote, settings.disconnect) {
+        (true, true) => "Locking the remote and disconnecting",
+        (true, false) => "Locking the remote",
+        _ => "Disconnecting",
+    };
+    container(
+        text(format!("{action} in {seconds} s for inactivity. Move the mouse or press a key to stay."))
+            .size(14)
+            .color(TEXT_PRIMARY),
+    )
+    .style(card_container_style)
+    .padding([10, 18])
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warns_for_the_last_minute() {
+        let settings = SessionIdleSettings { minutes: 5, lock_remote: true, disconnect: false };
+        let start = Instant::now();
+        assert_eq!(stage(&settings, start, start + Duration::from_secs(200)), IdleStage::Active);
+        assert_eq!(stage(&settings, start, start + Duration::from_secs(270)), IdleStage::Warning(30));
+        assert_eq!(stage(&settings, start, start + Duration::from_secs(300)), IdleStage::Act);
+
+        let off = SessionIdleSettings { minutes: 5, ..Default::default() };
+        assert_eq!(stage(&off, start, start + Duration::from_secs(900)), IdleStage::Active);
+    }
+}
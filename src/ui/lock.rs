@@ -0,0 +1,163 @@
+use iced::widget::{button, column, container, text, text_input};
+use iced::{Center, Element, Fill};
+
+use crate::config::settings::{LockSettings, MIN_PIN_LEN};
+use crate::ui::theme::*;
+
+#[derive(Debug, Clone)]
+pub enum LockMessage {
+    PinChanged(String),
+    ConfirmChanged(String),
+    Submit,
+}
+
+/// Result of submitting the lock form.
+#[derive(Debug, PartialEq)]
+pub enum LockOutcome {
+    Unlocked,
+    PinSet(String),
+}
+
+#[derive(Debug, Default)]
+pub struct LockState {
+    pub pin: String,
+    pub confirm: String,
+    pub error: Option<String>,
+}
+
+impl LockState {
+    pub fn update(&mut self, message: LockMessage, settings: &LockSettings) -> Option<LockOutcome> {
+        match message {
+            LockMessage::PinChanged(pin) => {
+                self.pin = pin.chars().filter(|c| c.is_ascii_digit()).collect();
+                None
+            }
+            LockMessage::ConfirmChanged(confirm) => {
+                self.confirm = confirm.chars().filter(|c| c.is_ascii_digit()).collect();
+                None
+            }
+            LockMessage::Submit => {
+                let outcome = self.submit(settings);
+                self.pin.clear();
+                self.confirm.clear();
+                outcome
+            }
+        }
+    }
+
+    fn submit(&mut self, settings: &LockSettings) -> Option<LockOutcome> {
+        if settings.has_pin() {
+            if settings.verify_pin(&self.pin) {
+                self.error = None;
+                return Some(LockOutcome::Unlocked);
+            }
+            self.error = Some("Wrong PIN".to_string());
+            return None;
+        }
+
+        if self.pin.len() < MIN_PIN_LEN {
+            self.error = Some(format!("PIN must be at least {MIN_PIN_LEN} digits"));
+            None
+        } else if self.pin != self.confirm {
+            self.error = Some("PINs do not match".to_string());
+            None
+        } else {
+            self.error = None;
+            Some(LockOutcome::PinSet(self.pin.clone()))
+        }
+    }
+
+    pub fn view(&self, settings: &LockSettings) -> Element<'_, LockMessage> {
+        let setting_pin = !settings.has_pin();
+        let title = if setting_pin { "Choose a PIN" } else { "Locked" };
+        let subtitle = if setting_pin {
+            "App lock is enabled. Choose a PIN to unlock with after inactivity."
+        } else {
+            "Enter your PIN to unlock. Sessions are still running."
+        };
+
+        let mut content = column![
+            text(title).size(28).color(TEXT_PRIMARY),
+            text(subtitle).size(14).color(TEXT_SECONDARY),
+            text_input("PIN", &self.pin)
+                .on_input(LockMessage::PinChanged)
+                .on_submit(LockMessage::Submit)
+                .secure(true)
+                .style(input_style)
+                .padding(10),
+        ]
+        .spacing(16)
+        .align_x(Center);
+
+        if setting_pin {
+            content = content.push(
+                text_input("Confirm PIN", &self.confirm)
+                    .on_input(LockMessage::ConfirmChanged)
+                    .on_submit(LockMessage::Submit)
+                    .secure(true)
+                    .style(input_style)
+                    .padding(10),
+            );
+        }
+        if let Some(error) = &self.error {
+            content = content.push(text(error).size(13).color(DANGER));
+        }
+        content = content.push(
+            button(if setting_pin { "Set PIN" } else { "Unlock" })
+                .on_press(LockMessage::Submit)
+                .style(primary_button_style)
+                .padding([10, 24]),
+        );
+
+        let card = container(content)
+            .style(card_container_style)
+            .padding(40)
+            .max_width(400);
+
+        container(card).center_x(Fill).center_y(Fill).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_pin_must_match_confirmation() {
+        let settings = LockSettings::default();
+        let mut state = LockState::default();
+        state.update(LockMessage::PinChanged("1234".to_string()), &settings);
+        state.update(LockMessage::ConfirmChanged("1243".to_string()), &settings);
+        assert_eq!(state.update(LockMessage::Submit, &settings), None);
+        assert!(state.error.is_some());
+
+        state.update(LockMessage::PinChanged("1234".to_string()), &settings);
+        state.update(LockMessage::ConfirmChanged("1234".to_string()), &settings);
+        assert_eq!(
+            state.update(LockMessage::Submit, &settings),
+            Some(LockOutcome::PinSet("1234".to_string()))
+        );
+    }
+
+    #[test]
+    fn unlock_requires_correct_pin() {
+        let mut settings = LockSettings::default();
+        settings.set_pin("9876").unwrap();
+        let mut state = LockState::default();
+
+        state.update(LockMessage::PinChanged("0000".to_string()), &settings);
+        assert_eq!(state.update(LockMessage::Submit, &settings), None);
+        assert_eq!(state.error.as_deref(), Some("Wrong PIN"));
+        assert!(state.pin.is_empty());
+
+        state.update(LockMessage::PinChanged("9876".to_string()), &settings);
+        assert_eq!(state.update(LockMessage::Submit, &settings), Some(LockOutcome::Unlocked));
+    }
+
+    #[test]
+    fn non_digits_are_dropped() {
+        let mut state = LockState::default();
+        state.update(LockMessage::PinChanged("12a3 4".to_string()), &LockSettings::default());
+        assert_eq!(state.pin, "1234");
+    }
+}
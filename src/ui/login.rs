@@ -1,7 +1,10 @@
-use iced::widget::{button, column, container, row, text, text_input};
+use iced::widget::{button, column, container, pick_list, row, scrollable, text, text_input};
 use iced::{Center, Element, Fill};
 
-use crate::config::ConnectionProfile;
+use crate::config::secret::SecretString;
+use crate::config::{ColorDepth, ConnectionProfile};
+use crate::config::known_hosts::KnownHost;
+use crate::input_handler::layout::LayoutChoice;
 use crate::protocol::DEFAULT_PORT;
 use crate::ui::theme::*;
 
@@ -9,8 +12,23 @@ use crate::ui::theme::*;
 pub enum LoginMessage {
     HostIpChanged(String),
     PortChanged(String),
+    AccessCodeChanged(String),
     DisplayNameChanged(String),
+    SharedFolderChanged(String),
+    ToggleShareFolder,
+    ToggleUnlock,
+    UnlockPasswordChanged(String),
+    KeyboardLayoutSelected(LayoutChoice),
+    ColorDepthSelected(ColorDepth),
+    TogglePartialUpdates,
+    ProfileSelected(usize),
+    ImportPathChanged(String),
+    Import,
+    ForgetHost(usize),
+    CopyKnownHosts,
+    TestLink,
     Connect,
+    Reconnect,
     BackToModeSelect,
 }
 
@@ -18,7 +36,28 @@ pub enum LoginMessage {
 pub struct LoginState {
     pub host_ip: String,
     pub port: String,
+    /// Code shown on the host screen; never saved with the profile.
+    pub access_code: SecretString,
     pub display_name: String,
+    pub shared_folder: String,
+    pub share_folder: bool,
+    pub unlock_enabled: bool,
+    /// New unlock password; empty keeps the one already saved for the host.
+    pub unlock_password: SecretString,
+    pub has_unlock_password: bool,
+    pub keyboard_layout: LayoutChoice,
+    pub color_depth: ColorDepth,
+    pub partial_updates: bool,
+    pub saved_profiles: Vec<ConnectionProfile>,
+    pub import_path: String,
+    pub import_status: Option<String>,
+    pub known_hosts: Vec<KnownHost>,
+    /// Result of the last link test, or a note that one is running.
+    pub link_status: Option<String>,
+    /// Host of the last session, when it can be reconnected in one click.
+    pub reconnect_to: Option<String>,
+    /// Why the host turned down the last access code, shown under the field.
+    pub access_error: Option<String>,
 }
 
 impl LoginState {
@@ -26,7 +65,30 @@ impl LoginState {
         Self {
             host_ip: String::new(),
             port: DEFAULT_PORT.to_string(),
+            access_code: SecretString::default(),
             display_name: String::new(),
+            shared_folder: String::new(),
+            share_folder: false,
+            unlock_enabled: false,
+            unlock_password: SecretString::default(),
+            has_unlock_password: false,
+            keyboard_layout: LayoutChoice::FollowSettings,
+            color_depth: ColorDepth::Full,
+            partial_updates: true,
+            saved_profiles: Vec::new(),
+            import_path: String::new(),
+            import_status: None,
+            known_hosts: Vec::new(),
+            link_status: None,
+            reconnect_to: None,
+            access_error: None,
+        }
+    }
+
+    pub fn with_profiles(profiles: Vec<ConnectionProfile>) -> Self {
+        Self {
+            saved_profiles: profiles,
+            ..Self::new()
         }
     }
 
@@ -34,23 +96,117 @@ impl LoginState {
         match msg {
             LoginMessage::HostIpChanged(s) => self.host_ip = s,
             LoginMessage::PortChanged(s) => self.port = s,
+            LoginMessage::AccessCodeChanged(s) => {
+                self.access_code = s.into();
+                self.access_error = None;
+            }
             LoginMessage::DisplayNameChanged(s) => self.display_name = s,
+            LoginMessage::SharedFolderChanged(s) => self.shared_folder = s,
+            LoginMessage::ToggleShareFolder => self.share_folder = !self.share_folder,
+            LoginMessage::ToggleUnlock => self.unlock_enabled = !self.unlock_enabled,
+            LoginMessage::UnlockPasswordChanged(s) => self.unlock_password = s.into(),
+            LoginMessage::KeyboardLayoutSelected(choice) => self.keyboard_layout = choice,
+            LoginMessage::ColorDepthSelected(depth) => self.color_depth = depth,
+            LoginMessage::TogglePartialUpdates => self.partial_updates = !self.partial_updates,
+            LoginMessage::ProfileSelected(index) => {
+                if let Some(profile) = self.saved_profiles.get(index).cloned() {
+                    self.fill(&profile);
+                }
+            }
+            LoginMessage::ImportPathChanged(s) => self.import_path = s,
             LoginMessage::Connect => {
-                if self.host_ip.is_empty() {
+                let host_ip = self.host_ip.trim();
+                if host_ip.is_empty() {
                     return None;
                 }
-                let port = self.port.parse::<u16>().unwrap_or(DEFAULT_PORT);
+                let port = self.port.trim().parse::<u16>().unwrap_or(DEFAULT_PORT);
+                let saved = self.saved_profiles.iter().find(|p| p.host_ip.eq_ignore_ascii_case(host_ip));
+                let viewer = saved.map(|p| p.viewer.clone()).unwrap_or_default();
+                let (last_good, last_failed) =
+                    saved.map(|p| (p.last_good.clone(), p.last_failed.clone())).unwrap_or_default();
+                let unlock_password = match (self.unlock_enabled, self.unlock_password.is_empty()) {
+                    (false, _) => SecretString::default(),
+                    (true, true) => saved.map(|p| p.unlock_password.clone()).unwrap_or_default(),
+                    (true, false) => self.unlock_password.clone(),
+                };
                 return Some(ConnectionProfile {
-                    host_ip: self.host_ip.clone(),
+                    host_ip: host_ip.to_string(),
                     port,
-                    display_name: self.display_name.clone(),
+                    display_name: self.display_name.trim().to_string(),
+                    enabled: true,
+                    viewer,
+                    shared_folder: self.shared_folder.trim().to_string(),
+                    share_folder: self.share_folder && !self.shared_folder.trim().is_empty(),
+                    unlock_enabled: self.unlock_enabled,
+                    unlock_password,
+                    keyboard_layout: self.keyboard_layout.to_setting(),
+                    color_depth: self.color_depth,
+                    partial_updates: self.partial_updates,
+                    last_good,
+                    last_failed,
                 });
             }
-            LoginMessage::BackToModeSelect => {}
+            LoginMessage::Import
+            | LoginMessage::ForgetHost(_)
+            | LoginMessage::CopyKnownHosts
+            | LoginMessage::TestLink
+            | LoginMessage::Reconnect
+            | LoginMessage::BackToModeSelect => {}
         }
         None
     }
 
+    /// Loads `profile` into the form. Passwords and the access code are
+    /// never put back into the fields.
+    pub fn fill(&mut self, profile: &ConnectionProfile) {
+        self.host_ip = profile.host_ip.clone();
+        self.port = profile.port.to_string();
+        self.display_name = profile.display_name.clone();
+        self.shared_folder = profile.shared_folder.clone();
+        self.share_folder = profile.share_folder;
+        self.unlock_enabled = profile.unlock_enabled;
+        self.unlock_password = SecretString::default();
+        self.has_unlock_password = !profile.unlock_password.is_empty();
+        self.keyboard_layout = LayoutChoice::from_setting(profile.keyboard_layout.as_deref());
+        self.color_depth = profile.color_depth;
+        self.partial_updates = profile.partial_updates;
+        self.access_code = SecretString::default();
+    }
+
+    pub fn input_warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.host_ip.starts_with(char::is_whitespace) {
+            warnings.push("Address starts with a space; it will be removed".to_string());
+        }
+        if let Some(ch) = self.host_ip.trim().chars().find(|c| !c.is_ascii()) {
+            warnings.push(format!(
+                "Address contains non-ASCII character '{ch}' (U+{:04X}); check for look-alike characters",
+                ch as u32
+            ));
+        }
+        let port = self.port.trim();
+        if !port.is_empty() && port.parse::<u16>().is_err() {
+            warnings.push(format!("Port \"{port}\" is invalid; {DEFAULT_PORT} will be used"));
+        }
+        let saved = self.saved_profiles.iter().find(|p| p.host_ip.eq_ignore_ascii_case(self.host_ip.trim()));
+        if let Some(saved) = saved
+            && saved.failed_with(&self.keyboard_layout.to_setting(), |r| &r.keyboard_layout)
+        {
+            let worked = saved.last_good.as_ref().map(|r| LayoutChoice::from_setting(r.keyboard_layout.as_deref()));
+            warnings.push(match worked {
+                Some(layout) => format!("The last session failed with this keyboard layout; \"{layout}\" worked"),
+                None => "The last session failed with this keyboard layout".to_string(),
+            });
+        }
+        warnings
+    }
+
+    /// The typed access code, if any.
+    pub fn access_code(&self) -> Option<SecretString> {
+        let code = self.access_code.expose().trim();
+        (!code.is_empty()).then(|| code.into())
+    }
+
     pub fn view(&self) -> Element<'_, LoginMessage> {
         let title = text("Connect to Remote").size(28).color(TEXT_PRIMARY);
 
@@ -64,12 +220,52 @@ impl LoginState {
             .style(input_style)
             .padding(10);
 
+        let code_input = text_input("Access code (if the host shows one)", self.access_code.expose())
+            .on_input(LoginMessage::AccessCodeChanged)
+            .style(input_style)
+            .padding(10);
+
         let name_input = text_input("Display Name (optional)", &self.display_name)
             .on_input(LoginMessage::DisplayNameChanged)
             .style(input_style)
             .padding(10);
 
-        let connect_button = if self.host_ip.is_empty() {
+        let folder_input = text_input("Folder to share with the host (optional)", &self.shared_folder)
+            .on_input(LoginMessage::SharedFolderChanged)
+            .style(input_style)
+            .padding(8);
+
+        let share_button = button(text(if self.share_folder { "Share: On" } else { "Share: Off" }).size(13))
+            .on_press(LoginMessage::ToggleShareFolder)
+            .style(secondary_button_style)
+            .padding([8, 16]);
+
+        let unlock_button = button(text(if self.unlock_enabled { "Unlock: On" } else { "Unlock: Off" }).size(13))
+            .on_press(LoginMessage::ToggleUnlock)
+            .style(secondary_button_style)
+            .padding([8, 16]);
+
+        let layout_row = row![
+            text("Keyboard layout").size(13).color(TEXT_SECONDARY).width(Fill),
+            pick_list(LayoutChoice::options(), Some(self.keyboard_layout.clone()), LoginMessage::KeyboardLayoutSelected)
+                .text_size(13),
+        ]
+        .spacing(10)
+        .align_y(Center);
+
+        // Cheaper frames for slow links, at some cost in quality.
+        let graphics_row = row![
+            text("Graphics").size(13).color(TEXT_SECONDARY).width(Fill),
+            pick_list(ColorDepth::ALL, Some(self.color_depth), LoginMessage::ColorDepthSelected).text_size(13),
+            button(text(if self.partial_updates { "Partial updates: On" } else { "Partial updates: Off" }).size(13))
+                .on_press(LoginMessage::TogglePartialUpdates)
+                .style(secondary_button_style)
+                .padding([8, 16]),
+        ]
+        .spacing(10)
+        .align_y(Center);
+
+        let connect_button = if self.host_ip.trim().is_empty() {
             button("Connect")
                 .style(primary_button_style)
                 .padding([12, 24])
@@ -80,19 +276,98 @@ impl LoginState {
                 .padding([12, 24])
         };
 
+        let test_button = if self.host_ip.trim().is_empty() {
+            button("Test link").style(secondary_button_style).padding([12, 24])
+        } else {
+            button("Test link")
+                .on_press(LoginMessage::TestLink)
+                .style(secondary_button_style)
+                .padding([12, 24])
+        };
+
         let back_button = button("Back")
             .on_press(LoginMessage::BackToModeSelect)
             .style(secondary_button_style)
             .padding([12, 24]);
 
-        let form = column![
-            title,
-            host_ip_input,
-            row![port_input, name_input].spacing(10),
-            row![back_button, connect_button].spacing(10),
-        ]
-        .spacing(12)
-        .align_x(Center);
+        let mut form = column![title].spacing(12).align_x(Center);
+
+        if let Some(host) = &self.reconnect_to {
+            form = form.push(
+                button(text(format!("Reconnect to {host}")).size(13))
+                    .on_press(LoginMessage::Reconnect)
+                    .style(secondary_button_style)
+                    .padding([8, 16]),
+            );
+        }
+
+        if !self.saved_profiles.is_empty() {
+            let list = self.saved_profiles.iter().enumerate().fold(
+                column![].spacing(4),
+                |col, (index, profile)| {
+                    let label = if profile.display_name.is_empty() {
+                        profile.host_ip.clone()
+                    } else {
+                        format!("{} ({})", profile.display_name, profile.host_ip)
+                    };
+                    let label = if profile.enabled {
+                        text(label).size(13).color(TEXT_PRIMARY)
+                    } else {
+                        text(format!("{label} — needs setup")).size(13).color(TEXT_MUTED)
+                    };
+                    col.push(
+                        button(label)
+                            .on_press(LoginMessage::ProfileSelected(index))
+                            .style(secondary_button_style)
+                            .width(Fill)
+                            .padding([6, 12]),
+                    )
+                },
+            );
+            form = form.push(container(scrollable(list)).max_height(160));
+        }
+
+        let import_input = text_input("~/.ssh/config or hosts.csv", &self.import_path)
+            .on_input(LoginMessage::ImportPathChanged)
+            .style(input_style)
+            .padding(8);
+
+        let import_button = button(text("Import").size(13))
+            .on_press(LoginMessage::Import)
+            .style(secondary_button_style)
+            .padding([8, 16]);
+
+        form = form
+            .push(host_ip_input)
+            .push(row![port_input, name_input].spacing(10))
+            .push(code_input);
+        if let Some(e) = &self.access_error {
+            form = form.push(text(e.as_str()).size(13).color(DANGER));
+        }
+        form = form
+            .push(row![folder_input, share_button].spacing(10).align_y(Center))
+            .push(self.unlock_row(unlock_button))
+            .push(layout_row)
+            .push(graphics_row)
+            .push(row![import_input, import_button].spacing(10).align_y(Center));
+
+        for warning in self.input_warnings() {
+            form = form.push(text(warning).size(13).color(DANGER));
+        }
+
+        if let Some(ref status) = self.import_status {
+            form = form.push(text(status.as_str()).size(13).color(TEXT_SECONDARY));
+        }
+
+        if let Some(ref status) = self.link_status {
+            form = form.push(text(status.as_str()).size(13).color(TEXT_SECONDARY));
+        }
+
+        if !self.known_hosts.is_empty() {
+            form = form.push(self.known_hosts_view());
+        }
+
+        form = form.push(row![back_button, test_button, connect_button].spacing(10));
 
         let card = container(form)
             .style(card_container_style)
@@ -104,11 +379,64 @@ impl LoginState {
             .center_y(Fill)
             .into()
     }
+
+    /// Stored lock-screen password, offered only after opting in.
+    fn unlock_row<'a>(&'a self, toggle: iced::widget::Button<'a, LoginMessage>) -> Element<'a, LoginMessage> {
+        if !self.unlock_enabled {
+            return row![
+                text("Unlock remote lock screen (your own machines only)").size(13).color(TEXT_SECONDARY).width(Fill),
+                toggle,
+            ]
+            .spacing(10)
+            .align_y(Center)
+            .into();
+        }
+        let placeholder = if self.has_unlock_password { "Saved (type to replace)" } else { "Host account password" };
+        let input = text_input(placeholder, self.unlock_password.expose())
+            .on_input(LoginMessage::UnlockPasswordChanged)
+            .secure(true)
+            .style(input_style)
+            .padding(8);
+        row![input, toggle].spacing(10).align_y(Center).into()
+    }
+
+    fn known_hosts_view(&self) -> Element<'_, LoginMessage> {
+        let header = row![
+            text("Known hosts").size(13).color(TEXT_SECONDARY).width(Fill),
+            button(text("Copy").size(12))
+                .on_press(LoginMessage::CopyKnownHosts)
+                .style(secondary_button_style)
+                .padding([4, 10]),
+        ]
+        .align_y(Center);
+
+        let list = self.known_hosts.iter().enumerate().fold(column![].spacing(4), |col, (index, entry)| {
+            let fingerprint: String = entry.key_hash.chars().take(19).collect();
+            col.push(
+                row![
+                    text(&entry.host).size(12).color(TEXT_PRIMARY).width(Fill),
+                    text(format!("{fingerprint}\u{2026}")).size(12).color(TEXT_MUTED),
+                    button(text("Forget").size(12))
+                        .on_press(LoginMessage::ForgetHost(index))
+                        .style(secondary_button_style)
+                        .padding([4, 10]),
+                ]
+                .spacing(8)
+                .align_y(Center),
+            )
+        });
+
+        column![header, container(scrollable(list)).max_height(120)]
+            .spacing(6)
+            .into()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::settings::Resolution;
+    use crate::config::{SessionRecord, ViewerPrefs};
 
     #[test]
     fn default_state() {
@@ -156,4 +484,134 @@ mod tests {
         let profile = result.unwrap();
         assert_eq!(profile.port, 12345);
     }
+
+    #[test]
+    fn connect_trims_pasted_whitespace() {
+        let mut state = LoginState::new();
+        state.host_ip = " 100.64.0.1\r\n".to_string();
+        state.port = "9867\n".to_string();
+        state.display_name = "Office \t".to_string();
+
+        let profile = state.update(LoginMessage::Connect).unwrap();
+        assert_eq!(profile.host_ip, "100.64.0.1");
+        assert_eq!(profile.port, 9867);
+        assert_eq!(profile.display_name, "Office");
+    }
+
+    #[test]
+    fn whitespace_only_host_returns_none() {
+        let mut state = LoginState::new();
+        state.host_ip = "  \n".to_string();
+        assert!(state.update(LoginMessage::Connect).is_none());
+    }
+
+    #[test]
+    fn warns_about_leading_space_and_confusables() {
+        let mut state = LoginState::new();
+        assert!(state.input_warnings().is_empty());
+
+        state.host_ip = " 100.64.0.1".to_string();
+        assert_eq!(state.input_warnings().len(), 1);
+
+        state.host_ip = "１00.64.0.1".to_string();
+        let warnings = state.input_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("U+FF11"));
+
+        state.host_ip = "100.64.0.1".to_string();
+        state.port = "98a".to_string();
+        assert_eq!(state.input_warnings().len(), 1);
+    }
+
+    #[test]
+    fn warns_about_layout_that_failed_before() {
+        let record = |layout: &str| SessionRecord {
+            resolution: Resolution::Window,
+            keyboard_layout: Some(layout.to_string()),
+        };
+        let mut state = LoginState::with_profiles(vec![ConnectionProfile {
+            host_ip: "100.64.0.5".to_string(),
+            last_good: Some(record("00000409")),
+            last_failed: Some(record("host")),
+            ..Default::default()
+        }]);
+        state.update(LoginMessage::ProfileSelected(0));
+        assert!(state.input_warnings().is_empty());
+
+        state.update(LoginMessage::KeyboardLayoutSelected(LayoutChoice::Host));
+        let warnings = state.input_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("US"));
+    }
+
+    #[test]
+    fn selecting_saved_profile_fills_form() {
+        let mut state = LoginState::with_profiles(vec![ConnectionProfile {
+            host_ip: "100.64.0.5".to_string(),
+            port: 9000,
+            display_name: "Lab".to_string(),
+            enabled: false,
+            viewer: ViewerPrefs {
+                show_stats: false,
+                ..Default::default()
+            },
+            shared_folder: "C:\\Share".to_string(),
+            share_folder: true,
+            unlock_enabled: true,
+            unlock_password: "hunter2".into(),
+            keyboard_layout: Some("00000407".to_string()),
+            color_depth: ColorDepth::Reduced,
+            partial_updates: false,
+            last_good: Some(SessionRecord {
+                resolution: Resolution::Native,
+                keyboard_layout: Some("00000407".to_string()),
+            }),
+            last_failed: None,
+        }]);
+        state.update(LoginMessage::ProfileSelected(0));
+        assert_eq!(state.host_ip, "100.64.0.5");
+        assert_eq!(state.port, "9000");
+        assert_eq!(state.display_name, "Lab");
+        assert!(state.share_folder);
+
+        let profile = state.update(LoginMessage::Connect).unwrap();
+        assert!(profile.enabled);
+        assert!(!profile.viewer.show_stats);
+        assert_eq!(profile.shared_folder, "C:\\Share");
+        assert!(profile.share_folder);
+        assert!(state.has_unlock_password && state.unlock_password.is_empty());
+        assert_eq!(profile.unlock_password.expose(), "hunter2");
+        assert_eq!(profile.keyboard_layout.as_deref(), Some("00000407"));
+        assert_eq!(profile.color_depth, ColorDepth::Reduced);
+        assert!(!profile.partial_updates);
+        assert_eq!(profile.last_good.map(|r| r.resolution), Some(Resolution::Native));
+
+        state.update(LoginMessage::ToggleUnlock);
+        assert!(!state.update(LoginMessage::Connect).unwrap().can_unlock());
+    }
+
+    #[test]
+    fn editing_the_code_clears_the_denial() {
+        let mut state = LoginState::new();
+        state.host_ip = "100.64.0.1".to_string();
+        let profile = state.update(LoginMessage::Connect).unwrap();
+        let mut state = LoginState::new();
+        state.access_code = "111111".into();
+        state.fill(&profile);
+        assert!(state.access_code.is_empty());
+        assert_eq!(state.host_ip, "100.64.0.1");
+
+        state.access_error = Some("Wrong access code".to_string());
+        state.update(LoginMessage::AccessCodeChanged("2".to_string()));
+        assert_eq!(state.access_error, None);
+    }
+
+    #[test]
+    fn sharing_needs_a_folder() {
+        let mut state = LoginState::new();
+        state.host_ip = "100.64.0.1".to_string();
+        state.update(LoginMessage::ToggleShareFolder);
+        let profile = state.update(LoginMessage::Connect).unwrap();
+        assert!(!profile.share_folder);
+    }
 }
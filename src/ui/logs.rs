@@ -0,0 +1,120 @@
+use iced::widget::{button, column, container, pick_list, row, scrollable, text};
+use iced::{Center, Element, Fill};
+
+use crate::config::settings::LogLevel;
+use crate::logging;
+use crate::ui::theme::*;
+
+/// Lines of the current log file kept on screen.
+const TAIL_LINES: usize = 500;
+
+#[derive(Debug, Clone)]
+pub enum LogsMessage {
+    LevelSelected(LogLevel),
+    Refresh,
+    OpenFolder,
+    Back,
+}
+
+/// Tails the current log file, refreshed while the screen is open.
+#[derive(Debug, Clone)]
+pub struct LogsState {
+    pub level: LogLevel,
+    pub lines: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl LogsState {
+    pub fn new(level: LogLevel) -> Self {
+        let mut state = Self { level, lines: Vec::new(), error: None };
+        state.refresh();
+        state
+    }
+
+    pub fn update(&mut self, message: LogsMessage) {
+        match message {
+            LogsMessage::LevelSelected(level) => self.level = level,
+            LogsMessage::Refresh => self.refresh(),
+            LogsMessage::OpenFolder => self.error = logging::open_folder(&logging::log_dir()).err(),
+            LogsMessage::Back => {}
+        }
+    }
+
+    fn refresh(&mut self) {
+        match logging::tail(&logging::current_log(), TAIL_LINES) {
+            Ok(lines) => {
+                self.lines = lines;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// Lines at or above the selected severity; continuation lines follow
+    /// the line they belong to.
+    fn visible(&self) -> Vec<&str> {
+        let mut shown = true;
+        self.lines
+            .iter()
+            .filter(|line| {
+                if let Some(level) = logging::line_level(line) {
+                    shown = level <= self.level;
+                }
+                shown
+            })
+            .map(|line| line.as_str())
+            .collect()
+    }
+
+    pub fn view(&self) -> Element<'_, LogsMessage> {
+        let header = row![
+            text("Logs").size(24).color(TEXT_PRIMARY).width(Fill),
+            pick_list(LogLevel::ALL, Some(self.level), LogsMessage::LevelSelected),
+            button("Open log folder")
+                .on_press(LogsMessage::OpenFolder)
+                .style(secondary_button_style)
+                .padding([8, 16]),
+            button("Back")
+                .on_press(LogsMessage::Back)
+                .style(secondary_button_style)
+                .padding([8, 16]),
+        ]
+        .spacing(12)
+        .align_y(Center);
+
+        let lines = self.visible();
+        let body: Element<'_, LogsMessage> = if lines.is_empty() {
+            text("No log lines at this level yet").size(13).color(TEXT_MUTED).into()
+        } else {
+            scrollable(text(lines.join("\n")).size(12).color(TEXT_SECONDARY).font(iced::Font::MONOSPACE))
+                .anchor_bottom()
+                .width(Fill)
+                .height(Fill)
+                .into()
+        };
+
+        let mut content = column![header].spacing(16);
+        if let Some(e) = &self.error {
+            content = content.push(text(e).size(13).color(DANGER));
+        }
+        container(content.push(container(body).style(card_container_style).padding(12).height(Fill)))
+            .padding(24)
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn continuation_lines_follow_their_level() {
+        let mut state = LogsState { level: LogLevel::Warn, lines: Vec::new(), error: None };
+        state.lines = ["t  INFO app: hello", "  detail", "t ERROR app: boom", "  cause"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(state.visible(), ["t ERROR app: boom", "  cause"]);
+        state.update(LogsMessage::LevelSelected(LogLevel::Trace));
+        assert_eq!(state.visible().len(), 4);
+    }
+}
@@ -0,0 +1,49 @@
+pub const LENS_SOURCE: u32 = 120;
+pub const LENS_ZOOM: f32 = 2.0;
+
+/// Copies a `size`×`size` RGBA square centered on (`cx`, `cy`), shifted inward at
+/// the frame edges. Frames smaller than the lens are returned clipped.
+pub fn crop_rgba(pixels: &[u8], width: u32, height: u32, cx: u32, cy: u32, size: u32) -> (Vec<u8>, u32, u32) {
+    let w = size.min(width);
+    let h = size.min(height);
+    let left = cx.saturating_sub(w / 2).min(width - w);
+    let top = cy.saturating_sub(h / 2).min(height - h);
+
+    let mut out = Vec::with_capacity((w * h * 4) as usize);
+    for y in top..top + h {
+        let start = ((y * width + left) * 4) as usize;
+        let end = start + (w * 4) as usize;
+        match pixels.get(start..end) {
+            Some(row) => out.extend_from_slice(row),
+            None => return (Vec::new(), 0, 0),
+        }
+    }
+    (out, w, h)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crop_clamps_to_edges() {
+        let width = 10;
+        let height = 10;
+        let pixels: Vec<u8> = (0..width * height).flat_map(|i| [i as u8, 0, 0, 255]).collect();
+        let (crop, w, h) = crop_rgba(&pixels, width, height, 0, 0, 4);
+        assert_eq!((w, h), (4, 4));
+        assert_eq!(crop[0], 0);
+        assert_eq!(crop.len(), 4 * 4 * 4);
+
+        let (crop, _, _) = crop_rgba(&pixels, width, height, 9, 9, 4);
+        assert_eq!(crop[0], 66);
+    }
+
+    #[test]
+    fn crop_of_small_frame_is_clipped() {
+        let pixels = vec![0u8; 2 * 2 * 4];
+        let (crop, w, h) = crop_rgba(&pixels, 2, 2, 1, 1, LENS_SOURCE);
+        assert_eq!((w, h), (2, 2));
+        assert_eq!(crop.len(), 16);
+    }
+}
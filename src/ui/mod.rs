@@ -1,7 +1,28 @@
+pub mod annotation;
+pub mod clipboard_history;
+pub mod code_prompt;
+pub mod color_adjust;
+pub mod connecting;
+pub mod crash_report;
+pub mod file_browser;
+pub mod frame_surface;
 pub mod host;
+pub mod idle;
+pub mod lock;
+pub mod logs;
 pub mod login;
+pub mod magnifier;
 pub mod mode_select;
+pub mod paste_file;
+pub mod privacy;
+pub mod redaction;
+pub mod reboot_wait;
+pub mod scaling;
+pub mod settings;
 pub mod tailscale_setup;
 pub mod theme;
+pub mod timeline;
+pub mod transfers;
+pub mod tray;
 pub mod update;
 pub mod viewer;
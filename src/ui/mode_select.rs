@@ -7,6 +7,7 @@ use crate::ui::theme::*;
 pub enum ModeSelectMessage {
     ConnectSelected,
     HostSelected,
+    OpenSettings,
 }
 
 #[derive(Debug, Clone)]
@@ -53,7 +54,12 @@ impl ModeSelectState {
             .size(12)
             .color(TEXT_MUTED);
 
-        let content = column![title, subtitle, cards, version]
+        let settings_button = button(text("Settings").size(14))
+            .on_press(ModeSelectMessage::OpenSettings)
+            .style(secondary_button_style)
+            .padding([8, 20]);
+
+        let content = column![title, subtitle, cards, settings_button, version]
             .spacing(24)
             .align_x(Center);
 
@@ -0,0 +1,121 @@
+use iced::widget::{button, column, container, progress_bar, row, text, text_input};
+use iced::{Element, Fill};
+
+use crate::network::paste::PasteProgress;
+use crate::ui::theme::*;
+
+pub const DEFAULT_NAME: &str = "pasted.txt";
+
+#[derive(Debug, Clone)]
+pub enum PasteFileMessage {
+    NameChanged(String),
+    FolderChanged(String),
+    /// Reads the local clipboard and sends it on.
+    Send,
+    Progress(PasteProgress),
+}
+
+/// Saves the local clipboard's text as a file on the host instead of typing
+/// it out, for logs and scripts too long to paste.
+#[derive(Debug, Clone)]
+pub struct PasteFileState {
+    pub name: String,
+    /// Relative to the host's desktop; remembered per host.
+    pub folder: String,
+    pub progress: Option<(u64, u64)>,
+    pub note: Option<String>,
+}
+
+impl Default for PasteFileState {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_NAME.to_string(),
+            folder: String::new(),
+            progress: None,
+            note: None,
+        }
+    }
+}
+
+impl PasteFileState {
+    pub fn sending(&self) -> bool {
+        self.progress.is_some_and(|(sent, total)| sent < total)
+    }
+
+    /// File name to send, falling back to the default when left blank.
+    pub fn file_name(&self) -> String {
+        match self.name.trim() {
+            "" => DEFAULT_NAME.to_string(),
+            name => name.to_string(),
+        }
+    }
+
+    pub fn update(&mut self, message: PasteFileMessage) {
+        match message {
+            PasteFileMessage::NameChanged(s) => self.name = s,
+            PasteFileMessage::FolderChanged(s) => self.folder = s,
+            PasteFileMessage::Send => self.note = None,
+            PasteFileMessage::Progress(PasteProgress::Sent { sent, total }) => {
+                self.progress = Some((sent, total));
+                if sent >= total {
+                    self.note = Some(format!("Sent {} to the host", self.file_name()));
+                }
+            }
+            PasteFileMessage::Progress(PasteProgress::Failed(e)) => {
+                self.progress = None;
+                self.note = Some(e);
+            }
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, PasteFileMessage> {
+        let send = button(text("Send clipboard").size(12)).style(primary_button_style).padding([4, 12]);
+        let send = if self.sending() { send } else { send.on_press(PasteFileMessage::Send) };
+
+        let mut content = column![
+            text("Paste as file").size(14).color(TEXT_PRIMARY),
+            text_input(DEFAULT_NAME, &self.name)
+                .on_input(PasteFileMessage::NameChanged)
+                .style(input_style)
+                .size(12)
+                .padding(6),
+            text_input("Folder on the host's desktop (optional)", &self.folder)
+                .on_input(PasteFileMessage::FolderChanged)
+                .style(input_style)
+                .size(12)
+                .padding(6),
+            row![text("Saves the copied text on the host").size(12).color(TEXT_MUTED).width(Fill), send]
+                .spacing(8)
+                .align_y(iced::Center),
+        ]
+        .spacing(8);
+        if let Some((sent, total)) = self.progress {
+            content = content.push(progress_bar(0.0..=total.max(1) as f32, sent as f32).girth(6).style(progress_bar_style));
+        }
+        if let Some(note) = &self.note {
+            content = content.push(text(note).size(12).color(TEXT_SECONDARY));
+        }
+        container(content)
+            .style(card_container_style)
+            .padding(12)
+            .width(360)
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_ends_with_a_note() {
+        let mut state = PasteFileState::default();
+        state.update(PasteFileMessage::NameChanged("  ".to_string()));
+        assert_eq!(state.file_name(), DEFAULT_NAME);
+        state.update(PasteFileMessage::Progress(PasteProgress::Sent { sent: 10, total: 20 }));
+        assert!(state.sending());
+        state.update(PasteFileMessage::Progress(PasteProgress::Sent { sent: 20, total: 20 }));
+        assert!(!state.sending());
+        assert_eq!(state.note.as_deref(), Some("Sent pasted.txt to the host"));
+    }
+}
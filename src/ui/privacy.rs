@@ -0,0 +1,76 @@
+use std::time::{Duration, Instant};
+
+use crate::config::settings::{PrivacyMode, PrivacySettings};
+
+/// Width of the blurred stand-in; the viewer scales it back up with linear
+/// filtering, which is what produces the blur.
+const BLUR_WIDTH: u32 = 48;
+
+pub fn obscure_mode(
+    settings: &PrivacySettings,
+    focused: bool,
+    last_activity: Instant,
+    now: Instant,
+) -> Option<PrivacyMode> {
+    if settings.mode == PrivacyMode::Off {
+        return None;
+    }
+    let unfocused = settings.on_focus_loss && !focused;
+    let idle = settings.idle_secs > 0
+        && now.saturating_duration_since(last_activity) >= Duration::from_secs(settings.idle_secs);
+    (unfocused || idle).then_some(settings.mode)
+}
+
+pub fn blurred(rgba: &[u8], width: u32, height: u32) -> Option<(Vec<u8>, u32, u32)> {
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let src = image::RgbaImage::from_raw(width, height, rgba.to_vec())?;
+    let w = BLUR_WIDTH.min(width);
+    let h = ((height as u64 * w as u64) / width as u64).max(1) as u32;
+    let small = image::imageops::thumbnail(&src, w, h);
+    Some((small.into_raw(), w, h))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(mode: PrivacyMode, on_focus_loss: bool, idle_secs: u64) -> PrivacySettings {
+        PrivacySettings {
+            mode,
+            on_focus_loss,
+            idle_secs,
+        }
+    }
+
+    #[test]
+    fn off_never_obscures() {
+        let now = Instant::now();
+        assert_eq!(obscure_mode(&settings(PrivacyMode::Off, true, 1), false, now, now), None);
+    }
+
+    #[test]
+    fn focus_loss_obscures() {
+        let now = Instant::now();
+        let s = settings(PrivacyMode::Dim, true, 0);
+        assert_eq!(obscure_mode(&s, false, now, now), Some(PrivacyMode::Dim));
+        assert_eq!(obscure_mode(&s, true, now, now), None);
+    }
+
+    #[test]
+    fn idle_timeout_obscures() {
+        let start = Instant::now();
+        let s = settings(PrivacyMode::Blur, false, 60);
+        assert_eq!(obscure_mode(&s, true, start, start + Duration::from_secs(59)), None);
+        assert_eq!(obscure_mode(&s, true, start, start + Duration::from_secs(60)), Some(PrivacyMode::Blur));
+    }
+
+    #[test]
+    fn blurred_keeps_aspect() {
+        let pixels = vec![128u8; 200 * 100 * 4];
+        let (small, w, h) = blurred(&pixels, 200, 100).unwrap();
+        assert_eq!((w, h), (BLUR_WIDTH, BLUR_WIDTH / 2));
+        assert_eq!(small.len(), (w * h * 4) as usize);
+    }
+}
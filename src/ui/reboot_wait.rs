@@ -0,0 +1,118 @@
+use std::time::{Duration, Instant};
+
+use iced::widget::{button, column, container, text};
+use iced::{Center, Element, Fill};
+
+use crate::ui::theme::*;
+
+/// How often the host's port is checked.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(3);
+/// A host still answering after this did not restart.
+const GOING_DOWN_TIMEOUT: Duration = Duration::from_secs(120);
+const REBOOT_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Clone)]
+pub enum RebootWaitMessage {
+    Tick,
+    Checked(bool),
+    Cancel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebootPhase {
+    /// The restart was sent; the host has not dropped off yet.
+    GoingDown,
+    Offline,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RebootOutcome {
+    Back,
+    GaveUp(String),
+}
+
+/// Waits for a host restarted from the viewer to come back, checking its
+/// port until it answers again.
+#[derive(Debug, Clone)]
+pub struct RebootWaitState {
+    pub host: String,
+    pub port: u16,
+    pub phase: RebootPhase,
+    pub started: Instant,
+    pub checks: u32,
+}
+
+impl RebootWaitState {
+    pub fn new(host: String, port: u16, now: Instant) -> Self {
+        Self { host, port, phase: RebootPhase::GoingDown, started: now, checks: 0 }
+    }
+
+    /// Takes the result of one port check.
+    pub fn checked(&mut self, open: bool, now: Instant) -> Option<RebootOutcome> {
+        self.checks += 1;
+        let waited = now.duration_since(self.started);
+        match (self.phase, open) {
+            (RebootPhase::GoingDown, false) => self.phase = RebootPhase::Offline,
+            (RebootPhase::GoingDown, true) if waited > GOING_DOWN_TIMEOUT => {
+                return Some(RebootOutcome::GaveUp(format!(
+                    "{} did not restart; remote restart may be off in its settings",
+                    self.host
+                )));
+            }
+            (RebootPhase::Offline, true) => return Some(RebootOutcome::Back),
+            _ => {}
+        }
+        (waited > REBOOT_TIMEOUT).then(|| {
+            RebootOutcome::GaveUp(format!(
+                "{} did not come back within {} minutes",
+                self.host,
+                REBOOT_TIMEOUT.as_secs() / 60
+            ))
+        })
+    }
+
+    pub fn view(&self) -> Element<'_, RebootWaitMessage> {
+        let status = match self.phase {
+            RebootPhase::GoingDown => "Waiting for the host to shut down\u{2026}",
+            RebootPhase::Offline => "The host is offline. Reconnecting as soon as it is back\u{2026}",
+        };
+        let elapsed = self.started.elapsed().as_secs();
+        let inner = column![
+            text(format!("Restarting {}", self.host)).size(24).color(TEXT_PRIMARY),
+            text(status).size(14).color(TEXT_SECONDARY),
+            text(format!("{}:{:02} elapsed, {} checks", elapsed / 60, elapsed % 60, self.checks))
+                .size(13)
+                .color(TEXT_MUTED),
+            text("Rust RDP has to start hosting on its own after the restart, for example from a startup shortcut with --host.")
+                .size(12)
+                .color(TEXT_MUTED),
+            button("Cancel")
+                .on_press(RebootWaitMessage::Cancel)
+                .style(secondary_button_style)
+                .padding([10, 24]),
+        ]
+        .spacing(12)
+        .align_x(Center);
+
+        let card = container(inner).style(card_container_style).padding(40).max_width(440);
+        container(card).center_x(Fill).center_y(Fill).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnects_only_after_the_host_went_away() {
+        let start = Instant::now();
+        let mut wait = RebootWaitState::new("pc".to_string(), 9000, start);
+        assert_eq!(wait.checked(true, start + Duration::from_secs(3)), None);
+        assert_eq!(wait.checked(false, start + Duration::from_secs(6)), None);
+        assert_eq!(wait.phase, RebootPhase::Offline);
+        assert_eq!(wait.checked(true, start + Duration::from_secs(60)), Some(RebootOutcome::Back));
+
+        let mut stuck = RebootWaitState::new("pc".to_string(), 9000, start);
+        assert!(matches!(stuck.checked(true, start + GOING_DOWN_TIMEOUT * 2), Some(RebootOutcome::GaveUp(_))));
+    }
+}
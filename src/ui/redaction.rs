@@ -0,0 +1,176 @@
+use iced::widget::{Space, button, column, container, image, mouse_area, responsive, row, text};
+use iced::{Center, Element, Fill, Length};
+
+use crate::recording::{Region, redact};
+use crate::ui::theme::*;
+
+/// Drags smaller than this in either direction are treated as stray clicks.
+const MIN_REGION: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedactPurpose {
+    Screenshot,
+    Recording,
+}
+
+#[derive(Debug, Clone)]
+pub enum RedactionMessage {
+    /// Pointer position in frame pixels.
+    Moved(f32, f32),
+    Pressed,
+    Released,
+    Undo,
+    Clear,
+    Confirm,
+    Cancel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedactionOutcome {
+    Confirmed,
+    Cancelled,
+}
+
+/// Lets the user blur parts of a frame before it is written to disk.
+pub struct RedactionEditor {
+    pub purpose: RedactPurpose,
+    pub width: u32,
+    pub height: u32,
+    pub regions: Vec<Region>,
+    original: Vec<u8>,
+    preview: image::Handle,
+    pointer: Option<(u32, u32)>,
+    drag_from: Option<(u32, u32)>,
+}
+
+impl RedactionEditor {
+    pub fn new(purpose: RedactPurpose, width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        let preview = image::Handle::from_rgba(width, height, pixels.clone());
+        Self { purpose, width, height, regions: Vec::new(), original: pixels, preview, pointer: None, drag_from: None }
+    }
+
+    /// The frame with every region blurred.
+    pub fn redacted(&self) -> Vec<u8> {
+        let mut pixels = self.original.clone();
+        redact(&mut pixels, self.width, self.height, &self.regions);
+        pixels
+    }
+
+    fn dragged(&self) -> Option<Region> {
+        let region = Region::between(self.drag_from?, self.pointer?);
+        (region.width >= MIN_REGION && region.height >= MIN_REGION).then_some(region)
+    }
+
+    fn refresh(&mut self) {
+        let mut pixels = self.original.clone();
+        let regions: Vec<Region> = self.regions.iter().copied().chain(self.dragged()).collect();
+        redact(&mut pixels, self.width, self.height, &regions);
+        self.preview = image::Handle::from_rgba(self.width, self.height, pixels);
+    }
+
+    pub fn update(&mut self, message: RedactionMessage) -> Option<RedactionOutcome> {
+        match message {
+            RedactionMessage::Moved(x, y) => {
+                let x = (x.max(0.0) as u32).min(self.width);
+                let y = (y.max(0.0) as u32).min(self.height);
+                self.pointer = Some((x, y));
+                if self.drag_from.is_some() {
+                    self.refresh();
+                }
+            }
+            RedactionMessage::Pressed => self.drag_from = self.pointer,
+            RedactionMessage::Released => {
+                if let Some(region) = self.dragged() {
+                    self.regions.push(region);
+                }
+                self.drag_from = None;
+                self.refresh();
+            }
+            RedactionMessage::Undo => {
+                self.regions.pop();
+                self.refresh();
+            }
+            RedactionMessage::Clear => {
+                self.regions.clear();
+                self.refresh();
+            }
+            RedactionMessage::Confirm => return Some(RedactionOutcome::Confirmed),
+            RedactionMessage::Cancel => return Some(RedactionOutcome::Cancelled),
+        }
+        None
+    }
+
+    pub fn view(&self) -> Element<'_, RedactionMessage> {
+        let (width, height) = (self.width.max(1) as f32, self.height.max(1) as f32);
+        let preview = self.preview.clone();
+        let canvas = responsive(move |size| {
+            let scale = (size.width / width).min(size.height / height).max(f32::EPSILON);
+            let frame = image(preview.clone()).width(width * scale).height(height * scale);
+            let area = mouse_area(frame)
+                .on_move(move |p| RedactionMessage::Moved(p.x / scale, p.y / scale))
+                .on_press(RedactionMessage::Pressed)
+                .on_release(RedactionMessage::Released)
+                .interaction(iced::mouse::Interaction::Crosshair);
+            container(area).center(Fill).into()
+        });
+
+        let (hint, confirm) = match self.purpose {
+            RedactPurpose::Screenshot => ("Drag over anything that should be blurred in the screenshot.", "Save Screenshot"),
+            RedactPurpose::Recording => (
+                "Drag over anything that should be blurred for the whole recording.",
+                "Start Recording",
+            ),
+        };
+        let controls = row![
+            text(format!("{} region(s)", self.regions.len())).size(13).color(TEXT_SECONDARY),
+            button(text("Undo").size(13))
+                .on_press_maybe((!self.regions.is_empty()).then_some(RedactionMessage::Undo))
+                .style(secondary_button_style)
+                .padding([6, 16]),
+            button(text("Clear").size(13))
+                .on_press_maybe((!self.regions.is_empty()).then_some(RedactionMessage::Clear))
+                .style(secondary_button_style)
+                .padding([6, 16]),
+            Space::new().width(Length::Fill),
+            button(text("Cancel").size(13))
+                .on_press(RedactionMessage::Cancel)
+                .style(secondary_button_style)
+                .padding([6, 16]),
+            button(text(confirm).size(13))
+                .on_press(RedactionMessage::Confirm)
+                .style(primary_button_style)
+                .padding([6, 16]),
+        ]
+        .spacing(12)
+        .align_y(Center);
+
+        let content = column![text(hint).size(14).color(TEXT_PRIMARY), canvas, controls].spacing(12);
+        container(content).style(card_container_style).padding(16).width(Fill).height(Fill).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drags_become_regions() {
+        let mut editor = RedactionEditor::new(RedactPurpose::Screenshot, 100, 50, vec![0; 100 * 50 * 4]);
+        let mut drag = |from: (f32, f32), to: (f32, f32)| {
+            editor.update(RedactionMessage::Moved(from.0, from.1));
+            editor.update(RedactionMessage::Pressed);
+            editor.update(RedactionMessage::Moved(to.0, to.1));
+            editor.update(RedactionMessage::Released);
+        };
+        drag((60.0, 40.0), (10.0, 10.0));
+        drag((5.0, 5.0), (6.0, 6.0));
+        drag((90.0, 10.0), (500.0, 500.0));
+        assert_eq!(
+            editor.regions,
+            [Region { x: 10, y: 10, width: 50, height: 30 }, Region { x: 90, y: 10, width: 10, height: 40 }]
+        );
+        editor.update(RedactionMessage::Undo);
+        assert_eq!(editor.regions.len(), 1);
+        assert_eq!(editor.update(RedactionMessage::Confirm), Some(RedactionOutcome::Confirmed));
+    }
+}
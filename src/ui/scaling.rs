@@ -0,0 +1,303 @@
+use iced::{Point, Rectangle, Size};
+use serde::{Deserialize, Serialize};
+
+/// How the remote frame is laid out in the viewer area.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScaleMode {
+    /// The whole frame, aspect kept, letterboxed.
+    #[default]
+    Fit,
+    /// One remote pixel per logical pixel, centered and cropped.
+    Actual,
+    /// Fills the area, ignoring the aspect ratio.
+    Stretch,
+    /// Percent of the remote size, centered and cropped.
+    Zoom(u16),
+}
+
+impl ScaleMode {
+    pub const ALL: [ScaleMode; 8] = [
+        ScaleMode::Fit,
+        ScaleMode::Actual,
+        ScaleMode::Stretch,
+        ScaleMode::Zoom(50),
+        ScaleMode::Zoom(75),
+        ScaleMode::Zoom(125),
+        ScaleMode::Zoom(150),
+        ScaleMode::Zoom(200),
+    ];
+}
+
+impl std::fmt::Display for ScaleMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScaleMode::Fit => f.write_str("Fit"),
+            ScaleMode::Actual => f.write_str("1:1"),
+            ScaleMode::Stretch => f.write_str("Stretch"),
+            ScaleMode::Zoom(percent) => write!(f, "{percent}%"),
+        }
+    }
+}
+
+/// Clockwise rotation of the frame on screen, for portrait-mounted displays.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rotation {
+    #[default]
+    None,
+    Cw90,
+    Cw180,
+    Cw270,
+}
+
+impl Rotation {
+    pub const ALL: [Rotation; 4] = [Rotation::None, Rotation::Cw90, Rotation::Cw180, Rotation::Cw270];
+
+    pub fn radians(self) -> f32 {
+        match self {
+            Rotation::None => 0.0,
+            Rotation::Cw90 => std::f32::consts::FRAC_PI_2,
+            Rotation::Cw180 => std::f32::consts::PI,
+            Rotation::Cw270 => 3.0 * std::f32::consts::FRAC_PI_2,
+        }
+    }
+
+    /// Size of a `width`x`height` frame as it is shown.
+    pub fn shown_size(self, width: u32, height: u32) -> (u32, u32) {
+        match self {
+            Rotation::Cw90 | Rotation::Cw270 => (height, width),
+            Rotation::None | Rotation::Cw180 => (width, height),
+        }
+    }
+
+    /// Maps a pixel of the shown frame back to the `width`x`height` frame.
+    pub fn to_frame(self, x: u32, y: u32, width: u32, height: u32) -> (u32, u32) {
+        let (w, h) = (width.saturating_sub(1), height.saturating_sub(1));
+        match self {
+            Rotation::None => (x, y),
+            Rotation::Cw90 => (y, h.saturating_sub(x)),
+            Rotation::Cw180 => (w.saturating_sub(x), h.saturating_sub(y)),
+            Rotation::Cw270 => (w.saturating_sub(y), x),
+        }
+    }
+
+    /// The inverse of `to_frame`.
+    pub fn to_shown(self, x: u32, y: u32, width: u32, height: u32) -> (u32, u32) {
+        let (w, h) = (width.saturating_sub(1), height.saturating_sub(1));
+        match self {
+            Rotation::None => (x, y),
+            Rotation::Cw90 => (h.saturating_sub(y), x),
+            Rotation::Cw180 => (w.saturating_sub(x), h.saturating_sub(y)),
+            Rotation::Cw270 => (y, w.saturating_sub(x)),
+        }
+    }
+
+    /// Texture coordinates (0..=1) of a point given in shown coordinates.
+    pub fn texture_uv(self, u: f32, v: f32) -> (f32, f32) {
+        match self {
+            Rotation::None => (u, v),
+            Rotation::Cw90 => (v, 1.0 - u),
+            Rotation::Cw180 => (1.0 - u, 1.0 - v),
+            Rotation::Cw270 => (1.0 - v, u),
+        }
+    }
+}
+
+impl std::fmt::Display for Rotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rotation::None => f.write_str("No rotation"),
+            Rotation::Cw90 => f.write_str("Rotate 90\u{00b0}"),
+            Rotation::Cw180 => f.write_str("Rotate 180\u{00b0}"),
+            Rotation::Cw270 => f.write_str("Rotate 270\u{00b0}"),
+        }
+    }
+}
+
+/// Rotates a small RGBA image, such as the magnifier crop, for display.
+pub fn rotate_rgba(pixels: &[u8], width: u32, height: u32, rotation: Rotation) -> (Vec<u8>, u32, u32) {
+    if rotation == Rotation::None {
+        return (pixels.to_vec(), width, height);
+    }
+    let (out_w, out_h) = rotation.shown_size(width, height);
+    let mut out = Vec::with_capacity(pixels.len());
+    for y in 0..out_h {
+        for x in 0..out_w {
+            let (fx, fy) = rotation.to_frame(x, y, width, height);
+            let i = ((fy * width + fx) * 4) as usize;
+            out.extend_from_slice(&pixels[i..i + 4]);
+        }
+    }
+    (out, out_w, out_h)
+}
+
+/// The part of the frame that is visible (`crop`, in frame pixels) and the
+/// rectangle of the viewer area it is drawn into.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub crop: Rectangle<u32>,
+    pub dest: Rectangle,
+}
+
+impl Placement {
+    /// `crop` for an image of a different size than the frame, such as the
+    /// downscaled privacy blur.
+    pub fn crop_for(&self, frame_width: u32, frame_height: u32, width: u32, height: u32) -> Rectangle<u32> {
+        let sx = |v: u32| (v as u64 * width as u64 / frame_width.max(1) as u64) as u32;
+        let sy = |v: u32| (v as u64 * height as u64 / frame_height.max(1) as u64) as u32;
+        Rectangle {
+            x: sx(self.crop.x),
+            y: sy(self.crop.y),
+            width: sx(self.crop.width).max(1),
+            height: sy(self.crop.height).max(1),
+        }
+    }
+}
+
+pub fn placement(mode: ScaleMode, area: Size, frame_width: u32, frame_height: u32) -> Option<Placement> {
+    if area.width <= 0.0 || area.height <= 0.0 || frame_width == 0 || frame_height == 0 {
+        return None;
+    }
+    let (sx, sy) = match mode {
+        ScaleMode::Fit => {
+            let s = (area.width / frame_width as f32).min(area.height / frame_height as f32);
+            (s, s)
+        }
+        ScaleMode::Actual => (1.0, 1.0),
+        ScaleMode::Stretch => (area.width / frame_width as f32, area.height / frame_height as f32),
+        ScaleMode::Zoom(percent) => {
+            let s = percent.max(1) as f32 / 100.0;
+            (s, s)
+        }
+    };
+    let (x, width, dest_x, dest_width) = place_axis(frame_width, sx, area.width);
+    let (y, height, dest_y, dest_height) = place_axis(frame_height, sy, area.height);
+    Some(Placement {
+        crop: Rectangle { x, y, width, height },
+        dest: Rectangle::new(Point::new(dest_x, dest_y), Size::new(dest_width, dest_height)),
+    })
+}
+
+/// Centers `extent` pixels drawn at `scale` in `available` space. When they do
+/// not fit, only the whole pixels that show are kept, and the destination is
+/// clamped to the area so it can be laid out with padding.
+fn place_axis(extent: u32, scale: f32, available: f32) -> (u32, u32, f32, f32) {
+    let drawn = extent as f32 * scale;
+    let offset = (available - drawn) / 2.0;
+    if offset >= 0.0 {
+        return (0, extent, offset, drawn);
+    }
+    let start = ((-offset / scale).floor() as u32).min(extent - 1);
+    let end = (((available - offset) / scale).ceil() as u32).clamp(start + 1, extent);
+    let dest = (offset + start as f32 * scale).max(0.0);
+    let size = ((end - start) as f32 * scale).min(available - dest);
+    (start, end - start, dest, size)
+}
+
+/// Maps a cursor position in the viewer area to frame pixels. Returns `None`
+/// outside the drawn frame, e.g. over letterbox bars.
+pub fn frame_point(cursor: Point, placement: &Placement) -> Option<(u32, u32)> {
+    let Placement { crop, dest } = placement;
+    if dest.width <= 0.0 || dest.height <= 0.0 {
+        return None;
+    }
+    let rx = (cursor.x - dest.x) / dest.width;
+    let ry = (cursor.y - dest.y) / dest.height;
+    if !(0.0..1.0).contains(&rx) || !(0.0..1.0).contains(&ry) {
+        return None;
+    }
+    Some((crop.x + (rx * crop.width as f32) as u32, crop.y + (ry * crop.height as f32) as u32))
+}
+
+/// The center of a frame pixel in the viewer area, or `None` when it is
+/// cropped out of view. The inverse of `frame_point`.
+pub fn area_point(x: u32, y: u32, placement: &Placement) -> Option<Point> {
+    let Placement { crop, dest } = placement;
+    if !(crop.x..crop.x + crop.width).contains(&x) || !(crop.y..crop.y + crop.height).contains(&y) {
+        return None;
+    }
+    Some(Point::new(
+        dest.x + ((x - crop.x) as f32 + 0.5) / crop.width as f32 * dest.width,
+        dest.y + ((y - crop.y) as f32 + 0.5) / crop.height as f32 * dest.height,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(mode: ScaleMode, cursor: (f32, f32), area: (f32, f32), frame: (u32, u32)) -> Option<(u32, u32)> {
+        let placement = placement(mode, Size::new(area.0, area.1), frame.0, frame.1)?;
+        frame_point(Point::new(cursor.0, cursor.1), &placement)
+    }
+
+    #[test]
+    fn fit_accounts_for_letterbox() {
+        // 200x100 frame in a 200x200 area: scale 1, 50px bars top and bottom.
+        assert_eq!(point(ScaleMode::Fit, (10.0, 60.0), (200.0, 200.0), (200, 100)), Some((10, 10)));
+        assert_eq!(point(ScaleMode::Fit, (10.0, 20.0), (200.0, 200.0), (200, 100)), None);
+        assert_eq!(point(ScaleMode::Fit, (480.0, 270.0), (960.0, 540.0), (1920, 1080)), Some((960, 540)));
+    }
+
+    #[test]
+    fn stretch_scales_axes_independently() {
+        assert_eq!(point(ScaleMode::Stretch, (100.0, 50.0), (200.0, 200.0), (200, 100)), Some((100, 25)));
+        assert_eq!(point(ScaleMode::Stretch, (0.0, 199.0), (200.0, 200.0), (200, 100)), Some((0, 99)));
+    }
+
+    #[test]
+    fn actual_size_crops_around_the_center() {
+        // 1920x1080 frame at 1:1 in 960x540: the middle quarter shows.
+        let placement = placement(ScaleMode::Actual, Size::new(960.0, 540.0), 1920, 1080).unwrap();
+        assert_eq!(placement.crop, Rectangle { x: 480, y: 270, width: 960, height: 540 });
+        assert_eq!(frame_point(Point::new(0.0, 0.0), &placement), Some((480, 270)));
+        // A small frame is centered with borders around it.
+        assert_eq!(point(ScaleMode::Actual, (10.0, 10.0), (200.0, 200.0), (100, 100)), None);
+        assert_eq!(point(ScaleMode::Actual, (60.0, 60.0), (200.0, 200.0), (100, 100)), Some((10, 10)));
+    }
+
+    #[test]
+    fn area_point_inverts_frame_point() {
+        let fit = placement(ScaleMode::Fit, Size::new(200.0, 200.0), 200, 100).unwrap();
+        let point = area_point(10, 10, &fit).unwrap();
+        assert_eq!(frame_point(point, &fit), Some((10, 10)));
+        let cropped = placement(ScaleMode::Actual, Size::new(960.0, 540.0), 1920, 1080).unwrap();
+        assert_eq!(area_point(0, 0, &cropped), None);
+    }
+
+    #[test]
+    fn zoom_maps_through_the_scale() {
+        assert_eq!(point(ScaleMode::Zoom(200), (100.0, 100.0), (200.0, 200.0), (100, 100)), Some((50, 50)));
+        assert_eq!(point(ScaleMode::Zoom(50), (30.0, 30.0), (100.0, 100.0), (100, 100)), Some((10, 10)));
+    }
+
+    #[test]
+    fn rotation_maps_shown_pixels_to_the_frame() {
+        // A 4x2 frame shown at 90 degrees is 2x4; its top-left pixel ends up top-right.
+        assert_eq!(Rotation::Cw90.shown_size(4, 2), (2, 4));
+        assert_eq!(Rotation::Cw90.to_frame(1, 0, 4, 2), (0, 0));
+        assert_eq!(Rotation::Cw270.to_frame(0, 3, 4, 2), (0, 0));
+        assert_eq!(Rotation::Cw180.to_frame(0, 0, 4, 2), (3, 1));
+        assert_eq!(Rotation::Cw90.texture_uv(1.0, 0.0), (0.0, 0.0));
+        for rotation in Rotation::ALL {
+            let (x, y) = rotation.to_shown(3, 0, 4, 2);
+            assert_eq!(rotation.to_frame(x, y, 4, 2), (3, 0));
+        }
+
+        let pixels: Vec<u8> = (0..8).flat_map(|i| [i, 0, 0, 255]).collect();
+        let (rotated, w, h) = rotate_rgba(&pixels, 4, 2, Rotation::Cw90);
+        assert_eq!((w, h), (2, 4));
+        // First shown row: frame (0,1) then (0,0).
+        assert_eq!((rotated[0], rotated[4]), (4, 0));
+    }
+
+    #[test]
+    fn crop_follows_a_smaller_image() {
+        let placement = placement(ScaleMode::Actual, Size::new(960.0, 540.0), 1920, 1080).unwrap();
+        assert_eq!(
+            placement.crop_for(1920, 1080, 480, 270),
+            Rectangle { x: 120, y: 67, width: 240, height: 135 }
+        );
+    }
+}
@@ -0,0 +1,596 @@
+use iced::widget::{button, column, container, pick_list, row, scrollable, text, text_input};
+use iced::{Center, Element, Fill};
+
+use crate::config::settings::{
+    AppSettings, CREDENTIAL_SECS, Corner, LogLevel, MAX_CLIPBOARD_MB, MAX_FPS_LIMIT, MAX_IDLE_MINUTES, MAX_TYPE_DELAY_MS, Resolution, UpdateChannel,
+};
+use crate::input_handler::layout::is_valid_klid;
+use crate::protocol::DisplayInfo;
+use crate::storage::{self, Usage};
+use crate::updater::{self, Backup};
+use crate::ui::theme::*;
+
+/// An entry in the shared display picker; `index` is `None` for all displays.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayChoice {
+    pub index: Option<u32>,
+    label: String,
+}
+
+impl std::fmt::Display for DisplayChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.label)
+    }
+}
+
+fn display_choices(displays: &[DisplayInfo], saved: Option<u32>) -> Vec<DisplayChoice> {
+    let mut choices = vec![DisplayChoice { index: None, label: "All (viewer chooses)".to_string() }];
+    choices.extend(displays.iter().enumerate().map(|(i, d)| DisplayChoice {
+        index: Some(i as u32),
+        label: format!("Display {} only ({}x{})", i + 1, d.width, d.height),
+    }));
+    if let Some(index) = saved.filter(|&i| i as usize >= displays.len()) {
+        choices.push(DisplayChoice { index: Some(index), label: format!("Display {} only (not connected)", index + 1) });
+    }
+    choices
+}
+
+#[derive(Debug, Clone)]
+pub enum SettingsMessage {
+    ResolutionSelected(Resolution),
+    KeyboardLayoutChanged(String),
+    ToggleUpdateCheck,
+    UpdateChannelSelected(UpdateChannel),
+    MaxFpsChanged(String),
+    ViewerMaxFpsChanged(String),
+    HostPortChanged(String),
+    ToggleRequireCode,
+    ToggleAllowRestart,
+    SharedDisplaySelected(DisplayChoice),
+    LogLevelSelected(LogLevel),
+    ToggleReconnectAfterRestart,
+    IdleMinutesChanged(String),
+    TypeDelayChanged(String),
+    ToggleIdleLock,
+    ToggleIdleDisconnect,
+    ClipboardImageLimitChanged(String),
+    ClipboardFilesLimitChanged(String),
+    CredentialSecsChanged(String),
+    ToggleWatermark,
+    WatermarkTextChanged(String),
+    WatermarkCornerSelected(Corner),
+    StorageScanned(Vec<Usage>),
+    CleanUp,
+    CleanedUp(u64),
+    BackupSelected(Backup),
+    RollBack,
+    ViewLogs,
+    Save,
+    Back,
+}
+
+/// Drafts of the general settings; nothing is written until Save.
+#[derive(Debug, Clone)]
+pub struct SettingsState {
+    pub resolution: Resolution,
+    pub keyboard_layout: String,
+    pub check_updates: bool,
+    pub update_channel: UpdateChannel,
+    pub max_fps: String,
+    /// Empty or 0 takes what the host sends.
+    pub viewer_max_fps: String,
+    pub host_port: String,
+    pub require_code: bool,
+    pub allow_restart: bool,
+    pub shared_display: DisplayChoice,
+    pub display_choices: Vec<DisplayChoice>,
+    pub log_level: LogLevel,
+    pub reconnect_after_restart: bool,
+    pub idle_minutes: String,
+    pub type_delay_ms: String,
+    pub idle_lock: bool,
+    pub idle_disconnect: bool,
+    pub clipboard_image_mb: String,
+    pub clipboard_files_mb: String,
+    pub credential_secs: String,
+    pub watermark: bool,
+    /// Empty names the local user.
+    pub watermark_text: String,
+    pub watermark_corner: Corner,
+    pub error: Option<String>,
+    /// Filled in by a scan started when the screen opens.
+    pub storage: Vec<Usage>,
+    pub cleaning: bool,
+    pub storage_note: Option<String>,
+    /// Earlier versions kept by the updater, newest first.
+    pub backups: Vec<Backup>,
+    pub rollback_to: Option<Backup>,
+}
+
+impl SettingsState {
+    pub fn new(settings: &AppSettings) -> Self {
+        let choices = display_choices(&[], settings.host.shared_display);
+        Self {
+            resolution: settings.general.resolution,
+            keyboard_layout: settings.general.keyboard_layout.clone(),
+            check_updates: settings.general.check_updates,
+            update_channel: settings.general.update_channel,
+            max_fps: settings.general.max_fps.to_string(),
+            viewer_max_fps: match settings.general.viewer_max_fps {
+                0 => String::new(),
+                fps => fps.to_string(),
+            },
+            host_port: settings.host.port.to_string(),
+            require_code: settings.host.require_code,
+            allow_restart: settings.host.allow_restart,
+            shared_display: choices
+                .iter()
+                .find(|c| c.index == settings.host.shared_display)
+                .cloned()
+                .unwrap_or_else(|| choices[0].clone()),
+            display_choices: choices,
+            log_level: settings.general.log_level,
+            reconnect_after_restart: settings.general.reconnect_after_restart,
+            idle_minutes: settings.idle.minutes.to_string(),
+            type_delay_ms: settings.general.type_delay_ms.to_string(),
+            idle_lock: settings.idle.lock_remote,
+            idle_disconnect: settings.idle.disconnect,
+            clipboard_image_mb: settings.clipboard.max_image_mb.to_string(),
+            clipboard_files_mb: settings.clipboard.max_files_mb.to_string(),
+            credential_secs: settings.clipboard.credential_secs.to_string(),
+            watermark: settings.recording.watermark,
+            watermark_text: settings.recording.watermark_text.clone(),
+            watermark_corner: settings.recording.watermark_corner,
+            error: None,
+            storage: Vec::new(),
+            cleaning: false,
+            storage_note: None,
+            backups: updater::list_backups()
+                .into_iter()
+                .filter(|b| b.version != env!("CARGO_PKG_VERSION"))
+                .collect(),
+            rollback_to: None,
+        }
+    }
+
+    /// Lists the host's displays in the shared display picker.
+    pub fn with_displays(mut self, displays: &[DisplayInfo]) -> Self {
+        self.display_choices = display_choices(displays, self.shared_display.index);
+        if let Some(choice) = self.display_choices.iter().find(|c| c.index == self.shared_display.index) {
+            self.shared_display = choice.clone();
+        }
+        self
+    }
+
+    pub fn update(&mut self, msg: SettingsMessage) {
+        match msg {
+            SettingsMessage::ResolutionSelected(r) => self.resolution = r,
+            SettingsMessage::KeyboardLayoutChanged(s) => self.keyboard_layout = s,
+            SettingsMessage::ToggleUpdateCheck => self.check_updates = !self.check_updates,
+            SettingsMessage::UpdateChannelSelected(channel) => self.update_channel = channel,
+            SettingsMessage::MaxFpsChanged(s) => self.max_fps = s,
+            SettingsMessage::ViewerMaxFpsChanged(s) => self.viewer_max_fps = s,
+            SettingsMessage::HostPortChanged(s) => self.host_port = s,
+            SettingsMessage::ToggleRequireCode => self.require_code = !self.require_code,
+            SettingsMessage::ToggleAllowRestart => self.allow_restart = !self.allow_restart,
+            SettingsMessage::SharedDisplaySelected(choice) => self.shared_display = choice,
+            SettingsMessage::LogLevelSelected(level) => self.log_level = level,
+            SettingsMessage::ToggleReconnectAfterRestart => {
+                self.reconnect_after_restart = !self.reconnect_after_restart;
+            }
+            SettingsMessage::IdleMinutesChanged(s) => self.idle_minutes = s,
+            SettingsMessage::TypeDelayChanged(s) => self.type_delay_ms = s,
+            SettingsMessage::ToggleIdleLock => self.idle_lock = !self.idle_lock,
+            SettingsMessage::ToggleIdleDisconnect => self.idle_disconnect = !self.idle_disconnect,
+            SettingsMessage::ClipboardImageLimitChanged(s) => self.clipboard_image_mb = s,
+            SettingsMessage::ClipboardFilesLimitChanged(s) => self.clipboard_files_mb = s,
+            SettingsMessage::CredentialSecsChanged(s) => self.credential_secs = s,
+            SettingsMessage::ToggleWatermark => self.watermark = !self.watermark,
+            SettingsMessage::WatermarkTextChanged(s) => self.watermark_text = s,
+            SettingsMessage::WatermarkCornerSelected(corner) => self.watermark_corner = corner,
+            SettingsMessage::StorageScanned(usage) => {
+                self.storage = usage;
+                return;
+            }
+            SettingsMessage::CleanUp => {
+                self.cleaning = true;
+                return;
+            }
+            SettingsMessage::CleanedUp(freed) => {
+                self.cleaning = false;
+                self.storage_note = Some(format!("Freed {}.", storage::format_size(freed)));
+                return;
+            }
+            SettingsMessage::BackupSelected(backup) => self.rollback_to = Some(backup),
+            SettingsMessage::ViewLogs | SettingsMessage::RollBack | SettingsMessage::Save | SettingsMessage::Back => {}
+        }
+        self.error = None;
+    }
+
+    /// Validates the drafts and copies them into `settings`.
+    pub fn apply(&self, settings: &mut AppSettings) -> Result<(), String> {
+        let max_fps = match self.max_fps.trim().parse::<u32>() {
+            Ok(fps) if (1..=MAX_FPS_LIMIT).contains(&fps) => fps,
+            _ => return Err(format!("Frame-rate cap must be a number from 1 to {MAX_FPS_LIMIT}")),
+        };
+        let viewer_max_fps = match self.viewer_max_fps.trim() {
+            "" => 0,
+            draft => match draft.parse::<u32>() {
+                Ok(fps) if fps <= MAX_FPS_LIMIT => fps,
+                _ => return Err(format!("Viewer frame-rate cap must be empty or a number up to {MAX_FPS_LIMIT}")),
+            },
+        };
+        let host_port = match self.host_port.trim().parse::<u16>() {
+            Ok(0) | Err(_) => return Err("Port must be a number from 1 to 65535".to_string()),
+            Ok(port) => port,
+        };
+        let clipboard_mb = |draft: &str| match draft.trim().parse::<u32>() {
+            Ok(mb) if (1..=MAX_CLIPBOARD_MB).contains(&mb) => Ok(mb),
+            _ => Err(format!("Clipboard limits must be a number of MB from 1 to {MAX_CLIPBOARD_MB}")),
+        };
+        let idle_minutes = match self.idle_minutes.trim() {
+            "" => 0,
+            draft => match draft.parse::<u64>() {
+                Ok(minutes) if minutes <= MAX_IDLE_MINUTES => minutes,
+                _ => return Err(format!("Idle minutes must be a number from 0 (off) to {MAX_IDLE_MINUTES}")),
+            },
+        };
+        let type_delay_ms = match self.type_delay_ms.trim().parse::<u64>() {
+            Ok(ms) if ms <= MAX_TYPE_DELAY_MS => ms,
+            _ => return Err(format!("Typing delay must be a number of ms from 0 to {MAX_TYPE_DELAY_MS}")),
+        };
+        let max_image_mb = clipboard_mb(&self.clipboard_image_mb)?;
+        let max_files_mb = clipboard_mb(&self.clipboard_files_mb)?;
+        let credential_secs = match self.credential_secs.trim().parse::<u16>() {
+            Ok(secs) if CREDENTIAL_SECS.contains(&secs) => secs,
+            _ => {
+                return Err(format!(
+                    "Credential clearing must be a number of seconds from {} to {}",
+                    CREDENTIAL_SECS.start(),
+                    CREDENTIAL_SECS.end()
+                ));
+            }
+        };
+        let layout = self.keyboard_layout.trim();
+        if !layout.is_empty() && layout != "host" && !is_valid_klid(layout) {
+            return Err("Keyboard layout must be empty, \"host\" or an 8-digit layout ID like 00000409".to_string());
+        }
+        settings.general.resolution = self.resolution;
+        settings.general.keyboard_layout = layout.to_string();
+        settings.general.check_updates = self.check_updates;
+        settings.general.update_channel = self.update_channel;
+        settings.general.max_fps = max_fps;
+        settings.general.viewer_max_fps = viewer_max_fps;
+        settings.general.log_level = self.log_level;
+        settings.general.reconnect_after_restart = self.reconnect_after_restart;
+        settings.idle.minutes = idle_minutes;
+        settings.general.type_delay_ms = type_delay_ms;
+        settings.idle.lock_remote = self.idle_lock;
+        settings.idle.disconnect = self.idle_disconnect;
+        settings.host.port = host_port;
+        settings.host.require_code = self.require_code;
+        settings.host.allow_restart = self.allow_restart;
+        settings.host.shared_display = self.shared_display.index;
+        settings.clipboard.max_image_mb = max_image_mb;
+        settings.clipboard.max_files_mb = max_files_mb;
+        settings.clipboard.credential_secs = credential_secs;
+        settings.recording.watermark = self.watermark;
+        settings.recording.watermark_text = self.watermark_text.trim().to_string();
+        settings.recording.watermark_corner = self.watermark_corner;
+        Ok(())
+    }
+
+    pub fn view(&self) -> Element<'_, SettingsMessage> {
+        let field = |label: &'static str, input: Element<'static, SettingsMessage>| {
+            row![text(label).size(14).color(TEXT_SECONDARY).width(170), input]
+                .spacing(12)
+                .align_y(Center)
+        };
+        let updates_label = if self.check_updates { "On" } else { "Off" };
+        let code_label = if self.require_code { "On" } else { "Off" };
+        let allow_restart_label = if self.allow_restart { "On" } else { "Off" };
+        let restart_label = if self.reconnect_after_restart { "On" } else { "Off" };
+        let idle_lock_label = if self.idle_lock { "On" } else { "Off" };
+        let idle_disconnect_label = if self.idle_disconnect { "On" } else { "Off" };
+        let watermark_label = if self.watermark { "On" } else { "Off" };
+
+        let mut form = column![
+            text("Settings").size(28).color(TEXT_PRIMARY),
+            field(
+                "Resolution",
+                pick_list(Resolution::ALL, Some(self.resolution), SettingsMessage::ResolutionSelected).into(),
+            ),
+            field(
+                "Keyboard layout",
+                text_input("This PC's layout", &self.keyboard_layout)
+                    .on_input(SettingsMessage::KeyboardLayoutChanged)
+                    .style(input_style)
+                    .padding(8)
+                    .into(),
+            ),
+            field(
+                "Check for updates",
+                button(text(updates_label).size(13))
+                    .on_press(SettingsMessage::ToggleUpdateCheck)
+                    .style(secondary_button_style)
+                    .padding([6, 16])
+                    .into(),
+            ),
+            field(
+                "Update channel",
+                pick_list(UpdateChannel::ALL, Some(self.update_channel), SettingsMessage::UpdateChannelSelected).into(),
+            ),
+            field(
+                "Frame-rate cap (host)",
+                text_input("30", &self.max_fps)
+                    .on_input(SettingsMessage::MaxFpsChanged)
+                    .style(input_style)
+                    .padding(8)
+                    .width(90)
+                    .into(),
+            ),
+            field(
+                "Frame-rate cap (viewer)",
+                text_input("Host's", &self.viewer_max_fps)
+                    .on_input(SettingsMessage::ViewerMaxFpsChanged)
+                    .style(input_style)
+                    .padding(8)
+                    .width(90)
+                    .into(),
+            ),
+            field(
+                "Host port",
+                text_input("9867", &self.host_port)
+                    .on_input(SettingsMessage::HostPortChanged)
+                    .style(input_style)
+                    .padding(8)
+                    .width(90)
+                    .into(),
+            ),
+            field(
+                "Require access code",
+                button(text(code_label).size(13))
+                    .on_press(SettingsMessage::ToggleRequireCode)
+                    .style(secondary_button_style)
+                    .padding([6, 16])
+                    .into(),
+            ),
+            field(
+                "Allow remote restart",
+                button(text(allow_restart_label).size(13))
+                    .on_press(SettingsMessage::ToggleAllowRestart)
+                    .style(secondary_button_style)
+                    .padding([6, 16])
+                    .into(),
+            ),
+            field(
+                "Shared display",
+                pick_list(
+                    self.display_choices.clone(),
+                    Some(self.shared_display.clone()),
+                    SettingsMessage::SharedDisplaySelected,
+                )
+                .into(),
+            ),
+            field(
+                "Clipboard image limit (MB)",
+                text_input("16", &self.clipboard_image_mb)
+                    .on_input(SettingsMessage::ClipboardImageLimitChanged)
+                    .style(input_style)
+                    .padding(8)
+                    .width(90)
+                    .into(),
+            ),
+            field(
+                "Clipboard files limit (MB)",
+                text_input("64", &self.clipboard_files_mb)
+                    .on_input(SettingsMessage::ClipboardFilesLimitChanged)
+                    .style(input_style)
+                    .padding(8)
+                    .width(90)
+                    .into(),
+            ),
+            field(
+                "Clear sent credentials after (s)",
+                text_input("30", &self.credential_secs)
+                    .on_input(SettingsMessage::CredentialSecsChanged)
+                    .style(input_style)
+                    .padding(8)
+                    .width(90)
+                    .into(),
+            ),
+            field(
+                "Watermark captures",
+                button(text(watermark_label).size(13))
+                    .on_press(SettingsMessage::ToggleWatermark)
+                    .style(secondary_button_style)
+                    .padding([6, 16])
+                    .into(),
+            ),
+            field(
+                "Watermark name",
+                text_input("Your user name", &self.watermark_text)
+                    .on_input(SettingsMessage::WatermarkTextChanged)
+                    .style(input_style)
+                    .padding(8)
+                    .width(200)
+                    .into(),
+            ),
+            field(
+                "Watermark corner",
+                pick_list(Corner::ALL, Some(self.watermark_corner), SettingsMessage::WatermarkCornerSelected).into(),
+            ),
+            field(
+                "Reconnect after restart",
+                button(text(restart_label).size(13))
+                    .on_press(SettingsMessage::ToggleReconnectAfterRestart)
+                    .style(secondary_button_style)
+                    .padding([6, 16])
+                    .into(),
+            ),
+            field(
+                "Type Clipboard delay (ms)",
+                text_input("30", &self.type_delay_ms)
+                    .on_input(SettingsMessage::TypeDelayChanged)
+                    .style(input_style)
+                    .padding(8)
+                    .width(90)
+                    .into(),
+            ),
+            field(
+                "Idle minutes (0 = off)",
+                text_input("0", &self.idle_minutes)
+                    .on_input(SettingsMessage::IdleMinutesChanged)
+                    .style(input_style)
+                    .padding(8)
+                    .width(90)
+                    .into(),
+            ),
+            field(
+                "Lock remote when idle",
+                button(text(idle_lock_label).size(13))
+                    .on_press(SettingsMessage::ToggleIdleLock)
+                    .style(secondary_button_style)
+                    .padding([6, 16])
+                    .into(),
+            ),
+            field(
+                "Disconnect when idle",
+                button(text(idle_disconnect_label).size(13))
+                    .on_press(SettingsMessage::ToggleIdleDisconnect)
+                    .style(secondary_button_style)
+                    .padding([6, 16])
+                    .into(),
+            ),
+            field(
+                "Log level",
+                pick_list(LogLevel::ALL, Some(self.log_level), SettingsMessage::LogLevelSelected).into(),
+            ),
+            text("Log level changes apply after a restart. Use \"host\" as the layout to keep the host's own.")
+                .size(12)
+                .color(TEXT_MUTED),
+        ]
+        .spacing(14);
+
+        form = form.push(text("Data on this computer").size(16).color(TEXT_PRIMARY));
+        for usage in &self.storage {
+            let color = if usage.over_quota() { DANGER } else { TEXT_SECONDARY };
+            form = form.push(
+                text(format!(
+                    "{}: {} of {}",
+                    usage.category.name,
+                    storage::format_size(usage.bytes),
+                    storage::format_size(usage.category.quota)
+                ))
+                .size(13)
+                .color(color),
+            );
+        }
+        let clean = button(text(if self.cleaning { "Cleaning up..." } else { "Clean up now" }).size(13))
+            .on_press_maybe((!self.cleaning).then_some(SettingsMessage::CleanUp))
+            .style(secondary_button_style)
+            .padding([6, 16]);
+        form = form.push(clean);
+        if let Some(note) = &self.storage_note {
+            form = form.push(text(note).size(12).color(TEXT_MUTED));
+        }
+        if !self.backups.is_empty() {
+            let roll_back = button(text("Roll back").size(13))
+                .on_press_maybe(self.rollback_to.as_ref().map(|_| SettingsMessage::RollBack))
+                .style(secondary_button_style)
+                .padding([6, 16]);
+            form = form.push(field(
+                "Earlier version",
+                row![
+                    pick_list(self.backups.clone(), self.rollback_to.clone(), SettingsMessage::BackupSelected)
+                        .placeholder("Choose..."),
+                    roll_back,
+                ]
+                .spacing(8)
+                .into(),
+            ));
+        }
+
+        if let Some(e) = &self.error {
+            form = form.push(text(e).size(13).color(DANGER));
+        }
+
+        let buttons = row![
+            button("Save")
+                .on_press(SettingsMessage::Save)
+                .style(primary_button_style)
+                .padding([10, 24]),
+            button("Back")
+                .on_press(SettingsMessage::Back)
+                .style(secondary_button_style)
+                .padding([10, 24]),
+            button("View logs")
+                .on_press(SettingsMessage::ViewLogs)
+                .style(secondary_button_style)
+                .padding([10, 24]),
+        ]
+        .spacing(12);
+
+        let card = container(form.push(buttons))
+            .style(card_container_style)
+            .padding(32)
+            .max_width(560);
+
+        // The form outgrows small windows.
+        container(scrollable(card)).center_x(Fill).center_y(Fill).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_validates_drafts() {
+        let mut settings = AppSettings::default();
+        let mut state = SettingsState::new(&settings);
+        state.update(SettingsMessage::MaxFpsChanged("0".to_string()));
+        assert!(state.apply(&mut settings).is_err());
+
+        state.update(SettingsMessage::MaxFpsChanged("20".to_string()));
+        state.update(SettingsMessage::ViewerMaxFpsChanged("90".to_string()));
+        assert!(state.apply(&mut settings).is_err());
+
+        state.update(SettingsMessage::ViewerMaxFpsChanged("30".to_string()));
+        state.update(SettingsMessage::KeyboardLayoutChanged("german".to_string()));
+        assert!(state.apply(&mut settings).is_err());
+
+        state.update(SettingsMessage::KeyboardLayoutChanged(" 00000407 ".to_string()));
+        state.update(SettingsMessage::HostPortChanged("7000".to_string()));
+        state.update(SettingsMessage::ToggleUpdateCheck);
+        state.update(SettingsMessage::UpdateChannelSelected(UpdateChannel::Nightly));
+        state.update(SettingsMessage::ClipboardFilesLimitChanged("100000".to_string()));
+        assert!(state.apply(&mut settings).is_err());
+
+        state.update(SettingsMessage::ClipboardFilesLimitChanged("128".to_string()));
+        state.update(SettingsMessage::IdleMinutesChanged("abc".to_string()));
+        assert!(state.apply(&mut settings).is_err());
+
+        state.update(SettingsMessage::IdleMinutesChanged("15".to_string()));
+        state.update(SettingsMessage::ToggleIdleDisconnect);
+        state.apply(&mut settings).unwrap();
+        assert_eq!(settings.idle.minutes, 15);
+        assert!(settings.idle.enabled());
+        assert_eq!(settings.clipboard.max_files_mb, 128);
+        assert_eq!(settings.general.max_fps, 20);
+        assert_eq!(settings.general.viewer_max_fps, 30);
+        assert_eq!(settings.general.keyboard_layout, "00000407");
+        assert_eq!(settings.host.port, 7000);
+        assert!(!settings.general.check_updates);
+        assert_eq!(settings.general.update_channel, UpdateChannel::Nightly);
+    }
+
+    #[test]
+    fn display_choices_keep_a_disconnected_saved_display() {
+        let displays = [DisplayInfo { x: 0, y: 0, width: 1920, height: 1080 }];
+        let indexes = |saved| display_choices(&displays, saved).into_iter().map(|c| c.index).collect::<Vec<_>>();
+        assert_eq!(indexes(None), [None, Some(0)]);
+        assert_eq!(indexes(Some(0)), [None, Some(0)]);
+        assert_eq!(indexes(Some(2)), [None, Some(0), Some(2)]);
+    }
+}
@@ -16,6 +16,12 @@ pub enum TailscaleSetupStatus {
     Checking,
 }
 
+/// What the app does for a setup message the screen cannot handle itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetupAction {
+    CheckTailscale,
+}
+
 pub struct TailscaleSetupState {
     pub status: TailscaleSetupStatus,
 }
@@ -31,6 +37,19 @@ impl TailscaleSetupState {
         }
     }
 
+    pub fn update(&mut self, msg: TailscaleSetupMessage) -> Option<SetupAction> {
+        match msg {
+            TailscaleSetupMessage::Install => {
+                crate::tailscale::open_install_page();
+                None
+            }
+            TailscaleSetupMessage::Recheck => {
+                self.status = TailscaleSetupStatus::Checking;
+                Some(SetupAction::CheckTailscale)
+            }
+        }
+    }
+
     pub fn view(&self) -> Element<'_, TailscaleSetupMessage> {
         let title = text("Tailscale Required").size(28).color(TEXT_PRIMARY);
 
@@ -108,4 +127,11 @@ mod tests {
         let state = TailscaleSetupState::new(true);
         assert!(matches!(state.status, TailscaleSetupStatus::NotRunning));
     }
+
+    #[test]
+    fn recheck_asks_for_a_check() {
+        let mut state = TailscaleSetupState::new(true);
+        assert_eq!(state.update(TailscaleSetupMessage::Recheck), Some(SetupAction::CheckTailscale));
+        assert!(matches!(state.status, TailscaleSetupStatus::Checking));
+    }
 }
@@ -0,0 +1,111 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use iced::widget::{button, column, container, row, scrollable, text};
+use iced::{Element, Fill};
+
+use crate::network::stats::format_elapsed;
+use crate::ui::theme::*;
+
+/// Oldest events are dropped past this many.
+const MAX_EVENTS: usize = 200;
+
+#[derive(Debug, Clone)]
+pub struct TimelineEvent {
+    pub since_start: Duration,
+    pub wall: SystemTime,
+    pub text: String,
+}
+
+/// Notable things that happened during a viewer session, kept for the
+/// timeline panel and the summary after the session ends.
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    started: Instant,
+    pub events: VecDeque<TimelineEvent>,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            events: VecDeque::new(),
+        }
+    }
+}
+
+impl Timeline {
+    pub fn record(&mut self, text: impl Into<String>) {
+        self.record_at(Instant::now(), SystemTime::now(), text.into());
+    }
+
+    fn record_at(&mut self, at: Instant, wall: SystemTime, text: String) {
+        if self.events.len() == MAX_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(TimelineEvent {
+            since_start: at.saturating_duration_since(self.started),
+            wall,
+            text,
+        });
+    }
+
+    /// One line per event: UTC time, offset into the session, description.
+    pub fn to_text(&self) -> String {
+        self.events.iter().map(|e| format!("{}\n", line(e))).collect()
+    }
+
+    pub fn view<'a, M: Clone + 'a>(&'a self, on_copy: M) -> Element<'a, M> {
+        let lines = self
+            .events
+            .iter()
+            .rev()
+            .fold(column![].spacing(2), |col, e| col.push(text(line(e)).size(12).color(TEXT_SECONDARY)));
+        let header = row![
+            text("Session timeline").size(14).color(TEXT_PRIMARY).width(Fill),
+            button(text("Copy").size(12))
+                .on_press(on_copy)
+                .style(secondary_button_style)
+                .padding([2, 10]),
+        ]
+        .align_y(iced::Center);
+        container(column![header, scrollable(lines).height(220)].spacing(8))
+            .style(card_container_style)
+            .padding(12)
+            .width(360)
+            .into()
+    }
+}
+
+fn line(e: &TimelineEvent) -> String {
+    format!("{} (+{}) {}", utc_clock(e.wall), format_elapsed(e.since_start), e.text)
+}
+
+fn utc_clock(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() % 86_400;
+    format!("{:02}:{:02}:{:02}Z", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_carry_utc_time_and_offset() {
+        let mut timeline = Timeline::default();
+        let start = timeline.started;
+        let wall = UNIX_EPOCH + Duration::from_secs(86_400 * 3 + 14 * 3600 + 5 * 60 + 9);
+        timeline.record_at(start + Duration::from_secs(75), wall, "Reconnecting (attempt 1/5)".to_string());
+        assert_eq!(timeline.to_text(), "14:05:09Z (+1m 15s) Reconnecting (attempt 1/5)\n");
+    }
+
+    #[test]
+    fn keeps_the_latest_events() {
+        let mut timeline = Timeline::default();
+        for i in 0..MAX_EVENTS + 5 {
+            timeline.record(format!("event {i}"));
+        }
+        assert_eq!(timeline.events.len(), MAX_EVENTS);
+        assert_eq!(timeline.events[0].text, "event 5");
+    }
+}
@@ -0,0 +1,482 @@
+use std::path::PathBuf;
+
+use iced::widget::{Column, button, column, progress_bar, row, text};
+use iced::{Center, Element, Fill};
+
+use crate::network::files::MAX_TRANSFER_BYTES;
+use crate::network::paste::{PasteProgress, PasteWriter};
+use crate::storage::format_size;
+use crate::ui::theme::*;
+
+/// A failed transfer starts over this many times before it is given up.
+pub const MAX_RETRIES: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Upload,
+    Download,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransferStatus {
+    Queued,
+    Active,
+    Paused,
+    /// Sent in full; waiting for the checksums to agree.
+    Verifying,
+    Done,
+    Failed(String),
+}
+
+impl TransferStatus {
+    fn running(&self) -> bool {
+        matches!(self, Self::Active | Self::Verifying)
+    }
+
+    fn finished(&self) -> bool {
+        matches!(self, Self::Done | Self::Failed(_))
+    }
+}
+
+pub struct Transfer {
+    pub id: u64,
+    pub direction: Direction,
+    pub name: String,
+    /// Upload: the file sent. Download: the folder it is saved in.
+    local: PathBuf,
+    /// Upload: the host folder it goes to. Download: the host's file.
+    remote: String,
+    pub done: u64,
+    pub total: u64,
+    pub attempts: u32,
+    pub status: TransferStatus,
+    /// Download side; kept while paused so it can carry on.
+    writer: Option<PasteWriter>,
+    saved: Option<PathBuf>,
+    /// The host's SHA-256 of its copy.
+    expected: Option<String>,
+}
+
+impl Transfer {
+    fn start(&mut self) -> TransferCommand {
+        self.status = TransferStatus::Active;
+        match self.direction {
+            Direction::Download => {
+                let folder = self.local.clone();
+                self.writer.get_or_insert_with(|| PasteWriter::with_root(folder, MAX_TRANSFER_BYTES));
+                TransferCommand::Download { path: self.remote.clone(), offset: self.done }
+            }
+            Direction::Upload => TransferCommand::Upload {
+                id: self.id,
+                source: self.local.clone(),
+                folder: self.remote.clone(),
+                name: self.name.clone(),
+                offset: self.done,
+            },
+        }
+    }
+
+    fn view(&self) -> Element<'_, TransfersMessage> {
+        let arrow = match self.direction {
+            Direction::Upload => "\u{2191}",
+            Direction::Download => "\u{2193}",
+        };
+        let progress = format!("{} of {}", format_size(self.done), format_size(self.total));
+        let status = match &self.status {
+            TransferStatus::Queued if self.attempts > 0 => format!("Retrying ({}/{MAX_RETRIES})", self.attempts),
+            TransferStatus::Queued => "Queued".to_string(),
+            TransferStatus::Active => progress,
+            TransferStatus::Paused => format!("Paused at {progress}"),
+            TransferStatus::Verifying => "Verifying\u{2026}".to_string(),
+            TransferStatus::Done => "Done, checksum verified".to_string(),
+            TransferStatus::Failed(e) => e.clone(),
+        };
+        let color = match self.status {
+            TransferStatus::Done => SUCCESS,
+            TransferStatus::Failed(_) => DANGER,
+            _ => TEXT_SECONDARY,
+        };
+        let action = match self.status {
+            TransferStatus::Active => Some(("Pause", TransfersMessage::Pause(self.id))),
+            TransferStatus::Paused => Some(("Resume", TransfersMessage::Resume(self.id))),
+            TransferStatus::Failed(_) => Some(("Retry", TransfersMessage::Retry(self.id))),
+            _ => None,
+        };
+
+        let mut line = row![
+            text(format!("{arrow} {}", self.name)).size(12).color(TEXT_PRIMARY).width(Fill),
+            text(status).size(11).color(color),
+        ]
+        .spacing(6)
+        .align_y(Center);
+        if let Some((label, msg)) = action {
+            line = line.push(button(text(label).size(11)).on_press(msg).style(secondary_button_style).padding([2, 8]));
+        }
+        if !self.status.running() {
+            line = line.push(
+                button(text("Remove").size(11))
+                    .on_press(TransfersMessage::Remove(self.id))
+                    .style(secondary_button_style)
+                    .padding([2, 8]),
+            );
+        }
+        let mut item = column![line].spacing(2);
+        if matches!(self.status, TransferStatus::Active | TransferStatus::Paused) {
+            item = item.push(progress_bar(0.0..=self.total.max(1) as f32, self.done as f32).girth(4).style(progress_bar_style));
+        }
+        item.into()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TransfersMessage {
+    Pause(u64),
+    Resume(u64),
+    Retry(u64),
+    Remove(u64),
+    ClearFinished,
+    UploadProgress(u64, PasteProgress),
+    /// Our copy's SHA-256, worked out after the host sent its own.
+    Hashed(u64, Result<String, String>),
+}
+
+/// What the app has to do to move the queue along.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TransferCommand {
+    Download { path: String, offset: u64 },
+    CancelDownload,
+    Upload { id: u64, source: PathBuf, folder: String, name: String, offset: u64 },
+    /// Hash `path` and report back with `Hashed`.
+    Hash { id: u64, path: PathBuf },
+    /// The browser's listing on that side is out of date.
+    Finished(Direction),
+}
+
+/// File browser transfers, run one at a time in the order they were asked
+/// for.
+#[derive(Default)]
+pub struct TransferQueue {
+    pub items: Vec<Transfer>,
+    next_id: u64,
+    /// The running upload; aborted to pause it.
+    pub upload_task: Option<iced::task::Handle>,
+}
+
+impl TransferQueue {
+    pub fn upload(&mut self, source: PathBuf, name: String, total: u64, folder: String) -> Vec<TransferCommand> {
+        self.push(Direction::Upload, name, total, source, folder)
+    }
+
+    pub fn download(&mut self, path: String, name: String, total: u64, folder: PathBuf) -> Vec<TransferCommand> {
+        self.push(Direction::Download, name, total, folder, path)
+    }
+
+    fn push(&mut self, direction: Direction, name: String, total: u64, local: PathBuf, remote: String) -> Vec<TransferCommand> {
+        self.next_id += 1;
+        self.items.push(Transfer {
+            id: self.next_id,
+            direction,
+            name,
+            local,
+            remote,
+            done: 0,
+            total,
+            attempts: 0,
+            status: TransferStatus::Queued,
+            writer: None,
+            saved: None,
+            expected: None,
+        });
+        self.start_next()
+    }
+
+    fn start_next(&mut self) -> Vec<TransferCommand> {
+        if self.items.iter().any(|t| t.status.running()) {
+            return Vec::new();
+        }
+        match self.items.iter_mut().find(|t| t.status == TransferStatus::Queued) {
+            Some(transfer) => vec![transfer.start()],
+            None => Vec::new(),
+        }
+    }
+
+    fn index(&self, id: u64) -> Option<usize> {
+        self.items.iter().position(|t| t.id == id)
+    }
+
+    fn running(&self) -> Option<usize> {
+        self.items.iter().position(|t| t.status.running())
+    }
+
+    pub fn update(&mut self, msg: TransfersMessage) -> Vec<TransferCommand> {
+        match msg {
+            TransfersMessage::Pause(id) => {
+                let Some(index) = self.index(id).filter(|&i| self.items[i].status == TransferStatus::Active) else {
+                    return Vec::new();
+                };
+                let mut commands = self.stop(index);
+                self.items[index].status = TransferStatus::Paused;
+                commands.extend(self.start_next());
+                commands
+            }
+            TransfersMessage::Resume(id) => {
+                if let Some(index) = self.index(id).filter(|&i| self.items[i].status == TransferStatus::Paused) {
+                    self.items[index].status = TransferStatus::Queued;
+                }
+                self.start_next()
+            }
+            TransfersMessage::Retry(id) => {
+                if let Some(index) = self.index(id).filter(|&i| matches!(self.items[i].status, TransferStatus::Failed(_))) {
+                    self.items[index].attempts = 0;
+                    self.items[index].status = TransferStatus::Queued;
+                }
+                self.start_next()
+            }
+            TransfersMessage::Remove(id) => {
+                if let Some(index) = self.index(id).filter(|&i| !self.items[i].status.running()) {
+                    let mut transfer = self.items.remove(index);
+                    if let Some(mut writer) = transfer.writer.take() {
+                        writer.abandon();
+                    }
+                }
+                Vec::new()
+            }
+            TransfersMessage::ClearFinished => {
+                self.items.retain(|t| !t.status.finished());
+                Vec::new()
+            }
+            TransfersMessage::UploadProgress(id, progress) => {
+                let Some(index) = self.index(id).filter(|&i| self.items[i].status == TransferStatus::Active) else {
+                    return Vec::new();
+                };
+                match progress {
+                    PasteProgress::Sent { sent, total } => {
+                        let transfer = &mut self.items[index];
+                        (transfer.done, transfer.total) = (sent, total);
+                        if sent < total {
+                            return Vec::new();
+                        }
+                        transfer.status = TransferStatus::Verifying;
+                        self.upload_task = None;
+                        self.verify(index)
+                    }
+                    PasteProgress::Failed(e) => self.fail(index, e),
+                }
+            }
+            TransfersMessage::Hashed(id, hash) => {
+                let Some(index) = self.index(id).filter(|&i| self.items[i].status == TransferStatus::Verifying) else {
+                    return Vec::new();
+                };
+                match hash {
+                    Ok(hash) if self.items[index].expected.as_deref() == Some(hash.as_str()) => {
+                        let transfer = &mut self.items[index];
+                        transfer.status = TransferStatus::Done;
+                        let mut commands = vec![TransferCommand::Finished(transfer.direction)];
+                        commands.extend(self.start_next());
+                        commands
+                    }
+                    Ok(_) => self.fail(index, "Checksum mismatch".to_string()),
+                    Err(e) => self.fail(index, e),
+                }
+            }
+        }
+    }
+
+    /// A piece of the running download.
+    pub fn chunk(&mut self, name: &str, offset: u64, total: u64, data: &[u8]) -> Vec<TransferCommand> {
+        let Some(index) = self.items.iter().position(|t| {
+            t.direction == Direction::Download && t.status == TransferStatus::Active && t.name == name
+        }) else {
+            return Vec::new();
+        };
+        let transfer = &mut self.items[index];
+        // Left over from before a pause; the resumed download sends it again.
+        if offset != transfer.done {
+            return Vec::new();
+        }
+        let Some(writer) = &mut transfer.writer else {
+            return Vec::new();
+        };
+        match writer.write("", name, offset, total, data) {
+            Ok(Some(path)) => {
+                transfer.writer = None;
+                transfer.saved = Some(path);
+                (transfer.done, transfer.total) = (total, total);
+                transfer.status = TransferStatus::Verifying;
+                self.verify(index)
+            }
+            Ok(None) => {
+                (transfer.done, transfer.total) = (offset + data.len() as u64, total);
+                Vec::new()
+            }
+            Err(e) => self.fail(index, e),
+        }
+    }
+
+    /// The host's checksum for the running transfer.
+    pub fn digest(&mut self, sha256: String) -> Vec<TransferCommand> {
+        let Some(index) = self.running() else {
+            return Vec::new();
+        };
+        self.items[index].expected = Some(sha256);
+        self.verify(index)
+    }
+
+    /// The host gave up on the running transfer.
+    pub fn host_failed(&mut self, error: String) -> Vec<TransferCommand> {
+        match self.running() {
+            Some(index) => self.fail(index, error),
+            None => Vec::new(),
+        }
+    }
+
+    /// The connection came back; the host has forgotten what it was doing.
+    pub fn reconnected(&mut self) -> Vec<TransferCommand> {
+        if let Some(index) = self.running() {
+            let transfer = &mut self.items[index];
+            match (transfer.direction, &transfer.status) {
+                // Downloads carry on from what was saved so far.
+                (Direction::Download, TransferStatus::Active) => transfer.status = TransferStatus::Queued,
+                _ => {
+                    let mut commands = self.fail(index, "Connection lost".to_string());
+                    commands.retain(|c| *c != TransferCommand::CancelDownload);
+                    return commands;
+                }
+            }
+            if let Some(task) = self.upload_task.take() {
+                task.abort();
+            }
+        }
+        self.start_next()
+    }
+
+    fn verify(&mut self, index: usize) -> Vec<TransferCommand> {
+        let transfer = &self.items[index];
+        if transfer.status != TransferStatus::Verifying || transfer.expected.is_none() {
+            return Vec::new();
+        }
+        let path = match transfer.direction {
+            Direction::Upload => transfer.local.clone(),
+            Direction::Download => match &transfer.saved {
+                Some(path) => path.clone(),
+                None => return Vec::new(),
+            },
+        };
+        vec![TransferCommand::Hash { id: transfer.id, path }]
+    }
+
+    /// Stops the running transfer where it is.
+    fn stop(&mut self, index: usize) -> Vec<TransferCommand> {
+        match self.items[index].direction {
+            Direction::Upload => {
+                if let Some(task) = self.upload_task.take() {
+                    task.abort();
+                }
+                Vec::new()
+            }
+            Direction::Download => vec![TransferCommand::CancelDownload],
+        }
+    }
+
+    /// Starts the transfer over from the beginning, or gives up on it.
+    fn fail(&mut self, index: usize, error: String) -> Vec<TransferCommand> {
+        let mut commands = if self.items[index].status == TransferStatus::Active { self.stop(index) } else { Vec::new() };
+        let transfer = &mut self.items[index];
+        if let Some(mut writer) = transfer.writer.take() {
+            writer.abandon();
+        }
+        if let Some(saved) = transfer.saved.take() {
+            let _ = std::fs::remove_file(saved);
+        }
+        transfer.done = 0;
+        transfer.expected = None;
+        transfer.attempts += 1;
+        transfer.status = if transfer.attempts > MAX_RETRIES {
+            TransferStatus::Failed(error)
+        } else {
+            TransferStatus::Queued
+        };
+        commands.extend(self.start_next());
+        commands
+    }
+
+    pub fn view(&self) -> Element<'_, TransfersMessage> {
+        let header = row![
+            text("Transfers").size(12).color(TEXT_SECONDARY).width(Fill),
+            button(text("Clear finished").size(11))
+                .on_press_maybe(self.items.iter().any(|t| t.status.finished()).then_some(TransfersMessage::ClearFinished))
+                .style(secondary_button_style)
+                .padding([2, 8]),
+        ]
+        .align_y(Center);
+        let list = Column::with_children(self.items.iter().map(Transfer::view)).spacing(4);
+        column![header, list].spacing(6).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downloads_pause_resume_and_verify() {
+        let dir = std::env::temp_dir().join(format!("rust-rdp-transfers-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut queue = TransferQueue::default();
+
+        let start = queue.download("docs/a.txt".to_string(), "a.txt".to_string(), 4, dir.clone());
+        assert_eq!(start, [TransferCommand::Download { path: "docs/a.txt".to_string(), offset: 0 }]);
+        assert!(queue.upload(dir.join("b.txt"), "b.txt".to_string(), 1, String::new()).is_empty());
+        queue.chunk("a.txt", 0, 4, b"ab");
+
+        // Pausing lets the upload behind it go first.
+        let paused = queue.update(TransfersMessage::Pause(1));
+        assert_eq!(paused[0], TransferCommand::CancelDownload);
+        assert!(matches!(paused[1], TransferCommand::Upload { id: 2, offset: 0, .. }));
+        queue.update(TransfersMessage::Resume(1));
+        assert_eq!(queue.items[0].status, TransferStatus::Queued);
+        queue.update(TransfersMessage::UploadProgress(2, PasteProgress::Sent { sent: 1, total: 1 }));
+        queue.digest("b".to_string());
+        let resumed = queue.update(TransfersMessage::Hashed(2, Ok("b".to_string())));
+        assert_eq!(
+            resumed,
+            [
+                TransferCommand::Finished(Direction::Upload),
+                TransferCommand::Download { path: "docs/a.txt".to_string(), offset: 2 },
+            ]
+        );
+
+        // A chunk sent before the pause is not written twice.
+        queue.chunk("a.txt", 0, 4, b"ab");
+        assert!(queue.chunk("a.txt", 2, 4, b"cd").is_empty());
+        assert_eq!(queue.items[0].status, TransferStatus::Verifying);
+        let saved = dir.join("a.txt");
+        assert_eq!(queue.digest("sum".to_string()), [TransferCommand::Hash { id: 1, path: saved.clone() }]);
+        assert_eq!(std::fs::read(&saved).unwrap(), b"abcd");
+
+        // A bad checksum throws the copy away and starts over.
+        assert_eq!(
+            queue.update(TransfersMessage::Hashed(1, Ok("other".to_string()))),
+            [TransferCommand::Download { path: "docs/a.txt".to_string(), offset: 0 }]
+        );
+        assert!(!saved.exists());
+        assert_eq!(queue.items[0].attempts, 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn gives_up_after_the_retries() {
+        let mut queue = TransferQueue::default();
+        queue.upload(PathBuf::from("a.bin"), "a.bin".to_string(), 10, String::new());
+        for _ in 0..MAX_RETRIES {
+            let retry = queue.update(TransfersMessage::UploadProgress(1, PasteProgress::Failed("gone".to_string())));
+            assert!(matches!(retry[..], [TransferCommand::Upload { id: 1, offset: 0, .. }]));
+        }
+        assert!(queue.host_failed("disk full".to_string()).is_empty());
+        assert_eq!(queue.items[0].status, TransferStatus::Failed("disk full".to_string()));
+
+        assert_eq!(queue.update(TransfersMessage::Retry(1)).len(), 1);
+        assert_eq!(queue.items[0].attempts, 0);
+    }
+}
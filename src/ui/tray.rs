@@ -0,0 +1,196 @@
+use std::pin::Pin;
+
+use futures::{SinkExt, Stream, StreamExt};
+
+/// Only the Windows shell has a tray this talks to.
+pub const SUPPORTED: bool = cfg!(windows);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub enum TrayEvent {
+    /// The icon was clicked: bring the window back.
+    Activate,
+    CopyUrl,
+    StopHosting,
+}
+
+/// Shows a tray icon for as long as the subscription runs. It is keyed by the
+/// tooltip, so a status change replaces the icon.
+pub fn tray_subscription(tooltip: String) -> iced::Subscription<TrayEvent> {
+    iced::Subscription::run_with(tooltip, |tooltip| tray_stream(tooltip.clone()))
+}
+
+fn tray_stream(tooltip: String) -> Pin<Box<dyn Stream<Item = TrayEvent> + Send>> {
+    Box::pin(iced::stream::channel(16, move |mut output: futures::channel::mpsc::Sender<TrayEvent>| async move {
+        let (tx, mut rx) = futures::channel::mpsc::unbounded();
+        let (ready_tx, ready_rx) = futures::channel::oneshot::channel();
+        std::thread::spawn(move || platform::run(&tooltip, tx, ready_tx));
+
+        // Closes the icon's window when the subscription is dropped.
+        let _icon = match ready_rx.await {
+            Ok(Ok(icon)) => icon,
+            Ok(Err(e)) => {
+                tracing::warn!("Tray icon unavailable: {e}");
+                std::future::pending::<()>().await;
+                return;
+            }
+            Err(_) => return,
+        };
+        while let Some(event) = rx.next().await {
+            let _ = output.send(event).await;
+        }
+        std::future::pending::<()>().await;
+    }))
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::cell::RefCell;
+
+    use futures::channel::mpsc::UnboundedSender;
+    use futures::channel::oneshot;
+    use windows_sys::Win32::Foundation::{HWND, LPARAM, LRESULT, POINT, WPARAM};
+    use windows_sys::Win32::UI::Shell::{
+        NIF_ICON, NIF_MESSAGE, NIF_TIP, NIM_ADD, NIM_DELETE, NOTIFYICONDATAW, Shell_NotifyIconW,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        AppendMenuW, CreatePopupMenu, CreateWindowExW, DefWindowProcW, DestroyMenu, DestroyWindow, DispatchMessageW,
+        GetCursorPos, GetMessageW, IDI_APPLICATION, LoadIconW, MF_STRING, MSG, PostMessageW, PostQuitMessage,
+        RegisterClassW, SetForegroundWindow, TPM_NONOTIFY, TPM_RETURNCMD, TrackPopupMenu, TranslateMessage, WM_APP,
+        WM_CLOSE, WM_DESTROY, WM_LBUTTONUP, WM_RBUTTONUP, WNDCLASSW,
+    };
+
+    use super::TrayEvent;
+
+    const WM_TRAY: u32 = WM_APP + 1;
+    const ID_COPY_URL: usize = 1;
+    const ID_STOP: usize = 2;
+
+    thread_local! {
+        static EVENTS: RefCell<Option<UnboundedSender<TrayEvent>>> = const { RefCell::new(None) };
+    }
+
+    pub struct Icon(HWND);
+
+    impl Drop for Icon {
+        fn drop(&mut self) {
+            // SAFETY: posting to a window that is already gone just fails.
+            unsafe {
+                PostMessageW(self.0, WM_CLOSE, 0, 0);
+            }
+        }
+    }
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    fn emit(event: TrayEvent) {
+        EVENTS.with(|events| {
+            if let Some(tx) = &*events.borrow() {
+                let _ = tx.unbounded_send(event);
+            }
+        });
+    }
+
+    unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        match msg {
+            WM_TRAY => match lparam as u32 {
+                WM_LBUTTONUP => emit(TrayEvent::Activate),
+                // SAFETY: called on the window's own thread with a live handle.
+                WM_RBUTTONUP => unsafe { show_menu(hwnd) },
+                _ => {}
+            },
+            // SAFETY: plain Win32 call on the window's thread.
+            WM_DESTROY => unsafe { PostQuitMessage(0) },
+            // SAFETY: forwards the arguments we were given.
+            _ => return unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+        }
+        0
+    }
+
+    unsafe fn show_menu(hwnd: HWND) {
+        let copy = wide("Copy URL");
+        let stop = wide("Stop Hosting");
+        // SAFETY: the menu and strings live until the end of this function.
+        let command = unsafe {
+            let menu = CreatePopupMenu();
+            AppendMenuW(menu, MF_STRING, ID_COPY_URL, copy.as_ptr());
+            AppendMenuW(menu, MF_STRING, ID_STOP, stop.as_ptr());
+            let mut cursor = POINT { x: 0, y: 0 };
+            GetCursorPos(&mut cursor);
+            // Without this the menu does not close when clicking elsewhere.
+            SetForegroundWindow(hwnd);
+            let command = TrackPopupMenu(menu, TPM_RETURNCMD | TPM_NONOTIFY, cursor.x, cursor.y, 0, hwnd, std::ptr::null());
+            DestroyMenu(menu);
+            command as usize
+        };
+        match command {
+            ID_COPY_URL => emit(TrayEvent::CopyUrl),
+            ID_STOP => emit(TrayEvent::StopHosting),
+            _ => {}
+        }
+    }
+
+    /// Runs on its own thread: creates a hidden window to receive the icon's
+    /// messages, adds the icon, and pumps messages until the window closes.
+    pub fn run(tooltip: &str, events: UnboundedSender<TrayEvent>, ready: oneshot::Sender<Result<Icon, String>>) {
+        EVENTS.with(|e| *e.borrow_mut() = Some(events));
+        let class = wide("RustRdpTray");
+        // SAFETY: all pointers passed below outlive the calls; the window and
+        // icon belong to this thread and are removed before it returns.
+        unsafe {
+            let wc = WNDCLASSW {
+                lpfnWndProc: Some(wnd_proc),
+                lpszClassName: class.as_ptr(),
+                ..std::mem::zeroed()
+            };
+            // Fails harmlessly when an earlier icon already registered the class.
+            RegisterClassW(&wc);
+            let hwnd = CreateWindowExW(0, class.as_ptr(), class.as_ptr(), 0, 0, 0, 0, 0, 0, 0, 0, std::ptr::null());
+            if hwnd == 0 {
+                let _ = ready.send(Err("could not create the tray window".to_string()));
+                return;
+            }
+
+            let mut data: NOTIFYICONDATAW = std::mem::zeroed();
+            data.cbSize = std::mem::size_of::<NOTIFYICONDATAW>() as u32;
+            data.hWnd = hwnd;
+            data.uID = 1;
+            data.uFlags = NIF_ICON | NIF_MESSAGE | NIF_TIP;
+            data.uCallbackMessage = WM_TRAY;
+            data.hIcon = LoadIconW(0, IDI_APPLICATION);
+            for (dst, src) in data.szTip.iter_mut().zip(tooltip.encode_utf16().take(data.szTip.len() - 1)) {
+                *dst = src;
+            }
+            if Shell_NotifyIconW(NIM_ADD, &data) == 0 {
+                DestroyWindow(hwnd);
+                let _ = ready.send(Err("the shell refused the tray icon".to_string()));
+                return;
+            }
+            // If nobody is waiting any more, the returned Icon closes the window.
+            let _ = ready.send(Ok(Icon(hwnd)));
+
+            let mut msg: MSG = std::mem::zeroed();
+            while GetMessageW(&mut msg, 0, 0, 0) > 0 {
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+            Shell_NotifyIconW(NIM_DELETE, &data);
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use futures::channel::mpsc::UnboundedSender;
+    use futures::channel::oneshot;
+
+    use super::TrayEvent;
+
+    pub struct Icon;
+
+    pub fn run(_tooltip: &str, _events: UnboundedSender<TrayEvent>, ready: oneshot::Sender<Result<Icon, String>>) {
+        let _ = ready.send(Err("the tray is only available on Windows".to_string()));
+    }
+}
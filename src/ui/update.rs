@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use iced::widget::{button, container, progress_bar, row, text, Space};
 use iced::{Center, Element, Fill, Length};
 
+use crate::config::settings::UpdateChannel;
 use crate::ui::theme::*;
 use crate::updater::{ReleaseInfo, UpdateProgress};
 
@@ -11,7 +12,8 @@ pub enum UpdateMessage {
     StartDownload,
     DownloadProgress(UpdateProgress),
     DownloadComplete(PathBuf),
-    VerifyComplete(Result<PathBuf, String>),
+    /// The verified download and the release tag it installs.
+    VerifyComplete(Result<(PathBuf, String), String>),
     ApplyAndRestart,
     Dismiss,
     Retry,
@@ -27,12 +29,82 @@ pub enum UpdateBannerState {
         total: u64,
     },
     Verifying,
-    Ready(PathBuf),
+    Ready(PathBuf, String),
     Applying,
     Error(String),
     Dismissed,
 }
 
+/// Work the banner hands back to the app.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdateAction {
+    CheckForUpdates,
+    Verify {
+        path: PathBuf,
+        checksum_url: Option<String>,
+        signature_url: Option<String>,
+        version: String,
+    },
+    /// Replace this exe with the verified download and restart.
+    Install(PathBuf, String),
+}
+
+impl UpdateBannerState {
+    pub fn update(&mut self, msg: UpdateMessage) -> Option<UpdateAction> {
+        match msg {
+            UpdateMessage::StartDownload => {
+                if let UpdateBannerState::Available(release) = self {
+                    *self = UpdateBannerState::Downloading { release: release.clone(), downloaded: 0, total: 0 };
+                }
+            }
+            UpdateMessage::Retry => {
+                *self = UpdateBannerState::Hidden;
+                return Some(UpdateAction::CheckForUpdates);
+            }
+            UpdateMessage::DownloadProgress(progress) => match progress {
+                UpdateProgress::Started { total_bytes } => {
+                    if let UpdateBannerState::Downloading { downloaded, total, .. } = self {
+                        (*downloaded, *total) = (0, total_bytes);
+                    }
+                }
+                UpdateProgress::Progress { downloaded: now, total: of } => {
+                    if let UpdateBannerState::Downloading { downloaded, total, .. } = self {
+                        (*downloaded, *total) = (now, of);
+                    }
+                }
+                UpdateProgress::Verifying => *self = UpdateBannerState::Verifying,
+                UpdateProgress::Finished(_) => {}
+                UpdateProgress::Error(e) => *self = UpdateBannerState::Error(e),
+            },
+            UpdateMessage::DownloadComplete(path) => {
+                let (checksum_url, signature_url, version) = match self {
+                    UpdateBannerState::Downloading { release, .. } => {
+                        (release.checksum_url.clone(), release.signature_url.clone(), release.version.clone())
+                    }
+                    _ => (None, None, String::new()),
+                };
+                *self = UpdateBannerState::Verifying;
+                return Some(UpdateAction::Verify { path, checksum_url, signature_url, version });
+            }
+            UpdateMessage::VerifyComplete(result) => {
+                *self = match result {
+                    Ok((path, version)) => UpdateBannerState::Ready(path, version),
+                    Err(e) => UpdateBannerState::Error(e),
+                };
+            }
+            UpdateMessage::ApplyAndRestart => {
+                if let UpdateBannerState::Ready(path, version) = self {
+                    let action = UpdateAction::Install(path.clone(), version.clone());
+                    *self = UpdateBannerState::Applying;
+                    return Some(action);
+                }
+            }
+            UpdateMessage::Dismiss => *self = UpdateBannerState::Dismissed,
+        }
+        None
+    }
+}
+
 pub fn update_banner_view(state: &UpdateBannerState) -> Element<'_, UpdateMessage> {
     match state {
         UpdateBannerState::Hidden | UpdateBannerState::Dismissed => {
@@ -40,7 +112,10 @@ pub fn update_banner_view(state: &UpdateBannerState) -> Element<'_, UpdateMessag
         }
         UpdateBannerState::Available(release) => {
             let content = row![
-                text(format!("Update {} available", release.version))
+                text(match release.channel {
+                    UpdateChannel::Stable => format!("Update {} available", release.version),
+                    channel => format!("Update {} available ({channel} channel)", release.version),
+                })
                     .size(14)
                     .color(TEXT_PRIMARY),
                 Space::new().width(Length::Fill),
@@ -104,7 +179,7 @@ pub fn update_banner_view(state: &UpdateBannerState) -> Element<'_, UpdateMessag
                 .width(Fill)
                 .into()
         }
-        UpdateBannerState::Ready(_) => {
+        UpdateBannerState::Ready(..) => {
             let content = row![
                 text("Update ready!").size(14).color(SUCCESS),
                 Space::new().width(Length::Fill),
@@ -161,3 +236,48 @@ pub fn update_banner_view(state: &UpdateBannerState) -> Element<'_, UpdateMessag
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release() -> ReleaseInfo {
+        ReleaseInfo {
+            version: "1.2.0".to_string(),
+            download_url: "https://example.invalid/rust-rdp.exe".to_string(),
+            checksum_url: None,
+            signature_url: Some("https://example.invalid/rust-rdp.exe.sig".to_string()),
+            body: String::new(),
+            channel: UpdateChannel::Stable,
+        }
+    }
+
+    #[test]
+    fn finished_download_is_verified_then_installed() {
+        let mut banner = UpdateBannerState::Available(release());
+        assert_eq!(banner.update(UpdateMessage::StartDownload), None);
+        banner.update(UpdateMessage::DownloadProgress(UpdateProgress::Progress { downloaded: 5, total: 10 }));
+        assert!(matches!(banner, UpdateBannerState::Downloading { downloaded: 5, total: 10, .. }));
+
+        let path = PathBuf::from("rust-rdp-update.exe");
+        assert_eq!(
+            banner.update(UpdateMessage::DownloadComplete(path.clone())),
+            Some(UpdateAction::Verify {
+                path: path.clone(),
+                checksum_url: None,
+                signature_url: release().signature_url,
+                version: "1.2.0".to_string(),
+            })
+        );
+        // Restarting before verification finishes does nothing.
+        assert_eq!(banner.update(UpdateMessage::ApplyAndRestart), None);
+        assert!(matches!(banner, UpdateBannerState::Verifying));
+
+        banner.update(UpdateMessage::VerifyComplete(Ok((path.clone(), "1.2.0".to_string()))));
+        assert_eq!(
+            banner.update(UpdateMessage::ApplyAndRestart),
+            Some(UpdateAction::Install(path, "1.2.0".to_string()))
+        );
+        assert!(matches!(banner, UpdateBannerState::Applying));
+    }
+}
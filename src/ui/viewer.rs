@@ -1,49 +1,476 @@
 use std::collections::VecDeque;
 use std::time::Instant;
 
-use iced::widget::{button, column, container, image, mouse_area, row, text};
-use iced::{Color, Element, Fill};
+use bytes::{Bytes, BytesMut};
+use iced::widget::{
+    Space, button, column, container, image, mouse_area, pick_list, responsive, row, shader, slider, stack, text, text_input,
+    tooltip,
+};
+use iced::{Color, ContentFit, Element, Fill, Padding, Point, Size};
 
+use crate::capture::damage;
+use crate::capture::display_scale::boost_label;
+use crate::config::ViewerPrefs;
+use crate::config::secret::SecretString;
+use crate::config::settings::{PrivacyMode, PrivacySettings, Resolution, SessionIdleSettings};
+use crate::input_handler::combo::KeyCombo;
+use crate::input_handler::translate::{iced_key_to_keycode, mouse_button_to_protocol, unmapped_text};
+use crate::network::paste::PasteProgress;
+use crate::network::stats::{self, SessionStats};
+use crate::protocol::{CursorImage, DirtyRect, DisplayInfo, HostInfo, ProtocolMessage, PROTOCOL_VERSION};
+use crate::recording::RecordingClock;
+use crate::tailscale::PeerPath;
+use crate::ui::annotation::{AnnotationTool, Annotations};
+use crate::ui::clipboard_history::ClipboardHistory;
+use crate::ui::code_prompt::{CodePromptMessage, CodePromptState};
+use crate::ui::color_adjust::{self, ColorAdjust, BRIGHTNESS_RANGE, CONTRAST_RANGE, GAMMA_RANGE};
+use crate::ui::frame_surface::{FrameDamage, FrameSurface};
+use crate::ui::idle::{self, IdleStage};
+use crate::ui::magnifier::{self, LENS_SOURCE, LENS_ZOOM};
+use crate::ui::file_browser::{BrowserRequest, FileBrowserMessage, FileBrowserState};
+use crate::ui::paste_file::{PasteFileMessage, PasteFileState};
+use crate::ui::transfers::{TransferCommand, TransferQueue, TransfersMessage};
+use crate::ui::privacy;
+use crate::ui::redaction::{RedactPurpose, RedactionEditor, RedactionMessage, RedactionOutcome};
+use crate::ui::scaling::{self, Placement, Rotation, ScaleMode};
+use crate::ui::timeline::Timeline;
 use crate::ui::theme::*;
 
 #[derive(Debug, Clone)]
 pub enum ViewerMessage {
-    MouseMoved(iced::Point),
+    MouseMoved(iced::Point, Option<(u16, u16)>),
+    MouseLeft,
     MousePressed(iced::mouse::Button),
     MouseReleased(iced::mouse::Button),
-    MouseWheel(f32),
+    /// Horizontal and vertical scroll amounts.
+    MouseWheel(f32, f32),
     KeyPressed(iced::keyboard::Key),
     KeyReleased(iced::keyboard::Key),
+    ToggleStats,
+    /// Turned on while typing a secret into the remote.
+    TogglePasswordMode,
+    CodePrompt(CodePromptMessage),
+    ToggleColorPanel,
+    ToggleMagnifier,
+    ScaleModeSelected(ScaleMode),
+    RotationSelected(Rotation),
+    ResolutionSelected(Resolution),
+    TogglePresentation,
+    ToggleFullscreen,
+    RevealToolbar(bool),
+    SendKeys(KeyCombo),
+    UnlockRemote,
+    /// Arms the restart button; a second press restarts the host.
+    RestartHost,
+    SelectDisplay(u32),
+    ToggleRecording,
+    PauseRecording,
+    Screenshot,
+    ScreenshotSaved(Result<std::path::PathBuf, String>),
+    Redaction(RedactionMessage),
+    ToggleTimeline,
+    CopyTimeline,
+    ToggleClipboardHistory,
+    /// Copies a snippet from the clipboard history again.
+    Recopy(usize),
+    TogglePasteFile,
+    PasteFile(PasteFileMessage),
+    ToggleFileBrowser,
+    FileBrowser(FileBrowserMessage),
+    Transfers(TransfersMessage),
+    ToggleCredential,
+    CredentialChanged(String),
+    SendCredential,
+    /// Types the local clipboard's text into the session.
+    TypeClipboard,
+    TypingProgress(PasteProgress),
+    RecordingTick,
+    CycleAnnotationTool,
+    ClearAnnotations,
+    AnnotationTick,
+    IdleTick,
+    ToggleGreyscale,
+    ToggleBandwidthSaver,
+    CycleTextBoost,
+    BrightnessChanged(f32),
+    ContrastChanged(f32),
+    GammaChanged(f32),
+    ResetColor,
     Disconnect,
 }
 
+/// Work the viewer hands back to the app: anything that needs the
+/// connection, the recorder, the window or app settings.
+pub enum ViewerAction {
+    /// Goes to the host on the control path.
+    Send(ProtocolMessage),
+    /// Input for the host, sent in order.
+    Input(Vec<ProtocolMessage>),
+    /// A pointer move; dropped when the input queue is full, since the next
+    /// one carries the newer position.
+    MovePointer { x: u16, y: u16 },
+    /// Ask for frames at the rate and quality for this bandwidth saver state.
+    Delivery(bool),
+    Greyscale(bool),
+    /// The session sat idle too long.
+    Idle { lock_remote: bool, disconnect: bool },
+    TogglePresentation,
+    Fullscreen(bool),
+    StopRecording,
+    StartRecording(RedactionEditor),
+    SaveScreenshot(RedactionEditor),
+    /// Hand the current frame to the recorder.
+    RecordFrame,
+    /// Keep the log down to warnings while a secret is typed.
+    PasswordMode(bool),
+    Copy(String),
+    ReadClipboardForPaste,
+    ReadClipboardForTyping,
+    Transfers(Vec<TransferCommand>),
+    SendCredential(SecretString),
+    UnlockRemote,
+    /// `changed` is false when the pick matches the current resolution.
+    SelectResolution { resolution: Resolution, changed: bool },
+    RestartHost,
+    Disconnect,
+    /// Connect again with the code typed into the prompt.
+    RetryWithCode(SecretString),
+    /// The prompt was dismissed; carries the host's reason.
+    CancelCodePrompt(String),
+}
+
+/// Maps a cursor position in the viewer area to the host's pointer coordinates,
+/// which span the whole shared screen as 0..=u16::MAX on each axis regardless of
+/// the resolution frames are streamed at. `placement` is of the frame as rotated.
+fn pointer_position(
+    cursor: Point,
+    placement: Option<Placement>,
+    rotation: Rotation,
+    frame_width: u32,
+    frame_height: u32,
+) -> Option<(u16, u16)> {
+    let (x, y) = scaling::frame_point(cursor, &placement?)?;
+    let (x, y) = rotation.to_frame(x, y, frame_width, frame_height);
+    Some((
+        normalize_axis(x, frame_width),
+        normalize_axis(y, frame_height),
+    ))
+}
+
+/// Explains a black or frozen frame while the host shows a UAC prompt or its
+/// lock screen, which capture from the user's session cannot see.
+fn secure_desktop_notice<'a>(can_unlock: bool) -> Element<'a, ViewerMessage> {
+    let mut fixes = vec![
+        "Ask someone at the host to answer the prompt",
+        "Run Rust RDP on the host as administrator so its own prompts stay visible",
+        "Or turn off \"Switch to the secure desktop when prompting for elevation\" in the host's security policy",
+    ];
+    if can_unlock {
+        fixes.push("If the host is locked, use Unlock remote");
+    }
+    let fixes = fixes
+        .into_iter()
+        .fold(column![].spacing(4), |col, fix| col.push(text(format!("\u{2022} {fix}")).size(13).color(TEXT_SECONDARY)));
+    container(
+        column![
+            text("The host is showing a secure screen").size(18).color(TEXT_PRIMARY),
+            text("A UAC prompt or the lock screen can't be streamed; the picture resumes once it closes.")
+                .size(13)
+                .color(TEXT_SECONDARY),
+            fixes,
+        ]
+        .spacing(10),
+    )
+    .style(card_container_style)
+    .padding([16, 28])
+    .max_width(520)
+    .into()
+}
+
+/// The inverse of `normalize_axis`.
+fn denormalize_axis(value: u16, extent: u32) -> u32 {
+    (value as u64 * extent.saturating_sub(1) as u64 / u16::MAX as u64) as u32
+}
+
+fn normalize_axis(value: u32, extent: u32) -> u16 {
+    if extent <= 1 {
+        return 0;
+    }
+    ((value.min(extent - 1) as u64 * u16::MAX as u64) / (extent - 1) as u64) as u16
+}
+
+/// How the pointer looks over the frame.
+#[derive(Debug, Clone, Default)]
+pub enum RemoteCursor {
+    /// The host has not sent a shape; the local pointer shows.
+    #[default]
+    Local,
+    /// The host hides its pointer, for example while typing.
+    Hidden,
+    Image { handle: image::Handle, width: f32, height: f32, hot_x: f32, hot_y: f32 },
+}
+
+impl RemoteCursor {
+    pub fn from_shape(shape: Option<CursorImage>) -> Self {
+        match shape {
+            Some(shape) if shape.rgba.len() == shape.width as usize * shape.height as usize * 4 => RemoteCursor::Image {
+                width: shape.width as f32,
+                height: shape.height as f32,
+                hot_x: shape.hot_x as f32,
+                hot_y: shape.hot_y as f32,
+                handle: image::Handle::from_rgba(shape.width as u32, shape.height as u32, shape.rgba),
+            },
+            Some(_) => RemoteCursor::Local,
+            None => RemoteCursor::Hidden,
+        }
+    }
+}
+
 pub struct ViewerState {
     pub frame_width: u32,
     pub frame_height: u32,
-    pub frame_pixels: Vec<u8>,
+    pub frame_pixels: Bytes,
+    /// Handle over `frame_pixels` for the presentation window and the
+    /// obscured views; the live view draws through `FrameSurface`.
+    frame: image::Handle,
+    damage: FrameDamage,
     frame_times: VecDeque<Instant>,
     pub fps: f32,
     pub latency_ms: Option<u64>,
+    /// Direct or relayed over Tailscale, once checked.
+    pub path: Option<PeerPath>,
+    pub session: Option<SessionStats>,
+    pub show_stats: bool,
+    /// A secret is being typed: recording skips frames and sent clipboard
+    /// text stays out of the history.
+    pub password_mode: bool,
+    pub show_color_panel: bool,
+    pub greyscale: bool,
+    pub bandwidth_saver: bool,
+    pub scale_mode: ScaleMode,
+    pub rotation: Rotation,
+    /// What the host is asked to stream; changed live from the toolbar.
+    pub resolution: Resolution,
+    pub text_boost: u8,
+    pub magnifier: bool,
+    pub fullscreen: bool,
+    pub toolbar_revealed: bool,
+    pub cursor: Option<Point>,
+    /// Where the person at the host last pointed, shown as a ghost cursor
+    /// until this viewer moves the pointer itself.
+    pub host_cursor: Option<(u16, u16)>,
+    /// The host's pointer image, drawn in place of the local pointer.
+    pub remote_cursor: RemoteCursor,
+    pub reconnecting: Option<(u32, u32)>,
+    /// This machine is going to sleep; cleared when the session reconnects.
+    pub suspended: bool,
+    /// The host is on its secure desktop, so the frame is black or frozen.
+    pub secure_desktop: bool,
+    pub waiting_for_host: bool,
+    /// What the host runs; stays `None` with hosts before protocol 9.
+    pub host_info: Option<HostInfo>,
+    /// The host refused the access code; the session has ended until a new one is sent.
+    pub code_prompt: Option<CodePromptState>,
+    /// The profile opted in to typing a stored password at the lock screen.
+    pub can_unlock: bool,
+    pub restart_armed: bool,
+    /// Host monitors; empty when it only has one.
+    pub displays: Vec<DisplayInfo>,
+    pub display: u32,
+    pub recording: Option<RecordingClock>,
+    /// Where the last recording went, or why it stopped.
+    pub recording_note: Option<String>,
+    pub timeline: Timeline,
+    pub show_timeline: bool,
+    pub clipboard_history: ClipboardHistory,
+    pub show_clipboard_history: bool,
+    pub paste_file: PasteFileState,
+    pub show_paste_file: bool,
+    /// Open while the Files panel is shown.
+    pub file_browser: Option<FileBrowserState>,
+    /// Keeps going while the Files panel is hidden.
+    pub transfers: TransferQueue,
+    /// The Send Credential prompt's masked draft, while it is open.
+    pub credential: Option<SecretString>,
+    /// Open before a screenshot is saved or a recording starts.
+    pub redaction: Option<RedactionEditor>,
+    /// Characters typed and in total while Type Clipboard runs.
+    pub typing: Option<(u64, u64)>,
+    pub privacy: PrivacySettings,
+    pub idle: SessionIdleSettings,
+    /// Seconds left before the idle action, while the countdown shows.
+    pub idle_warning: Option<u64>,
+    /// The idle action was taken; it is not repeated until the next input.
+    pub idle_acted: bool,
+    pub focused: bool,
+    pub last_activity: Instant,
+    pub annotations: Annotations,
+    viewport: Option<(u32, u32)>,
+    color: ColorAdjust,
+    color_lut: Option<Box<[u8; 256]>>,
 }
 
 impl ViewerState {
     pub fn new(width: u32, height: u32) -> Self {
-        let size = (width * height * 4) as usize;
+        let frame_pixels = Bytes::from(vec![0; (width * height * 4) as usize]);
         Self {
             frame_width: width,
             frame_height: height,
-            frame_pixels: vec![0; size],
+            frame: image::Handle::from_rgba(width, height, frame_pixels.clone()),
+            frame_pixels,
+            damage: FrameDamage::default(),
             frame_times: VecDeque::new(),
             fps: 0.0,
             latency_ms: None,
+            path: None,
+            session: None,
+            show_stats: true,
+            password_mode: false,
+            show_color_panel: false,
+            greyscale: false,
+            bandwidth_saver: false,
+            scale_mode: ScaleMode::Fit,
+            rotation: Rotation::None,
+            resolution: Resolution::default(),
+            text_boost: 0,
+            magnifier: false,
+            fullscreen: false,
+            toolbar_revealed: false,
+            cursor: None,
+            host_cursor: None,
+            remote_cursor: RemoteCursor::Local,
+            reconnecting: None,
+            suspended: false,
+            secure_desktop: false,
+            waiting_for_host: false,
+            host_info: None,
+            code_prompt: None,
+            can_unlock: false,
+            restart_armed: false,
+            displays: Vec::new(),
+            display: 0,
+            recording: None,
+            recording_note: None,
+            timeline: Timeline::default(),
+            show_timeline: false,
+            clipboard_history: ClipboardHistory::default(),
+            show_clipboard_history: false,
+            paste_file: PasteFileState::default(),
+            show_paste_file: false,
+            file_browser: None,
+            transfers: TransferQueue::default(),
+            credential: None,
+            redaction: None,
+            typing: None,
+            privacy: PrivacySettings::default(),
+            idle: SessionIdleSettings::default(),
+            idle_warning: None,
+            idle_acted: false,
+            focused: true,
+            last_activity: Instant::now(),
+            annotations: Annotations::default(),
+            viewport: None,
+            color: ColorAdjust::default(),
+            color_lut: None,
         }
     }
 
-    pub fn update_frame(&mut self, width: u32, height: u32, pixels: Vec<u8>) {
+    pub fn with_prefs(mut self, prefs: &ViewerPrefs) -> Self {
+        self.show_stats = prefs.show_stats;
+        self.greyscale = prefs.greyscale;
+        self.bandwidth_saver = prefs.bandwidth_saver;
+        self.fullscreen = prefs.fullscreen;
+        self.scale_mode = prefs.scale;
+        self.rotation = prefs.rotation;
+        self.paste_file.folder = prefs.paste_folder.clone();
+        self.set_color(prefs.color);
+        self
+    }
+
+    pub fn prefs(&self) -> ViewerPrefs {
+        ViewerPrefs {
+            show_stats: self.show_stats,
+            color: self.color,
+            greyscale: self.greyscale,
+            scale: self.scale_mode,
+            rotation: self.rotation,
+            paste_folder: self.paste_file.folder.trim().to_string(),
+            bandwidth_saver: self.bandwidth_saver,
+            fullscreen: self.fullscreen,
+        }
+    }
+
+    pub fn color(&self) -> ColorAdjust {
+        self.color
+    }
+
+    pub fn set_color(&mut self, color: ColorAdjust) {
+        self.color = color;
+        self.color_lut = (!color.is_identity()).then(|| Box::new(color.lut()));
+    }
+
+    /// Records the physical size of the viewer window, returning it when it changed.
+    pub fn set_viewport(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let size = (width, height);
+        if width == 0 || height == 0 || self.viewport == Some(size) {
+            return None;
+        }
+        self.viewport = Some(size);
+        Some(size)
+    }
+
+    pub fn viewport(&self) -> Option<(u32, u32)> {
+        self.viewport
+    }
+
+    pub fn reset_viewport(&mut self) {
+        self.viewport = None;
+    }
+
+    pub fn update_frame(&mut self, width: u32, height: u32, mut pixels: Vec<u8>) {
+        if let Some(lut) = &self.color_lut {
+            color_adjust::apply_lut(&mut pixels, lut);
+        }
+        let first_frame = (self.frame_width, self.frame_height) == (1, 1);
+        if (width, height) != (self.frame_width, self.frame_height) && !first_frame {
+            self.timeline.record(format!("Resolution changed to {width}x{height}"));
+        }
         self.frame_width = width;
         self.frame_height = height;
-        self.frame_pixels = pixels;
+        self.frame_pixels = Bytes::from(pixels);
+        self.frame = image::Handle::from_rgba(width, height, self.frame_pixels.clone());
+        self.damage.full();
+        self.count_frame();
+    }
 
+    /// Composites a partial update into the retained frame. Updates that don't
+    /// fit the current frame are dropped; the next full frame repairs it.
+    pub fn update_region(&mut self, width: u32, height: u32, rect: DirtyRect, mut pixels: Vec<u8>) {
+        let fits = (width, height) == (self.frame_width, self.frame_height)
+            && rect.x + rect.width <= width
+            && rect.y + rect.height <= height
+            && pixels.len() == (rect.width * rect.height * 4) as usize;
+        if !fits {
+            return;
+        }
+        if let Some(lut) = &self.color_lut {
+            color_adjust::apply_lut(&mut pixels, lut);
+        }
+
+        // Release the handle's reference first so the buffer can usually be
+        // reused in place rather than copied.
+        self.frame = image::Handle::from_rgba(0, 0, Bytes::new());
+        let mut buffer = std::mem::take(&mut self.frame_pixels)
+            .try_into_mut()
+            .unwrap_or_else(|shared| BytesMut::from(&shared[..]));
+        damage::blit(&mut buffer, width, rect, &pixels);
+        self.frame_pixels = buffer.freeze();
+        self.frame = image::Handle::from_rgba(width, height, self.frame_pixels.clone());
+        self.damage.region(rect);
+        self.count_frame();
+    }
+
+    fn count_frame(&mut self) {
         let now = Instant::now();
         self.frame_times.push_back(now);
         while let Some(&front) = self.frame_times.front() {
@@ -56,31 +483,926 @@ impl ViewerState {
         self.fps = self.frame_times.len() as f32;
     }
 
+    /// Why some features may not work with this host, if it is older.
+    pub fn host_compat_note(&self) -> Option<String> {
+        match &self.host_info {
+            Some(info) if info.protocol < PROTOCOL_VERSION => Some(format!(
+                "Host speaks protocol {}, this viewer {PROTOCOL_VERSION}; update it for all features",
+                info.protocol
+            )),
+            Some(_) => None,
+            // Stats start a second in, long after a current host has sent its info.
+            None if self.session.is_some() => Some("Host runs an older version; some features may not work".to_string()),
+            None => None,
+        }
+    }
+
     pub fn update_latency(&mut self, rtt_ms: u64) {
         self.latency_ms = Some(rtt_ms);
     }
 
-    pub fn view(&self) -> Element<'_, ViewerMessage> {
-        let handle = image::Handle::from_rgba(
-            self.frame_width,
-            self.frame_height,
-            self.frame_pixels.clone(),
-        );
-
-        let image_widget = image(handle).width(Fill).height(Fill);
-
-        let viewer_area = mouse_area(image_widget)
-            .on_press(ViewerMessage::MousePressed(iced::mouse::Button::Left))
-            .on_release(ViewerMessage::MouseReleased(iced::mouse::Button::Left))
-            .on_move(ViewerMessage::MouseMoved)
-            .on_scroll(|delta| {
-                let y = match delta {
-                    iced::mouse::ScrollDelta::Lines { y, .. } => y,
-                    iced::mouse::ScrollDelta::Pixels { y, .. } => y,
+    pub fn update_stats(&mut self, stats: SessionStats) {
+        self.session = Some(stats);
+    }
+
+    /// Whether keystrokes typed now reach the host. System shortcuts (Alt+Tab,
+    /// the Windows key, Ctrl+Alt+Del) are never grabbed and always act locally.
+    pub fn forwards_keys(&self) -> bool {
+        self.focused && self.reconnecting.is_none() && !self.waiting_for_host
+    }
+
+    fn keyboard_indicator(&self) -> Element<'_, ViewerMessage> {
+        let label = if self.forwards_keys() {
+            text("Keyboard \u{2192} host").size(13).color(SUCCESS)
+        } else {
+            text("Keyboard: local").size(13).color(TEXT_MUTED)
+        };
+        tooltip(
+            container(label).padding([4, 8]),
+            container(
+                text("System shortcuts such as Alt+Tab and the Windows key stay on this machine. Use Send keys to send them.")
+                    .size(12)
+                    .color(TEXT_PRIMARY),
+            )
+            .style(card_container_style)
+            .padding(8)
+            .max_width(280),
+            tooltip::Position::Bottom,
+        )
+        .into()
+    }
+
+    /// Takes the host's monitor list, returning the display to request again
+    /// when an earlier choice (from before a reconnect) is still valid.
+    pub fn set_displays(&mut self, displays: Vec<DisplayInfo>) -> Option<u32> {
+        self.displays = displays;
+        if (self.display as usize) < self.displays.len() && self.display != 0 {
+            Some(self.display)
+        } else {
+            self.display = 0;
+            None
+        }
+    }
+
+    fn recording_controls(&self) -> Element<'_, ViewerMessage> {
+        let Some(clock) = &self.recording else {
+            let record = row![
+                button("Screenshot")
+                    .on_press_maybe(self.redaction.is_none().then_some(ViewerMessage::Screenshot))
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+                button("Record")
+                    .on_press_maybe(self.redaction.is_none().then_some(ViewerMessage::ToggleRecording))
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+            ]
+            .spacing(4);
+            return match &self.recording_note {
+                Some(note) => row![record, text(note).size(12).color(TEXT_MUTED)]
+                    .spacing(6)
+                    .align_y(iced::Center)
+                    .into(),
+                None => record.into(),
+            };
+        };
+        let elapsed = stats::format_elapsed(clock.elapsed(Instant::now()));
+        row![
+            button(text(format!("\u{25a0} REC {elapsed}")))
+                .on_press(ViewerMessage::ToggleRecording)
+                .style(danger_button_style)
+                .padding([4, 12]),
+            button(if clock.is_paused() { "Resume" } else { "Pause" })
+                .on_press(ViewerMessage::PauseRecording)
+                .style(secondary_button_style)
+                .padding([4, 12]),
+        ]
+        .spacing(4)
+        .into()
+    }
+
+    fn display_tabs(&self) -> Element<'_, ViewerMessage> {
+        self.displays
+            .iter()
+            .enumerate()
+            .fold(row![].spacing(2), |tabs, (i, d)| {
+                let index = i as u32;
+                let style = if index == self.display { primary_button_style } else { secondary_button_style };
+                tabs.push(
+                    button(text(format!("{} \u{00b7} {}x{}", i + 1, d.width, d.height)).size(12))
+                        .on_press(ViewerMessage::SelectDisplay(index))
+                        .style(style)
+                        .padding([4, 10]),
+                )
+            })
+            .into()
+    }
+
+    pub fn touch(&mut self) {
+        self.last_activity = Instant::now();
+        self.idle_warning = None;
+        self.idle_acted = false;
+    }
+
+    pub fn update(&mut self, msg: ViewerMessage) -> Option<ViewerAction> {
+        if matches!(
+            msg,
+            ViewerMessage::MouseMoved(..)
+                | ViewerMessage::MousePressed(_)
+                | ViewerMessage::MouseReleased(_)
+                | ViewerMessage::MouseWheel(..)
+                | ViewerMessage::KeyPressed(_)
+                | ViewerMessage::KeyReleased(_)
+        ) {
+            self.touch();
+            // Anything sent now would be replayed once the host lets us in.
+            if self.waiting_for_host {
+                return None;
+            }
+        }
+        match msg {
+            ViewerMessage::ToggleStats => self.show_stats = !self.show_stats,
+            ViewerMessage::TogglePasswordMode => {
+                self.password_mode = !self.password_mode;
+                self.timeline.record(if self.password_mode { "Password mode on" } else { "Password mode off" });
+                return Some(ViewerAction::PasswordMode(self.password_mode));
+            }
+            ViewerMessage::CodePrompt(msg) => {
+                let prompt = self.code_prompt.as_mut()?;
+                match msg {
+                    CodePromptMessage::CodeChanged(code) => prompt.code = code.into(),
+                    CodePromptMessage::Submit => return prompt.code().map(ViewerAction::RetryWithCode),
+                    CodePromptMessage::Cancel => return Some(ViewerAction::CancelCodePrompt(prompt.reason.clone())),
+                }
+            }
+            ViewerMessage::ToggleBandwidthSaver => {
+                self.bandwidth_saver = !self.bandwidth_saver;
+                return Some(ViewerAction::Delivery(self.bandwidth_saver));
+            }
+            ViewerMessage::ToggleGreyscale => {
+                self.greyscale = !self.greyscale;
+                return Some(ViewerAction::Greyscale(self.greyscale));
+            }
+            ViewerMessage::CycleTextBoost => {
+                self.text_boost = crate::capture::display_scale::next_boost(self.text_boost);
+                return Some(ViewerAction::Send(ProtocolMessage::SetTextBoost(self.text_boost)));
+            }
+            ViewerMessage::CycleAnnotationTool => {
+                self.annotations.finish(Instant::now());
+                self.annotations.tool = AnnotationTool::next(self.annotations.tool);
+            }
+            ViewerMessage::ClearAnnotations => self.annotations.clear(),
+            ViewerMessage::IdleTick => {
+                if self.idle_acted {
+                    return None;
+                }
+                match idle::stage(&self.idle, self.last_activity, Instant::now()) {
+                    IdleStage::Active => self.idle_warning = None,
+                    IdleStage::Warning(seconds) => self.idle_warning = Some(seconds),
+                    IdleStage::Act => {
+                        self.idle_warning = None;
+                        self.idle_acted = true;
+                        let SessionIdleSettings { minutes, lock_remote, disconnect } = self.idle;
+                        if lock_remote {
+                            self.timeline.record(format!("Locked the remote after {minutes} idle minutes"));
+                        }
+                        if disconnect {
+                            tracing::info!("Disconnecting after {minutes} idle minutes");
+                        }
+                        return Some(ViewerAction::Idle { lock_remote, disconnect });
+                    }
+                }
+            }
+            ViewerMessage::AnnotationTick => self.annotations.prune(Instant::now()),
+            ViewerMessage::TogglePresentation => return Some(ViewerAction::TogglePresentation),
+            ViewerMessage::ToggleFullscreen => {
+                self.fullscreen = !self.fullscreen;
+                self.toolbar_revealed = false;
+                return Some(ViewerAction::Fullscreen(self.fullscreen));
+            }
+            ViewerMessage::SelectDisplay(index) => {
+                if self.display != index {
+                    self.display = index;
+                    self.timeline.record(format!("Switched to display {}", index + 1));
+                    return Some(ViewerAction::Send(ProtocolMessage::SelectDisplay(index)));
+                }
+            }
+            ViewerMessage::ToggleRecording => {
+                if self.recording.is_some() {
+                    return Some(ViewerAction::StopRecording);
+                }
+                self.redaction = Some(self.redaction_editor(RedactPurpose::Recording));
+            }
+            ViewerMessage::Screenshot => self.redaction = Some(self.redaction_editor(RedactPurpose::Screenshot)),
+            ViewerMessage::ScreenshotSaved(result) => {
+                let note = match result {
+                    Ok(path) => format!("Saved {}", path.display()),
+                    Err(e) => e,
                 };
-                ViewerMessage::MouseWheel(y)
+                self.timeline.record(format!("Screenshot: {note}"));
+                self.recording_note = Some(note);
+            }
+            ViewerMessage::Redaction(message) => match self.redaction.as_mut()?.update(message)? {
+                RedactionOutcome::Confirmed => {
+                    let editor = self.redaction.take().expect("editor is open");
+                    return Some(match editor.purpose {
+                        RedactPurpose::Screenshot => ViewerAction::SaveScreenshot(editor),
+                        RedactPurpose::Recording => ViewerAction::StartRecording(editor),
+                    });
+                }
+                RedactionOutcome::Cancelled => self.redaction = None,
+            },
+            ViewerMessage::PauseRecording => {
+                if let Some(clock) = &mut self.recording {
+                    clock.toggle_pause(Instant::now());
+                }
+            }
+            ViewerMessage::RecordingTick => {
+                if !self.password_mode && self.recording.is_some_and(|clock| !clock.is_paused()) {
+                    return Some(ViewerAction::RecordFrame);
+                }
+            }
+            ViewerMessage::ToggleTimeline => self.show_timeline = !self.show_timeline,
+            ViewerMessage::CopyTimeline => return Some(ViewerAction::Copy(self.timeline.to_text())),
+            ViewerMessage::ToggleClipboardHistory => self.show_clipboard_history = !self.show_clipboard_history,
+            ViewerMessage::TogglePasteFile => self.show_paste_file = !self.show_paste_file,
+            ViewerMessage::PasteFile(PasteFileMessage::Send) => {
+                if self.paste_file.sending() {
+                    return None;
+                }
+                self.paste_file.update(PasteFileMessage::Send);
+                return Some(ViewerAction::ReadClipboardForPaste);
+            }
+            ViewerMessage::PasteFile(msg) => {
+                if let PasteFileMessage::Progress(PasteProgress::Failed(e)) = &msg {
+                    self.timeline.record(format!("Paste as file failed: {e}"));
+                }
+                self.paste_file.update(msg);
+            }
+            ViewerMessage::ToggleFileBrowser => {
+                if self.file_browser.take().is_none() {
+                    let start = dirs_next::download_dir().or_else(dirs_next::home_dir).unwrap_or_default();
+                    self.file_browser = Some(FileBrowserState::new(start));
+                    return Some(ViewerAction::Send(ProtocolMessage::ListFiles(String::new())));
+                }
+            }
+            ViewerMessage::FileBrowser(msg) => {
+                let browser = self.file_browser.as_mut()?;
+                let commands = match browser.update(msg)? {
+                    BrowserRequest::List(path) => return Some(ViewerAction::Send(ProtocolMessage::ListFiles(path))),
+                    BrowserRequest::Download { path, name, size } => {
+                        let folder = browser.local_dir.clone();
+                        self.transfers.download(path, name, size, folder)
+                    }
+                    BrowserRequest::Upload { source, name, size, folder } => {
+                        self.transfers.upload(source, name, size, folder)
+                    }
+                };
+                return Some(ViewerAction::Transfers(commands));
+            }
+            ViewerMessage::ToggleCredential => {
+                self.credential = match self.credential {
+                    Some(_) => None,
+                    None => Some(SecretString::default()),
+                };
+            }
+            ViewerMessage::CredentialChanged(draft) => {
+                if let Some(credential) = &mut self.credential {
+                    *credential = draft.into();
+                }
+            }
+            ViewerMessage::SendCredential => {
+                let secret = self.credential.take().filter(|s| !s.is_empty())?;
+                return Some(ViewerAction::SendCredential(secret));
+            }
+            ViewerMessage::Transfers(msg) => return Some(ViewerAction::Transfers(self.transfers.update(msg))),
+            ViewerMessage::TypeClipboard => {
+                if self.typing.is_none() {
+                    return Some(ViewerAction::ReadClipboardForTyping);
+                }
+            }
+            ViewerMessage::TypingProgress(progress) => match progress {
+                PasteProgress::Sent { sent, total } if sent < total => self.typing = Some((sent, total)),
+                PasteProgress::Sent { .. } => self.typing = None,
+                PasteProgress::Failed(e) => {
+                    self.typing = None;
+                    self.timeline.record(format!("Type Clipboard failed: {e}"));
+                }
+            },
+            // The next clipboard poll sends it on to the host as well.
+            ViewerMessage::Recopy(index) => {
+                return self.clipboard_history.get(index).map(|text| ViewerAction::Copy(text.to_string()));
+            }
+            ViewerMessage::SendKeys(combo) => {
+                return Some(ViewerAction::Send(ProtocolMessage::KeyCombo(combo.scancodes())));
+            }
+            ViewerMessage::UnlockRemote => return Some(ViewerAction::UnlockRemote),
+            ViewerMessage::RevealToolbar(revealed) => self.toolbar_revealed = revealed,
+            ViewerMessage::ScaleModeSelected(mode) => self.scale_mode = mode,
+            ViewerMessage::RotationSelected(rotation) => self.rotation = rotation,
+            ViewerMessage::ResolutionSelected(resolution) => {
+                let changed = self.resolution != resolution;
+                self.resolution = resolution;
+                return Some(ViewerAction::SelectResolution { resolution, changed });
+            }
+            ViewerMessage::ToggleMagnifier => self.magnifier = !self.magnifier,
+            ViewerMessage::ToggleColorPanel => self.show_color_panel = !self.show_color_panel,
+            ViewerMessage::BrightnessChanged(v) => self.set_color(ColorAdjust { brightness: v, ..self.color() }),
+            ViewerMessage::ContrastChanged(v) => self.set_color(ColorAdjust { contrast: v, ..self.color() }),
+            ViewerMessage::GammaChanged(v) => self.set_color(ColorAdjust { gamma: v, ..self.color() }),
+            ViewerMessage::ResetColor => self.set_color(ColorAdjust::default()),
+            ViewerMessage::RestartHost => {
+                if !self.restart_armed {
+                    self.restart_armed = true;
+                    return None;
+                }
+                self.restart_armed = false;
+                self.timeline.record("Asked the host to restart");
+                return Some(ViewerAction::RestartHost);
+            }
+            ViewerMessage::Disconnect => return Some(ViewerAction::Disconnect),
+            ViewerMessage::MouseMoved(point, pointer) => {
+                self.cursor = Some(point);
+                if pointer.is_some() {
+                    self.host_cursor = None;
+                }
+                if self.annotations.tool.is_some() {
+                    self.annotations.extend(point);
+                } else if let Some((x, y)) = pointer {
+                    return Some(ViewerAction::MovePointer { x, y });
+                }
+            }
+            ViewerMessage::MouseLeft => self.cursor = None,
+            // Side buttons arrive from a window-wide listener; only pass
+            // them on while the pointer is over the session.
+            ViewerMessage::MousePressed(btn) | ViewerMessage::MouseReleased(btn)
+                if matches!(btn, iced::mouse::Button::Back | iced::mouse::Button::Forward)
+                    && self.cursor.is_none() => {}
+            ViewerMessage::MousePressed(btn) => {
+                if self.annotations.tool.is_some() {
+                    if btn == iced::mouse::Button::Left
+                        && let Some(point) = self.cursor
+                    {
+                        self.annotations.begin(point);
+                    }
+                    return None;
+                }
+                let button = mouse_button_to_protocol(&btn)?;
+                return Some(ViewerAction::Input(vec![ProtocolMessage::MouseButton { button, pressed: true }]));
+            }
+            ViewerMessage::MouseReleased(btn) => {
+                if self.annotations.tool.is_some() {
+                    if btn == iced::mouse::Button::Left {
+                        self.annotations.finish(Instant::now());
+                    }
+                    return None;
+                }
+                let button = mouse_button_to_protocol(&btn)?;
+                return Some(ViewerAction::Input(vec![ProtocolMessage::MouseButton { button, pressed: false }]));
+            }
+            ViewerMessage::MouseWheel(x, y) => {
+                let scroll = ProtocolMessage::MouseScroll { delta_x: x as i16, delta_y: y as i16 };
+                return Some(ViewerAction::Input(vec![scroll]));
+            }
+            ViewerMessage::KeyPressed(key) => {
+                if let Some(keycode) = iced_key_to_keycode(&key) {
+                    return Some(ViewerAction::Input(vec![ProtocolMessage::KeyEvent { keycode, pressed: true }]));
+                }
+                let text = unmapped_text(&key)?;
+                return Some(ViewerAction::Input(text.chars().map(ProtocolMessage::UnicodeChar).collect()));
+            }
+            ViewerMessage::KeyReleased(key) => {
+                let keycode = iced_key_to_keycode(&key)?;
+                return Some(ViewerAction::Input(vec![ProtocolMessage::KeyEvent { keycode, pressed: false }]));
+            }
+        }
+        None
+    }
+
+    fn redaction_editor(&self, purpose: RedactPurpose) -> RedactionEditor {
+        RedactionEditor::new(purpose, self.frame_width, self.frame_height, self.frame_pixels.to_vec())
+    }
+
+    pub fn recording_started(&mut self, clock: RecordingClock, path: &std::path::Path) {
+        self.timeline.record(format!("Recording to {}", path.display()));
+        self.recording = Some(clock);
+        self.recording_note = None;
+    }
+
+    /// The recording ended, saved or not; `note` says which.
+    pub fn recording_stopped(&mut self, note: String, failed: bool) {
+        self.recording = None;
+        let label = if failed { "Recording failed" } else { "Recording stopped" };
+        self.timeline.record(format!("{label}: {note}"));
+        self.recording_note = Some(note);
+    }
+
+    pub fn view(&self) -> Element<'_, ViewerMessage> {
+        let obscured = privacy::obscure_mode(&self.privacy, self.focused, self.last_activity, Instant::now());
+        let blurred = (obscured == Some(PrivacyMode::Blur))
+            .then(|| privacy::blurred(&self.frame_pixels, self.frame_width, self.frame_height))
+            .flatten();
+        let (frame_width, frame_height) = (self.frame_width, self.frame_height);
+        let (handle, handle_width, handle_height) = match blurred {
+            Some((pixels, w, h)) => (image::Handle::from_rgba(w, h, pixels), w, h),
+            None => (self.frame.clone(), frame_width, frame_height),
+        };
+
+        let (scale_mode, rotation) = (self.scale_mode, self.rotation);
+        let (shown_width, shown_height) = rotation.shown_size(frame_width, frame_height);
+        let viewer_area = responsive(move |size| {
+            let placement = scaling::placement(scale_mode, size, shown_width, shown_height);
+            let opacity = if obscured == Some(PrivacyMode::Dim) { 0.08 } else { 1.0 };
+            let base: Element<'_, ViewerMessage> = match placement {
+                // Obscured frames only need to roughly show where things are,
+                // so a rotated one is simply fitted.
+                Some(_) if obscured.is_some() && rotation != Rotation::None => image(handle.clone())
+                    .rotation(iced::Rotation::Solid(iced::Radians(rotation.radians())))
+                    .width(Fill)
+                    .height(Fill)
+                    .opacity(opacity)
+                    .into(),
+                Some(p) => container(if obscured.is_none() {
+                    let surface = FrameSurface {
+                        pixels: self.frame_pixels.clone(),
+                        width: frame_width,
+                        height: frame_height,
+                        crop: p.crop,
+                        rotation,
+                        damage: self.damage.clone(),
+                    };
+                    Element::from(shader(surface).width(p.dest.width).height(p.dest.height))
+                } else {
+                    image(handle.clone())
+                        .crop(p.crop_for(frame_width, frame_height, handle_width, handle_height))
+                        .content_fit(ContentFit::Fill)
+                        .width(p.dest.width)
+                        .height(p.dest.height)
+                        .opacity(opacity)
+                        .into()
+                })
+                .padding(Padding { top: p.dest.y, left: p.dest.x, right: 0.0, bottom: 0.0 })
+                .width(Fill)
+                .height(Fill)
+                .into(),
+                None => image(handle.clone()).width(Fill).height(Fill).opacity(opacity).into(),
+            };
+            let lens = (obscured.is_none() && self.magnifier).then(|| self.lens(size, placement)).flatten();
+            let frame: Element<'_, ViewerMessage> = match lens {
+                Some(lens) => stack![base, lens].into(),
+                None => base,
+            };
+            let frame: Element<'_, ViewerMessage> = match placement.and_then(|p| self.ghost_cursor(&p)) {
+                Some(ghost) => stack![frame, ghost].into(),
+                None => frame,
+            };
+
+            let frame: Element<'_, ViewerMessage> = match self.remote_cursor_layer() {
+                Some(pointer) => stack![frame, pointer].into(),
+                None => frame,
+            };
+
+            let frame: Element<'_, ViewerMessage> = if self.annotations.is_empty() {
+                frame
+            } else {
+                stack![frame, self.annotations.view(Instant::now())].into()
+            };
+
+            let area = mouse_area(frame);
+            let area = match self.remote_cursor {
+                RemoteCursor::Local => area,
+                _ => area.interaction(iced::mouse::Interaction::Hidden),
+            };
+            area.on_press(ViewerMessage::MousePressed(iced::mouse::Button::Left))
+                .on_release(ViewerMessage::MouseReleased(iced::mouse::Button::Left))
+                .on_right_press(ViewerMessage::MousePressed(iced::mouse::Button::Right))
+                .on_right_release(ViewerMessage::MouseReleased(iced::mouse::Button::Right))
+                .on_middle_press(ViewerMessage::MousePressed(iced::mouse::Button::Middle))
+                .on_middle_release(ViewerMessage::MouseReleased(iced::mouse::Button::Middle))
+                .on_move(move |point| {
+                    let pointer = pointer_position(point, placement, rotation, frame_width, frame_height);
+                    ViewerMessage::MouseMoved(point, pointer)
+                })
+                .on_exit(ViewerMessage::MouseLeft)
+                .on_scroll(|delta| {
+                    let (x, y) = match delta {
+                        iced::mouse::ScrollDelta::Lines { x, y } => (x, y),
+                        iced::mouse::ScrollDelta::Pixels { x, y } => (x, y),
+                    };
+                    ViewerMessage::MouseWheel(x, y)
+                })
+                .into()
+        });
+
+        let notice = match self.reconnecting {
+            Some((attempt, max)) => Some(format!("Reconnecting (attempt {attempt}/{max})\u{2026}")),
+            None if self.suspended => Some("Paused while this computer sleeps".to_string()),
+            None if self.waiting_for_host => {
+                Some("Someone else is connected. Waiting for the host to let you in\u{2026}".to_string())
+            }
+            None => None,
+        };
+        let viewer_area: Element<'_, ViewerMessage> = match notice {
+            Some(notice) => {
+                let notice = container(text(notice).size(18).color(TEXT_PRIMARY))
+                    .style(card_container_style)
+                    .padding([16, 28]);
+                stack![viewer_area, container(notice).center_x(Fill).center_y(Fill)].into()
+            }
+            None => match &self.code_prompt {
+                Some(prompt) => {
+                    let prompt = prompt.view().map(ViewerMessage::CodePrompt);
+                    stack![viewer_area, container(prompt).center_x(Fill).center_y(Fill)].into()
+                }
+                None if self.secure_desktop => {
+                    stack![viewer_area, container(secure_desktop_notice(self.can_unlock)).center_x(Fill).center_y(Fill)]
+                        .into()
+                }
+                None => viewer_area.into(),
+            },
+        };
+
+        let viewer_area: Element<'_, ViewerMessage> = match self.idle_warning {
+            Some(seconds) => {
+                stack![viewer_area, container(idle::warning(&self.idle, seconds)).center_x(Fill).padding(24)].into()
+            }
+            None => viewer_area,
+        };
+
+        let viewer_area: Element<'_, ViewerMessage> = if self.show_stats {
+            stack![viewer_area, container(self.stats_overlay()).align_right(Fill).padding(12)].into()
+        } else {
+            viewer_area
+        };
+
+        let viewer_area: Element<'_, ViewerMessage> = if self.show_timeline {
+            stack![viewer_area, container(self.timeline.view(ViewerMessage::CopyTimeline)).padding(12)].into()
+        } else {
+            viewer_area
+        };
+
+        let viewer_area: Element<'_, ViewerMessage> = if self.show_clipboard_history {
+            let panel = self.clipboard_history.view(ViewerMessage::Recopy);
+            stack![viewer_area, container(panel).align_bottom(Fill).padding(12)].into()
+        } else {
+            viewer_area
+        };
+
+        let viewer_area: Element<'_, ViewerMessage> = if self.show_paste_file {
+            let panel = self.paste_file.view().map(ViewerMessage::PasteFile);
+            stack![viewer_area, container(panel).align_right(Fill).align_bottom(Fill).padding(12)].into()
+        } else {
+            viewer_area
+        };
+
+        let viewer_area: Element<'_, ViewerMessage> = match &self.credential {
+            Some(draft) => {
+                let send = button(text("Send").size(12)).style(primary_button_style).padding([4, 12]);
+                let panel = container(
+                    column![
+                        text("Send credential").size(14).color(TEXT_PRIMARY),
+                        text_input("Password", draft.expose())
+                            .secure(true)
+                            .on_input(ViewerMessage::CredentialChanged)
+                            .on_submit(ViewerMessage::SendCredential)
+                            .style(input_style)
+                            .size(12)
+                            .padding(6),
+                        row![
+                            text("Kept out of clipboard history and cleared from both clipboards")
+                                .size(12)
+                                .color(TEXT_MUTED)
+                                .width(Fill),
+                            send.on_press_maybe((!draft.is_empty()).then_some(ViewerMessage::SendCredential)),
+                        ]
+                        .spacing(8)
+                        .align_y(iced::Center),
+                    ]
+                    .spacing(8),
+                )
+                .style(card_container_style)
+                .padding(12)
+                .width(360);
+                stack![viewer_area, container(panel).align_right(Fill).align_bottom(Fill).padding(12)].into()
+            }
+            None => viewer_area,
+        };
+
+        let viewer_area: Element<'_, ViewerMessage> = match &self.file_browser {
+            Some(browser) => {
+                let panel = container(
+                    column![browser.view().map(ViewerMessage::FileBrowser), self.transfers.view().map(ViewerMessage::Transfers)]
+                        .spacing(12),
+                )
+                .style(card_container_style)
+                .padding(12)
+                .width(640);
+                stack![viewer_area, container(panel).align_right(Fill).padding(12)].into()
+            }
+            None => viewer_area,
+        };
+
+        let viewer_area: Element<'_, ViewerMessage> = match &self.redaction {
+            Some(editor) => {
+                stack![viewer_area, container(editor.view().map(ViewerMessage::Redaction)).padding(12)].into()
+            }
+            None => viewer_area,
+        };
+
+        let toolbar_row = row![text("Connected").size(14).color(SUCCESS), self.keyboard_indicator()]
+            .spacing(10)
+            .padding(6);
+        let toolbar_row = if self.displays.len() > 1 {
+            toolbar_row.push(self.display_tabs())
+        } else {
+            toolbar_row
+        };
+        let toolbar_row = toolbar_row
+            .push(
+                button(text(boost_label(self.text_boost)))
+                    .on_press(ViewerMessage::CycleTextBoost)
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+            )
+            .push(
+                button(if self.greyscale { "Full Color" } else { "Greyscale" })
+                    .on_press(ViewerMessage::ToggleGreyscale)
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+            )
+            .push(
+                button(if self.bandwidth_saver { "Saver On" } else { "Bandwidth Saver" })
+                    .on_press(ViewerMessage::ToggleBandwidthSaver)
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+            )
+            .push(
+                button(AnnotationTool::label(self.annotations.tool))
+                    .on_press(ViewerMessage::CycleAnnotationTool)
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+            );
+        let toolbar_row = if self.annotations.is_empty() {
+            toolbar_row
+        } else {
+            toolbar_row.push(
+                button("Clear")
+                    .on_press(ViewerMessage::ClearAnnotations)
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+            )
+        };
+        let toolbar_row = toolbar_row
+            .push(
+                button(if self.magnifier { "Magnifier On" } else { "Magnifier" })
+                    .on_press(ViewerMessage::ToggleMagnifier)
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+            )
+            .push(
+                pick_list(ScaleMode::ALL, Some(self.scale_mode), ViewerMessage::ScaleModeSelected)
+                    .text_size(13)
+                    .padding([4, 12]),
+            )
+            .push(
+                pick_list(Rotation::ALL, Some(self.rotation), ViewerMessage::RotationSelected)
+                    .text_size(13)
+                    .padding([4, 12]),
+            )
+            .push(
+                pick_list(Resolution::ALL, Some(self.resolution), ViewerMessage::ResolutionSelected)
+                    .text_size(13)
+                    .padding([4, 12]),
+            )
+            .push(self.recording_controls())
+            .push(
+                pick_list(KeyCombo::ALL, None::<KeyCombo>, ViewerMessage::SendKeys)
+                    .placeholder("Send keys")
+                    .text_size(13)
+                    .padding([4, 12]),
+            );
+        let toolbar_row = if self.can_unlock {
+            toolbar_row.push(
+                button("Unlock remote")
+                    .on_press(ViewerMessage::UnlockRemote)
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+            )
+        } else {
+            toolbar_row
+        };
+        let toolbar_row = toolbar_row
+            .push(
+                button(if self.fullscreen { "Exit Fullscreen" } else { "Fullscreen" })
+                    .on_press(ViewerMessage::ToggleFullscreen)
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+            )
+            .push(
+                button("Present")
+                    .on_press(ViewerMessage::TogglePresentation)
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+            )
+            .push(
+                button("Color")
+                    .on_press(ViewerMessage::ToggleColorPanel)
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+            )
+            .push(
+                button(if self.show_timeline { "Hide Timeline" } else { "Timeline" })
+                    .on_press(ViewerMessage::ToggleTimeline)
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+            )
+            .push(
+                button(if self.show_clipboard_history { "Hide Clipboard" } else { "Clipboard" })
+                    .on_press(ViewerMessage::ToggleClipboardHistory)
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+            )
+            .push(
+                button(if self.show_paste_file { "Hide Paste as File" } else { "Paste as File" })
+                    .on_press(ViewerMessage::TogglePasteFile)
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+            )
+            .push(
+                button(if self.credential.is_some() { "Cancel Credential" } else { "Send Credential" })
+                    .on_press(ViewerMessage::ToggleCredential)
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+            )
+            .push(
+                button(if self.file_browser.is_some() { "Hide Files" } else { "Files" })
+                    .on_press(ViewerMessage::ToggleFileBrowser)
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+            )
+            .push(
+                button(text(match self.typing {
+                    Some((typed, total)) => format!("Typing {typed}/{total}"),
+                    None => "Type Clipboard".to_string(),
+                }))
+                .on_press_maybe(self.typing.is_none().then_some(ViewerMessage::TypeClipboard))
+                .style(secondary_button_style)
+                .padding([4, 12]),
+            )
+            .push(
+                button(if self.password_mode { "End Password Mode" } else { "Password Mode" })
+                    .on_press(ViewerMessage::TogglePasswordMode)
+                    .style(if self.password_mode { primary_button_style } else { secondary_button_style })
+                    .padding([4, 12]),
+            )
+            .push(
+                button(if self.show_stats { "Hide Stats" } else { "Show Stats" })
+                    .on_press(ViewerMessage::ToggleStats)
+                    .style(secondary_button_style)
+                    .padding([4, 12]),
+            )
+            .push(
+                button(if self.restart_armed { "Confirm Restart" } else { "Restart Host" })
+                    .on_press(ViewerMessage::RestartHost)
+                    .style(if self.restart_armed { danger_button_style } else { secondary_button_style })
+                    .padding([4, 12]),
+            )
+            .push(
+                button("Disconnect")
+                    .on_press(ViewerMessage::Disconnect)
+                    .style(danger_button_style)
+                    .padding([4, 12]),
+            );
+
+        let toolbar = container(toolbar_row)
+        .style(toolbar_container_style)
+        .width(Fill);
+
+        let mut controls = column![toolbar].spacing(0);
+        if self.show_color_panel {
+            controls = controls.push(self.color_panel());
+        }
+
+        if self.fullscreen {
+            // The toolbar floats over the frame and only shows while hovered.
+            let top: Element<'_, ViewerMessage> = if self.toolbar_revealed {
+                mouse_area(controls).on_exit(ViewerMessage::RevealToolbar(false)).into()
+            } else {
+                mouse_area(container(Space::new()).width(Fill).height(6))
+                    .on_enter(ViewerMessage::RevealToolbar(true))
+                    .into()
+            };
+            return container(stack![viewer_area, top]).width(Fill).height(Fill).into();
+        }
+
+        let content = controls.push(viewer_area);
+
+        container(content).width(Fill).height(Fill).into()
+    }
+
+    /// Frame-only view for the presentation window. It takes no input apart
+    /// from the window-management gestures passed in by the caller.
+    pub fn mirror_view<'a, M: Clone + 'a>(&self, on_drag: M, on_double_click: M, on_close: M) -> Element<'a, M> {
+        let frame = container(image(self.frame.clone()).width(Fill).height(Fill))
+            .width(Fill)
+            .height(Fill)
+            .style(|_theme: &iced::Theme| container::Style {
+                background: Some(Color::BLACK.into()),
+                ..Default::default()
             });
 
+        mouse_area(frame)
+            .on_press(on_drag)
+            .on_double_click(on_double_click)
+            .on_right_press(on_close)
+            .into()
+    }
+
+    fn remote_cursor_layer(&self) -> Option<Element<'_, ViewerMessage>> {
+        let RemoteCursor::Image { handle, width, height, hot_x, hot_y } = &self.remote_cursor else {
+            return None;
+        };
+        let cursor = self.cursor?;
+        Some(
+            container(image(handle.clone()).width(*width).height(*height))
+                .padding(Padding {
+                    top: (cursor.y - hot_y).max(0.0),
+                    left: (cursor.x - hot_x).max(0.0),
+                    right: 0.0,
+                    bottom: 0.0,
+                })
+                .into(),
+        )
+    }
+
+    fn ghost_cursor(&self, placement: &Placement) -> Option<Element<'_, ViewerMessage>> {
+        const SIZE: f32 = 16.0;
+        let (x, y) = self.host_cursor?;
+        let frame_x = denormalize_axis(x, self.frame_width);
+        let frame_y = denormalize_axis(y, self.frame_height);
+        let (x, y) = self.rotation.to_shown(frame_x, frame_y, self.frame_width, self.frame_height);
+        let point = scaling::area_point(x, y, placement)?;
+
+        let ring = container(Space::new().width(SIZE).height(SIZE)).style(|_theme: &iced::Theme| container::Style {
+            background: Some(Color { a: 0.25, ..ACCENT }.into()),
+            border: iced::Border {
+                radius: (SIZE / 2.0).into(),
+                width: 2.0,
+                color: ACCENT_HOVER,
+            },
+            ..Default::default()
+        });
+        Some(
+            container(ring)
+                .padding(Padding {
+                    top: (point.y - SIZE / 2.0).max(0.0),
+                    left: (point.x - SIZE / 2.0).max(0.0),
+                    right: 0.0,
+                    bottom: 0.0,
+                })
+                .into(),
+        )
+    }
+
+    fn lens(&self, area: Size, placement: Option<Placement>) -> Option<Element<'_, ViewerMessage>> {
+        let cursor = self.cursor?;
+        let placement = placement?;
+        let (x, y) = scaling::frame_point(cursor, &placement)?;
+        let (fx, fy) = self.rotation.to_frame(x, y, self.frame_width, self.frame_height);
+        let (pixels, w, h) =
+            magnifier::crop_rgba(&self.frame_pixels, self.frame_width, self.frame_height, fx, fy, LENS_SOURCE);
+        if w == 0 || h == 0 {
+            return None;
+        }
+        let (pixels, w, h) = scaling::rotate_rgba(&pixels, w, h, self.rotation);
+
+        let lens_w = w as f32 * LENS_ZOOM;
+        let lens_h = h as f32 * LENS_ZOOM;
+        let left = (cursor.x - lens_w / 2.0).clamp(0.0, (area.width - lens_w).max(0.0));
+        let top = (cursor.y - lens_h / 2.0).clamp(0.0, (area.height - lens_h).max(0.0));
+
+        let lens = container(
+            image(image::Handle::from_rgba(w, h, pixels))
+                .filter_method(image::FilterMethod::Nearest)
+                .width(lens_w)
+                .height(lens_h),
+        )
+        .style(|_theme: &iced::Theme| container::Style {
+            border: iced::Border {
+                radius: 4.0.into(),
+                width: 2.0,
+                color: BORDER_SUBTLE,
+            },
+            ..Default::default()
+        });
+
+        Some(
+            container(lens)
+                .padding(iced::Padding { top, left, right: 0.0, bottom: 0.0 })
+                .into(),
+        )
+    }
+
+    fn stats_overlay(&self) -> Element<'_, ViewerMessage> {
         let fps_color = if self.fps > 20.0 {
             SUCCESS
         } else if self.fps > 10.0 {
@@ -90,32 +1412,99 @@ impl ViewerState {
         };
 
         let latency_text = match self.latency_ms {
-            Some(ms) => format!("{ms}ms"),
-            None => "-- ms".to_string(),
+            Some(ms) => format!("RTT {ms}ms"),
+            None => "RTT -- ms".to_string(),
+        };
+
+        let (received, sent, elapsed) = match &self.session {
+            Some(s) => (
+                stats::format_rate(s.received_per_sec),
+                stats::format_rate(s.sent_per_sec),
+                stats::format_elapsed(s.connected_for),
+            ),
+            None => ("--".to_string(), "--".to_string(), "--".to_string()),
+        };
+
+        let (path_text, path_color) = match &self.path {
+            Some(path @ PeerPath::Relayed(_)) => (path.to_string(), Color::from_rgb(1.0, 0.8, 0.0)),
+            Some(path) => (path.to_string(), TEXT_SECONDARY),
+            None => ("Path --".to_string(), TEXT_SECONDARY),
         };
 
-        let resolution_text = format!("{}x{}", self.frame_width, self.frame_height);
+        let panel = column![
+            text(format!("{:.0} FPS", self.fps)).size(13).color(fps_color),
+            text(latency_text).size(13).color(TEXT_SECONDARY),
+            text(path_text).size(13).color(path_color),
+            text(format!("\u{2193} {received}")).size(13).color(TEXT_SECONDARY),
+            text(format!("\u{2191} {sent}")).size(13).color(TEXT_SECONDARY),
+            text(format!("{}x{}", self.frame_width, self.frame_height)).size(13).color(TEXT_SECONDARY),
+            text(format!("Session {elapsed}")).size(13).color(TEXT_SECONDARY),
+        ]
+        .spacing(2);
+        let panel = match &self.host_info {
+            Some(info) => panel
+                .push(text(format!("Host {} on {}", info.app_version, info.os)).size(13).color(TEXT_SECONDARY))
+                .push(text(format!("Protocol {}, {}", info.protocol, info.codecs.join(", "))).size(13).color(TEXT_SECONDARY))
+                .push(text(info.security()).size(13).color(TEXT_SECONDARY)),
+            None => panel,
+        };
+        let panel = match self.host_compat_note() {
+            Some(note) => panel.push(text(note).size(13).color(DANGER)),
+            None => panel,
+        };
+
+        container(panel).style(card_container_style).padding([8, 12]).into()
+    }
 
-        let toolbar = container(
+    fn color_panel(&self) -> Element<'_, ViewerMessage> {
+        let labeled = |label: &'static str, value: String, control: Element<'static, ViewerMessage>| {
             row![
-                text("Connected").size(14).color(SUCCESS),
-                text(format!("{:.0} FPS", self.fps)).size(14).color(fps_color),
-                text(latency_text).size(14).color(TEXT_SECONDARY),
-                text(resolution_text).size(14).color(TEXT_SECONDARY),
-                button("Disconnect")
-                    .on_press(ViewerMessage::Disconnect)
-                    .style(danger_button_style)
-                    .padding([4, 12]),
+                text(label).size(13).color(TEXT_SECONDARY).width(80),
+                control,
+                text(value).size(13).color(TEXT_SECONDARY).width(48),
             ]
-            .spacing(10)
-            .padding(6),
-        )
-        .style(toolbar_container_style)
-        .width(Fill);
+            .spacing(8)
+            .align_y(iced::Alignment::Center)
+        };
 
-        let content = column![toolbar, viewer_area].spacing(0);
+        let panel = row![
+            labeled(
+                "Brightness",
+                format!("{:+.2}", self.color.brightness),
+                slider(BRIGHTNESS_RANGE, self.color.brightness, ViewerMessage::BrightnessChanged)
+                    .step(0.01)
+                    .width(140)
+                    .into(),
+            ),
+            labeled(
+                "Contrast",
+                format!("{:.2}", self.color.contrast),
+                slider(CONTRAST_RANGE, self.color.contrast, ViewerMessage::ContrastChanged)
+                    .step(0.01)
+                    .width(140)
+                    .into(),
+            ),
+            labeled(
+                "Gamma",
+                format!("{:.2}", self.color.gamma),
+                slider(GAMMA_RANGE, self.color.gamma, ViewerMessage::GammaChanged)
+                    .step(0.01)
+                    .width(140)
+                    .into(),
+            ),
+            button("Reset")
+                .on_press(ViewerMessage::ResetColor)
+                .style(secondary_button_style)
+                .padding([4, 12]),
+        ]
+        .spacing(16)
+        .padding(6)
+        .align_y(iced::Alignment::Center);
 
-        container(content).width(Fill).height(Fill).into()
+        container(panel)
+            .style(toolbar_container_style)
+            .width(Fill)
+            .into()
     }
 }
 
@@ -132,6 +1521,54 @@ mod tests {
         assert!(state.latency_ms.is_none());
     }
 
+    #[test]
+    fn old_hosts_are_noted() {
+        let mut state = ViewerState::new(1, 1);
+        assert!(state.host_compat_note().is_none());
+        state.update_stats(SessionStats::default());
+        assert!(state.host_compat_note().is_some());
+
+        state.host_info = Some(HostInfo::local(false));
+        assert!(state.host_compat_note().is_none());
+        state.host_info = Some(HostInfo { protocol: 7, ..HostInfo::local(true) });
+        assert!(state.host_compat_note().unwrap().contains("protocol 7"));
+    }
+
+    #[test]
+    fn input_is_held_while_waiting_for_the_host() {
+        let mut state = ViewerState::new(1, 1);
+        let key = iced::keyboard::Key::Character("a".into());
+        state.waiting_for_host = true;
+        assert!(state.update(ViewerMessage::KeyPressed(key.clone())).is_none());
+
+        state.waiting_for_host = false;
+        let Some(ViewerAction::Input(msgs)) = state.update(ViewerMessage::KeyPressed(key)) else {
+            panic!("key press was not sent");
+        };
+        assert!(matches!(msgs[..], [ProtocolMessage::KeyEvent { pressed: true, .. }]));
+    }
+
+    #[test]
+    fn restart_needs_a_second_press() {
+        let mut state = ViewerState::new(1, 1);
+        assert!(state.update(ViewerMessage::RestartHost).is_none());
+        assert!(state.restart_armed);
+        assert!(matches!(state.update(ViewerMessage::RestartHost), Some(ViewerAction::RestartHost)));
+        assert!(!state.restart_armed);
+    }
+
+    #[test]
+    fn password_mode_skips_recording_frames() {
+        let mut state = ViewerState::new(1, 1);
+        state.recording = Some(RecordingClock::start(Instant::now()));
+        assert!(matches!(state.update(ViewerMessage::RecordingTick), Some(ViewerAction::RecordFrame)));
+        assert!(matches!(state.update(ViewerMessage::TogglePasswordMode), Some(ViewerAction::PasswordMode(true))));
+        assert!(state.update(ViewerMessage::RecordingTick).is_none());
+        assert!(matches!(state.update(ViewerMessage::TogglePasswordMode), Some(ViewerAction::PasswordMode(false))));
+        assert!(matches!(state.update(ViewerMessage::RecordingTick), Some(ViewerAction::RecordFrame)));
+        assert!(state.timeline.to_text().contains("Password mode on"));
+    }
+
     #[test]
     fn fps_tracking() {
         let mut state = ViewerState::new(100, 100);
@@ -142,6 +1579,67 @@ mod tests {
         assert!(state.fps >= 1.0);
     }
 
+    #[test]
+    fn display_choice_survives_reconnect_when_still_valid() {
+        let display = |width| DisplayInfo { x: 0, y: 0, width, height: 1080 };
+        let mut state = ViewerState::new(1, 1);
+        assert_eq!(state.set_displays(vec![display(1920), display(1280)]), None);
+        state.display = 1;
+        assert_eq!(state.set_displays(vec![display(1920), display(1280)]), Some(1));
+        assert_eq!(state.set_displays(vec![display(1920)]), None);
+        assert_eq!(state.display, 0);
+    }
+
+    #[test]
+    fn keys_forwarded_only_when_focused_and_live() {
+        let mut state = ViewerState::new(1, 1);
+        assert!(state.forwards_keys());
+        state.focused = false;
+        assert!(!state.forwards_keys());
+        state.focused = true;
+        state.waiting_for_host = true;
+        assert!(!state.forwards_keys());
+        state.waiting_for_host = false;
+        state.reconnecting = Some((1, 5));
+        assert!(!state.forwards_keys());
+    }
+
+    #[test]
+    fn viewport_reported_only_on_change() {
+        let mut state = ViewerState::new(1, 1);
+        assert_eq!(state.set_viewport(1280, 720), Some((1280, 720)));
+        assert_eq!(state.set_viewport(1280, 720), None);
+        assert_eq!(state.set_viewport(0, 720), None);
+        assert_eq!(state.set_viewport(1600, 900), Some((1600, 900)));
+    }
+
+    #[test]
+    fn reset_viewport_reports_same_size_again() {
+        let mut state = ViewerState::new(1, 1);
+        assert!(state.set_viewport(800, 600).is_some());
+        state.reset_viewport();
+        assert_eq!(state.set_viewport(800, 600), Some((800, 600)));
+    }
+
+    #[test]
+    fn pointer_position_normalizes_scaled_frame() {
+        let fit = |w, h| scaling::placement(ScaleMode::Fit, Size::new(w, h), 1920, 1080);
+        let position = |x, y, placement| pointer_position(Point::new(x, y), placement, Rotation::None, 1920, 1080);
+        assert_eq!(position(0.0, 0.0, fit(960.0, 540.0)), Some((0, 0)));
+        assert_eq!(position(959.9, 539.9, fit(960.0, 540.0)), Some((u16::MAX, u16::MAX)));
+        // 1920x1080 frame letterboxed in a square area: the bars map to nothing.
+        assert_eq!(position(480.0, 10.0, fit(960.0, 960.0)), None);
+    }
+
+    #[test]
+    fn pointer_position_follows_rotation() {
+        // Shown at 90 degrees the frame is 1080x1920; its top-right corner is
+        // the frame's top-left.
+        let placement = scaling::placement(ScaleMode::Fit, Size::new(540.0, 960.0), 1080, 1920);
+        let position = pointer_position(Point::new(539.9, 0.0), placement, Rotation::Cw90, 1920, 1080);
+        assert_eq!(position, Some((0, 0)));
+    }
+
     #[test]
     fn latency_update() {
         let mut state = ViewerState::new(100, 100);
@@ -149,4 +1647,54 @@ mod tests {
         state.update_latency(42);
         assert_eq!(state.latency_ms, Some(42));
     }
+
+    #[test]
+    fn prefs_round_trip() {
+        let prefs = ViewerPrefs {
+            show_stats: false,
+            color: ColorAdjust {
+                brightness: 0.1,
+                ..Default::default()
+            },
+            greyscale: true,
+            scale: ScaleMode::Stretch,
+            rotation: Rotation::Cw270,
+            paste_folder: "Scripts".to_string(),
+            bandwidth_saver: true,
+            fullscreen: true,
+        };
+        let state = ViewerState::new(100, 100).with_prefs(&prefs);
+        assert!(!state.show_stats);
+        assert!(state.fullscreen);
+        assert!(state.greyscale);
+        assert_eq!(state.prefs(), prefs);
+    }
+
+    #[test]
+    fn regions_composite_into_frame() {
+        let mut state = ViewerState::new(2, 2);
+        let rect = DirtyRect { x: 1, y: 1, width: 1, height: 1 };
+        state.update_region(2, 2, rect, vec![1, 2, 3, 4]);
+        assert_eq!(&state.frame_pixels[12..], &[1, 2, 3, 4]);
+        assert_eq!(&state.frame_pixels[..12], &[0; 12]);
+
+        // A region for a different frame size waits for the next full frame.
+        state.update_region(4, 4, rect, vec![9, 9, 9, 9]);
+        assert_eq!(&state.frame_pixels[12..], &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn color_adjust_applied_to_frames() {
+        let mut state = ViewerState::new(1, 1);
+        state.update_frame(1, 1, vec![10, 20, 30, 255]);
+        assert_eq!(state.frame_pixels, vec![10, 20, 30, 255]);
+
+        state.set_color(ColorAdjust {
+            brightness: 0.2,
+            ..Default::default()
+        });
+        state.update_frame(1, 1, vec![10, 20, 30, 255]);
+        assert!(state.frame_pixels[0] > 10);
+        assert_eq!(state.frame_pixels[3], 255);
+    }
 }
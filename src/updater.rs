@@ -5,22 +5,26 @@ use sha2::{Digest, Sha256};
 use tokio::sync::mpsc;
 use tracing::info;
 
-fn app_data_dir() -> PathBuf {
-    if let Some(data_dir) = dirs_next::data_dir() {
-        data_dir.join("rust-rdp")
-    } else if let Ok(appdata) = std::env::var("APPDATA") {
-        PathBuf::from(appdata).join("rust-rdp")
-    } else {
-        PathBuf::from(".").join("rust-rdp")
-    }
-}
+use crate::config::app_data_dir;
+use crate::config::settings::UpdateChannel;
+
+/// Ed25519 key that release builds are signed with. `rust-rdp.exe.sig` holds
+/// the hex-encoded signature over the exe's bytes. The private half is the
+/// `RELEASE_SIGNING_KEY` repository secret used by `.github/workflows/release.yml`;
+/// see `feature/self-update.md` for how the pair is generated and rotated.
+const RELEASE_PUBLIC_KEY: [u8; 32] = [
+    0xe5, 0x21, 0xa3, 0x3f, 0x54, 0xa9, 0xf2, 0xa7, 0x02, 0x84, 0xba, 0x36, 0xa6, 0x10, 0x5c, 0x70, 0xff, 0x10,
+    0xac, 0x81, 0x34, 0x3c, 0xd6, 0x3f, 0x88, 0x80, 0x93, 0xb9, 0x15, 0x34, 0xb5, 0x8b,
+];
 
 #[derive(Debug, Clone)]
 pub struct ReleaseInfo {
     pub version: String,
     pub download_url: String,
     pub checksum_url: Option<String>,
+    pub signature_url: Option<String>,
     pub body: String,
+    pub channel: UpdateChannel,
 }
 
 #[derive(Debug, Clone)]
@@ -35,6 +39,10 @@ pub enum UpdateProgress {
 #[derive(Deserialize)]
 struct GitHubRelease {
     tag_name: String,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    draft: bool,
     body: Option<String>,
     assets: Vec<GitHubAsset>,
 }
@@ -47,6 +55,8 @@ struct GitHubAsset {
 
 pub fn parse_version(tag: &str) -> Option<(u32, u32, u32)> {
     let tag = tag.strip_prefix('v').unwrap_or(tag);
+    // `0.5.0-beta.2` is a pre-release of 0.5.0.
+    let tag = tag.split(['-', '+']).next().unwrap_or(tag);
     let parts: Vec<&str> = tag.split('.').collect();
     if parts.is_empty() {
         return None;
@@ -57,32 +67,59 @@ pub fn parse_version(tag: &str) -> Option<(u32, u32, u32)> {
     Some((major, minor, patch))
 }
 
+fn is_prerelease_tag(tag: &str) -> bool {
+    tag.contains('-')
+}
+
+/// A release of a version sorts after its pre-releases.
 pub fn is_newer(remote_tag: &str, current: &str) -> bool {
     match (parse_version(remote_tag), parse_version(current)) {
-        (Some(remote), Some(curr)) => remote > curr,
+        (Some(remote), Some(curr)) => {
+            (remote, !is_prerelease_tag(remote_tag)) > (curr, !is_prerelease_tag(current))
+        }
         _ => false,
     }
 }
 
-pub async fn check_for_update() -> Result<Option<ReleaseInfo>, String> {
+/// The channel a release belongs to, from its tag suffix or GitHub's
+/// pre-release flag.
+pub fn release_channel(tag: &str, prerelease: bool) -> UpdateChannel {
+    let tag = tag.to_ascii_lowercase();
+    if tag.contains("-nightly") {
+        UpdateChannel::Nightly
+    } else if prerelease || is_prerelease_tag(&tag) {
+        UpdateChannel::Beta
+    } else {
+        UpdateChannel::Stable
+    }
+}
+
+fn pick_release(releases: Vec<GitHubRelease>, channel: UpdateChannel, current: &str) -> Option<GitHubRelease> {
+    releases
+        .into_iter()
+        .filter(|r| !r.draft && release_channel(&r.tag_name, r.prerelease) <= channel)
+        .filter(|r| is_newer(&r.tag_name, current))
+        .reduce(|best, r| if is_newer(&r.tag_name, &best.tag_name) { r } else { best })
+}
+
+pub async fn check_for_update(channel: UpdateChannel) -> Result<Option<ReleaseInfo>, String> {
     let client = reqwest::Client::builder()
         .user_agent("rust-rdp")
         .build()
         .map_err(|e| format!("Failed to build HTTP client: {e}"))?;
 
-    let release: GitHubRelease = client
-        .get("https://api.github.com/repos/kaykay0201/remote-desktop-rdp/releases/latest")
+    let releases: Vec<GitHubRelease> = client
+        .get("https://api.github.com/repos/kaykay0201/remote-desktop-rdp/releases?per_page=30")
         .send()
         .await
-        .map_err(|e| format!("Failed to fetch release: {e}"))?
+        .map_err(|e| format!("Failed to fetch releases: {e}"))?
         .json()
         .await
-        .map_err(|e| format!("Failed to parse release: {e}"))?;
+        .map_err(|e| format!("Failed to parse releases: {e}"))?;
 
-    let current = env!("CARGO_PKG_VERSION");
-    if !is_newer(&release.tag_name, current) {
+    let Some(release) = pick_release(releases, channel, env!("CARGO_PKG_VERSION")) else {
         return Ok(None);
-    }
+    };
 
     let asset = release
         .assets
@@ -96,10 +133,18 @@ pub async fn check_for_update() -> Result<Option<ReleaseInfo>, String> {
         .find(|a| a.name == "rust-rdp.exe.sha256")
         .map(|a| a.browser_download_url.clone());
 
+    let signature_url = release
+        .assets
+        .iter()
+        .find(|a| a.name == "rust-rdp.exe.sig")
+        .map(|a| a.browser_download_url.clone());
+
     Ok(Some(ReleaseInfo {
+        channel: release_channel(&release.tag_name, release.prerelease),
         version: release.tag_name,
         download_url: asset.browser_download_url.clone(),
         checksum_url,
+        signature_url,
         body: release.body.unwrap_or_default(),
     }))
 }
@@ -173,51 +218,149 @@ pub fn compute_sha256(path: &Path) -> Result<String, String> {
     Ok(format!("{:x}", hash))
 }
 
-pub async fn verify_checksum(exe_path: &Path, checksum_url: &str) -> Result<(), String> {
-    let client = reqwest::Client::builder()
-        .user_agent("rust-rdp")
-        .build()
-        .map_err(|e| format!("Failed to build HTTP client: {e}"))?;
-
+async fn fetch_text(client: &reqwest::Client, url: &str, what: &str) -> Result<String, String> {
     let response = client
-        .get(checksum_url)
+        .get(url)
         .send()
         .await
-        .map_err(|e| format!("Failed to download checksum: {e}"))?;
+        .map_err(|e| format!("Failed to download {what}: {e}"))?;
 
     if !response.status().is_success() {
-        return Err(format!(
-            "Checksum download failed with status: {}",
-            response.status()
-        ));
+        return Err(format!("{what} download failed with status: {}", response.status()));
     }
 
-    let checksum_text = response
+    response
         .text()
         .await
-        .map_err(|e| format!("Failed to read checksum: {e}"))?;
+        .map_err(|e| format!("Failed to read {what}: {e}"))
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+pub fn verify_signature(public_key: &[u8], data: &[u8], signature_hex: &str) -> Result<(), String> {
+    let signature = decode_hex(signature_hex).ok_or_else(|| "Malformed update signature".to_string())?;
+    ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, public_key)
+        .verify(data, &signature)
+        .map_err(|_| "Update signature is not valid".to_string())
+}
 
-    let expected_hash = checksum_text
-        .split_whitespace()
-        .next()
-        .ok_or_else(|| "Empty checksum file".to_string())?
-        .to_lowercase();
+/// Checks the downloaded exe against the release's signature and, when the
+/// release has one, its SHA256 file. An unsigned release is refused.
+pub async fn verify_checksum(
+    exe_path: &Path,
+    checksum_url: Option<&str>,
+    signature_url: Option<&str>,
+) -> Result<(), String> {
+    let signature_url = signature_url.ok_or_else(|| "Release has no signature (rust-rdp.exe.sig)".to_string())?;
 
-    let actual_hash = compute_sha256(exe_path)?;
+    let client = reqwest::Client::builder()
+        .user_agent("rust-rdp")
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {e}"))?;
 
-    if actual_hash != expected_hash {
-        return Err(format!(
-            "Checksum mismatch: expected {expected_hash}, got {actual_hash}"
-        ));
+    if let Some(checksum_url) = checksum_url {
+        let checksum_text = fetch_text(&client, checksum_url, "checksum").await?;
+
+        let expected_hash = checksum_text
+            .split_whitespace()
+            .next()
+            .ok_or_else(|| "Empty checksum file".to_string())?
+            .to_lowercase();
+
+        let actual_hash = compute_sha256(exe_path)?;
+
+        if actual_hash != expected_hash {
+            return Err(format!(
+                "Checksum mismatch: expected {expected_hash}, got {actual_hash}"
+            ));
+        }
+
+        info!("SHA256 verification passed");
     }
 
-    info!("SHA256 verification passed");
+    let signature = fetch_text(&client, signature_url, "signature").await?;
+    let bytes = std::fs::read(exe_path).map_err(|e| format!("Failed to read update for verification: {e}"))?;
+    verify_signature(&RELEASE_PUBLIC_KEY, &bytes, &signature)?;
+
+    info!("Signature verification passed");
     Ok(())
 }
 
-pub fn apply_update(new_exe_path: &Path) -> Result<(), String> {
-    let dir = app_data_dir();
-    let backup_path = dir.join("rust-rdp-backup.exe");
+/// How many earlier versions are kept around to roll back to.
+pub const MAX_BACKUPS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub version: String,
+    pub path: PathBuf,
+}
+
+impl std::fmt::Display for Backup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{}", self.version)
+    }
+}
+
+fn backups_dir() -> PathBuf {
+    app_data_dir().join("backups")
+}
+
+fn backup_exe_path(dir: &Path, version: &str) -> PathBuf {
+    dir.join(format!("rust-rdp-{version}.exe"))
+}
+
+/// Newest first.
+fn list_backups_in(dir: &Path) -> Vec<Backup> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<Backup> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let version = name.strip_prefix("rust-rdp-")?.strip_suffix(".exe")?.to_string();
+            parse_version(&version)?;
+            Some(Backup { version, path: entry.path() })
+        })
+        .collect();
+    backups.sort_by(|a, b| {
+        if is_newer(&a.version, &b.version) {
+            std::cmp::Ordering::Less
+        } else if is_newer(&b.version, &a.version) {
+            std::cmp::Ordering::Greater
+        } else {
+            std::cmp::Ordering::Equal
+        }
+    });
+    backups
+}
+
+pub fn list_backups() -> Vec<Backup> {
+    list_backups_in(&backups_dir())
+}
+
+fn prune_backups_in(dir: &Path, keep: usize) {
+    for backup in list_backups_in(dir).into_iter().skip(keep) {
+        match std::fs::remove_file(&backup.path) {
+            Ok(()) => info!("Removed backup of v{}", backup.version),
+            Err(e) => tracing::warn!("Could not remove backup of v{}: {e}", backup.version),
+        }
+    }
+}
+
+fn back_up_current_exe() -> Result<(), String> {
+    let dir = backups_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create backup folder: {e}"))?;
+    let backup_path = backup_exe_path(&dir, env!("CARGO_PKG_VERSION"));
 
     let current_exe =
         std::env::current_exe().map_err(|e| format!("Failed to get current exe: {e}"))?;
@@ -225,16 +368,51 @@ pub fn apply_update(new_exe_path: &Path) -> Result<(), String> {
     std::fs::copy(&current_exe, &backup_path)
         .map_err(|e| format!("Failed to create backup: {e}"))?;
     info!("Backed up current exe to {}", backup_path.display());
+    Ok(())
+}
+
+/// `version` is the release tag being installed; old backups are only
+/// pruned once that exact version has run for a while.
+pub fn apply_update(new_exe_path: &Path, version: &str) -> Result<(), String> {
+    back_up_current_exe()?;
 
     self_replace::self_replace(new_exe_path)
         .map_err(|e| format!("Self-replace failed: {e}"))?;
     info!("Self-replace succeeded");
 
     let _ = std::fs::remove_file(new_exe_path);
+    let marker = UpdateMarker { expected: version.trim_start_matches('v').to_string(), launches: 0 };
+    if let Err(e) = std::fs::write(update_marker_path(), marker.to_text()) {
+        tracing::warn!("Could not write update marker: {e}");
+    }
+    relaunch()
+}
+
+/// Swaps in an earlier version. The running one is backed up first, so
+/// the rollback itself can be undone from the same list.
+pub fn roll_back(backup: &Backup) -> Result<(), String> {
+    if backup.version == env!("CARGO_PKG_VERSION") {
+        return Err(format!("v{} is already running", backup.version));
+    }
+    back_up_current_exe()?;
+
+    self_replace::self_replace(&backup.path)
+        .map_err(|e| format!("Rollback failed: {e}"))?;
+    info!("Rolled back to v{}", backup.version);
 
+    let _ = std::fs::remove_file(&backup.path);
+    prune_backups_in(&backups_dir(), MAX_BACKUPS);
+    relaunch()
+}
+
+fn relaunch() -> Result<(), String> {
     let current_exe =
         std::env::current_exe().map_err(|e| format!("Failed to get new exe path: {e}"))?;
-    std::process::Command::new(current_exe)
+    let mut relaunch = std::process::Command::new(current_exe);
+    if crate::config::is_portable() {
+        relaunch.arg("--portable");
+    }
+    relaunch
         .spawn()
         .map_err(|e| format!("Failed to relaunch: {e}"))?;
 
@@ -242,24 +420,75 @@ pub fn apply_update(new_exe_path: &Path) -> Result<(), String> {
 }
 
 fn update_marker_path() -> PathBuf {
-    app_data_dir().join(".update-ok")
+    app_data_dir().join(".update-pending")
 }
 
-fn backup_exe_path() -> PathBuf {
+/// Left by `apply_update` until the new version has proven itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct UpdateMarker {
+    expected: String,
+    launches: u32,
+}
+
+impl UpdateMarker {
+    fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        let expected = lines.next()?.trim().to_string();
+        let launches = lines.next().map_or(Some(0), |l| l.trim().parse().ok())?;
+        (!expected.is_empty()).then_some(Self { expected, launches })
+    }
+
+    fn to_text(&self) -> String {
+        format!("{}\n{}\n", self.expected, self.launches)
+    }
+}
+
+/// Launches of a fresh update without a finished trial before suggesting a rollback.
+const SUSPECT_LAUNCHES: u32 = 3;
+
+fn legacy_backup_path() -> PathBuf {
     app_data_dir().join("rust-rdp-backup.exe")
 }
 
-pub fn check_post_update_health() {
-    let marker = update_marker_path();
-    let backup = backup_exe_path();
+/// Returns the version on trial when this is a freshly updated build; the
+/// caller confirms it with `confirm_update` once it has run long enough.
+pub fn check_post_update_health() -> Option<String> {
+    // Older builds kept one unversioned backup for a single session.
+    let legacy = legacy_backup_path();
+    if legacy.exists() {
+        let _ = std::fs::remove_file(&legacy);
+        let _ = std::fs::remove_file(app_data_dir().join(".update-ok"));
+        info!("Post-update: removed the old single backup");
+    }
 
-    if backup.exists() && !marker.exists() {
-        let _ = std::fs::write(&marker, "ok");
-        info!("Post-update: marker created, backup preserved for one session");
-    } else if backup.exists() && marker.exists() {
-        let _ = std::fs::remove_file(&backup);
-        let _ = std::fs::remove_file(&marker);
-        info!("Post-update: backup and marker cleaned up");
+    let path = update_marker_path();
+    let mut marker = UpdateMarker::parse(&std::fs::read_to_string(&path).ok()?)?;
+    let current = env!("CARGO_PKG_VERSION");
+    if marker.expected != current {
+        tracing::warn!("Post-update: expected v{} but v{current} is running; backups kept", marker.expected);
+        let _ = std::fs::remove_file(&path);
+        return None;
+    }
+    marker.launches += 1;
+    if marker.launches > SUSPECT_LAUNCHES {
+        tracing::warn!(
+            "Post-update: v{current} has started {} times without finishing a trial run; an earlier version can be restored from Settings",
+            marker.launches
+        );
+    }
+    let _ = std::fs::write(&path, marker.to_text());
+    Some(marker.expected)
+}
+
+/// The updated build ran for the whole trial: drop the marker and prune
+/// backups down to `MAX_BACKUPS`.
+pub fn confirm_update(version: &str) {
+    let path = update_marker_path();
+    let marker = std::fs::read_to_string(&path).ok().and_then(|text| UpdateMarker::parse(&text));
+    if marker.is_some_and(|m| m.expected == version) {
+        let _ = std::fs::remove_file(&path);
+        prune_backups_in(&backups_dir(), MAX_BACKUPS);
+        info!("Post-update: v{version} confirmed");
     }
 }
 
@@ -314,6 +543,38 @@ mod tests {
         assert!(!is_newer("v0.2.0", "0.3.1"));
     }
 
+    #[test]
+    fn pre_releases_sort_before_their_release() {
+        assert_eq!(parse_version("v0.5.0-beta.2"), Some((0, 5, 0)));
+        assert!(is_newer("v0.5.0-beta.1", "0.4.2"));
+        assert!(is_newer("v0.5.0", "0.5.0-beta.1"));
+        assert!(!is_newer("v0.5.0-beta.1", "0.5.0"));
+    }
+
+    #[test]
+    fn channels_filter_releases() {
+        let release = |tag: &str, prerelease: bool| GitHubRelease {
+            tag_name: tag.to_string(),
+            prerelease,
+            draft: false,
+            body: None,
+            assets: Vec::new(),
+        };
+        let releases = || {
+            vec![
+                release("v0.4.1", false),
+                release("v0.5.0-nightly.20240101", true),
+                release("v0.5.0-rc1", true),
+                release("v0.4.0", false),
+            ]
+        };
+        let pick = |channel| pick_release(releases(), channel, "0.4.0").map(|r| r.tag_name);
+        assert_eq!(pick(UpdateChannel::Stable).as_deref(), Some("v0.4.1"));
+        assert_eq!(pick(UpdateChannel::Beta).as_deref(), Some("v0.5.0-rc1"));
+        assert!(pick(UpdateChannel::Nightly).is_some_and(|tag| tag.starts_with("v0.5.0-")));
+        assert_eq!(release_channel("v0.6.0-beta", false), UpdateChannel::Beta);
+    }
+
     #[test]
     fn staging_path_correct() {
         let path = staging_exe_path();
@@ -342,18 +603,74 @@ mod tests {
 
     #[test]
     fn backup_path_correct() {
-        let path = backup_exe_path();
-        assert_eq!(path.file_name().unwrap(), "rust-rdp-backup.exe");
+        let path = backup_exe_path(Path::new("backups"), "1.2.3");
+        assert_eq!(path.file_name().unwrap(), "rust-rdp-1.2.3.exe");
+    }
+
+    #[test]
+    fn backups_list_newest_first_and_prune() {
+        let dir = std::env::temp_dir().join(format!("rust-rdp-test-backups-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for version in ["0.9.0", "1.10.0", "1.2.0", "1.3.0-beta.1"] {
+            std::fs::write(backup_exe_path(&dir, version), b"exe").unwrap();
+        }
+        std::fs::write(dir.join("notes.txt"), b"ignored").unwrap();
+
+        let versions = |dir: &Path| list_backups_in(dir).into_iter().map(|b| b.version).collect::<Vec<_>>();
+        assert_eq!(versions(&dir), ["1.10.0", "1.3.0-beta.1", "1.2.0", "0.9.0"]);
+        prune_backups_in(&dir, 2);
+        assert_eq!(versions(&dir), ["1.10.0", "1.3.0-beta.1"]);
+        let _ = std::fs::remove_dir_all(&dir);
     }
 
     #[test]
     fn marker_path_correct() {
         let path = update_marker_path();
-        assert_eq!(path.file_name().unwrap(), ".update-ok");
+        assert_eq!(path.file_name().unwrap(), ".update-pending");
+    }
+
+    #[test]
+    fn update_marker_round_trips() {
+        let marker = UpdateMarker { expected: "1.4.0".to_string(), launches: 2 };
+        assert_eq!(UpdateMarker::parse(&marker.to_text()), Some(marker));
+        assert_eq!(
+            UpdateMarker::parse("1.4.0"),
+            Some(UpdateMarker { expected: "1.4.0".to_string(), launches: 0 })
+        );
+        assert_eq!(UpdateMarker::parse(""), None);
+        assert_eq!(UpdateMarker::parse("1.4.0\nlots"), None);
     }
 
     #[test]
     fn health_check_no_panic() {
         check_post_update_health();
     }
+
+    #[test]
+    fn signature_must_match_key_and_data() {
+        use ring::signature::{Ed25519KeyPair, KeyPair};
+
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let signature: String = pair
+            .sign(b"release")
+            .as_ref()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+
+        let public = pair.public_key().as_ref();
+        assert!(verify_signature(public, b"release", &format!("{signature}\n")).is_ok());
+        assert!(verify_signature(public, b"tampered", &signature).is_err());
+        assert!(verify_signature(&RELEASE_PUBLIC_KEY, b"release", &signature).is_err());
+        assert!(verify_signature(public, b"release", "not hex").is_err());
+    }
+
+    #[tokio::test]
+    async fn unsigned_release_is_refused() {
+        let result = verify_checksum(Path::new("rust-rdp-update.exe"), Some("https://example.invalid/sha256"), None).await;
+        assert_eq!(result, Err("Release has no signature (rust-rdp.exe.sig)".to_string()));
+    }
 }